@@ -0,0 +1,131 @@
+use crate::schema_history_dir;
+use crate::shadow::SHADOW_FILE_PREFIX;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use toasty_migrate::SCHEMA_HISTORY_DIR;
+
+/// Toasty's scratch directory in the project, which `toasty init` keeps out
+/// of git
+const CACHE_DIR: &str = ".toasty";
+
+/// Parse an age like `30m`, `12h` or `7d`
+pub fn parse_age(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("expected an age like 30m, 12h or 7d, got `{}`", value);
+
+    let split = value.len().saturating_sub(1);
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+
+    Ok(Duration::from_secs(amount * seconds))
+}
+
+/// `age` in the largest unit that divides it, the reverse of [`parse_age`]
+fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    for (unit, size) in [("d", 24 * 60 * 60), ("h", 60 * 60), ("m", 60)] {
+        if seconds >= size && seconds.is_multiple_of(size) {
+            return format!("{}{}", seconds / size, unit);
+        }
+    }
+    format!("{}s", seconds)
+}
+
+/// Remove shadow databases left in the temp directory by crashed runs, and
+/// files under `.toasty/`, that were last modified before `older_than` ago.
+/// The migrations directory and the schema history `--dump-schema-on-apply`
+/// saves in `.toasty/history` are never touched.
+pub fn cmd_clean(dir: String, older_than: Duration) -> Result<()> {
+    say!(
+        "🗑️  Removing Toasty artifacts older than {}",
+        format_age(older_than)
+    );
+
+    let cutoff = SystemTime::now()
+        .checked_sub(older_than)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let keep: Vec<PathBuf> = [
+        PathBuf::from(&dir),
+        schema_history_dir(&dir),
+        PathBuf::from(SCHEMA_HISTORY_DIR),
+    ]
+    .iter()
+    .filter_map(|path| std::fs::canonicalize(path).ok())
+    .collect();
+    let mut removed = Vec::new();
+
+    // Shadow databases, with any journal SQLite left beside them
+    let temp_dir = std::env::temp_dir();
+    for entry in std::fs::read_dir(&temp_dir)? {
+        let path = entry?.path();
+        let is_shadow = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(SHADOW_FILE_PREFIX));
+
+        if is_shadow && path.is_file() && is_stale(&path, cutoff) {
+            std::fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+
+    let cache_dir = Path::new(CACHE_DIR);
+    if cache_dir.is_dir() {
+        remove_stale(cache_dir, cutoff, &keep, &mut removed)?;
+    }
+
+    for path in &removed {
+        say!("   🗑️  {}", path.display());
+    }
+    if removed.is_empty() {
+        say!("✅ Nothing to clean");
+    } else {
+        say!("✅ Removed {} file(s)", removed.len());
+    }
+
+    Ok(())
+}
+
+/// Remove the stale files under `dir`, then any subdirectory left empty,
+/// skipping everything under the directories in `keep`
+fn remove_stale(
+    dir: &Path,
+    cutoff: SystemTime,
+    keep: &[PathBuf],
+    removed: &mut Vec<PathBuf>,
+) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        let kept = std::fs::canonicalize(&path)
+            .is_ok_and(|path| keep.iter().any(|dir| path.starts_with(dir)));
+        if kept || path.file_name().is_some_and(|name| name == ".schema.json") {
+            continue;
+        }
+
+        if path.is_dir() {
+            remove_stale(&path, cutoff, keep, removed)?;
+            if std::fs::read_dir(&path)?.next().is_none() {
+                std::fs::remove_dir(&path)?;
+            }
+        } else if is_stale(&path, cutoff) {
+            std::fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn is_stale(path: &Path, cutoff: SystemTime) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified <= cutoff)
+}
@@ -1,23 +1,81 @@
 use anyhow::Result;
-use toasty_migrate::*;
+use std::time::Duration;
+#[cfg(feature = "sqlite")]
+use toasty_migrate::apply_sqlite_pragmas;
+use toasty_migrate::prelude::*;
+#[cfg(feature = "sqlite")]
+use toasty_migrate::SqliteMigrationConnection;
+use toasty_migrate::{dependency_order, sqlite_url_parts, MigrationTracker, SCHEMA_LOG_TABLE};
+
+/// How long SQLite connections wait on a locked database by default
+pub const DEFAULT_SQLITE_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serializes SQLite writes made by this process, so concurrent migration
+/// operations on the same file queue up instead of failing with
+/// "database is locked".
+#[cfg(feature = "sqlite")]
+static SQLITE_WRITE_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
 
 /// Execute SQL migrations against a database
 pub struct MigrationExecutor {
     url: String,
+    busy_timeout: Duration,
 }
 
 impl MigrationExecutor {
     pub fn new(url: String) -> Self {
-        Self { url }
+        Self {
+            url,
+            busy_timeout: DEFAULT_SQLITE_BUSY_TIMEOUT,
+        }
+    }
+
+    /// Set how long SQLite waits for a lock held by another connection
+    /// before giving up
+    pub fn busy_timeout(mut self, timeout: Duration) -> Self {
+        self.busy_timeout = timeout;
+        self
+    }
+
+    /// Open the SQLite database with the busy timeout applied and WAL
+    /// journaling enabled, so readers don't block migrations (and vice versa)
+    ///
+    /// `PRAGMA` settings in the URL query string are applied first; an
+    /// explicit `journal_mode` there replaces the WAL default.
+    #[cfg(feature = "sqlite")]
+    fn open_sqlite(&self) -> Result<rusqlite::Connection> {
+        let (db_path, pragmas) = sqlite_url_parts(&self.url)?;
+        let conn = rusqlite::Connection::open(db_path)?;
+
+        conn.busy_timeout(self.busy_timeout)?;
+        apply_sqlite_pragmas(&conn, &pragmas)?;
+
+        let journal_mode_set = pragmas
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("journal_mode"));
+
+        if db_path != ":memory:" && !journal_mode_set {
+            let mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+            if !mode.eq_ignore_ascii_case("wal") {
+                conn.query_row("PRAGMA journal_mode = WAL", [], |row| {
+                    row.get::<_, String>(0)
+                })?;
+            }
+        }
+
+        Ok(conn)
     }
 
     /// Execute a migration context's statements against the database
+    ///
+    /// All statements, including raw `execute_sql` ones, run in a single
+    /// transaction so a failing statement leaves the schema untouched.
     #[cfg(feature = "postgresql")]
     pub async fn execute_postgresql(&self, context: &SqlMigrationContext) -> Result<()> {
         use tokio_postgres::NoTls;
 
-        println!("🔌 Connecting to PostgreSQL...");
-        let (client, connection) = tokio_postgres::connect(&self.url, NoTls).await?;
+        say!("🔌 Connecting to PostgreSQL...");
+        let (mut client, connection) = tokio_postgres::connect(&self.url, NoTls).await?;
 
         // Spawn connection
         tokio::spawn(async move {
@@ -26,13 +84,21 @@ impl MigrationExecutor {
             }
         });
 
+        let tx = client.transaction().await?;
+
         // Execute each SQL statement
         for (i, sql) in context.statements().iter().enumerate() {
-            println!("   Executing statement {}: {}", i + 1, sql.lines().next().unwrap_or(sql));
-            client.execute(sql, &[]).await?;
+            say!(
+                "   Executing statement {}: {}",
+                i + 1,
+                sql.lines().next().unwrap_or(sql)
+            );
+            tx.batch_execute(sql).await?;
         }
 
-        println!("✅ Executed {} statement(s)", context.statements().len());
+        tx.commit().await?;
+
+        say!("✅ Executed {} statement(s)", context.statements().len());
         Ok(())
     }
 
@@ -41,9 +107,13 @@ impl MigrationExecutor {
         Err(anyhow::anyhow!("PostgreSQL support not enabled"))
     }
 
-    /// Drop all tables in the database
+    /// Drop all tables in the database, children before the tables their
+    /// foreign keys reference
+    ///
+    /// With `cascade` unset, anything still depending on a table (a view, or
+    /// a foreign key cycle) makes the drop fail instead of being dropped too.
     #[cfg(feature = "postgresql")]
-    pub async fn drop_all_tables_postgresql(&self) -> Result<usize> {
+    pub async fn drop_all_tables_postgresql(&self, cascade: bool) -> Result<usize> {
         use tokio_postgres::NoTls;
 
         let (client, connection) = tokio_postgres::connect(&self.url, NoTls).await?;
@@ -55,22 +125,47 @@ impl MigrationExecutor {
         });
 
         // Get all tables
-        let rows = client.query(
-            "SELECT tablename FROM pg_tables WHERE schemaname = 'public'",
-            &[],
-        ).await?;
+        let rows = client
+            .query(
+                "SELECT tablename FROM pg_tables WHERE schemaname = 'public'",
+                &[],
+            )
+            .await?;
+
+        // Skip migration tracking and audit tables
+        let tables: Vec<String> = rows
+            .iter()
+            .map(|row| row.get(0))
+            .filter(|name: &String| name != "_toasty_migrations" && name != SCHEMA_LOG_TABLE)
+            .collect();
+
+        let rows = client
+            .query(
+                "SELECT conrelid::regclass::text, confrelid::regclass::text
+             FROM pg_constraint
+             WHERE contype = 'f' AND connamespace = 'public'::regnamespace",
+                &[],
+            )
+            .await?;
+        let references: Vec<(String, String)> =
+            rows.iter().map(|row| (row.get(0), row.get(1))).collect();
 
         let mut dropped = 0;
-        for row in rows {
-            let table_name: String = row.get(0);
-
-            // Skip migration tracking table
-            if table_name == "_toasty_migrations" {
-                continue;
+        for table_name in dependency_order(&tables, &references).iter().rev() {
+            say!("   Dropping table: {}", table_name);
+
+            if cascade {
+                client
+                    .execute(&format!("DROP TABLE IF EXISTS {} CASCADE", table_name), &[])
+                    .await?;
+            } else {
+                client
+                    .execute(&format!("DROP TABLE IF EXISTS {}", table_name), &[])
+                    .await
+                    .map_err(|e| {
+                        anyhow::anyhow!("Cannot drop {} without CASCADE: {}", table_name, e)
+                    })?;
             }
-
-            println!("   Dropping table: {}", table_name);
-            client.execute(&format!("DROP TABLE IF EXISTS {} CASCADE", table_name), &[]).await?;
             dropped += 1;
         }
 
@@ -78,7 +173,7 @@ impl MigrationExecutor {
     }
 
     #[cfg(not(feature = "postgresql"))]
-    pub async fn drop_all_tables_postgresql(&self) -> Result<usize> {
+    pub async fn drop_all_tables_postgresql(&self, _cascade: bool) -> Result<usize> {
         Err(anyhow::anyhow!("PostgreSQL support not enabled"))
     }
 
@@ -95,17 +190,24 @@ impl MigrationExecutor {
             }
         });
 
-        client.execute(
-            "CREATE TABLE IF NOT EXISTS _toasty_migrations (
+        client
+            .execute(
+                "CREATE TABLE IF NOT EXISTS _toasty_migrations (
                 version VARCHAR(255) PRIMARY KEY,
                 applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
             )",
-            &[],
-        ).await?;
+                &[],
+            )
+            .await?;
 
         Ok(())
     }
 
+    #[cfg(not(feature = "postgresql"))]
+    pub async fn create_tracking_table_postgresql(&self) -> Result<()> {
+        Err(anyhow::anyhow!("PostgreSQL support not enabled"))
+    }
+
     /// Check if migration is applied
     #[cfg(feature = "postgresql")]
     pub async fn is_migration_applied_postgresql(&self, version: &str) -> Result<bool> {
@@ -119,10 +221,12 @@ impl MigrationExecutor {
             }
         });
 
-        let rows = client.query(
-            "SELECT 1 FROM _toasty_migrations WHERE version = $1",
-            &[&version],
-        ).await?;
+        let rows = client
+            .query(
+                "SELECT 1 FROM _toasty_migrations WHERE version = $1",
+                &[&version],
+            )
+            .await?;
 
         Ok(!rows.is_empty())
     }
@@ -140,10 +244,12 @@ impl MigrationExecutor {
             }
         });
 
-        client.execute(
-            "INSERT INTO _toasty_migrations (version) VALUES ($1)",
-            &[&version],
-        ).await?;
+        client
+            .execute(
+                "INSERT INTO _toasty_migrations (version) VALUES ($1)",
+                &[&version],
+            )
+            .await?;
 
         Ok(())
     }
@@ -161,30 +267,44 @@ impl MigrationExecutor {
             }
         });
 
-        client.execute(
-            "DELETE FROM _toasty_migrations WHERE version = $1",
-            &[&version],
-        ).await?;
+        client
+            .execute(
+                "DELETE FROM _toasty_migrations WHERE version = $1",
+                &[&version],
+            )
+            .await?;
 
         Ok(())
     }
 
     /// Execute SQL migrations against SQLite
+    ///
+    /// All statements, including raw `execute_sql` ones, run in a single
+    /// transaction so a failing statement leaves the schema untouched.
     #[cfg(feature = "sqlite")]
     pub async fn execute_sqlite(&self, context: &SqlMigrationContext) -> Result<()> {
-        use rusqlite::Connection;
+        use rusqlite::TransactionBehavior;
 
-        println!("🔌 Connecting to SQLite...");
-        let db_path = self.url.trim_start_matches("sqlite:");
-        let conn = Connection::open(db_path)?;
+        say!("🔌 Connecting to SQLite...");
+        let mut conn = self.open_sqlite()?;
+
+        // Take the write lock up-front rather than upgrading mid-transaction
+        let _guard = SQLITE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
 
         // Execute each SQL statement
         for (i, sql) in context.statements().iter().enumerate() {
-            println!("   Executing statement {}: {}", i + 1, sql.lines().next().unwrap_or(sql));
-            conn.execute(sql, [])?;
+            say!(
+                "   Executing statement {}: {}",
+                i + 1,
+                sql.lines().next().unwrap_or(sql)
+            );
+            tx.execute_batch(sql)?;
         }
 
-        println!("✅ Executed {} statement(s)", context.statements().len());
+        tx.commit()?;
+
+        say!("✅ Executed {} statement(s)", context.statements().len());
         Ok(())
     }
 
@@ -193,26 +313,38 @@ impl MigrationExecutor {
         Err(anyhow::anyhow!("SQLite support not enabled"))
     }
 
-    /// Drop all tables in SQLite
+    /// Drop all tables in SQLite, children before the tables their foreign
+    /// keys reference
     #[cfg(feature = "sqlite")]
     pub async fn drop_all_tables_sqlite(&self) -> Result<usize> {
-        use rusqlite::Connection;
-
-        let db_path = self.url.trim_start_matches("sqlite:");
-        let conn = Connection::open(db_path)?;
+        let conn = self.open_sqlite()?;
+        let _guard = SQLITE_WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
 
         // Get all tables
-        let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'")?;
-        let tables: Vec<String> = stmt
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
+        )?;
+        let mut tables: Vec<String> = stmt
             .query_map([], |row| row.get(0))?
             .collect::<Result<Vec<_>, _>>()?;
+        tables.retain(|name| name != "_toasty_migrations" && name != SCHEMA_LOG_TABLE);
+
+        let mut references = Vec::new();
+        for table_name in &tables {
+            let mut stmt = conn.prepare(&format!("PRAGMA foreign_key_list({})", table_name))?;
+            let parents = stmt
+                .query_map([], |row| row.get::<_, String>(2))?
+                .collect::<Result<Vec<_>, _>>()?;
+            references.extend(
+                parents
+                    .into_iter()
+                    .map(|parent| (table_name.clone(), parent)),
+            );
+        }
 
         let mut dropped = 0;
-        for table_name in tables {
-            if table_name == "_toasty_migrations" {
-                continue;
-            }
-            println!("   Dropping table: {}", table_name);
+        for table_name in dependency_order(&tables, &references).iter().rev() {
+            say!("   Dropping table: {}", table_name);
             conn.execute(&format!("DROP TABLE IF EXISTS {}", table_name), [])?;
             dropped += 1;
         }
@@ -224,4 +356,110 @@ impl MigrationExecutor {
     pub async fn drop_all_tables_sqlite(&self) -> Result<usize> {
         Err(anyhow::anyhow!("SQLite support not enabled"))
     }
+
+    /// The name of the database the URL actually connects to, and the host
+    /// serving it, as `(database, host)`
+    pub async fn connected_database(&self) -> Result<(String, String)> {
+        if self.url.starts_with("postgresql") || self.url.starts_with("postgres") {
+            self.connected_database_postgresql().await
+        } else if self.url.starts_with("sqlite") {
+            let (path, _) = sqlite_url_parts(&self.url)?;
+            let name = std::path::Path::new(path)
+                .file_name()
+                .map_or(path.to_string(), |name| name.to_string_lossy().to_string());
+            Ok((name, "local file".to_string()))
+        } else {
+            Err(anyhow::anyhow!("Unsupported database type"))
+        }
+    }
+
+    #[cfg(feature = "postgresql")]
+    async fn connected_database_postgresql(&self) -> Result<(String, String)> {
+        use tokio_postgres::NoTls;
+
+        let (client, connection) = tokio_postgres::connect(&self.url, NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("Connection error: {}", e);
+            }
+        });
+
+        // No server address means a Unix socket on this machine
+        let row = client
+            .query_one(
+                "SELECT current_database()::text, COALESCE(host(inet_server_addr()), 'localhost')",
+                &[],
+            )
+            .await?;
+
+        Ok((row.get(0), row.get(1)))
+    }
+
+    #[cfg(not(feature = "postgresql"))]
+    async fn connected_database_postgresql(&self) -> Result<(String, String)> {
+        Err(anyhow::anyhow!("PostgreSQL support not enabled"))
+    }
+
+    /// The PostgreSQL server's `server_version_num`, e.g. `160002`
+    #[cfg(feature = "postgresql")]
+    pub async fn server_version_postgresql(&self) -> Result<u32> {
+        use tokio_postgres::NoTls;
+
+        let (client, connection) = tokio_postgres::connect(&self.url, NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("Connection error: {}", e);
+            }
+        });
+
+        let row = client.query_one("SHOW server_version_num", &[]).await?;
+        let version: String = row.get(0);
+        Ok(version.trim().parse()?)
+    }
+
+    #[cfg(not(feature = "postgresql"))]
+    pub async fn server_version_postgresql(&self) -> Result<u32> {
+        Err(anyhow::anyhow!("PostgreSQL support not enabled"))
+    }
+
+    /// Execute a migration context's statements, in one transaction, against
+    /// whichever database the URL points at
+    pub async fn execute(&self, context: &SqlMigrationContext) -> Result<()> {
+        if self.url.starts_with("postgresql") || self.url.starts_with("postgres") {
+            self.execute_postgresql(context).await
+        } else if self.url.starts_with("sqlite") {
+            self.execute_sqlite(context).await
+        } else {
+            Err(anyhow::anyhow!("Unsupported database type"))
+        }
+    }
+
+    /// A live connection to whichever database the URL points at, with
+    /// the SQLite busy timeout applied
+    pub async fn connect(&self) -> Result<Box<dyn AsyncMigrationContext>> {
+        #[cfg(feature = "sqlite")]
+        if self.url.starts_with("sqlite") {
+            return Ok(Box::new(SqliteMigrationConnection::new(
+                self.open_sqlite()?,
+            )));
+        }
+
+        toasty_migrate::connect(&self.url).await
+    }
+
+    /// Create the migration tracking table for whichever database the URL
+    /// points at, unless it already exists
+    pub async fn create_tracking_table(&self) -> Result<()> {
+        MigrationTracker::new()
+            .initialize(&mut *self.connect().await?)
+            .await
+    }
+
+    /// Read applied migrations as `(version, applied_at)`, in version order,
+    /// for whichever database the URL points at
+    pub async fn applied_versions(&self) -> Result<Vec<(String, String)>> {
+        MigrationTracker::applied_versions(&mut *self.connect().await?).await
+    }
 }
@@ -1,14 +1,26 @@
-mod reset;
+#[macro_use]
+mod output;
+mod clean;
 mod executor;
+mod reset;
 mod shadow;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use std::path::PathBuf;
-use toasty_migrate::*;
-use reset::cmd_reset;
+use clean::{cmd_clean, parse_age};
 use executor::MigrationExecutor;
-use shadow::ShadowDatabase;
+use reset::cmd_reset;
+use shadow::{reverted_changes, ShadowSchemaSource};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use toasty_migrate::prelude::*;
+use toasty_migrate::{
+    apply_renames, detect_changes, load_snapshot, rename_candidates, save_snapshot,
+    AppliedMigration, ColumnRename, DiagramFormat, EntityParser, FileSchemaSource,
+    MigrationFileInfo, MigrationGenerator, MigrationLoader, MigrationRunner, MigrationTracker,
+    RenameHints, SchemaDiff, SchemaSource, SqlIntrospector, RENAMES_FILE, SCHEMA_HISTORY_DIR,
+};
 
 #[derive(Parser)]
 #[command(name = "toasty")]
@@ -16,6 +28,15 @@ use shadow::ShadowDatabase;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// How long to wait on a locked SQLite database, in milliseconds
+    #[arg(long, global = true, default_value_t = 5000)]
+    busy_timeout: u64,
+
+    /// Decorate output with emoji markers; plain output uses ASCII ones
+    /// like [ok] and [warn]
+    #[arg(long, global = true, value_enum, default_value_t = output::ColorChoice::Auto)]
+    color: output::ColorChoice,
 }
 
 #[derive(Subcommand)]
@@ -25,14 +46,18 @@ enum Commands {
         /// Project directory (defaults to current directory)
         #[arg(short, long, default_value = ".")]
         dir: String,
+
+        /// Write (or extend) a .gitignore excluding local databases and caches
+        #[arg(long)]
+        init_git: bool,
     },
 
     /// Generate a new migration from schema changes
     #[command(name = "migrate:generate")]
     MigrateGenerate {
         /// Description of the migration
-        #[arg(short, long)]
-        message: String,
+        #[arg(short, long, required_unless_present = "amend")]
+        message: Option<String>,
 
         /// Database connection URL (required for introspection)
         #[arg(short, long)]
@@ -45,6 +70,82 @@ enum Commands {
         /// Path to entity crate directory
         #[arg(short, long, default_value = "entity")]
         entity_dir: Option<String>,
+
+        /// Don't add indexes on `belongs_to` key columns automatically
+        #[arg(long)]
+        no_auto_fk_index: bool,
+
+        /// Add constraints as NOT VALID and validate them in a follow-up step
+        /// outside the migration transaction (PostgreSQL)
+        #[arg(long)]
+        safe_constraints: bool,
+
+        /// Add NOT NULL columns with a default as nullable, then backfill
+        /// them in batches and make them NOT NULL in a follow-up step, unless
+        /// the server can add them without rewriting the table (PostgreSQL)
+        #[arg(long)]
+        safe_add_column: bool,
+
+        /// Rows per batch when --safe-add-column backfills a column
+        #[arg(long, default_value_t = 10_000, requires = "safe_add_column")]
+        batch_size: usize,
+
+        /// Diff against a saved schema snapshot (JSON) instead of replaying
+        /// the migrations in a shadow database
+        #[arg(long)]
+        from_snapshot: Option<PathBuf>,
+
+        /// Print the SQL the generated migration will run
+        #[arg(long)]
+        show_sql: bool,
+
+        /// Write down() as explicit SQL for the --url database instead of
+        /// structural calls
+        #[arg(long)]
+        sql_down: bool,
+
+        /// Introspect up to N tables concurrently
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        parallel: usize,
+
+        /// Rewrite the latest migration in place, keeping its version, if it
+        /// has not been applied yet
+        #[arg(long, conflicts_with_all = ["message", "from_snapshot"])]
+        amend: bool,
+
+        /// Directory containing a migration.rs.tmpl to render migration
+        /// files from instead of the built-in template
+        #[arg(long)]
+        template_dir: Option<PathBuf>,
+
+        /// Generate even though the --url database has migrations that are
+        /// not applied yet
+        #[arg(long)]
+        allow_pending: bool,
+
+        /// Ask whether each dropped and added column pair is a rename
+        #[arg(long)]
+        interactive: bool,
+
+        /// Replay the migrations in an in-memory SQLite database instead of
+        /// a temp file
+        #[arg(long, conflicts_with = "from_snapshot")]
+        shadow_in_memory: bool,
+
+        /// Exit with an error if any entity field can't be fully handled,
+        /// e.g. a type with no SQL mapping
+        #[arg(long)]
+        fail_on_warnings: bool,
+
+        /// Also write the entity schema as Markdown tables to this file,
+        /// e.g. SCHEMA.md
+        #[arg(long, value_name = "PATH")]
+        docs: Option<PathBuf>,
+
+        /// Write a migration with empty up() and down() even when the
+        /// entities match the schema, e.g. for a hand-written data change
+        #[arg(long)]
+        allow_empty: bool,
     },
 
     /// Run pending migrations
@@ -57,6 +158,25 @@ enum Commands {
         /// Path to migrations directory
         #[arg(short, long, default_value = "migrations")]
         dir: String,
+
+        /// Only apply migrations authored at or before this timestamp
+        /// (YYYYMMDD_HHMMSS)
+        #[arg(long, value_parser = parse_cutoff)]
+        until: Option<String>,
+
+        /// Record each structural change, with its before and after
+        /// definition, in the _toasty_schema_log table
+        #[arg(long)]
+        audit: bool,
+
+        /// Save the schema after each applied migration to
+        /// .toasty/history/<version>.json
+        #[arg(long)]
+        dump_schema_on_apply: bool,
+
+        /// Report the applied versions as text or as a JSON object
+        #[arg(long, value_enum, default_value_t = output::ReportFormat::Text)]
+        format: output::ReportFormat,
     },
 
     /// Rollback migrations
@@ -73,6 +193,10 @@ enum Commands {
         /// Path to migrations directory
         #[arg(short, long, default_value = "migrations")]
         dir: String,
+
+        /// Report the rolled back versions as text or as a JSON object
+        #[arg(long, value_enum, default_value_t = output::ReportFormat::Text)]
+        format: output::ReportFormat,
     },
 
     /// Show migration status
@@ -85,6 +209,10 @@ enum Commands {
         /// Path to migrations directory
         #[arg(short, long, default_value = "migrations")]
         dir: String,
+
+        /// Exit with a non-zero code if any migration is pending
+        #[arg(long)]
+        check: bool,
     },
 
     /// Reset database: drop all tables and rerun all migrations
@@ -105,37 +233,185 @@ enum Commands {
         /// Skip confirmation prompt
         #[arg(long)]
         force: bool,
+
+        /// Fail if a table still has dependents after ordering the drops,
+        /// instead of dropping them with CASCADE (PostgreSQL)
+        #[arg(long)]
+        no_cascade: bool,
+
+        /// Show how the database differs from the entities and exit
+        /// without dropping anything
+        #[arg(long)]
+        diff_only: bool,
+
+        /// Abort unless the URL connects to the database with this name
+        #[arg(long, value_name = "NAME")]
+        expect_database: Option<String>,
+    },
+
+    /// Rebuild .schema.json by replaying the migrations in a shadow database
+    #[command(name = "migrate:fix-snapshot")]
+    MigrateFixSnapshot {
+        /// Path to migrations directory
+        #[arg(short, long, default_value = "migrations")]
+        dir: String,
+    },
+
+    /// Render the schema as an ER diagram in DBML (dbdiagram.io) or PlantUML
+    #[command(name = "schema:export")]
+    SchemaExport {
+        /// Diagram language: dbml or plantuml
+        #[arg(short, long, value_parser = parse_diagram_format, default_value = "dbml")]
+        format: DiagramFormat,
+
+        /// Introspect this database instead of reading .schema.json
+        #[arg(short, long)]
+        url: Option<String>,
+
+        /// Path to migrations directory holding .schema.json
+        #[arg(short, long, default_value = "migrations")]
+        dir: String,
+
+        /// Write the diagram to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Remove shadow databases left behind by crashed runs and stale files
+    /// under .toasty/. Migrations and .schema.json are never removed.
+    Clean {
+        /// Path to migrations directory, which is left untouched
+        #[arg(short, long, default_value = "migrations")]
+        dir: String,
+
+        /// Only remove files not modified for this long, e.g. 30m, 12h or 7d
+        #[arg(long, value_name = "AGE", value_parser = parse_age, default_value = "1d")]
+        older_than: Duration,
     },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    output::init(cli.color);
+    let busy_timeout = Duration::from_millis(cli.busy_timeout);
 
     match cli.command {
-        Commands::Init { dir } => cmd_init(dir).await,
+        Commands::Init { dir, init_git } => cmd_init(dir, init_git).await,
         Commands::MigrateGenerate {
             message,
             url,
             dir,
             entity_dir,
-        } => cmd_generate(message, url, dir, entity_dir).await,
-        Commands::MigrateUp { url, dir } => cmd_up(url, dir).await,
-        Commands::MigrateDown { url, count, dir } => cmd_down(url, count, dir).await,
-        Commands::MigrateStatus { url, dir } => cmd_status(url, dir).await,
+            no_auto_fk_index,
+            safe_constraints,
+            safe_add_column,
+            batch_size,
+            from_snapshot,
+            show_sql,
+            sql_down,
+            parallel,
+            amend,
+            template_dir,
+            allow_pending,
+            interactive,
+            shadow_in_memory,
+            fail_on_warnings,
+            docs,
+            allow_empty,
+        } => {
+            cmd_generate(
+                message.unwrap_or_default(),
+                url,
+                dir,
+                entity_dir,
+                no_auto_fk_index,
+                safe_constraints,
+                safe_add_column,
+                batch_size,
+                from_snapshot,
+                show_sql,
+                sql_down,
+                parallel,
+                amend,
+                template_dir,
+                allow_pending,
+                interactive,
+                shadow_in_memory,
+                fail_on_warnings,
+                docs,
+                allow_empty,
+            )
+            .await
+        }
+        Commands::MigrateUp {
+            url,
+            dir,
+            until,
+            audit,
+            dump_schema_on_apply,
+            format,
+        } => {
+            cmd_up(
+                url,
+                dir,
+                until,
+                audit,
+                dump_schema_on_apply,
+                format,
+                busy_timeout,
+            )
+            .await
+        }
+        Commands::MigrateDown {
+            url,
+            count,
+            dir,
+            format,
+        } => cmd_down(url, count, dir, format, busy_timeout).await,
+        Commands::MigrateStatus { url, dir, check } => {
+            cmd_status(url, dir, check, busy_timeout).await
+        }
         Commands::MigrateReset {
             url,
             dir,
             entity_dir,
             force,
-        } => cmd_reset(url, dir, entity_dir, force).await,
+            no_cascade,
+            diff_only,
+            expect_database,
+        } => {
+            cmd_reset(
+                url,
+                dir,
+                entity_dir,
+                force,
+                !no_cascade,
+                diff_only,
+                expect_database,
+                busy_timeout,
+            )
+            .await
+        }
+        Commands::MigrateFixSnapshot { dir } => cmd_fix_snapshot(dir).await,
+        Commands::SchemaExport {
+            format,
+            url,
+            dir,
+            output,
+        } => cmd_schema_export(format, url, dir, output).await,
+        Commands::Clean { dir, older_than } => cmd_clean(dir, older_than),
     }
 }
 
-async fn cmd_init(dir: String) -> Result<()> {
-    println!("🚀 Initializing Toasty project structure...");
-    println!("📁 Project directory: {}", dir);
-    println!();
+/// Entries Toasty adds to a project's `.gitignore`. Migrations and
+/// `.schema.json` are deliberately left tracked.
+const GITIGNORE_ENTRIES: &[&str] = &["*.db", "*.sqlite", ".toasty/"];
+
+async fn cmd_init(dir: String, init_git: bool) -> Result<()> {
+    say!("🚀 Initializing Toasty project structure...");
+    say!("📁 Project directory: {}", dir);
+    say!();
 
     let project_dir = PathBuf::from(&dir);
 
@@ -171,11 +447,16 @@ toasty = {{ workspace = true }}
 //     #[unique]
 //     pub email: String,
 // }
+//
+// List every model here so apps can register them all at once with
+// `toasty::Db::builder().register_all(entity::register_all)`:
+//
+// toasty::register_models!(User);
 
 pub use toasty;
 "#;
     std::fs::write(entity_dir.join("src/lib.rs"), entity_lib_rs)?;
-    println!("✅ Created entity crate: entity/");
+    say!("✅ Created entity crate: entity/");
 
     // Create migration directory
     let migration_dir = project_dir.join("migration");
@@ -188,7 +469,7 @@ pub use toasty;
         tables: vec![],
     };
     save_snapshot(&empty_snapshot, migration_dir.join(".schema.json"))?;
-    println!("✅ Created migration directory: migration/");
+    say!("✅ Created migration directory: migration/");
 
     // Create README
     let readme = r#"# Toasty Project
@@ -237,178 +518,859 @@ pub struct User {
 - `toasty migrate:status` - Show migration status
 "#;
     std::fs::write(project_dir.join("README.md"), readme)?;
-    println!("✅ Created README.md");
+    say!("✅ Created README.md");
 
-    println!();
-    println!("🎉 Toasty project initialized!");
+    if init_git {
+        match write_gitignore(&project_dir)? {
+            0 => say!("✅ .gitignore already up to date"),
+            added => say!("✅ Added {} entr(ies) to .gitignore", added),
+        }
+    }
+
+    say!();
+    say!("🎉 Toasty project initialized!");
 
     Ok(())
 }
 
+/// Append any missing Toasty entries to `<dir>/.gitignore`, creating it if
+/// needed. Returns the number of entries added.
+fn write_gitignore(dir: &std::path::Path) -> Result<usize> {
+    let path = dir.join(".gitignore");
+    let existing = if path.exists() {
+        std::fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+
+    let missing: Vec<&str> = GITIGNORE_ENTRIES
+        .iter()
+        .copied()
+        .filter(|entry| !existing.lines().any(|line| line.trim() == *entry))
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(0);
+    }
+
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str("# Toasty local databases and cache\n");
+    for entry in &missing {
+        content.push_str(entry);
+        content.push('\n');
+    }
+
+    std::fs::write(&path, content)?;
+    Ok(missing.len())
+}
+
 async fn cmd_generate(
     message: String,
     url: String,
     dir: String,
     entity_dir: Option<String>,
+    no_auto_fk_index: bool,
+    safe_constraints: bool,
+    safe_add_column: bool,
+    batch_size: usize,
+    from_snapshot: Option<PathBuf>,
+    show_sql: bool,
+    sql_down: bool,
+    parallel: usize,
+    amend: bool,
+    template_dir: Option<PathBuf>,
+    allow_pending: bool,
+    interactive: bool,
+    shadow_in_memory: bool,
+    fail_on_warnings: bool,
+    docs: Option<PathBuf>,
+    allow_empty: bool,
 ) -> Result<()> {
-    println!("🔍 Generating migration: {}", message);
-    println!("📁 Migration directory: {}", dir);
+    let loader = MigrationLoader::new(&dir);
+    // Amending only ever touches a pending migration
+    if !allow_pending && !amend {
+        refuse_pending(&loader, &url).await?;
+    }
+
+    let amended = if amend {
+        let latest = amendable_migration(&loader, &url).await?;
+        say!("✏️  Amending migration: {}", latest.version);
+        Some(latest)
+    } else {
+        say!("🔍 Generating migration: {}", message);
+        None
+    };
+    say!("📁 Migration directory: {}", dir);
 
     // Check if entity directory exists
     let entity_path = PathBuf::from(entity_dir.as_deref().unwrap_or("entity"));
     if entity_path.exists() {
-        println!("📦 Entity directory: {}", entity_path.display());
+        say!("📦 Entity directory: {}", entity_path.display());
     } else {
-        println!("⚠️  Entity directory not found: {}", entity_path.display());
-        println!("   Run 'toasty init' to create the project structure");
-        println!("   Or specify custom path with --entity-dir");
+        say!("⚠️  Entity directory not found: {}", entity_path.display());
+        say!("   Run 'toasty init' to create the project structure");
+        say!("   Or specify custom path with --entity-dir");
     }
-    println!();
+    say!();
 
     // Create migration directory if it doesn't exist
     let migration_dir = PathBuf::from(&dir);
     std::fs::create_dir_all(&migration_dir)?;
 
-    let loader = MigrationLoader::new(&migration_dir);
     let snapshot_path = loader.snapshot_path();
 
     // Build desired schema from entity files (what developer wants)
-    println!("📖 Building desired schema from entity files...");
-    let parser = EntityParser::new(&entity_path);
-    let desired_schema = match parser.parse_entities() {
-        Ok(snapshot) => {
-            println!("✅ Parsed {} model(s) from entities", snapshot.tables.len());
+    say!("📖 Building desired schema from entity files...");
+    let desired = EntityParser::new(&entity_path)
+        .auto_fk_index(!no_auto_fk_index)
+        .flavor(SqlFlavor::from_url(&url));
+    let desired_schema = match desired.parse_entities_with_warnings() {
+        Ok((snapshot, warnings)) => {
+            for warning in &warnings {
+                say!("⚠️  {}", warning);
+            }
+            if fail_on_warnings && !warnings.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "{} warning(s) while parsing entities (--fail-on-warnings)",
+                    warnings.len()
+                ));
+            }
+            say!("✅ Parsed {} model(s) from entities", snapshot.tables.len());
             snapshot
         }
         Err(e) => {
             return Err(anyhow::anyhow!(
-                "Failed to parse entity files: {}\nEnsure entity/src/ contains valid Toasty models", e
+                "Failed to parse entity files: {}\nEnsure entity/src/ contains valid Toasty models",
+                e
             ));
         }
     };
 
-    // Use shadow database approach (Prisma-style)
-    // Execute actual migrations in temp DB to get real current state
-    let shadow_db = ShadowDatabase::new()?;
-    let current_schema = shadow_db.apply_migrations(&migration_dir).await?;
+    // By default use the shadow database approach (Prisma-style): execute
+    // the actual migrations in a temp DB to get the real current state
+    let current: Box<dyn SchemaSource> = match from_snapshot {
+        Some(path) => {
+            say!("📄 Using schema snapshot: {}", path.display());
+            Box::new(FileSchemaSource::new(path))
+        }
+        None => Box::new(
+            ShadowSchemaSource::new(&migration_dir)
+                .parallel(parallel)
+                .excluding(amended.as_ref().map(|file| file.version.clone()))
+                .in_memory(shadow_in_memory),
+        ),
+    };
+    let current_schema = current.snapshot().await?;
 
     // Detect changes: current database state → desired entity state
-    println!();
-    println!("🔄 Comparing database vs entities...");
-    let diff = detect_changes(&current_schema, &desired_schema)?;
+    say!();
+    say!("🔄 Comparing database vs entities...");
+    let mut diff = detect_changes(&current_schema, &desired_schema)?;
+
+    // Renames confirmed in earlier runs apply without asking again
+    let renames_path = migration_dir.join(RENAMES_FILE);
+    let mut hints = RenameHints::load(&renames_path)?;
+    apply_renames(&mut diff, &hints.columns);
+
+    if interactive {
+        let confirmed = confirm_renames(rename_candidates(&diff))?;
+        if !confirmed.is_empty() {
+            apply_renames(&mut diff, &confirmed);
+            for rename in confirmed {
+                hints.add(rename);
+            }
+            hints.save(&renames_path)?;
+            say!("📝 Saved renames to {}/{}", dir, RENAMES_FILE);
+        }
+    }
 
-    if diff.changes.is_empty() {
-        println!("✅ Database matches entities - no migration needed!");
-        println!("   Your database schema is already up to date.");
+    if diff.changes.is_empty() && !allow_empty {
+        if let Some(file) = &amended {
+            say!(
+                "✅ Entities match the schema before {} - it has nothing left to do",
+                file.version
+            );
+            say!("   Delete {}/{} to drop it.", dir, file.filename);
+            return Ok(());
+        }
+
+        say!("✅ Database matches entities - no migration needed!");
+        say!("   Your database schema is already up to date.");
 
         // Save entity schema for documentation
         save_snapshot(&desired_schema, &snapshot_path)?;
-        println!("📝 Updated .schema.json for reference");
+        say!("📝 Updated .schema.json for reference");
+        save_docs(&desired_schema, docs.as_deref())?;
 
         // Don't create empty migration file
         return Ok(());
     }
 
     // Show detected changes
-    println!();
-    println!("✅ Detected {} schema change(s):", diff.changes.len());
+    say!();
+    if diff.changes.is_empty() {
+        say!("📝 No schema changes - writing an empty migration to fill in by hand");
+    } else {
+        say!("✅ Detected {} schema change(s):", diff.changes.len());
+    }
     for change in &diff.changes {
         let marker = if change.is_destructive() {
             "⚠️ "
         } else {
             "✅"
         };
-        println!("   {} {:?}", marker, change);
+        say!("   {} {:?}", marker, change);
     }
 
+    warn_reverted_pending(&loader, &url, &diff).await?;
+
     // Generate migration
-    let generator = MigrationGenerator::new(&migration_dir);
-    let migration = generator.generate(&diff, &message)?;
+    let mut generator = MigrationGenerator::new(&migration_dir).safe_constraints(safe_constraints);
+    if sql_down {
+        let flavor = SqlFlavor::from_url(&url)
+            .ok_or_else(|| anyhow::anyhow!("--sql-down only applies to SQL databases"))?;
+        generator = generator.sql_down(flavor);
+    }
+    if safe_add_column {
+        if !matches!(SqlFlavor::from_url(&url), Some(SqlFlavor::PostgreSQL)) {
+            return Err(anyhow::anyhow!(
+                "--safe-add-column only applies to PostgreSQL"
+            ));
+        }
+        generator = generator.safe_add_column(true).batch_size(batch_size);
+        match MigrationExecutor::new(url.clone())
+            .server_version_postgresql()
+            .await
+        {
+            Ok(version) => generator = generator.postgres_version(version),
+            Err(e) => {
+                say!(
+                    "⚠️  Couldn't read the PostgreSQL server version ({}); splitting every \
+                     NOT NULL column added with a default",
+                    e
+                );
+            }
+        }
+    }
+    if let Some(template_dir) = template_dir {
+        generator = generator.template_dir(template_dir);
+    }
+    let migration = match &amended {
+        Some(file) => generator.generate_version(&diff, &file.version)?,
+        None => generator.generate(&diff, &message)?,
+    };
 
     // Write migration file
     generator.write_migration_file(&migration)?;
-    println!();
-    println!("✅ Created migration file: {}/{}", dir, migration.filename);
+    say!();
+    if amended.is_some() {
+        say!("✅ Rewrote migration file: {}/{}", dir, migration.filename);
+    } else {
+        say!("✅ Created migration file: {}/{}", dir, migration.filename);
+    }
+
+    if show_sql {
+        print_migration_sql(&generator, &diff, &url)?;
+    }
 
     // Save entity schema (for documentation/reference)
     save_snapshot(&desired_schema, &snapshot_path)?;
-    println!("✅ Updated schema snapshot: {}/.schema.json", dir);
+    say!("✅ Updated schema snapshot: {}/.schema.json", dir);
+    save_docs(&desired_schema, docs.as_deref())?;
 
-    println!();
-    println!(
+    say!();
+    say!(
         "   - Review the generated migration: {}/{}",
-        dir, migration.filename
+        dir,
+        migration.filename
     );
-    println!("   - Apply with: toasty migrate:up --url <database-url>");
+    say!("   - Apply with: toasty migrate:up --url <database-url>");
+
+    Ok(())
+}
+
+/// Write `schema` as Markdown to `path`, if one was given with --docs
+fn save_docs(schema: &SchemaSnapshot, path: Option<&Path>) -> Result<()> {
+    if let Some(path) = path {
+        std::fs::write(path, schema.to_markdown())
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        say!("📝 Updated schema docs: {}", path.display());
+    }
+    Ok(())
+}
+
+/// Ask on stdin whether each candidate is a rename. A column is renamed at
+/// most once, so pairs involving an already confirmed column are skipped.
+fn confirm_renames(candidates: Vec<ColumnRename>) -> Result<Vec<ColumnRename>> {
+    use std::io::{self, BufRead, Write};
+
+    let mut confirmed: Vec<ColumnRename> = Vec::new();
+    let mut answers = io::stdin().lock().lines();
+
+    for candidate in candidates {
+        let taken = confirmed.iter().any(|rename| {
+            rename.table == candidate.table
+                && (rename.from == candidate.from || rename.to == candidate.to)
+        });
+        if taken {
+            continue;
+        }
+
+        print!(
+            "{}",
+            output::render(&format!(
+                "❓ Did you rename {}.{} to {}? [y/N]: ",
+                candidate.table, candidate.from, candidate.to
+            ))
+        );
+        io::stdout().flush()?;
+
+        let answer = answers.next().transpose()?.unwrap_or_default();
+        if answer.trim().eq_ignore_ascii_case("y") {
+            confirmed.push(candidate);
+        }
+    }
+
+    Ok(confirmed)
+}
+
+/// The latest migration, provided it has not been applied to the database at
+/// `url`. Applied migrations are never rewritten.
+async fn amendable_migration(loader: &MigrationLoader, url: &str) -> Result<MigrationFileInfo> {
+    let latest = loader
+        .discover_migrations()?
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("No migrations to amend"))?;
+
+    let applied = MigrationExecutor::new(url.to_string())
+        .applied_versions()
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Cannot check whether {} has been applied: {}",
+                latest.version,
+                e
+            )
+        })?;
+
+    if applied
+        .iter()
+        .any(|(version, _)| *version == latest.version)
+    {
+        return Err(anyhow::anyhow!(
+            "Migration {} has already been applied and can't be amended; generate a new migration instead",
+            latest.version
+        ));
+    }
+
+    Ok(latest)
+}
+
+/// Fail when the database at `url` hasn't applied every migration file yet.
+/// A migration generated now would be based on migrations the database
+/// doesn't reflect. Nothing is checked when the database can't be read.
+async fn refuse_pending(loader: &MigrationLoader, url: &str) -> Result<()> {
+    let applied: Vec<String> = match MigrationExecutor::new(url.to_string())
+        .applied_versions()
+        .await
+    {
+        Ok(applied) => applied.into_iter().map(|(version, _)| version).collect(),
+        Err(e) => {
+            say!("⚠️  Couldn't check for pending migrations: {}", e);
+            return Ok(());
+        }
+    };
+
+    let pending: Vec<String> = loader
+        .discover_migrations()?
+        .into_iter()
+        .map(|file| file.version)
+        .filter(|version| !applied.contains(version))
+        .collect();
+
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    Err(anyhow::anyhow!(
+        "{} migration(s) not applied to {} yet: {}\n\
+         Run `toasty migrate:up` first, or pass --allow-pending to generate from the migration files anyway",
+        pending.len(),
+        toasty_core::driver::redact_url(url),
+        pending.join(", ")
+    ))
+}
+
+/// Warn when the new changes undo something a pending (not yet applied)
+/// migration does, since those migrations are better squashed. Migrations
+/// are all treated as applied when the database can't be read.
+async fn warn_reverted_pending(
+    loader: &MigrationLoader,
+    url: &str,
+    diff: &SchemaDiff,
+) -> Result<()> {
+    let applied: Vec<String> = match MigrationExecutor::new(url.to_string())
+        .applied_versions()
+        .await
+    {
+        Ok(applied) => applied.into_iter().map(|(version, _)| version).collect(),
+        Err(_) => return Ok(()),
+    };
+
+    let registry = loader.collect_migrations()?;
+    for file in loader.discover_migrations()? {
+        if applied.contains(&file.version) {
+            continue;
+        }
+
+        let Some(migration) = registry.iter().find(|m| m.version() == file.version) else {
+            continue;
+        };
+        let reverted = reverted_changes(migration, diff)?;
+        if reverted.is_empty() {
+            continue;
+        }
+
+        say!();
+        say!(
+            "⚠️  This migration undoes changes from pending migration {}:",
+            file.version
+        );
+        for what in &reverted {
+            say!("   - {}", what);
+        }
+        say!(
+            "   Consider squashing them: delete {} and generate again.",
+            file.filename
+        );
+    }
 
     Ok(())
 }
 
-async fn cmd_up(_url: String, _dir: String) -> Result<()> {
-    println!("⬆️  Running migrations...");
-    println!();
-
-    println!("⚠️  Note: Migration execution requires database connection");
-    println!("   The migration runner is fully implemented in toasty-migrate");
-    println!();
-    println!("Example usage:");
-    println!("```rust");
-    println!("let mut tracker = MigrationTracker::new();");
-    println!("let mut runner = MigrationRunner::new(tracker);");
-    println!("runner.initialize().await?;");
-    println!();
-    println!("let loader = MigrationLoader::new(\"migrations\");");
-    println!("let migration_files = loader.discover_migrations()?;");
-    println!("let migrations: Vec<Box<dyn Migration>> = load_migrations(migration_files);");
-    println!();
-    println!("let mut context = SqlMigrationContext::new(SqlFlavor::Sqlite);");
-    println!("runner.run_pending(migrations, &mut context).await?;");
-    println!("```");
+/// Print the SQL a generated migration runs, by replaying its operations
+/// through a `SqlMigrationContext` for the target database
+fn print_migration_sql(generator: &MigrationGenerator, diff: &SchemaDiff, url: &str) -> Result<()> {
+    let Some(flavor) = SqlFlavor::from_url(url) else {
+        say!();
+        say!("⚠️  --show-sql only applies to SQL databases");
+        return Ok(());
+    };
+
+    let mut context = SqlMigrationContext::new(flavor);
+    generator.apply(diff, &mut context)?;
+
+    say!();
+    say!("📜 SQL ({:?}):", flavor);
+    for statement in context.statements() {
+        say!("{}", statement);
+    }
 
     Ok(())
 }
 
-async fn cmd_down(_url: String, _count: usize, _dir: String) -> Result<()> {
-    println!("⬇️  Rolling back migrations...");
-    println!();
+/// Validate a `--until` cutoff against the migration version timestamp format
+fn parse_cutoff(value: &str) -> Result<String, String> {
+    chrono::NaiveDateTime::parse_from_str(value, "%Y%m%d_%H%M%S")
+        .map(|_| value.to_string())
+        .map_err(|_| format!("expected a YYYYMMDD_HHMMSS timestamp, got `{}`", value))
+}
 
-    println!("⚠️  Note: Migration rollback requires database connection");
-    println!("   The rollback logic is fully implemented in toasty-migrate");
-    println!();
-    println!("Example usage:");
-    println!("```rust");
-    println!("let mut runner = MigrationRunner::new(tracker);");
-    println!("let mut context = SqlMigrationContext::new(SqlFlavor::Sqlite);");
-    println!("runner.rollback(count, migrations, &mut context).await?;");
-    println!("```");
+/// Where `--dump-schema-on-apply` saves snapshots: `.toasty/history` in the
+/// project holding the migrations directory `dir`, rather than in whatever
+/// directory the command runs from
+fn schema_history_dir(dir: &str) -> PathBuf {
+    Path::new(dir)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(SCHEMA_HISTORY_DIR)
+}
+
+async fn cmd_up(
+    url: String,
+    dir: String,
+    until: Option<String>,
+    audit: bool,
+    dump_schema_on_apply: bool,
+    format: output::ReportFormat,
+    busy_timeout: Duration,
+) -> Result<()> {
+    let started = Instant::now();
+    output::set_quiet(format == output::ReportFormat::Json);
+
+    say!("⬆️  Running migrations...");
+    say!();
+
+    if SqlFlavor::from_url(&url).is_none() {
+        return Err(anyhow::anyhow!("migrate:up only applies to SQL databases"));
+    }
+
+    let migrations = MigrationLoader::new(PathBuf::from(&dir)).load_migrations()?;
+
+    let executor = MigrationExecutor::new(url.clone()).busy_timeout(busy_timeout);
+    let mut db = executor.connect().await?;
+
+    // Collected through the hooks rather than from the run's report, so a
+    // run that fails partway can still say which migrations committed
+    let applied = Arc::new(Mutex::new(Vec::new()));
+    let migration_started = Arc::new(Mutex::new(Instant::now()));
+
+    // Each migration commits together with its tracking row
+    let mut runner = MigrationRunner::new(MigrationTracker::new())
+        .quiet()
+        .on_before({
+            let migration_started = migration_started.clone();
+            move |version| {
+                say!("Applying migration: {}", version);
+                *migration_started.lock().unwrap() = Instant::now();
+            }
+        })
+        .on_after({
+            let applied = applied.clone();
+            move |version| {
+                applied.lock().unwrap().push(AppliedMigration {
+                    version: version.to_string(),
+                    duration: migration_started.lock().unwrap().elapsed(),
+                })
+            }
+        });
+    if let Some(cutoff) = &until {
+        runner = runner.until(cutoff.clone());
+        say!("⏱️  Applying migrations up to {}", cutoff);
+    }
+    if audit {
+        runner = runner.audit(url.clone());
+    }
+    if dump_schema_on_apply {
+        runner = runner.dump_schema(url.clone(), schema_history_dir(&dir));
+    }
+
+    runner.initialize(&mut *db).await?;
+    let deferred = runner.deferred(&migrations);
+    let result = runner.run_pending_live(migrations, &mut *db).await;
+    let applied = std::mem::take(&mut *applied.lock().unwrap());
+
+    if format == output::ReportFormat::Json {
+        let timings: Vec<_> = applied
+            .iter()
+            .map(|migration| {
+                serde_json::json!({
+                    "version": migration.version,
+                    "duration_ms": migration.duration.as_millis() as u64,
+                })
+            })
+            .collect();
+        let mut report = serde_json::json!({
+            "applied": applied.iter().map(|m| m.version.as_str()).collect::<Vec<_>>(),
+            "deferred": deferred,
+            "timings": timings,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        });
+        // The migrations before the failing one have committed
+        if let Err(e) = &result {
+            report["error"] = format!("{:#}", e).into();
+        }
+        println!("{}", report);
+        return result.map(|_| ());
+    }
+    result?;
+
+    say!();
+    if applied.is_empty() {
+        say!("✅ No pending migrations");
+    } else {
+        say!("✅ Applied {} migration(s):", applied.len());
+        for migration in &applied {
+            say!("   - {}", migration.version);
+        }
+    }
+    if !deferred.is_empty() {
+        say!(
+            "   {} migration(s) after the cutoff stay pending",
+            deferred.len()
+        );
+    }
 
     Ok(())
 }
 
-async fn cmd_status(_url: String, dir: String) -> Result<()> {
-    println!("📊 Migration Status");
-    println!("📁 Migration directory: {}", dir);
-    println!();
+async fn cmd_down(
+    url: String,
+    count: usize,
+    dir: String,
+    format: output::ReportFormat,
+    busy_timeout: Duration,
+) -> Result<()> {
+    let started = Instant::now();
+    output::set_quiet(format == output::ReportFormat::Json);
+
+    say!("⬇️  Rolling back migrations...");
+    say!();
+
+    if SqlFlavor::from_url(&url).is_none() {
+        return Err(anyhow::anyhow!(
+            "migrate:down only applies to SQL databases"
+        ));
+    }
+
+    let executor = MigrationExecutor::new(url).busy_timeout(busy_timeout);
+    let mut db = executor.connect().await?;
+
+    let rolled_back = Arc::new(Mutex::new(Vec::new()));
+    let mut runner = MigrationRunner::new(MigrationTracker::new())
+        .quiet()
+        .on_before(|version| say!("Rolling back migration: {}", version))
+        .on_after({
+            let rolled_back = rolled_back.clone();
+            move |version| rolled_back.lock().unwrap().push(version.to_string())
+        });
+    runner.initialize(&mut *db).await?;
+
+    let applied = runner.tracker().applied_migrations();
+    let to_roll_back: Vec<&str> = applied
+        .iter()
+        .rev()
+        .take(count)
+        .map(String::as_str)
+        .collect();
+
+    if to_roll_back.is_empty() {
+        if format == output::ReportFormat::Json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "rolled_back": to_roll_back,
+                    "duration_ms": started.elapsed().as_millis() as u64,
+                })
+            );
+        }
+        say!("✅ No migrations to roll back");
+        return Ok(());
+    }
+
+    // Check every file is still there before touching the database, so a
+    // missing one doesn't leave the rollback half done
+    let migrations = MigrationLoader::new(PathBuf::from(&dir)).load_migrations()?;
+    for version in &to_roll_back {
+        if !migrations
+            .iter()
+            .any(|migration| migration.version() == *version)
+        {
+            return Err(anyhow::anyhow!(
+                "Migration {} is applied but its file is no longer in {}",
+                version,
+                dir
+            ));
+        }
+    }
+
+    // Each rollback commits together with the removal of its tracking row
+    let result = runner
+        .rollback_live(to_roll_back.len(), migrations, &mut *db)
+        .await;
+
+    if format == output::ReportFormat::Json {
+        let mut report = serde_json::json!({
+            "rolled_back": *rolled_back.lock().unwrap(),
+            "duration_ms": started.elapsed().as_millis() as u64,
+        });
+        // The rollbacks before the failing one have committed
+        if let Err(e) = &result {
+            report["error"] = format!("{:#}", e).into();
+        }
+        println!("{}", report);
+        return result.map(|_| ());
+    }
+    result?;
+
+    say!();
+    say!("✅ Rolled back {} migration(s):", to_roll_back.len());
+    for version in &to_roll_back {
+        say!("   - {}", version);
+    }
+
+    Ok(())
+}
+
+async fn cmd_status(url: String, dir: String, check: bool, busy_timeout: Duration) -> Result<()> {
+    if check {
+        return cmd_status_check(url, dir, busy_timeout).await;
+    }
+
+    say!("📊 Migration Status");
+    say!("📁 Migration directory: {}", dir);
+    say!();
 
     let loader = MigrationLoader::new(PathBuf::from(&dir));
     let migration_files = loader.discover_migrations()?;
 
     if migration_files.is_empty() {
-        println!("No migrations found in {}", dir);
+        say!("No migrations found in {}", dir);
         return Ok(());
     }
 
-    println!("Found {} migration file(s):\n", migration_files.len());
-    println!("Version                      | Filename");
-    println!("---------------------------- | --------");
+    // A database that was never migrated has no tracking table yet, and
+    // everything is pending
+    let executor = MigrationExecutor::new(url).busy_timeout(busy_timeout);
+    let applied: std::collections::HashMap<String, String> =
+        executor.applied_versions().await?.into_iter().collect();
+
+    say!("Found {} migration file(s):\n", migration_files.len());
+    say!("Version                      | Status  | Applied at          | Filename");
+    say!("---------------------------- | ------- | ------------------- | --------");
 
     for file in &migration_files {
-        println!("{:28} | {}", file.version, file.filename);
+        let (status, applied_at) = match applied.get(&file.version) {
+            Some(applied_at) => ("Applied", applied_at.as_str()),
+            None => ("Pending", ""),
+        };
+        say!(
+            "{:28} | {:7} | {:19} | {}",
+            file.version,
+            status,
+            applied_at,
+            file.filename
+        );
     }
 
-    println!();
-    println!("⚠️  Note: Applied/pending status requires database connection");
-    println!("   Migration tracking is fully implemented in toasty-migrate");
+    let pending = migration_files
+        .iter()
+        .filter(|file| !applied.contains_key(&file.version))
+        .count();
+
+    say!();
+    say!(
+        "{} applied, {} pending",
+        migration_files.len() - pending,
+        pending
+    );
+
+    // Recorded as applied, but nothing in the directory to roll back with
+    let mut missing: Vec<_> = applied
+        .keys()
+        .filter(|version| !migration_files.iter().any(|file| &file.version == *version))
+        .collect();
+    if !missing.is_empty() {
+        missing.sort();
+        say!("⚠️  Applied but missing from {}:", dir);
+        for version in missing {
+            say!("   - {}", version);
+        }
+    }
 
     Ok(())
 }
+
+/// Replace `.schema.json` with the schema the migrations actually produce.
+/// Only a shadow database is touched, never the real one.
+async fn cmd_fix_snapshot(dir: String) -> Result<()> {
+    say!("🩹 Rebuilding schema snapshot from migrations");
+    say!("📁 Migration directory: {}", dir);
+    say!();
+
+    let migration_dir = PathBuf::from(&dir);
+    if !migration_dir.is_dir() {
+        return Err(anyhow::anyhow!("Migration directory not found: {}", dir));
+    }
+
+    let snapshot_path = MigrationLoader::new(&migration_dir).snapshot_path();
+    let replayed = ShadowSchemaSource::new(&migration_dir).snapshot().await?;
+
+    let unchanged = match load_snapshot(&snapshot_path) {
+        Ok(existing) => detect_changes(&existing, &replayed)?.changes.is_empty(),
+        Err(_) => false,
+    };
+
+    save_snapshot(&replayed, &snapshot_path)?;
+
+    say!();
+    if unchanged {
+        say!("✅ {}/.schema.json already matched the migrations", dir);
+    } else {
+        say!(
+            "✅ Rewrote {}/.schema.json with {} table(s) from the migrations",
+            dir,
+            replayed.tables.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn parse_diagram_format(value: &str) -> Result<DiagramFormat, String> {
+    DiagramFormat::from_name(value)
+        .ok_or_else(|| format!("expected dbml or plantuml, got `{}`", value))
+}
+
+/// Print the schema as an ER diagram. The diagram alone goes to stdout so it
+/// can be piped into a renderer.
+async fn cmd_schema_export(
+    format: DiagramFormat,
+    url: Option<String>,
+    dir: String,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let snapshot = match url {
+        Some(url) => SqlIntrospector::new(url).introspect_schema().await?,
+        None => {
+            let snapshot_path = MigrationLoader::new(PathBuf::from(&dir)).snapshot_path();
+            load_snapshot(&snapshot_path)
+                .map_err(|e| anyhow::anyhow!("Couldn't read {}: {}", snapshot_path.display(), e))?
+        }
+    };
+
+    let diagram = snapshot.to_diagram(format);
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, diagram)?;
+            say!(
+                "✅ Wrote {} table(s) to {}",
+                snapshot.tables.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", diagram),
+    }
+
+    Ok(())
+}
+
+/// Compare discovered migrations against the database and exit non-zero if
+/// any are pending. Intended for gating deploys.
+async fn cmd_status_check(url: String, dir: String, busy_timeout: Duration) -> Result<()> {
+    let loader = MigrationLoader::new(PathBuf::from(&dir));
+    let migration_files = loader.discover_migrations()?;
+
+    let executor = MigrationExecutor::new(url).busy_timeout(busy_timeout);
+    let applied: std::collections::HashSet<String> = executor
+        .applied_versions()
+        .await?
+        .into_iter()
+        .map(|(version, _)| version)
+        .collect();
+
+    let pending: Vec<_> = migration_files
+        .iter()
+        .filter(|file| !applied.contains(&file.version))
+        .collect();
+
+    say!(
+        "{} applied, {} pending",
+        migration_files.len() - pending.len(),
+        pending.len()
+    );
+
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    for file in &pending {
+        say!("  pending: {}", file.version);
+    }
+
+    std::process::exit(1);
+}
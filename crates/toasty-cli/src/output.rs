@@ -0,0 +1,100 @@
+use std::borrow::Cow;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Print a line of status output, with the emoji markers swapped for ASCII
+/// ones when plain output is on
+macro_rules! say {
+    () => {
+        if !$crate::output::is_quiet() {
+            println!()
+        }
+    };
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            println!("{}", $crate::output::render(&format!($($arg)*)))
+        }
+    };
+}
+
+/// When to decorate output with emoji markers
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Emoji on a terminal, unless `NO_COLOR` is set
+    Auto,
+    Always,
+    Never,
+}
+
+/// How `migrate:up` and `migrate:down` report what they did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Status lines for a person to read
+    Text,
+    /// A single JSON object, for deploy tooling
+    Json,
+}
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Emoji markers and their ASCII replacements. Anything not listed is
+/// informational.
+const MARKERS: &[(&str, &str)] = &[
+    ("✅", "[ok]"),
+    ("⚠", "[warn]"),
+    ("🗑", "[drop]"),
+    ("❌", "[error]"),
+    ("❓", "[?]"),
+    ("⬆", "[up]"),
+    ("⬇", "[down]"),
+    ("🎉", "[done]"),
+];
+
+const INFO_MARKERS: &[&str] = &[
+    "📁", "🔍", "🔄", "📖", "📝", "🔌", "🚀", "✏", "📦", "📄", "📜", "⏱", "📊", "🩹", "🗄", "📂",
+    "📋", "🎯", "📸",
+];
+
+/// Decide whether output is plain for the rest of the run. `NO_COLOR` only
+/// needs to be present and non-empty, per <https://no-color.org>.
+pub fn init(choice: ColorChoice) {
+    let plain = match choice {
+        ColorChoice::Always => false,
+        ColorChoice::Never => true,
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+                || !std::io::stdout().is_terminal()
+        }
+    };
+
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+/// `text` with its emoji markers replaced when output is plain
+pub fn render(text: &str) -> Cow<'_, str> {
+    if !PLAIN.load(Ordering::Relaxed) {
+        return Cow::Borrowed(text);
+    }
+
+    // Markers are listed without the emoji variation selector
+    let mut text = text.replace('\u{fe0f}', "");
+    for (emoji, ascii) in MARKERS {
+        text = text.replace(emoji, ascii);
+    }
+    for emoji in INFO_MARKERS {
+        text = text.replace(emoji, "[info]");
+    }
+
+    Cow::Owned(text)
+}
+
+/// Stop printing status lines, so stdout holds only a report meant for
+/// another program
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
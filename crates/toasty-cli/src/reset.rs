@@ -1,26 +1,43 @@
+use crate::executor::MigrationExecutor;
 use anyhow::Result;
 use std::path::PathBuf;
-use toasty_migrate::*;
-use crate::executor::MigrationExecutor;
+use std::time::Duration;
+use toasty_core::driver::redact_url;
+use toasty_migrate::prelude::*;
+use toasty_migrate::{detect_changes, EntityParser, MigrationLoader, SqlIntrospector};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_reset(
     url: String,
     dir: String,
     entity_dir: Option<String>,
     force: bool,
+    cascade: bool,
+    diff_only: bool,
+    expect_database: Option<String>,
+    busy_timeout: Duration,
 ) -> Result<()> {
-    println!("🔄 Database Reset");
-    println!("📁 Migration directory: {}", dir);
-    println!("🗄️  Database: {}", url);
-    println!();
+    say!("🔄 Database Reset");
+    say!("📁 Migration directory: {}", dir);
+    say!("🗄️  Database: {}", redact_url(&url));
+    say!();
+
+    if diff_only {
+        return show_drift(&url, entity_dir).await;
+    }
+
+    // Use executor to actually drop tables
+    let executor = MigrationExecutor::new(url.clone()).busy_timeout(busy_timeout);
+
+    check_target(&executor, expect_database.as_deref()).await?;
 
     // Confirm destructive operation
     if !force {
-        println!("⚠️  WARNING: This will DROP ALL TABLES and rerun all migrations!");
-        println!("   All data will be lost.");
-        println!();
-        println!("   Use --force to skip this confirmation");
-        println!();
+        say!("⚠️  WARNING: This will DROP ALL TABLES and rerun all migrations!");
+        say!("   All data will be lost.");
+        say!();
+        say!("   Use --force to skip this confirmation");
+        say!();
         print!("   Continue? [y/N]: ");
 
         use std::io::{self, Write};
@@ -30,82 +47,106 @@ pub async fn cmd_reset(
         io::stdin().read_line(&mut input)?;
 
         if !input.trim().eq_ignore_ascii_case("y") {
-            println!("❌ Reset cancelled");
+            say!("❌ Reset cancelled");
             return Ok(());
         }
     }
 
-    println!("🗑️  Step 1: Dropping all tables...");
-
-    // Use executor to actually drop tables
-    let executor = MigrationExecutor::new(url.clone());
+    say!("🗑️  Step 1: Dropping all tables...");
 
     let dropped = if url.starts_with("postgresql") || url.starts_with("postgres") {
         #[cfg(feature = "postgresql")]
-        { executor.drop_all_tables_postgresql().await? }
+        {
+            executor.drop_all_tables_postgresql(cascade).await?
+        }
         #[cfg(not(feature = "postgresql"))]
-        { return Err(anyhow::anyhow!("PostgreSQL feature not enabled")); }
+        {
+            return Err(anyhow::anyhow!("PostgreSQL feature not enabled"));
+        }
     } else if url.starts_with("sqlite") {
         #[cfg(feature = "sqlite")]
-        { executor.drop_all_tables_sqlite().await? }
+        {
+            executor.drop_all_tables_sqlite().await?
+        }
         #[cfg(not(feature = "sqlite"))]
-        { return Err(anyhow::anyhow!("SQLite feature not enabled")); }
+        {
+            return Err(anyhow::anyhow!("SQLite feature not enabled"));
+        }
     } else {
         return Err(anyhow::anyhow!("Unsupported database type"));
     };
 
-    println!("✅ Dropped {} table(s)", dropped);
-    println!();
+    say!("✅ Dropped {} table(s)", dropped);
+    say!();
 
-    println!("📂 Step 2: Loading migration files...");
+    say!("📂 Step 2: Loading migration files...");
 
     let loader = MigrationLoader::new(PathBuf::from(&dir));
     let migration_files = loader.discover_migrations()?;
 
     if migration_files.is_empty() {
-        println!("⚠️  No migration files found in {}", dir);
-        println!("   Run 'toasty migrate:generate' to create initial migration");
+        say!("⚠️  No migration files found in {}", dir);
+        say!("   Run 'toasty migrate:generate' to create initial migration");
         return Ok(());
     }
 
-    println!("   Found {} migration(s)", migration_files.len());
+    say!("   Found {} migration(s)", migration_files.len());
     for file in &migration_files {
-        println!("   - {}", file.version);
+        say!("   - {}", file.version);
     }
-    println!();
+    say!();
 
-    println!("⬆️  Step 3: Recreating schema from entities...");
+    say!("⬆️  Step 3: Recreating schema from entities...");
 
     // Parse entities to get desired schema
     let entity_path = PathBuf::from(entity_dir.unwrap_or_else(|| "entity".to_string()));
-    let parser = EntityParser::new(&entity_path);
+    let parser = EntityParser::new(&entity_path).flavor(SqlFlavor::from_url(&url));
     let desired_schema = parser.parse_entities()?;
 
-    println!("   Creating {} table(s)", desired_schema.tables.len());
+    say!("   Creating {} table(s)", desired_schema.tables.len());
 
     // Generate and execute SQL
     let mut context = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
 
     for table in &desired_schema.tables {
-        let columns: Vec<ColumnDef> = table.columns.iter().map(|col| {
-            ColumnDef {
-                name: col.name.clone(),
-                ty: col.ty.clone(),
-                nullable: col.nullable,
-                default: if col.nullable { None } else { Some("''".to_string()) },
-            }
-        }).collect();
+        let columns: Vec<ColumnDef> = table
+            .columns
+            .iter()
+            .map(|col| {
+                let primary_key = table.primary_key.contains(&col.name);
+                ColumnDef {
+                    name: col.name.clone(),
+                    ty: col.ty.clone(),
+                    nullable: col.nullable,
+                    default: if col.nullable || col.generated.is_some() || primary_key {
+                        None
+                    } else {
+                        Some("''".to_string())
+                    },
+                    generated: col.generated.clone(),
+                    stored: col.stored,
+                    collation: col.collation.clone(),
+                    primary_key,
+                    references: None,
+                }
+            })
+            .collect();
 
         context.create_table(&table.name, columns)?;
 
         // Create indexes
         for index in &table.indices {
             if !index.primary_key && !index.columns.is_empty() {
-                context.create_index(&table.name, IndexDef {
-                    name: index.name.clone(),
-                    columns: index.columns.clone(),
-                    unique: index.unique,
-                })?;
+                context.create_index(
+                    &table.name,
+                    IndexDef {
+                        name: index.name.clone(),
+                        columns: index.columns.clone(),
+                        unique: index.unique,
+                        include: index.include.clone(),
+                        predicate: index.predicate.clone(),
+                    },
+                )?;
             }
         }
     }
@@ -113,21 +154,97 @@ pub async fn cmd_reset(
     // Execute the SQL statements
     if url.starts_with("postgresql") || url.starts_with("postgres") {
         #[cfg(feature = "postgresql")]
-        { executor.execute_postgresql(&context).await?; }
+        {
+            executor.execute_postgresql(&context).await?;
+        }
         #[cfg(not(feature = "postgresql"))]
-        { return Err(anyhow::anyhow!("PostgreSQL feature not enabled")); }
+        {
+            return Err(anyhow::anyhow!("PostgreSQL feature not enabled"));
+        }
     } else if url.starts_with("sqlite") {
         #[cfg(feature = "sqlite")]
-        { executor.execute_sqlite(&context).await?; }
+        {
+            executor.execute_sqlite(&context).await?;
+        }
         #[cfg(not(feature = "sqlite"))]
-        { return Err(anyhow::anyhow!("SQLite feature not enabled")); }
+        {
+            return Err(anyhow::anyhow!("SQLite feature not enabled"));
+        }
+    }
+
+    say!();
+    say!("✅ Reset complete!");
+    say!("   ✅ Dropped {} table(s)", dropped);
+    say!("   ✅ Created {} table(s)", desired_schema.tables.len());
+    say!("   ✅ Database schema matches entities");
+
+    Ok(())
+}
+
+/// Print how the live database differs from the entities, without dropping
+/// or recreating anything
+async fn show_drift(url: &str, entity_dir: Option<String>) -> Result<()> {
+    say!("🔍 Comparing live database against entities (--diff-only)...");
+
+    let current_schema = SqlIntrospector::new(url.to_string())
+        .introspect_schema()
+        .await?;
+
+    let entity_path = PathBuf::from(entity_dir.unwrap_or_else(|| "entity".to_string()));
+    let desired_schema = EntityParser::new(&entity_path)
+        .flavor(SqlFlavor::from_url(url))
+        .parse_entities()?;
+
+    let diff = detect_changes(&current_schema, &desired_schema)?;
+
+    say!();
+    if diff.changes.is_empty() {
+        say!("✅ Database matches entities - a reset would change nothing");
+    } else {
+        say!(
+            "📋 A reset would apply {} schema change(s):",
+            diff.changes.len()
+        );
+        for change in &diff.changes {
+            let marker = if change.is_destructive() {
+                "⚠️ "
+            } else {
+                "✅"
+            };
+            say!("   {} {:?}", marker, change);
+        }
     }
 
-    println!();
-    println!("✅ Reset complete!");
-    println!("   ✅ Dropped {} table(s)", dropped);
-    println!("   ✅ Created {} table(s)", desired_schema.tables.len());
-    println!("   ✅ Database schema matches entities");
+    say!();
+    say!("   No tables were dropped (--diff-only)");
+
+    Ok(())
+}
+
+/// Show which database the URL really points at and, when `expected` is
+/// given, refuse to continue unless it is that database. SQLite files match
+/// by file name with or without the extension.
+async fn check_target(executor: &MigrationExecutor, expected: Option<&str>) -> Result<()> {
+    let (database, host) = executor.connected_database().await?;
+    say!("🎯 Target: database '{}' on {}", database, host);
+    say!();
+
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+
+    let stem = std::path::Path::new(&database)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string());
+
+    if database != expected && stem.as_deref() != Some(expected) {
+        return Err(anyhow::anyhow!(
+            "Refusing to continue: connected to database '{}' on {}, but --expect-database is '{}'",
+            database,
+            host,
+            expected
+        ));
+    }
 
     Ok(())
 }
@@ -1,55 +1,109 @@
-use anyhow::Result;
-use toasty_migrate::*;
 use crate::executor::MigrationExecutor;
-use std::path::Path;
-
-/// Extract a quoted string from a line after a prefix
-fn extract_quoted_string(line: &str, after: &str) -> Option<String> {
-    let start = if after.is_empty() {
-        line.find('"')?
-    } else {
-        line.find(after)? + after.len()
-    };
-
-    let remaining = &line[start..];
-    let end = remaining.find('"')?;
-
-    Some(remaining[..end].to_string())
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use toasty_migrate::prelude::*;
+use toasty_migrate::{MigrationLoader, SchemaChange, SchemaDiff, SchemaSource, SqlIntrospector};
+
+/// Stand in for collations SQLite doesn't have (e.g. MySQL's
+/// `utf8mb4_unicode_ci`) so migrations using them can still be replayed.
+/// The shadow database is only introspected, so the ordering doesn't matter.
+#[cfg(feature = "sqlite")]
+fn register_collation(conn: &rusqlite::Connection, name: &str) -> rusqlite::Result<()> {
+    conn.create_collation(name, |a, b| a.cmp(b))
 }
 
+/// Name prefix of shadow database temp files, so `toasty clean` can find
+/// the ones a crashed run left behind
+pub const SHADOW_FILE_PREFIX: &str = "toasty-shadow-";
+
 /// Shadow database for migration diff calculation
-/// 
+///
 /// Creates a temporary database, applies all existing migrations to it,
 /// then introspects to get the "current state after all migrations".
 /// This is compared with desired entity schema to generate only new changes.
 pub struct ShadowDatabase {
     url: String,
     temp_file: Option<tempfile::NamedTempFile>,
+    /// Keeps an in-memory shadow database alive; it is gone once its last
+    /// connection closes
+    #[cfg(feature = "sqlite")]
+    memory: Option<std::sync::Mutex<rusqlite::Connection>>,
+    parallel: usize,
+    excluding: Option<String>,
 }
 
 impl ShadowDatabase {
     /// Create a new shadow database
     pub fn new() -> Result<Self> {
         // Create temporary SQLite database
-        let temp_file = tempfile::NamedTempFile::new()?;
+        let temp_file = tempfile::Builder::new()
+            .prefix(SHADOW_FILE_PREFIX)
+            .suffix(".db")
+            .tempfile()?;
         let shadow_url = format!("sqlite:{}", temp_file.path().display());
 
         Ok(Self {
             url: shadow_url,
             temp_file: Some(temp_file),
+            #[cfg(feature = "sqlite")]
+            memory: None,
+            parallel: 1,
+            excluding: None,
         })
     }
 
+    /// Create a shadow database that lives entirely in memory. It uses
+    /// SQLite's shared cache, so other connections in this process can open
+    /// the same database through [`url`](Self::url) while it is alive.
+    #[cfg(feature = "sqlite")]
+    pub fn in_memory() -> Result<Self> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let uri = format!(
+            "file:toasty_shadow_{}_{}?mode=memory&cache=shared",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        let conn = rusqlite::Connection::open(&uri)?;
+        conn.collation_needed(register_collation)?;
+
+        Ok(Self {
+            url: format!("sqlite:{}", uri),
+            temp_file: None,
+            memory: Some(std::sync::Mutex::new(conn)),
+            parallel: 1,
+            excluding: None,
+        })
+    }
+
+    /// Introspect up to `n` tables of the shadow database concurrently
+    pub fn parallel(mut self, n: usize) -> Self {
+        self.parallel = n;
+        self
+    }
+
+    /// Skip the migration with this version when replaying
+    pub fn excluding(mut self, version: Option<String>) -> Self {
+        self.excluding = version;
+        self
+    }
+
     /// Apply all migrations from directory to shadow database
     pub async fn apply_migrations(&self, migration_dir: &Path) -> Result<SchemaSnapshot> {
-        println!("🔄 Creating shadow database...");
+        say!("🔄 Creating shadow database...");
 
         // Load all migration files
         let loader = MigrationLoader::new(migration_dir);
-        let migration_files = loader.discover_migrations()?;
-
-        if migration_files.is_empty() {
-            println!("   No existing migrations - empty schema");
+        let registry = loader.collect_migrations()?;
+        let migrations: Vec<_> = registry
+            .iter()
+            .filter(|migration| Some(migration.version()) != self.excluding.as_deref())
+            .collect();
+
+        if migrations.is_empty() {
+            say!("   No existing migrations - empty schema");
             return Ok(SchemaSnapshot {
                 version: "1.0".to_string(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
@@ -57,258 +111,413 @@ impl ShadowDatabase {
             });
         }
 
-        println!("   Applying {} migration(s) to shadow database", migration_files.len());
-
-        // REAL SHADOW DATABASE APPROACH:
-        // We need to execute the actual SQL from each migration
-        // Since migration files are .rs code, we need to:
-        // 1. Generate SQL from each migration's operations
-        // 2. Execute that SQL in the shadow DB
-        // 3. Introspect to get real state
+        say!(
+            "   Applying {} migration(s) to shadow database",
+            migrations.len()
+        );
 
-        // Parse each migration file to extract SQL operations
+        // Run each migration's up() and execute the statements it builds,
+        // then introspect the shadow database for the real state
         #[cfg(feature = "sqlite")]
         {
-            use rusqlite::Connection;
-            let shadow_path = self.temp_file.as_ref().unwrap().path();
-            let conn = Connection::open(shadow_path)?;
+            let current_state = match &self.memory {
+                // Introspect over the same connection; a new one would only
+                // see the database while this one keeps it alive anyway
+                Some(conn) => {
+                    let conn = conn.lock().unwrap();
+                    let comments = self.replay(&conn, &migrations)?;
+                    let mut state = SqlIntrospector::introspect_sqlite_connection(&conn)?;
+                    set_column_comments(&mut state, &comments);
+                    state
+                }
+                None => {
+                    let shadow_path = self.temp_file.as_ref().unwrap().path();
+                    let conn = rusqlite::Connection::open(shadow_path)?;
+                    conn.collation_needed(register_collation)?;
+                    let comments = self.replay(&conn, &migrations)?;
+
+                    // Now introspect the shadow database to get real current state
+                    let introspector =
+                        SqlIntrospector::new(self.url.clone()).parallel(self.parallel);
+                    let mut state = introspector.introspect_schema().await?;
+                    set_column_comments(&mut state, &comments);
+                    state
+                }
+            };
 
-            // Execute each migration by parsing its SQL from the .rs file
-            for migration_file in &migration_files {
-                let content = std::fs::read_to_string(&migration_file.path)?;
+            say!(
+                "   ✅ Shadow database has {} table(s)",
+                current_state.tables.len()
+            );
 
-                // Extract SQL from migration file
-                let sql_statements = self.extract_sql_from_migration(&content)?;
+            return Ok(current_state);
+        }
 
-                for sql in sql_statements {
-                    if !sql.trim().is_empty() {
-                        println!("      Executing: {}", sql.lines().next().unwrap_or(&sql));
-                        conn.execute(&sql, [])?;
-                    }
-                }
+        #[cfg(not(feature = "sqlite"))]
+        {
+            return Err(anyhow::anyhow!("Shadow database requires SQLite feature"));
+        }
+    }
+
+    /// Execute the statements each migration's `up()` builds for SQLite.
+    /// SQLite can't store column comments, so they are returned instead.
+    #[cfg(feature = "sqlite")]
+    fn replay(
+        &self,
+        conn: &rusqlite::Connection,
+        migrations: &[&dyn Migration],
+    ) -> Result<ColumnComments> {
+        let mut comments = ColumnComments::new();
+
+        for migration in migrations {
+            // The context restates definitions from the schema where SQLite
+            // lacks an `ALTER` (column types, index renames)
+            let mut schema = SqlIntrospector::introspect_sqlite_connection(conn)?;
+            set_column_comments(&mut schema, &comments);
+
+            let mut db = SqlMigrationContext::new(SqlFlavor::Sqlite).schema(schema);
+            migration
+                .up(&mut db)
+                .map_err(|e| anyhow::anyhow!("Migration {}: {}", migration.version(), e))?;
+
+            for sql in db.statements() {
+                say!("      Executing: {}", sql.lines().next().unwrap_or(sql));
+                conn.execute_batch(sql)?;
             }
 
-            println!("   ✅ Applied {} migration(s) to shadow database", migration_files.len());
+            comments = column_comments(db.tracked_schema());
+        }
 
-            // Now introspect the shadow database to get real current state
-            let introspector = SqlIntrospector::new(self.url.clone());
-            let current_state = introspector.introspect_schema().await?;
+        say!(
+            "   ✅ Applied {} migration(s) to shadow database",
+            migrations.len()
+        );
+        Ok(comments)
+    }
 
-            println!("   ✅ Shadow database has {} table(s)", current_state.tables.len());
+    /// Get shadow database URL
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
 
-            return Ok(current_state);
+impl Drop for ShadowDatabase {
+    fn drop(&mut self) {
+        // Temp file automatically deleted
+        say!("🗑️  Shadow database cleaned up");
+    }
+}
+
+/// Column comments by table and column name
+#[cfg(feature = "sqlite")]
+type ColumnComments = std::collections::HashMap<(String, String), String>;
+
+/// The column comments in `schema`
+#[cfg(feature = "sqlite")]
+fn column_comments(schema: Option<&SchemaSnapshot>) -> ColumnComments {
+    let mut comments = ColumnComments::new();
+    for table in schema.iter().flat_map(|schema| &schema.tables) {
+        for column in &table.columns {
+            if let Some(comment) = &column.comment {
+                comments.insert(
+                    (table.qualified_name(), column.name.clone()),
+                    comment.clone(),
+                );
+            }
         }
+    }
+    comments
+}
 
-        #[cfg(not(feature = "sqlite"))]
-        {
-            return Err(anyhow::anyhow!("Shadow database requires SQLite feature"));
+/// Give the columns of `schema` the comments the migrations left them with
+#[cfg(feature = "sqlite")]
+fn set_column_comments(schema: &mut SchemaSnapshot, comments: &ColumnComments) {
+    for table in &mut schema.tables {
+        let name = table.qualified_name();
+        for column in &mut table.columns {
+            column.comment = comments.get(&(name.clone(), column.name.clone())).cloned();
         }
+    }
+}
+
+/// The schema a migration directory produces, obtained by replaying it in a
+/// fresh shadow database
+pub struct ShadowSchemaSource {
+    migration_dir: PathBuf,
+    parallel: usize,
+    excluding: Option<String>,
+    in_memory: bool,
+}
 
+impl ShadowSchemaSource {
+    pub fn new(migration_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            migration_dir: migration_dir.into(),
+            parallel: 1,
+            excluding: None,
+            in_memory: false,
+        }
     }
 
-    /// Extract SQL statements from migration .rs file
-    /// Parses Rust code to find db.create_table(), db.add_column(), etc. and converts to SQL
-    /// ONLY extracts from up() function, NOT down()
-    fn extract_sql_from_migration(&self, content: &str) -> Result<Vec<String>> {
-        let mut statements = Vec::new();
-        let lines: Vec<&str> = content.lines().collect();
+    /// Introspect up to `n` tables of the shadow database concurrently
+    pub fn parallel(mut self, n: usize) -> Self {
+        self.parallel = n;
+        self
+    }
 
-        // Find the up() function - extract only from up(), not down()
-        let mut in_up_function = false;
-        let mut i = 0;
+    /// Leave the migration with this version out of the replay
+    pub fn excluding(mut self, version: Option<String>) -> Self {
+        self.excluding = version;
+        self
+    }
 
-        while i < lines.len() {
-            let line = lines[i].trim();
+    /// Replay into an in-memory database instead of a temp file
+    pub fn in_memory(mut self, in_memory: bool) -> Self {
+        self.in_memory = in_memory;
+        self
+    }
+}
 
-            // Detect start of up() function
-            if line.contains("fn up(&self") {
-                in_up_function = true;
-                i += 1;
-                continue;
-            }
+#[async_trait]
+impl SchemaSource for ShadowSchemaSource {
+    async fn snapshot(&self) -> Result<SchemaSnapshot> {
+        #[cfg(feature = "sqlite")]
+        let shadow = if self.in_memory {
+            ShadowDatabase::in_memory()?
+        } else {
+            ShadowDatabase::new()?
+        };
+        #[cfg(not(feature = "sqlite"))]
+        let shadow = if self.in_memory {
+            return Err(anyhow::anyhow!(
+                "In-memory shadow database requires SQLite feature"
+            ));
+        } else {
+            ShadowDatabase::new()?
+        };
 
-            // Detect start of down() function - stop parsing
-            if line.contains("fn down(&self") {
-                in_up_function = false;
-                break;
-            }
+        shadow
+            .parallel(self.parallel)
+            .excluding(self.excluding.clone())
+            .apply_migrations(&self.migration_dir)
+            .await
+    }
+}
 
-            // Only parse lines inside up() function
-            if !in_up_function {
-                i += 1;
-                continue;
-            }
+/// Describe the operations in a migration's `up()` that `diff` undoes, e.g.
+/// `adds column users.nickname` when the diff drops that column again
+pub fn reverted_changes(migration: &dyn Migration, diff: &SchemaDiff) -> Result<Vec<String>> {
+    let mut up = RecordedOps::default();
+    migration.up(&mut up)?;
 
-            // Parse db.create_table()
-            if line.contains("db.create_table(\"") {
-                if let Some(sql) = self.parse_create_table(&lines, i)? {
-                    statements.push(sql);
-                }
-            }
-            // Parse db.add_column()
-            else if line.contains("db.add_column(\"") {
-                if let Some(sql) = self.parse_add_column(line)? {
-                    statements.push(sql);
-                }
-            }
-            // Parse db.create_index()
-            else if line.contains("db.create_index(\"") {
-                if let Some(sql) = self.parse_create_index(line)? {
-                    statements.push(sql);
+    let mut reverted = Vec::new();
+
+    for op in &up.0 {
+        for change in &diff.changes {
+            let undone = match (op, change) {
+                (RecordedOp::CreateTable(table), SchemaChange::DropTable(t)) => {
+                    t.qualified_name() == *table
                 }
-            }
-            // Parse db.drop_table()
-            else if line.contains("db.drop_table(\"") {
-                if let Some(table) = extract_quoted_string(line, "db.drop_table(\"") {
-                    statements.push(format!("DROP TABLE IF EXISTS {}", table));
+                (RecordedOp::DropTable(table), SchemaChange::CreateTable(t)) => {
+                    t.qualified_name() == *table
                 }
-            }
-            // Parse db.drop_column()
-            else if line.contains("db.drop_column(\"") {
-                // Extract table and column names
-                if let Some((table, column)) = self.parse_drop_column(line)? {
-                    statements.push(format!("ALTER TABLE {} DROP COLUMN {}", table, column));
+                (
+                    RecordedOp::AddColumn(table, name),
+                    SchemaChange::DropColumn { table: t, column },
+                )
+                | (
+                    RecordedOp::DropColumn(table, name),
+                    SchemaChange::AddColumn { table: t, column },
+                ) => t == table && column.name == *name,
+                (
+                    RecordedOp::CreateIndex(table, name),
+                    SchemaChange::DropIndex { table: t, index },
+                )
+                | (
+                    RecordedOp::DropIndex(table, name),
+                    SchemaChange::CreateIndex { table: t, index },
+                ) => t == table && index.name == *name,
+                _ => false,
+            };
+
+            if undone {
+                let what = match op {
+                    RecordedOp::CreateTable(table) => format!("creates table {}", table),
+                    RecordedOp::DropTable(table) => format!("drops table {}", table),
+                    RecordedOp::AddColumn(table, name) => format!("adds column {}.{}", table, name),
+                    RecordedOp::DropColumn(table, name) => {
+                        format!("drops column {}.{}", table, name)
+                    }
+                    RecordedOp::CreateIndex(_, name) => format!("creates index {}", name),
+                    RecordedOp::DropIndex(_, name) => format!("drops index {}", name),
+                };
+
+                if !reverted.contains(&what) {
+                    reverted.push(what);
                 }
             }
-
-            i += 1;
         }
+    }
+
+    Ok(reverted)
+}
+
+/// A table, column or index a migration creates or drops
+enum RecordedOp {
+    CreateTable(String),
+    DropTable(String),
+    AddColumn(String, String),
+    DropColumn(String, String),
+    CreateIndex(String, String),
+    DropIndex(String, String),
+}
+
+/// Records the operations [`reverted_changes`] compares against a diff,
+/// ignoring every other change a migration makes
+#[derive(Default)]
+struct RecordedOps(Vec<RecordedOp>);
 
-        Ok(statements)
+impl MigrationContext for RecordedOps {
+    fn execute_sql(&mut self, _sql: &str) -> Result<()> {
+        Ok(())
     }
 
-    fn parse_create_table(&self, lines: &[&str], start: usize) -> Result<Option<String>> {
-        let line = lines[start].trim();
+    fn create_table(&mut self, name: &str, _columns: Vec<ColumnDef>) -> Result<()> {
+        self.0.push(RecordedOp::CreateTable(name.to_string()));
+        Ok(())
+    }
 
-        // Extract table name: db.create_table("users", vec![
-        let table_name = extract_quoted_string(line, "db.create_table(\"")
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse table name"))?;
+    fn create_partitioned_table(
+        &mut self,
+        name: &str,
+        columns: Vec<ColumnDef>,
+        _partition_by: PartitionBy,
+    ) -> Result<()> {
+        self.create_table(name, columns)
+    }
 
-        // Parse column definitions
-        let mut columns = Vec::new();
-        let mut i = start;
+    fn create_partition(
+        &mut self,
+        _parent: &str,
+        _name: &str,
+        _from: &str,
+        _to: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
 
-        while i < lines.len() {
-            let col_line = lines[i].trim();
+    fn drop_table(&mut self, name: &str) -> Result<()> {
+        self.0.push(RecordedOp::DropTable(name.to_string()));
+        Ok(())
+    }
 
-            // Look for ColumnDef { name: "...", ty: "...", nullable: ... }
-            if col_line.contains("ColumnDef {") && col_line.contains("name:") {
-                if let Some(col_name) = extract_quoted_string(col_line, "name: \"") {
-                    let col_type = extract_quoted_string(col_line, "ty: \"").unwrap_or("TEXT".to_string());
-                    let nullable = col_line.contains("nullable: true");
+    fn rename_table(&mut self, _from: &str, _to: &str) -> Result<()> {
+        Ok(())
+    }
 
-                    let mut col_def = format!("{} {}", col_name, col_type);
-                    if !nullable {
-                        col_def.push_str(" NOT NULL");
-                    }
-                    columns.push(col_def);
-                }
-            }
+    fn truncate_table_with(&mut self, _table: &str, _options: TruncateOptions) -> Result<()> {
+        Ok(())
+    }
 
-            // Stop at ])?;
-            if col_line.contains("])?;") {
-                break;
-            }
+    fn add_column(&mut self, table: &str, column: ColumnDef) -> Result<()> {
+        self.0
+            .push(RecordedOp::AddColumn(table.to_string(), column.name));
+        Ok(())
+    }
 
-            i += 1;
-        }
+    fn drop_column(&mut self, table: &str, column: &str) -> Result<()> {
+        self.0.push(RecordedOp::DropColumn(
+            table.to_string(),
+            column.to_string(),
+        ));
+        Ok(())
+    }
 
-        if columns.is_empty() {
-            return Ok(None);
-        }
+    fn rename_column(&mut self, _table: &str, _from: &str, _to: &str) -> Result<()> {
+        Ok(())
+    }
 
-        let sql = format!(
-            "CREATE TABLE {} (\n  {}\n)",
-            table_name,
-            columns.join(",\n  ")
-        );
+    fn modify_column(
+        &mut self,
+        _table: &str,
+        _column: &str,
+        _old_ty: &str,
+        _new_ty: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
 
-        Ok(Some(sql))
+    fn make_not_null(&mut self, _table: &str, _column: &str, _default: &str) -> Result<()> {
+        Ok(())
     }
 
-    fn parse_add_column(&self, line: &str) -> Result<Option<String>> {
-        // db.add_column("users", ColumnDef { name: "bio", ty: "text", nullable: true })?;
-        let table = extract_quoted_string(line, "db.add_column(\"")
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse table"))?;
-        let col_name = extract_quoted_string(line, "name: \"")
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse column name"))?;
-        let col_type = extract_quoted_string(line, "ty: \"").unwrap_or("TEXT".to_string());
-        let nullable = line.contains("nullable: true");
+    fn backfill_column(
+        &mut self,
+        _table: &str,
+        _column: &str,
+        _value: &str,
+        _batch_size: usize,
+    ) -> Result<()> {
+        Ok(())
+    }
 
-        let mut sql = format!("ALTER TABLE {} ADD COLUMN {} {}", table, col_name, col_type);
-        if !nullable {
-            sql.push_str(" NOT NULL");
-        }
+    fn drop_not_null(&mut self, _table: &str, _column: &str) -> Result<()> {
+        Ok(())
+    }
 
-        Ok(Some(sql))
-    }
-
-    fn parse_create_index(&self, line: &str) -> Result<Option<String>> {
-        // db.create_index("users", IndexDef { name: "idx", columns: vec!["email"], unique: true })?;
-        let table = extract_quoted_string(line, "db.create_index(\"")
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse table"))?;
-        let idx_name = extract_quoted_string(line, "name: \"")
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse index name"))?;
-        let unique = line.contains("unique: true");
-
-        // Extract columns from vec!["col1".into(), "col2".into()]
-        let columns = if let Some(start) = line.find("columns: vec![") {
-            let remaining = &line[start + 14..];
-            if let Some(end) = remaining.find("]") {
-                let cols_str = &remaining[..end];
-                // Extract all quoted strings (handles "email".into() format)
-                let mut cols = Vec::new();
-                let mut search_str = cols_str;
-                while let Some(idx) = search_str.find('"') {
-                    search_str = &search_str[idx + 1..];
-                    if let Some(end_idx) = search_str.find('"') {
-                        cols.push(search_str[..end_idx].to_string());
-                        search_str = &search_str[end_idx + 1..];
-                    }
-                }
-                cols.join(", ")
-            } else {
-                String::new()
-            }
-        } else {
-            String::new()
-        };
+    fn set_column_default(
+        &mut self,
+        _table: &str,
+        _column: &str,
+        _default: Option<&str>,
+    ) -> Result<()> {
+        Ok(())
+    }
 
-        if columns.is_empty() {
-            return Ok(None);
-        }
+    fn create_index(&mut self, table: &str, index: IndexDef) -> Result<()> {
+        self.0
+            .push(RecordedOp::CreateIndex(table.to_string(), index.name));
+        Ok(())
+    }
 
-        let unique_str = if unique { "UNIQUE " } else { "" };
-        let sql = format!("CREATE {}INDEX {} ON {} ({})", unique_str, idx_name, table, columns);
+    fn drop_index(&mut self, table: &str, index_name: &str) -> Result<()> {
+        self.0.push(RecordedOp::DropIndex(
+            table.to_string(),
+            index_name.to_string(),
+        ));
+        Ok(())
+    }
 
-        Ok(Some(sql))
+    fn rename_index(&mut self, _table: &str, _from: &str, _to: &str) -> Result<()> {
+        Ok(())
     }
 
-    fn parse_drop_column(&self, line: &str) -> Result<Option<(String, String)>> {
-        // db.drop_column("users", "bio")?;
-        let table = extract_quoted_string(line, "db.drop_column(\"")
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse table"))?;
+    fn add_constraint(&mut self, _table: &str, _constraint: ConstraintDef) -> Result<()> {
+        Ok(())
+    }
 
-        // Find second quoted string
-        if let Some(first_end) = line.find("\", \"") {
-            let remaining = &line[first_end + 4..];
-            if let Some(column) = extract_quoted_string(remaining, "") {
-                return Ok(Some((table, column)));
-            }
-        }
+    fn validate_constraint(&mut self, _table: &str, _name: &str) -> Result<()> {
+        Ok(())
+    }
 
-        Ok(None)
+    fn drop_constraint(&mut self, _table: &str, _constraint: ConstraintDef) -> Result<()> {
+        Ok(())
     }
 
-    /// Get shadow database URL
-    pub fn url(&self) -> &str {
-        &self.url
+    fn comment_on_table(&mut self, _table: &str, _comment: &str) -> Result<()> {
+        Ok(())
     }
-}
 
-impl Drop for ShadowDatabase {
-    fn drop(&mut self) {
-        // Temp file automatically deleted
-        println!("🗑️  Shadow database cleaned up");
+    fn comment_on_column(
+        &mut self,
+        _table: &str,
+        _column: &str,
+        _comment: Option<&str>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn alter_table_options(&mut self, _table: &str, _options: TableOptions) -> Result<()> {
+        Ok(())
     }
 }
@@ -0,0 +1,118 @@
+use std::path::Path;
+use std::process::Command;
+
+const MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    pub name: String,
+}
+"#;
+
+const MODEL_WITH_EMAIL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    pub name: String,
+
+    pub email: String,
+}
+"#;
+
+fn generate(project: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:generate")
+        .args(args)
+        .arg("--url")
+        .arg(format!("sqlite:{}", project.join("app.db").display()))
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .output()
+        .unwrap()
+}
+
+fn migration_files(project: &Path) -> Vec<String> {
+    let mut files: Vec<String> = std::fs::read_dir(project.join("migrations"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".rs"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn setup() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::write(project.join("entity/src/user.rs"), MODEL).unwrap();
+
+    let output = generate(project, &["--message", "create users"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    // Tweak the model right after generating
+    std::fs::write(project.join("entity/src/user.rs"), MODEL_WITH_EMAIL).unwrap();
+    tmp
+}
+
+#[test]
+fn amend_rewrites_latest_pending_migration() {
+    let tmp = setup();
+    let project = tmp.path();
+    let before = migration_files(project);
+    assert_eq!(before.len(), 1);
+
+    let output = generate(project, &["--amend"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+
+    // Same file, now creating the table with the new column too
+    assert_eq!(migration_files(project), before);
+    let content = std::fs::read_to_string(project.join("migrations").join(&before[0])).unwrap();
+    assert!(content.contains("db.create_table(\"users\""), "{content}");
+    assert!(content.contains("name: \"email\""), "{content}");
+    assert!(!content.contains("db.add_column"), "{content}");
+}
+
+#[test]
+fn applied_migration_is_never_amended() {
+    let tmp = setup();
+    let project = tmp.path();
+    let before = migration_files(project);
+    let path = project.join("migrations").join(&before[0]);
+    let content = std::fs::read_to_string(&path).unwrap();
+
+    let conn = rusqlite::Connection::open(project.join("app.db")).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE _toasty_migrations (
+            version TEXT PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO _toasty_migrations (version) VALUES (?1)",
+        [before[0].trim_end_matches(".rs")],
+    )
+    .unwrap();
+    drop(conn);
+
+    let output = generate(project, &["--amend"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("already been applied"), "{stderr}");
+
+    assert_eq!(migration_files(project), before);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), content);
+}
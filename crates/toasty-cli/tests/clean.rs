@@ -0,0 +1,85 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+/// Write `path` with a modification time `age` in the past
+fn write_aged(path: &Path, age: Duration) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let file = std::fs::File::create(path).unwrap();
+    file.set_modified(SystemTime::now() - age).unwrap();
+}
+
+#[test]
+fn clean_removes_stale_artifacts_and_keeps_migrations() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path().join("project");
+    let temp_dir = tmp.path().join("tmp");
+    let two_days = Duration::from_secs(2 * 24 * 60 * 60);
+
+    write_aged(&temp_dir.join("toasty-shadow-crashed.db"), two_days);
+    write_aged(&temp_dir.join("toasty-shadow-crashed.db-journal"), two_days);
+    write_aged(&temp_dir.join("toasty-shadow-running.db"), Duration::ZERO);
+    write_aged(&temp_dir.join("unrelated.db"), two_days);
+    write_aged(&project.join(".toasty/cache/entities.json"), two_days);
+    write_aged(&project.join(".toasty/recent.json"), Duration::ZERO);
+
+    // Migrations are kept however old they are
+    write_aged(
+        &project.join("migrations/20240101_000000_create_users.rs"),
+        two_days,
+    );
+    write_aged(&project.join("migrations/.schema.json"), two_days);
+    write_aged(
+        &project.join(".toasty/history/20240101_000000_create_users.json"),
+        two_days,
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("clean")
+        .arg("--older-than")
+        .arg("1d")
+        .current_dir(&project)
+        .env("TMPDIR", &temp_dir)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{stdout}\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("toasty-shadow-crashed.db"), "{stdout}");
+    assert!(stdout.contains("Removed 3 file(s)"), "{stdout}");
+
+    assert!(!temp_dir.join("toasty-shadow-crashed.db").exists());
+    assert!(!temp_dir.join("toasty-shadow-crashed.db-journal").exists());
+    assert!(!project.join(".toasty/cache").exists());
+
+    assert!(temp_dir.join("toasty-shadow-running.db").exists());
+    assert!(temp_dir.join("unrelated.db").exists());
+    assert!(project.join(".toasty/recent.json").exists());
+    assert!(project
+        .join("migrations/20240101_000000_create_users.rs")
+        .exists());
+    assert!(project.join("migrations/.schema.json").exists());
+    assert!(project
+        .join(".toasty/history/20240101_000000_create_users.json")
+        .exists());
+}
+
+#[test]
+fn clean_rejects_invalid_age() {
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("clean")
+        .arg("--older-than")
+        .arg("soon")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("expected an age like 30m, 12h or 7d"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,34 @@
+use std::process::Command;
+
+fn init(args: &[&str], no_color: Option<&str>) -> String {
+    let tmp = tempfile::tempdir().unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_toasty"));
+    command.arg("init").arg("--dir").arg(tmp.path()).args(args);
+    match no_color {
+        Some(value) => command.env("NO_COLOR", value),
+        None => command.env_remove("NO_COLOR"),
+    };
+
+    let output = command.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn no_color_uses_ascii_markers() {
+    let stdout = init(&[], Some("1"));
+    assert!(stdout.contains("[ok] Created"), "{stdout}");
+    assert!(!stdout.contains('✅'), "{stdout}");
+    assert!(!stdout.chars().any(|c| c as u32 >= 0x1F000), "{stdout}");
+
+    // --color always wins over NO_COLOR
+    let stdout = init(&["--color", "always"], Some("1"));
+    assert!(stdout.contains("✅ Created"), "{stdout}");
+
+    let stdout = init(&["--color", "never"], None);
+    assert!(stdout.contains("[ok] Created"), "{stdout}");
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+use std::process::Command;
+
+mod support;
+
+fn toasty(command: &str, db_path: &Path, migrations: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg(command)
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(migrations)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn applied_versions(db_path: &Path) -> Vec<String> {
+    let conn = rusqlite::Connection::open(db_path).unwrap();
+    let mut stmt = conn
+        .prepare("SELECT version FROM _toasty_migrations ORDER BY version")
+        .unwrap();
+    stmt.query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn tables(db_path: &Path) -> Vec<String> {
+    let conn = rusqlite::Connection::open(db_path).unwrap();
+    let mut stmt = conn
+        .prepare(
+            "SELECT name FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE '\\_%' ESCAPE '\\'
+             ORDER BY name",
+        )
+        .unwrap();
+    stmt.query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn three_applied_migrations(tmp: &Path) -> (std::path::PathBuf, std::path::PathBuf) {
+    let migrations = tmp.join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.join("app.db");
+
+    support::write_table_migration(&migrations, "20250101_000000_create_users", "users");
+    support::write_table_migration(&migrations, "20250102_000000_create_posts", "posts");
+    support::write_table_migration(&migrations, "20250103_000000_create_tags", "tags");
+
+    let output = toasty("migrate:up", &db_path, &migrations, &[]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(applied_versions(&db_path).len(), 3);
+
+    (db_path, migrations)
+}
+
+#[test]
+fn down_rolls_back_the_last_count_migrations() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (db_path, migrations) = three_applied_migrations(tmp.path());
+
+    let output = toasty("migrate:down", &db_path, &migrations, &["--count", "2"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stdout}{stderr}");
+    assert!(stdout.contains("Rolled back 2 migration(s)"), "{stdout}");
+
+    // Newest first
+    let tags = stdout.find("Rolling back migration: 20250103").unwrap();
+    let posts = stdout.find("Rolling back migration: 20250102").unwrap();
+    assert!(tags < posts, "{stdout}");
+
+    assert_eq!(applied_versions(&db_path), ["20250101_000000_create_users"]);
+    assert_eq!(tables(&db_path), ["users"]);
+}
+
+#[test]
+fn down_fails_when_a_migration_file_is_missing() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (db_path, migrations) = three_applied_migrations(tmp.path());
+
+    std::fs::remove_file(migrations.join("20250102_000000_create_posts.rs")).unwrap();
+
+    let output = toasty("migrate:down", &db_path, &migrations, &["--count", "2"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("20250102_000000_create_posts is applied but its file is no longer in"),
+        "{stderr}"
+    );
+
+    // Nothing was rolled back, not even the migration whose file exists
+    assert_eq!(applied_versions(&db_path).len(), 3);
+    assert_eq!(tables(&db_path), ["posts", "tags", "users"]);
+}
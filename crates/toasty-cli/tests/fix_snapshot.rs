@@ -0,0 +1,50 @@
+use std::process::Command;
+
+mod support;
+
+const CREATE_USERS: &str = r#"db.create_table("users", vec![
+            ColumnDef { name: "id".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() },
+            ColumnDef { name: "email".into(), ty: "text".into(), nullable: true, ..Default::default() }
+        ])?;
+        db.create_index("users", IndexDef { name: "index_users_by_email".into(), columns: vec!["email".into()], unique: true })?;
+        Ok(())"#;
+
+#[test]
+fn fix_snapshot_restores_corrupt_snapshot() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    support::write_migration(
+        &migrations,
+        "20250101_000000_create_users",
+        CREATE_USERS,
+        r#"db.drop_table("users")"#,
+    );
+
+    // Leftover merge conflict markers
+    let snapshot_path = migrations.join(".schema.json");
+    std::fs::write(&snapshot_path, "<<<<<<< HEAD\n{\"tables\": []}\n=======\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:fix-snapshot")
+        .arg("--dir")
+        .arg(&migrations)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+
+    let snapshot = toasty_migrate::load_snapshot(&snapshot_path).unwrap();
+    assert_eq!(snapshot.tables.len(), 1);
+
+    let users = &snapshot.tables[0];
+    assert_eq!(users.name, "users");
+    let columns: Vec<_> = users.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(columns, ["id", "email"]);
+    assert!(users.columns[1].nullable);
+    assert!(users
+        .indices
+        .iter()
+        .any(|index| index.name == "index_users_by_email" && index.unique));
+}
@@ -0,0 +1,250 @@
+use std::path::Path;
+use std::process::Command;
+use toasty_migrate::{Migration, MigrationScript, SqlFlavor, SqlMigrationContext};
+
+const MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Todo {
+    #[key]
+    pub id: Id<Self>,
+
+    #[index]
+    pub title: String,
+}
+"#;
+
+fn generate_with_sql(project: &Path, url: &str) -> std::process::Output {
+    let empty = r#"{"version":"1.0","timestamp":"2025-01-01T00:00:00Z","tables":[]}"#;
+    std::fs::write(project.join("empty.json"), empty).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:generate")
+        .arg("--message")
+        .arg("create todos")
+        .arg("--url")
+        .arg(url)
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .arg("--from-snapshot")
+        .arg(project.join("empty.json"))
+        .arg("--show-sql")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn show_sql_prints_rendered_ddl() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::write(project.join("entity/src/todo.rs"), MODEL).unwrap();
+
+    let output = generate_with_sql(project, "postgresql://localhost/app");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains(
+            "CREATE TABLE todos (\n  id text NOT NULL,\n  title text NOT NULL DEFAULT '',\n  PRIMARY KEY (id)\n);"
+        ),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("CREATE INDEX index_todos_by_title ON todos (title);"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn show_sql_is_skipped_for_nosql() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::write(project.join("entity/src/todo.rs"), MODEL).unwrap();
+
+    let output = generate_with_sql(project, "mongodb://localhost/app");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{stdout}");
+    assert!(!stdout.contains("CREATE TABLE"), "{stdout}");
+}
+
+#[test]
+fn fail_on_warnings_rejects_unmapped_types() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Todo {
+    #[key]
+    pub id: Id<Self>,
+
+    pub weight: f64,
+}
+"#;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::write(project.join("entity/src/todo.rs"), model).unwrap();
+
+    let generate = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_toasty"))
+            .arg("migrate:generate")
+            .arg("--message")
+            .arg("create todos")
+            .arg("--url")
+            .arg("sqlite::memory:")
+            .arg("--dir")
+            .arg(project.join("migrations"))
+            .arg("--entity-dir")
+            .arg(project.join("entity"))
+            .arg("--allow-pending")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = generate(&["--fail-on-warnings"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{stdout}");
+    assert!(
+        stdout.contains("todos.weight: no SQL type for `f64`"),
+        "{stdout}"
+    );
+    assert!(stderr.contains("--fail-on-warnings"), "{stderr}");
+    assert!(!project.join("migrations/.schema.json").exists());
+
+    // Without the flag the warning is only reported
+    let output = generate(&[]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn docs_writes_markdown_schema() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    pub nickname: Option<String>,
+}
+
+#[derive(Debug, toasty::Model)]
+pub struct Post {
+    #[key]
+    pub id: Id<Self>,
+
+    pub user_id: Id<User>,
+
+    #[belongs_to]
+    pub user: toasty::BelongsTo<User>,
+}
+"#;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::write(project.join("entity/src/models.rs"), model).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:generate")
+        .arg("--message")
+        .arg("create users and posts")
+        .arg("--url")
+        .arg("sqlite::memory:")
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .arg("--allow-pending")
+        .arg("--docs")
+        .arg(project.join("SCHEMA.md"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let docs = std::fs::read_to_string(project.join("SCHEMA.md")).unwrap();
+    assert!(docs.contains("## users\n"), "{docs}");
+    assert!(
+        docs.contains("| `id` (primary key) | text | no |  |"),
+        "{docs}"
+    );
+    assert!(docs.contains("| `nickname` | text | yes |  |"), "{docs}");
+    assert!(docs.contains("## posts\n"), "{docs}");
+    assert!(docs.contains("| `user_id` | text | no |  |"), "{docs}");
+    assert!(
+        docs.contains("| `index_posts_by_user_id` | user_id | no |"),
+        "{docs}"
+    );
+    assert!(docs.contains("| `user_id` | `users.id` |"), "{docs}");
+}
+
+#[test]
+fn allow_empty_writes_a_migration_without_changes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::write(project.join("entity/src/todo.rs"), MODEL).unwrap();
+
+    let generate = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_toasty"))
+            .arg("migrate:generate")
+            .arg("--message")
+            .arg("backfill titles")
+            .arg("--url")
+            .arg("sqlite::memory:")
+            .arg("--dir")
+            .arg(project.join("migrations"))
+            .arg("--entity-dir")
+            .arg(project.join("entity"))
+            .arg("--allow-pending")
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let migration_files = || {
+        std::fs::read_dir(project.join("migrations"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+            .collect::<Vec<_>>()
+    };
+
+    // Bring the snapshot up to date with the entities
+    assert!(generate(&[]).status.success());
+    assert_eq!(migration_files().len(), 1);
+
+    // Nothing changed, so no new migration without the flag
+    let output = generate(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("no migration needed"), "{stdout}");
+    assert_eq!(migration_files().len(), 1);
+
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    let output = generate(&["--allow-empty"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{stdout}");
+
+    let mut files = migration_files();
+    files.sort();
+    assert_eq!(files.len(), 2);
+
+    // The new file is a valid migration that does nothing yet
+    let script = MigrationScript::load(files.last().unwrap()).unwrap();
+    let mut db = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    script.up(&mut db).unwrap();
+    script.down(&mut db).unwrap();
+    assert!(db.statements().is_empty(), "{:?}", db.statements());
+}
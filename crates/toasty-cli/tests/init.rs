@@ -0,0 +1,50 @@
+use std::path::Path;
+use std::process::Command;
+
+fn init_git(dir: &Path) {
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("init")
+        .arg("--dir")
+        .arg(dir)
+        .arg("--init-git")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{output:?}");
+}
+
+#[test]
+fn init_git_writes_gitignore_idempotently() {
+    let dir = tempfile::tempdir().unwrap();
+    let gitignore = dir.path().join(".gitignore");
+
+    init_git(dir.path());
+    let first = std::fs::read_to_string(&gitignore).unwrap();
+
+    for entry in ["*.db", "*.sqlite", ".toasty/"] {
+        assert!(first.lines().any(|line| line == entry), "{first}");
+    }
+
+    // Migrations and the schema snapshot stay tracked
+    assert!(!first.contains("migration"));
+    assert!(!first.contains(".schema.json"));
+
+    init_git(dir.path());
+    let second = std::fs::read_to_string(&gitignore).unwrap();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn init_git_appends_to_existing_gitignore() {
+    let dir = tempfile::tempdir().unwrap();
+    let gitignore = dir.path().join(".gitignore");
+    std::fs::write(&gitignore, "/target\n*.db").unwrap();
+
+    init_git(dir.path());
+    let content = std::fs::read_to_string(&gitignore).unwrap();
+
+    assert!(content.starts_with("/target\n*.db\n"), "{content}");
+    assert_eq!(content.lines().filter(|line| *line == "*.db").count(), 1);
+    assert!(content.lines().any(|line| line == "*.sqlite"));
+    assert!(content.lines().any(|line| line == ".toasty/"));
+}
@@ -0,0 +1,108 @@
+use std::path::Path;
+use std::process::Command;
+
+mod support;
+
+const CREATE_USERS: &str = r#"db.create_table("users", vec![
+            ColumnDef { name: "id".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() },
+            ColumnDef { name: "email".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() }
+        ])?;
+        db.create_index("users", IndexDef { name: "index_users_by_id".into(), columns: vec!["id".into()], unique: true })?;
+        Ok(())"#;
+
+const ADD_NICKNAME: &str = r#"db.add_column("users", ColumnDef { name: "nickname".into(), ty: "text".into(), nullable: true, ..Default::default() })?;
+        Ok(())"#;
+
+// The model no longer has `nickname`, so generating drops it again
+const MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    pub email: String,
+}
+"#;
+
+fn generate_command(project: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_toasty"));
+    command
+        .arg("migrate:generate")
+        .arg("--message")
+        .arg("drop nickname")
+        .arg("--url")
+        .arg(format!("sqlite:{}", project.join("app.db").display()))
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"));
+    command
+}
+
+fn generate(project: &Path) -> String {
+    let output = generate_command(project)
+        .arg("--allow-pending")
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    stdout
+}
+
+fn setup() -> tempfile::TempDir {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/user.rs"), MODEL).unwrap();
+    support::write_migration(
+        &project.join("migrations"),
+        "20250101_000000_create_users",
+        CREATE_USERS,
+        r#"db.drop_table("users")"#,
+    );
+    support::write_migration(
+        &project.join("migrations"),
+        "20250102_000000_add_nickname",
+        ADD_NICKNAME,
+        r#"db.drop_column("users", "nickname")"#,
+    );
+    tmp
+}
+
+#[test]
+fn pending_migrations_block_generate() {
+    let tmp = setup();
+    let project = tmp.path();
+
+    let output = generate_command(project).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("2 migration(s) not applied"), "{stderr}");
+    assert!(stderr.contains("toasty migrate:up"), "{stderr}");
+
+    let files = std::fs::read_dir(project.join("migrations"))
+        .unwrap()
+        .count();
+    assert_eq!(files, 2);
+}
+
+#[test]
+fn dropping_a_pending_added_column_warns() {
+    let tmp = setup();
+    let project = tmp.path();
+
+    // Nothing has been applied, so both migrations are pending
+    let stdout = generate(project);
+    assert!(
+        stdout.contains("undoes changes from pending migration 20250102_000000_add_nickname"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("- adds column users.nickname"), "{stdout}");
+    assert!(
+        !stdout.contains("pending migration 20250101_000000_create_users"),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,142 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    pub name: String,
+}
+"#;
+
+const MODEL_RENAMED: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    pub full_name: String,
+}
+"#;
+
+/// Run `migrate:generate`, feeding `input` to the prompts
+fn generate(project: &Path, args: &[&str], input: &str) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:generate")
+        .args(args)
+        .arg("--allow-pending")
+        .arg("--url")
+        .arg(format!("sqlite:{}", project.join("app.db").display()))
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn latest_migration(project: &Path) -> String {
+    let mut files: Vec<_> = std::fs::read_dir(project.join("migrations"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        .collect();
+    files.sort();
+    std::fs::read_to_string(files.last().unwrap()).unwrap()
+}
+
+#[test]
+fn interactive_rename_replaces_drop_and_add() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::write(project.join("entity/src/user.rs"), MODEL).unwrap();
+
+    let output = generate(project, &["--message", "create users"], "");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    std::fs::write(project.join("entity/src/user.rs"), MODEL_RENAMED).unwrap();
+    let output = generate(
+        project,
+        &["--message", "rename name", "--interactive"],
+        "y\n",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{stdout}\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("Did you rename users.name to full_name? [y/N]"),
+        "{stdout}"
+    );
+
+    let content = latest_migration(project);
+    assert!(
+        content.contains("db.rename_column(\"users\", \"name\", \"full_name\")?;"),
+        "{content}"
+    );
+    assert!(
+        content.contains("db.rename_column(\"users\", \"full_name\", \"name\")?;"),
+        "{content}"
+    );
+    assert!(!content.contains("db.drop_column"), "{content}");
+    assert!(!content.contains("db.add_column"), "{content}");
+
+    // The answer is kept for the next run
+    let hints = std::fs::read_to_string(project.join("migrations/.renames.json")).unwrap();
+    assert!(hints.contains("\"full_name\""), "{hints}");
+
+    // Replaying the rename leaves nothing to generate
+    let output = generate(project, &["--message", "again"], "");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("no migration needed"), "{stdout}");
+}
+
+#[test]
+fn declined_rename_keeps_drop_and_add() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::write(project.join("entity/src/user.rs"), MODEL).unwrap();
+    generate(project, &["--message", "create users"], "");
+
+    std::fs::write(project.join("entity/src/user.rs"), MODEL_RENAMED).unwrap();
+    let output = generate(
+        project,
+        &["--message", "replace name", "--interactive"],
+        "n\n",
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = latest_migration(project);
+    assert!(
+        content.contains("db.drop_column(\"users\", \"name\")?;"),
+        "{content}"
+    );
+    assert!(!content.contains("db.rename_column"), "{content}");
+    assert!(!project.join("migrations/.renames.json").exists());
+}
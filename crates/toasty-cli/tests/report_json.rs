@@ -0,0 +1,135 @@
+use std::path::Path;
+use std::process::Command;
+
+mod support;
+
+/// Run `command` with `--format json` and parse its stdout as one JSON value
+fn toasty_json(
+    command: &str,
+    db_path: &Path,
+    migrations: &Path,
+    args: &[&str],
+) -> serde_json::Value {
+    let (success, report) = try_toasty_json(command, db_path, migrations, args);
+    assert!(success, "{report}");
+    report
+}
+
+/// Like [`toasty_json`], for a command that may fail, returning whether it
+/// succeeded along with its report
+fn try_toasty_json(
+    command: &str,
+    db_path: &Path,
+    migrations: &Path,
+    args: &[&str],
+) -> (bool, serde_json::Value) {
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg(command)
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(migrations)
+        .args(["--format", "json"])
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let report = serde_json::from_str(&stdout)
+        .unwrap_or_else(|e| panic!("{e}: {stdout}{}", String::from_utf8_lossy(&output.stderr)));
+
+    (output.status.success(), report)
+}
+
+#[test]
+fn up_and_down_report_versions_as_json() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    support::write_table_migration(&migrations, "20250101_000000_create_users", "users");
+    support::write_table_migration(&migrations, "20250102_000000_create_posts", "posts");
+
+    let report = toasty_json("migrate:up", &db_path, &migrations, &[]);
+    assert_eq!(
+        report["applied"],
+        serde_json::json!([
+            "20250101_000000_create_users",
+            "20250102_000000_create_posts"
+        ])
+    );
+    assert_eq!(report["deferred"], serde_json::json!([]));
+    assert!(report["duration_ms"].is_u64(), "{report}");
+    assert_eq!(report["timings"].as_array().unwrap().len(), 2, "{report}");
+    assert_eq!(
+        report["timings"][1]["version"],
+        "20250102_000000_create_posts"
+    );
+    assert!(report["timings"][1]["duration_ms"].is_u64(), "{report}");
+    assert!(report.get("error").is_none(), "{report}");
+
+    let report = toasty_json("migrate:up", &db_path, &migrations, &[]);
+    assert_eq!(report["applied"], serde_json::json!([]));
+
+    let report = toasty_json("migrate:down", &db_path, &migrations, &["--count", "2"]);
+    assert_eq!(
+        report["rolled_back"],
+        serde_json::json!([
+            "20250102_000000_create_posts",
+            "20250101_000000_create_users"
+        ])
+    );
+    assert!(report["duration_ms"].is_u64(), "{report}");
+    assert_eq!(report.as_object().unwrap().len(), 2, "{report}");
+}
+
+#[test]
+fn up_reports_deferred_versions_as_json() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    support::write_table_migration(&migrations, "20250101_000000_create_users", "users");
+    support::write_table_migration(&migrations, "20250102_000000_create_posts", "posts");
+
+    let report = toasty_json(
+        "migrate:up",
+        &db_path,
+        &migrations,
+        &["--until", "20250101_120000"],
+    );
+    assert_eq!(
+        report["applied"],
+        serde_json::json!(["20250101_000000_create_users"])
+    );
+    assert_eq!(
+        report["deferred"],
+        serde_json::json!(["20250102_000000_create_posts"])
+    );
+}
+
+#[test]
+fn failed_up_reports_committed_migrations_and_the_error() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    support::write_table_migration(&migrations, "20250101_000000_create_users", "users");
+    support::write_migration(
+        &migrations,
+        "20250102_000000_broken",
+        r#"db.execute_sql("INSERT INTO missing_table VALUES (1)")"#,
+        "Ok(())",
+    );
+
+    let (success, report) = try_toasty_json("migrate:up", &db_path, &migrations, &[]);
+    assert!(!success, "{report}");
+    assert_eq!(
+        report["applied"],
+        serde_json::json!(["20250101_000000_create_users"])
+    );
+    let error = report["error"].as_str().unwrap();
+    assert!(error.contains("missing_table"), "{error}");
+}
@@ -0,0 +1,220 @@
+use std::process::Command;
+
+const MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Account {
+    #[key]
+    pub id: Id<Self>,
+}
+"#;
+
+#[test]
+fn reset_drops_children_before_parents() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/account.rs"), MODEL).unwrap();
+    std::fs::write(
+        project.join("migrations/20250101_000000_create_accounts.rs"),
+        "// migration placeholder\n",
+    )
+    .unwrap();
+
+    // Name order would drop `accounts` first
+    let db_path = project.join("app.db");
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE accounts (id text PRIMARY KEY);
+         CREATE TABLE profiles (id text PRIMARY KEY, account_id text REFERENCES accounts (id));
+         CREATE TABLE avatars (id text PRIMARY KEY, profile_id text REFERENCES profiles (id));",
+    )
+    .unwrap();
+    drop(conn);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:reset")
+        .arg("--force")
+        .arg("--no-cascade")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+
+    let drops: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Dropping table: "))
+        .collect();
+    assert_eq!(drops, ["avatars", "profiles", "accounts"], "{stdout}");
+}
+
+#[test]
+fn diff_only_leaves_database_untouched() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/account.rs"), MODEL).unwrap();
+
+    let db_path = project.join("app.db");
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE accounts (id text PRIMARY KEY, legacy text);
+         INSERT INTO accounts (id, legacy) VALUES ('a1', 'keep me');
+         CREATE TABLE sessions (id text PRIMARY KEY);",
+    )
+    .unwrap();
+    drop(conn);
+
+    // No --force: --diff-only must not prompt either
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:reset")
+        .arg("--diff-only")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(
+        stdout.contains("DropTable(TableSnapshot { schema: None, name: \"sessions\""),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("column: ColumnSnapshot { name: \"legacy\""),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("Dropping table"), "{stdout}");
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let legacy: String = conn
+        .query_row("SELECT legacy FROM accounts WHERE id = 'a1'", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(legacy, "keep me");
+    let tables: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'sessions'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(tables, 1);
+}
+
+#[test]
+fn diff_only_ignores_defaults_generated_migrations_add() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(
+        project.join("entity/src/account.rs"),
+        r#"
+#[derive(Debug, toasty::Model)]
+pub struct Account {
+    #[key]
+    pub id: Id<Self>,
+
+    pub name: String,
+}
+"#,
+    )
+    .unwrap();
+
+    // The table as a generated migration creates it
+    let db_path = project.join("app.db");
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE accounts (id text NOT NULL PRIMARY KEY, name text NOT NULL DEFAULT '');",
+    )
+    .unwrap();
+    drop(conn);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:reset")
+        .arg("--diff-only")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(stdout.contains("Database matches entities"), "{stdout}");
+}
+
+#[test]
+fn mismatched_expected_database_aborts() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/account.rs"), MODEL).unwrap();
+
+    let db_path = project.join("prod.db");
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute_batch("CREATE TABLE accounts (id text PRIMARY KEY);")
+        .unwrap();
+    drop(conn);
+
+    let reset = |expected: &str| {
+        Command::new(env!("CARGO_BIN_EXE_toasty"))
+            .arg("migrate:reset")
+            .arg("--force")
+            .arg("--expect-database")
+            .arg(expected)
+            .arg("--url")
+            .arg(format!("sqlite:{}", db_path.display()))
+            .arg("--dir")
+            .arg(project.join("migrations"))
+            .arg("--entity-dir")
+            .arg(project.join("entity"))
+            .output()
+            .unwrap()
+    };
+
+    let output = reset("dev");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "{stdout}");
+    assert!(stdout.contains("Target: database 'prod.db'"), "{stdout}");
+    assert!(stderr.contains("--expect-database is 'dev'"), "{stderr}");
+    assert!(!stdout.contains("Dropping table"), "{stdout}");
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let tables: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'accounts'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(tables, 1);
+    drop(conn);
+
+    // The file name, with or without extension, is the expected name
+    let output = reset("prod");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,382 @@
+use std::path::Path;
+use std::process::Command;
+
+mod support;
+
+// A generated column whose expression contains a comma inside a string
+// literal and escaped double quotes around an identifier
+const CREATE_PERSONS: &str = r#"db.create_table("persons", vec![
+            ColumnDef { name: "id".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() },
+            ColumnDef { name: "first_name".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() },
+            ColumnDef { name: "last_name".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() },
+            ColumnDef { name: "full_name".into(), ty: "text".into(), nullable: true, generated: Some("first_name || ', ' || \"last_name\"".into()), stored: false, ..Default::default() }
+        ])?;
+        db.create_index("persons", IndexDef { name: "index_persons_by_id".into(), columns: vec!["id".into()], unique: true })?;
+        Ok(())"#;
+
+const MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Person {
+    #[key]
+    pub id: Id<Self>,
+
+    pub first_name: String,
+
+    pub last_name: String,
+
+    #[generated("first_name || ', ' || \"last_name\"")]
+    pub full_name: Option<String>,
+}
+"#;
+
+fn generate(project: &Path) -> std::process::Output {
+    generate_with(project, &[])
+}
+
+fn generate_with(project: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:generate")
+        .args(args)
+        .arg("--message")
+        .arg("noop")
+        .arg("--url")
+        .arg("sqlite::memory:")
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .arg("--allow-pending")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn shadow_replays_strings_with_commas_and_escaped_quotes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/person.rs"), MODEL).unwrap();
+    support::write_migration(
+        &project.join("migrations"),
+        "20250101_000000_create_persons",
+        CREATE_PERSONS,
+        r#"db.drop_table("persons")"#,
+    );
+
+    let output = generate(project);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // The shadow database reproduces the expression exactly, so it matches
+    // the entity and there is nothing to generate
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(
+        stdout.contains("Database matches entities"),
+        "{stdout}\n{stderr}"
+    );
+}
+
+#[test]
+fn in_memory_shadow_replays_and_introspects() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/person.rs"), MODEL).unwrap();
+    support::write_migration(
+        &project.join("migrations"),
+        "20250101_000000_create_persons",
+        CREATE_PERSONS,
+        r#"db.drop_table("persons")"#,
+    );
+
+    let output = generate_with(project, &["--shadow-in-memory"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Introspection sees the tables the replay created on the same
+    // connection
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(
+        stdout.contains("Shadow database has 1 table(s)"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Database matches entities"),
+        "{stdout}\n{stderr}"
+    );
+}
+
+const CREATE_TAGS: &str = r#"db.create_table("tags", vec![
+            ColumnDef { name: "id".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() },
+            ColumnDef { name: "name".into(), ty: "text".into(), nullable: false, default: Some("''".into()), collation: Some("utf8mb4_unicode_ci".into()), ..Default::default() }
+        ])?;
+        db.create_index("tags", IndexDef { name: "index_tags_by_id".into(), columns: vec!["id".into()], unique: true })?;
+        Ok(())"#;
+
+const TAG_MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Tag {
+    #[key]
+    pub id: Id<Self>,
+
+    #[collation = "utf8mb4_unicode_ci"]
+    pub name: String,
+}
+"#;
+
+#[test]
+fn shadow_replays_collations_sqlite_lacks() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/tag.rs"), TAG_MODEL).unwrap();
+    support::write_migration(
+        &project.join("migrations"),
+        "20250101_000000_create_tags",
+        CREATE_TAGS,
+        r#"db.drop_table("tags")"#,
+    );
+
+    let output = generate(project);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(
+        stdout.contains("Database matches entities"),
+        "{stdout}\n{stderr}"
+    );
+}
+
+const RENAME_TAG_INDEX: &str = r#"db.rename_index("tags", "tags_name_idx", "index_tags_by_name")?;
+        Ok(())"#;
+
+const UNIQUE_TAG_MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Tag {
+    #[key]
+    pub id: Id<Self>,
+
+    #[unique]
+    pub name: String,
+}
+"#;
+
+#[test]
+fn shadow_replays_index_renames() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/tag.rs"), UNIQUE_TAG_MODEL).unwrap();
+
+    let create = CREATE_TAGS
+        .replace(", collation: Some(\"utf8mb4_unicode_ci\".into())", "")
+        .replace(
+            "Ok(())",
+            "db.create_index(\"tags\", IndexDef { name: \"tags_name_idx\".into(), columns: vec![\"name\".into()], unique: true })?;\n        Ok(())",
+        );
+    support::write_migration(
+        &project.join("migrations"),
+        "20250101_000000_create_tags",
+        &create,
+        r#"db.drop_table("tags")"#,
+    );
+    support::write_migration(
+        &project.join("migrations"),
+        "20250102_000000_rename_tag_index",
+        RENAME_TAG_INDEX,
+        r#"db.rename_index("tags", "index_tags_by_name", "tags_name_idx")"#,
+    );
+
+    let output = generate(project);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(
+        stdout.contains("Database matches entities"),
+        "{stdout}\n{stderr}"
+    );
+}
+
+const RETYPE_TAG_PRIORITY: &str = r#"db.modify_column("tags", "priority", "text", "integer")?;
+        Ok(())"#;
+
+const PRIORITY_TAG_MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Tag {
+    #[key]
+    pub id: Id<Self>,
+
+    pub name: String,
+
+    pub priority: i32,
+}
+"#;
+
+#[test]
+fn shadow_replays_column_type_changes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/tag.rs"), PRIORITY_TAG_MODEL).unwrap();
+
+    let create = CREATE_TAGS
+        .replace(", collation: Some(\"utf8mb4_unicode_ci\".into())", "")
+        .replace(
+            " ..Default::default() }\n        ])?;",
+            " ..Default::default() },\n            ColumnDef { name: \"priority\".into(), ty: \"text\".into(), nullable: false, default: Some(\"''\".into()), ..Default::default() }\n        ])?;",
+        );
+    support::write_migration(
+        &project.join("migrations"),
+        "20250101_000000_create_tags",
+        &create,
+        r#"db.drop_table("tags")"#,
+    );
+    support::write_migration(
+        &project.join("migrations"),
+        "20250102_000000_retype_tag_priority",
+        RETYPE_TAG_PRIORITY,
+        r#"db.modify_column("tags", "priority", "integer", "text")"#,
+    );
+
+    // The shadow rebuilds the table with the new type, so nothing is left
+    // to generate
+    let output = generate(project);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(
+        stdout.contains("SELECT id, name, CAST(priority AS integer) FROM tags"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Database matches entities"),
+        "{stdout}\n{stderr}"
+    );
+}
+
+// Formatted by hand: the column spans several lines and the index is raw SQL
+const ADD_TAG_NOTE: &str = r#"db.add_column(
+            "tags",
+            ColumnDef {
+                name: "note".into(),
+                ty: "text".into(),
+                nullable: true,
+                ..Default::default()
+            },
+        )?;
+        db.execute_sql("CREATE INDEX index_tags_by_note ON tags (note)")?;
+        Ok(())"#;
+
+const NOTE_TAG_MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Tag {
+    #[key]
+    pub id: Id<Self>,
+
+    #[collation = "utf8mb4_unicode_ci"]
+    pub name: String,
+
+    #[index]
+    pub note: Option<String>,
+}
+"#;
+
+#[test]
+fn shadow_runs_migrations_instead_of_reading_their_source() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/tag.rs"), NOTE_TAG_MODEL).unwrap();
+    support::write_migration(
+        &project.join("migrations"),
+        "20250101_000000_create_tags",
+        CREATE_TAGS,
+        r#"db.drop_table("tags")"#,
+    );
+    support::write_migration(
+        &project.join("migrations"),
+        "20250102_000000_add_tag_note",
+        ADD_TAG_NOTE,
+        r#"db.drop_column("tags", "note")"#,
+    );
+
+    let output = generate(project);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(
+        stdout.contains("Executing: CREATE INDEX index_tags_by_note ON tags (note)"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Database matches entities"),
+        "{stdout}\n{stderr}"
+    );
+}
+
+const PLAIN_TAG_MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Tag {
+    #[key]
+    pub id: Id<Self>,
+
+    pub name: String,
+}
+"#;
+
+#[test]
+fn dropped_columns_are_restored_with_their_defaults() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/tag.rs"), PLAIN_TAG_MODEL).unwrap();
+
+    let create = CREATE_TAGS
+        .replace(", collation: Some(\"utf8mb4_unicode_ci\".into())", "")
+        .replace(
+            " ..Default::default() }\n        ])?;",
+            " ..Default::default() },\n            ColumnDef { name: \"status\".into(), ty: \"text\".into(), nullable: false, default: Some(\"'active'\".into()), ..Default::default() }\n        ])?;",
+        );
+    support::write_migration(
+        &project.join("migrations"),
+        "20250101_000000_create_tags",
+        &create,
+        r#"db.drop_table("tags")"#,
+    );
+
+    let output = generate(project);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+
+    // The shadow keeps the default the column was created with, so `down()`
+    // can put it back
+    let generated = std::fs::read_dir(project.join("migrations"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.to_string_lossy().ends_with("_noop.rs"))
+        .unwrap();
+    let content = std::fs::read_to_string(generated).unwrap();
+    let down = &content[content.find("fn down(").unwrap()..];
+
+    assert!(
+        content.contains("db.drop_column(\"tags\", \"status\")"),
+        "{content}"
+    );
+    assert!(
+        down.contains("name: \"status\".into()")
+            && down.contains("default: Some(\"'active'\".into())"),
+        "{content}"
+    );
+}
@@ -0,0 +1,64 @@
+use std::path::Path;
+use std::process::Command;
+
+const MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Todo {
+    #[key]
+    #[auto]
+    pub id: Id<Self>,
+
+    pub title: String,
+}
+"#;
+
+fn reset(db_path: &Path, project: &Path) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:reset")
+        .arg("--force")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(project.join("migrations"))
+        .arg("--entity-dir")
+        .arg(project.join("entity"))
+        .arg("--busy-timeout")
+        .arg("200")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn reset_succeeds_while_reader_is_open() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path();
+    std::fs::create_dir_all(project.join("entity/src")).unwrap();
+    std::fs::create_dir_all(project.join("migrations")).unwrap();
+    std::fs::write(project.join("entity/src/todo.rs"), MODEL).unwrap();
+    std::fs::write(
+        project.join("migrations/20250101_000000_create_todos.rs"),
+        "// migration placeholder\n",
+    )
+    .unwrap();
+    let db_path = project.join("app.db");
+
+    let output = reset(&db_path, project);
+    assert!(output.status.success(), "{output:?}");
+
+    // Hold a read transaction open for the duration of the second reset
+    let mut reader = rusqlite::Connection::open(&db_path).unwrap();
+    let tx = reader.transaction().unwrap();
+    let count: i64 = tx
+        .query_row("SELECT COUNT(*) FROM todos", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 0);
+
+    let output = reset(&db_path, project);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+    assert!(!stderr.contains("database is locked"), "{stderr}");
+
+    tx.rollback().unwrap();
+}
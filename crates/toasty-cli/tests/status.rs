@@ -0,0 +1,172 @@
+use std::path::Path;
+use std::process::Command;
+
+fn write_migration(dir: &Path, version: &str) {
+    std::fs::write(
+        dir.join(format!("{version}.rs")),
+        "// migration placeholder\n",
+    )
+    .unwrap();
+}
+
+fn record_applied(db_path: &Path, versions: &[&str]) {
+    let conn = rusqlite::Connection::open(db_path).unwrap();
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS _toasty_migrations (
+            version TEXT PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )
+    .unwrap();
+
+    for version in versions {
+        conn.execute(
+            "INSERT INTO _toasty_migrations (version) VALUES (?1)",
+            [version],
+        )
+        .unwrap();
+    }
+}
+
+fn status_check(db_path: &Path, migrations: &Path) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:status")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(migrations)
+        .arg("--check")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn status_check_succeeds_when_all_applied() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    write_migration(&migrations, "20250101_000000_create_users");
+    write_migration(&migrations, "20250102_000000_add_email");
+    record_applied(
+        &db_path,
+        &["20250101_000000_create_users", "20250102_000000_add_email"],
+    );
+
+    let output = status_check(&db_path, &migrations);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("2 applied, 0 pending"), "{stdout}");
+}
+
+#[test]
+fn status_check_fails_when_pending() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    write_migration(&migrations, "20250101_000000_create_users");
+    write_migration(&migrations, "20250102_000000_add_email");
+    record_applied(&db_path, &["20250101_000000_create_users"]);
+
+    let output = status_check(&db_path, &migrations);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+    assert!(stdout.contains("1 applied, 1 pending"), "{stdout}");
+    assert!(
+        stdout.contains("pending: 20250102_000000_add_email"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn status_check_treats_missing_tracking_table_as_pending() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    write_migration(&migrations, "20250101_000000_create_users");
+
+    let output = status_check(&db_path, &migrations);
+
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn status_marks_each_migration_applied_or_pending() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    write_migration(&migrations, "20250101_000000_create_users");
+    write_migration(&migrations, "20250102_000000_add_email");
+    write_migration(&migrations, "20250103_000000_add_index");
+    record_applied(
+        &db_path,
+        &["20250101_000000_create_users", "20250102_000000_add_email"],
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:status")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(&migrations)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{stdout}");
+
+    let row = |version: &str| {
+        stdout
+            .lines()
+            .find(|line| line.starts_with(version))
+            .unwrap_or_else(|| panic!("no row for {version}: {stdout}"))
+            .split(" | ")
+            .map(str::trim)
+            .collect::<Vec<_>>()
+    };
+
+    for version in ["20250101_000000_create_users", "20250102_000000_add_email"] {
+        let row = row(version);
+        assert_eq!(row[1], "Applied", "{stdout}");
+        assert!(row[2].starts_with("20"), "{stdout}");
+    }
+    assert_eq!(
+        row("20250103_000000_add_index")[1..3],
+        ["Pending", ""],
+        "{stdout}"
+    );
+    assert!(stdout.contains("2 applied, 1 pending"), "{stdout}");
+}
+
+#[test]
+fn status_without_tracking_table_shows_all_pending() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    write_migration(&migrations, "20250101_000000_create_users");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:status")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(&migrations)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("0 applied, 1 pending"), "{stdout}");
+}
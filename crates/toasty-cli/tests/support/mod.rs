@@ -0,0 +1,55 @@
+// Each test crate compiles its own copy and uses only some of these
+#![allow(dead_code)]
+
+use std::path::Path;
+
+/// The source of migration `version`, laid out as `migrate:generate` writes
+/// it, with `up_body` and `down_body` as the bodies of `up()` and `down()`.
+/// Each body ends with the `Result` its function returns.
+pub fn migration_file(version: &str, up_body: &str, down_body: &str) -> String {
+    format!(
+        r#"use toasty_migrate::prelude::*;
+use anyhow::Result;
+
+pub struct Migration_{version};
+
+impl Migration for Migration_{version} {{
+    fn version(&self) -> &str {{
+        "{version}"
+    }}
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {{
+        {up_body}
+    }}
+
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {{
+        {down_body}
+    }}
+}}
+"#
+    )
+}
+
+/// Write [`migration_file`] to `{version}.rs` in `dir`
+pub fn write_migration(dir: &Path, version: &str, up_body: &str, down_body: &str) {
+    std::fs::write(
+        dir.join(format!("{version}.rs")),
+        migration_file(version, up_body, down_body),
+    )
+    .unwrap();
+}
+
+/// Write a migration creating `table` on the way up and dropping it on the
+/// way down
+pub fn write_table_migration(dir: &Path, version: &str, table: &str) {
+    write_migration(
+        dir,
+        version,
+        &format!(
+            r#"db.create_table("{table}", vec![
+            ColumnDef {{ name: "id".into(), ty: "text".into(), primary_key: true, ..Default::default() }},
+        ])"#
+        ),
+        &format!(r#"db.drop_table("{table}")"#),
+    );
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+use std::process::Command;
+
+mod support;
+
+fn migrate_up(db_path: &Path, migrations: &Path) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .arg("migrate:up")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(migrations)
+        .output()
+        .unwrap()
+}
+
+fn applied_versions(db_path: &Path) -> Vec<String> {
+    let conn = rusqlite::Connection::open(db_path).unwrap();
+    let mut stmt = conn
+        .prepare("SELECT version FROM _toasty_migrations ORDER BY version")
+        .unwrap();
+    stmt.query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+#[test]
+fn up_applies_pending_migrations_and_records_them() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = tmp.path().join("app.db");
+
+    support::write_migration(
+        &migrations,
+        "20250101_000000_create_users",
+        r#"db.create_table("users", vec![
+            ColumnDef { name: "id".into(), ty: "text".into(), primary_key: true, ..Default::default() },
+        ])"#,
+        "Ok(())",
+    );
+    support::write_migration(
+        &migrations,
+        "20250102_000000_add_email",
+        r#"db.add_column("users", ColumnDef { name: "email".into(), ty: "text".into(), nullable: true, ..Default::default() })"#,
+        "Ok(())",
+    );
+
+    let output = migrate_up(&db_path, &migrations);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{stdout}{stderr}");
+    assert!(stdout.contains("Applied 2 migration(s)"), "{stdout}");
+    assert_eq!(
+        applied_versions(&db_path),
+        ["20250101_000000_create_users", "20250102_000000_add_email"]
+    );
+
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute(
+        "INSERT INTO users (id, email) VALUES ('a', 'a@example.com')",
+        [],
+    )
+    .unwrap();
+    drop(conn);
+
+    // Already-applied versions are skipped on the next run
+    let output = migrate_up(&db_path, &migrations);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{stdout}");
+    assert!(stdout.contains("No pending migrations"), "{stdout}");
+    assert_eq!(applied_versions(&db_path).len(), 2);
+}
+
+#[test]
+fn dump_schema_on_apply_writes_history_next_to_the_migrations() {
+    let project = tempfile::tempdir().unwrap();
+    let elsewhere = tempfile::tempdir().unwrap();
+    let migrations = project.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    let db_path = project.path().join("app.db");
+
+    support::write_migration(
+        &migrations,
+        "20250101_000000_create_users",
+        r#"db.create_table("users", vec![
+            ColumnDef { name: "id".into(), ty: "text".into(), primary_key: true, ..Default::default() },
+        ])"#,
+        "Ok(())",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_toasty"))
+        .current_dir(elsewhere.path())
+        .arg("migrate:up")
+        .arg("--url")
+        .arg(format!("sqlite:{}", db_path.display()))
+        .arg("--dir")
+        .arg(&migrations)
+        .arg("--dump-schema-on-apply")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let snapshot = project
+        .path()
+        .join(".toasty/history/20250101_000000_create_users.json");
+    assert!(snapshot.is_file(), "{}", snapshot.display());
+    assert!(!elsewhere.path().join(".toasty").exists());
+}
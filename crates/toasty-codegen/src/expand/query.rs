@@ -69,6 +69,16 @@ impl Expand<'_> {
                     self
                 }
 
+                #vis fn limit(mut self, n: usize) -> #query_struct_ident {
+                    self.stmt.limit(n);
+                    self
+                }
+
+                #vis fn offset(mut self, n: usize) -> #query_struct_ident {
+                    self.stmt.offset(n);
+                    self
+                }
+
                 #vis fn include<T: ?Sized>(mut self, path: impl #toasty::Into<#toasty::Path<T>>) -> #query_struct_ident {
                     self.stmt.include(path.into());
                     self
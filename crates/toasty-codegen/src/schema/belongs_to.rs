@@ -12,6 +12,7 @@ pub(crate) struct BelongsTo {
 impl BelongsTo {
     pub(super) fn from_ast(
         attr: &syn::Attribute,
+        field_ident: &syn::Ident,
         ty: &syn::Type,
         names: &[syn::Ident],
     ) -> syn::Result<Self> {
@@ -19,22 +20,50 @@ impl BelongsTo {
         let mut fk_targets: Vec<syn::Ident> = vec![];
         let mut foreign_key = vec![];
 
-        attr.parse_nested_meta(|meta| {
-            if meta.path.is_ident("key") {
-                let value = meta.value()?;
-                fk_sources.push(value.parse()?);
-            } else if meta.path.is_ident("references") {
-                let value = meta.value()?;
-                fk_targets.push(value.parse()?);
-            } else {
+        // A bare `#[belongs_to]` has no arguments to parse; the key and
+        // reference are inferred below.
+        let bare = matches!(attr.meta, syn::Meta::Path(_));
+
+        if !bare {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("key") {
+                    let value = meta.value()?;
+                    fk_sources.push(value.parse()?);
+                } else if meta.path.is_ident("references") {
+                    let value = meta.value()?;
+                    fk_targets.push(value.parse()?);
+                } else {
+                    return Err(syn::Error::new_spanned(
+                        &meta.path,
+                        "expected `key` or `references`",
+                    ));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        // By convention, the key is `<field>_id` and it references the
+        // target's `id` field. Either side may be overridden explicitly.
+        if fk_sources.is_empty() && fk_targets.len() <= 1 {
+            let source = syn::Ident::new(&format!("{field_ident}_id"), field_ident.span());
+
+            if !names.contains(&source) {
                 return Err(syn::Error::new_spanned(
-                    &meta.path,
-                    "expected `key` or `references`",
+                    attr,
+                    format!(
+                        "could not infer the foreign key: field `{source}` not found; \
+                         specify it with `#[belongs_to(key = <field>, references = <field>)]`"
+                    ),
                 ));
             }
 
-            Ok(())
-        })?;
+            fk_sources.push(source);
+        }
+
+        if fk_targets.is_empty() && fk_sources.len() == 1 {
+            fk_targets.push(syn::Ident::new("id", field_ident.span()));
+        }
 
         if fk_sources.len() != fk_targets.len() {
             return Err(syn::Error::new_spanned(
@@ -110,7 +110,7 @@ impl Field {
                     ));
                 } else {
                     ty = Some(FieldTy::BelongsTo(BelongsTo::from_ast(
-                        attr, &field.ty, names,
+                        attr, ident, &field.ty, names,
                     )?));
                 }
             } else if attr.path().is_ident("has_many") {
@@ -147,6 +147,8 @@ impl Field {
                 }
             } else if attr.path().is_ident("toasty") {
                 // todo
+            } else if attr.path().is_ident("collation") {
+                // Read by `toasty migrate:generate` for the column definition
             }
         }
 
@@ -24,7 +24,7 @@ impl ModelAttr {
                 } else {
                     self.key = Some(KeyAttr::from_ast(attr, names)?);
                 }
-            } else if attr.path().is_ident("table") {
+            } else if attr.path().is_ident("table") || attr.path().is_ident("table_name") {
                 if self.table.is_some() {
                     return Err(syn::Error::new_spanned(attr, "duplicate `table` attribute"));
                 }
@@ -51,6 +51,13 @@ impl ModelAttr {
                 };
 
                 self.table = Some(lit.clone());
+            } else if attr.path().is_ident("table_options")
+                || attr.path().is_ident("partition")
+                || attr.path().is_ident("soft_delete")
+            {
+                // Read by `toasty migrate:generate` when building the table;
+                // they don't change the model. `soft_delete`'s `deleted_at`
+                // isn't a field, so queries aren't scoped to live rows
             }
         }
 
@@ -22,8 +22,67 @@ pub trait Driver: Debug + Send + Sync + 'static {
     /// Execute a database operation
     async fn exec(&self, schema: &Arc<Schema>, plan: Operation) -> crate::Result<Response>;
 
+    /// Open a transaction on a connection reserved for it.
+    ///
+    /// Operations executed through the returned driver run inside the
+    /// transaction, which ends with `Transaction::Commit` or
+    /// `Transaction::Rollback`. Dropping the returned driver before then
+    /// rolls the transaction back. Calling `begin` on a transaction's driver
+    /// opens a savepoint nested in it.
+    async fn begin(&self) -> crate::Result<Box<dyn Driver>> {
+        anyhow::bail!("the database driver does not support transactions")
+    }
+
+    /// Drop and recreate every table in the schema.
+    ///
+    /// SQL drivers run the reset under a database-level lock, so concurrent
+    /// calls from separate connections to the same database serialize and
+    /// each caller observes a complete, empty schema once it returns.
+    ///
     /// TODO: this will probably go away
     async fn reset_db(&self, _schema: &Schema) -> crate::Result<()> {
         unimplemented!()
     }
 }
+
+/// Mask credentials in a database URL so it can be safely logged.
+///
+/// The password component of the authority is replaced with `****`, as is the
+/// value of a `password` query parameter. URLs without credentials are
+/// returned unchanged.
+pub fn redact_url(url: &str) -> String {
+    let mut redacted = url.to_string();
+
+    if let Some(scheme_end) = url.find("://") {
+        let authority_start = scheme_end + 3;
+        let rest = &url[authority_start..];
+        let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+        let authority = &rest[..authority_end];
+
+        if let Some(at) = authority.rfind('@') {
+            if let Some(colon) = authority[..at].find(':') {
+                redacted = format!(
+                    "{}:****{}",
+                    &url[..authority_start + colon],
+                    &url[authority_start + at..]
+                );
+            }
+        }
+    }
+
+    if let Some(query_start) = redacted.find('?') {
+        let (base, query) = redacted.split_at(query_start + 1);
+        let query = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, _)) if key.eq_ignore_ascii_case("password") => format!("{key}=****"),
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+
+        redacted = format!("{base}{query}");
+    }
+
+    redacted
+}
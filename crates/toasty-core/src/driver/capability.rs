@@ -2,6 +2,10 @@ use crate::schema::db;
 
 #[derive(Debug)]
 pub struct Capability {
+    /// Name of the database, matching the scheme of its connection URL
+    /// (e.g. `"postgresql"`)
+    pub name: &'static str,
+
     /// When true, the database uses a SQL-based query language.
     pub sql: bool,
 
@@ -17,6 +21,10 @@ pub struct Capability {
 
     /// DynamoDB does not support != predicates on the primary key.
     pub primary_key_ne_predicate: bool,
+
+    /// Supports transactions, opened with `Driver::begin`. Statements
+    /// executed through the returned driver run inside it.
+    pub transactions: bool,
 }
 
 #[derive(Debug)]
@@ -60,15 +68,18 @@ impl Capability {
 
     /// SQLite capabilities.
     pub const SQLITE: Self = Self {
+        name: "sqlite",
         sql: true,
         storage_types: StorageTypes::SQLITE,
         cte_with_update: false,
         select_for_update: false,
         primary_key_ne_predicate: true,
+        transactions: true,
     };
 
     /// PostgreSQL capabilities
     pub const POSTGRESQL: Self = Self {
+        name: "postgresql",
         cte_with_update: true,
         storage_types: StorageTypes::POSTGRESQL,
         select_for_update: true,
@@ -77,6 +88,7 @@ impl Capability {
 
     /// MySQL capabilities
     pub const MYSQL: Self = Self {
+        name: "mysql",
         cte_with_update: false,
         storage_types: StorageTypes::MYSQL,
         select_for_update: true,
@@ -85,20 +97,24 @@ impl Capability {
 
     /// DynamoDB capabilities
     pub const DYNAMODB: Self = Self {
+        name: "dynamodb",
         sql: false,
         storage_types: StorageTypes::DYNAMODB,
         cte_with_update: false,
         select_for_update: false,
         primary_key_ne_predicate: false,
+        transactions: false,
     };
 
     /// MongoDB capabilities
     pub const MONGODB: Self = Self {
+        name: "mongodb",
         sql: false,
         storage_types: StorageTypes::MONGODB,
         cte_with_update: false,
         select_for_update: false,
         primary_key_ne_predicate: true,
+        transactions: false,
     };
 }
 
@@ -16,6 +16,9 @@ pub use query_pk::QueryPk;
 mod query_sql;
 pub use query_sql::QuerySql;
 
+mod raw_sql;
+pub use raw_sql::RawSql;
+
 mod transaction;
 pub use transaction::Transaction;
 
@@ -42,6 +45,10 @@ pub enum Operation {
     /// Execute a SQL query
     QuerySql(QuerySql),
 
+    /// Execute SQL written for the database, e.g. a migration's DDL. Only
+    /// SQL drivers support it.
+    RawSql(RawSql),
+
     /// Execute a transaction lifecycle op
     Transaction(Transaction),
 
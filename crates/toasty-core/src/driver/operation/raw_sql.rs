@@ -0,0 +1,37 @@
+use super::Operation;
+
+use crate::stmt;
+
+/// SQL written in the database's own dialect, run as-is. Used for schema
+/// changes, which Toasty's statements don't cover.
+#[derive(Debug, Clone)]
+pub struct RawSql {
+    /// The SQL to run. Without `params` or `ret`, it may hold several
+    /// `;`-separated statements.
+    pub sql: String,
+
+    /// Values bound to the statement's placeholders
+    pub params: Vec<stmt::Value>,
+
+    /// The return type, when the statement returns rows. Columns past the
+    /// end, or typed `Unknown`, are decoded by their type in the database,
+    /// so `Some(vec![])` reads rows of any shape.
+    pub ret: Option<Vec<stmt::Type>>,
+}
+
+impl RawSql {
+    /// One or more statements that take no parameters and return no rows
+    pub fn batch(sql: impl Into<String>) -> Self {
+        Self {
+            sql: sql.into(),
+            params: vec![],
+            ret: None,
+        }
+    }
+}
+
+impl From<RawSql> for Operation {
+    fn from(value: RawSql) -> Self {
+        Self::RawSql(value)
+    }
+}
@@ -12,6 +12,21 @@ pub enum Transaction {
     Rollback,
 }
 
+impl Transaction {
+    /// The SQL statement performing this operation, on a savepoint when the
+    /// transaction is nested in another one.
+    pub fn sql(&self, savepoint: Option<&str>) -> String {
+        match (self, savepoint) {
+            (Self::Start, None) => "BEGIN".to_string(),
+            (Self::Start, Some(name)) => format!("SAVEPOINT {name}"),
+            (Self::Commit, None) => "COMMIT".to_string(),
+            (Self::Commit, Some(name)) => format!("RELEASE SAVEPOINT {name}"),
+            (Self::Rollback, None) => "ROLLBACK".to_string(),
+            (Self::Rollback, Some(name)) => format!("ROLLBACK TO SAVEPOINT {name}"),
+        }
+    }
+}
+
 impl From<Transaction> for Operation {
     fn from(value: Transaction) -> Self {
         Self::Transaction(value)
@@ -3,6 +3,10 @@ use crate::{stmt::ValueStream, Result};
 #[derive(Debug)]
 pub struct Response {
     pub rows: Rows,
+
+    /// Names of the returned columns, in order. Only filled in for raw SQL
+    /// queries, and may be empty when no rows were returned.
+    pub columns: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -18,20 +22,28 @@ impl Response {
     pub fn count(count: u64) -> Self {
         Self {
             rows: Rows::Count(count),
+            columns: vec![],
         }
     }
 
     pub fn value_stream(values: impl Into<ValueStream>) -> Self {
         Self {
             rows: Rows::value_stream(values),
+            columns: vec![],
         }
     }
 
     pub fn empty_value_stream() -> Self {
         Self {
             rows: Rows::Values(ValueStream::default()),
+            columns: vec![],
         }
     }
+
+    pub fn with_columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = columns;
+        self
+    }
 }
 
 impl Rows {
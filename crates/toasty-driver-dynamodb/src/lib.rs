@@ -122,6 +122,7 @@ impl DynamoDb {
                 stmt::Statement::Insert(op) => self.exec_insert(schema, op).await,
                 _ => todo!("op={:#?}", op),
             },
+            RawSql(_) => Err(anyhow::anyhow!("DynamoDB does not run raw SQL")),
             _ => todo!("op={op:#?}"),
         }
     }
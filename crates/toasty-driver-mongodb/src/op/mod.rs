@@ -1,19 +1,19 @@
-mod insert;
-mod get_by_key;
-mod update_by_key;
 mod delete_by_key;
-mod query_pk;
 mod find_pk_by_index;
+mod get_by_key;
+mod insert;
+mod query_pk;
 mod query_sql;
 mod transaction;
+mod update_by_key;
 
 use crate::MongoDb;
+use std::sync::Arc;
 use toasty_core::{
     driver::{operation::Operation, Response},
     schema::db::Schema,
     Result,
 };
-use std::sync::Arc;
 
 pub async fn execute_operation(
     driver: &MongoDb,
@@ -29,5 +29,6 @@ pub async fn execute_operation(
         Operation::FindPkByIndex(op) => find_pk_by_index::execute(driver, schema, op).await,
         Operation::QuerySql(op) => query_sql::execute(driver, schema, op).await,
         Operation::Transaction(op) => transaction::execute(driver, schema, op).await,
+        Operation::RawSql(_) => Err(anyhow::anyhow!("MongoDB does not run raw SQL")),
     }
 }
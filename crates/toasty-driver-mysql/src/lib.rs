@@ -6,26 +6,43 @@ pub(crate) use value::Value;
 use mysql_async::{
     consts::ColumnType,
     prelude::{Queryable, ToValue},
-    Pool,
+    Conn, Pool,
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
 };
-use std::sync::Arc;
 use toasty_core::{
-    driver::{operation::Transaction, Capability, Operation, Response},
+    driver::{
+        operation::{RawSql, Transaction},
+        Capability, Operation, Response,
+    },
     schema::db::{Schema, Table},
     stmt::{self, ValueRecord},
     Driver, Result,
 };
 use toasty_sql as sql;
+use tokio::sync::{Mutex, MutexGuard};
 use url::Url;
 
+/// Named lock held while `reset_db` runs.
+const RESET_DB_LOCK_NAME: &str = "toasty_reset_db";
+
 #[derive(Debug)]
 pub struct MySQL {
     pool: Pool,
+
+    /// Connections of transactions dropped before they ended, kept until
+    /// the next operation rolls them back
+    abandoned: Arc<std::sync::Mutex<Vec<Arc<TransactionConn>>>>,
 }
 
 impl MySQL {
     pub fn new(pool: Pool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            abandoned: Arc::default(),
+        }
     }
 
     pub async fn connect(url: &str) -> Result<Self> {
@@ -52,7 +69,7 @@ impl MySQL {
         let opts = mysql_async::OptsBuilder::from_opts(opts).client_found_rows(true);
 
         let pool = Pool::new(opts);
-        Ok(Self { pool })
+        Ok(Self::new(pool))
     }
 
     pub async fn create_table(&self, schema: &Schema, table: &Table) -> Result<()> {
@@ -63,7 +80,7 @@ impl MySQL {
         let sql = serializer.serialize(
             &sql::Statement::create_table(table, &Capability::MYSQL),
             &mut params,
-        );
+        )?;
 
         assert!(
             params.is_empty(),
@@ -78,7 +95,7 @@ impl MySQL {
                 continue;
             }
 
-            let sql = serializer.serialize(&sql::Statement::create_index(index), &mut params);
+            let sql = serializer.serialize(&sql::Statement::create_index(index), &mut params)?;
 
             assert!(
                 params.is_empty(),
@@ -97,9 +114,9 @@ impl MySQL {
         let mut params = Vec::new();
 
         let sql = if if_exists {
-            serializer.serialize(&sql::Statement::drop_table_if_exists(table), &mut params)
+            serializer.serialize(&sql::Statement::drop_table_if_exists(table), &mut params)?
         } else {
-            serializer.serialize(&sql::Statement::drop_table(table), &mut params)
+            serializer.serialize(&sql::Statement::drop_table(table), &mut params)?
         };
 
         assert!(
@@ -112,10 +129,54 @@ impl MySQL {
         conn.exec_drop(&sql, ()).await?;
         Ok(())
     }
+
+    async fn exec_raw_sql(conn: &mut Conn, op: RawSql) -> Result<Response> {
+        let params = op.params.into_iter().map(Value::from).collect::<Vec<_>>();
+        let args = params
+            .iter()
+            .map(|param| param.to_value())
+            .collect::<Vec<_>>();
+
+        let Some(ret_tys) = op.ret else {
+            if args.is_empty() {
+                conn.query_drop(op.sql.as_str()).await?;
+                return Ok(Response::count(conn.affected_rows()));
+            }
+
+            let count = conn
+                .exec_iter(&op.sql, mysql_async::Params::Positional(args))
+                .await?
+                .affected_rows();
+            return Ok(Response::count(count));
+        };
+
+        let rows: Vec<mysql_async::Row> = conn.exec(&op.sql, &args).await?;
+        let columns = rows
+            .first()
+            .map(|row| {
+                row.columns_ref()
+                    .iter()
+                    .map(|column| column.name_str().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let results = rows.into_iter().map(move |mut row| {
+            let mut results = Vec::new();
+            for i in 0..row.len() {
+                let column = &row.columns()[i];
+                let ty = ret_tys.get(i).unwrap_or(&stmt::Type::Unknown);
+                results.push(mysql_to_toasty(i, &mut row, column, ty));
+            }
+
+            Ok(ValueRecord::from_vec(results))
+        });
+
+        Ok(Response::value_stream(stmt::ValueStream::from_iter(results)).with_columns(columns))
+    }
 }
 impl From<Pool> for MySQL {
     fn from(pool: Pool) -> Self {
-        Self { pool }
+        Self::new(pool)
     }
 }
 
@@ -130,29 +191,90 @@ impl Driver for MySQL {
     }
 
     async fn exec(&self, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
+        self.release_abandoned().await?;
         let mut conn = self.pool.get_conn().await?;
+        Self::exec_op(&mut conn, schema, op).await
+    }
+
+    async fn begin(&self) -> Result<Box<dyn Driver>> {
+        self.release_abandoned().await?;
+
+        // Pooled connections are handed out per operation, so a transaction
+        // keeps its own until it ends
+        let conn = self.pool.get_conn().await?;
+        let conn = Arc::new(TransactionConn {
+            conn: Mutex::new(conn),
+            rollbacks: Default::default(),
+        });
+        let tx = MySQLTransaction::open(conn, self.abandoned.clone(), 0).await?;
+        Ok(Box::new(tx))
+    }
+
+    async fn reset_db(&self, schema: &Schema) -> Result<()> {
+        self.release_abandoned().await?;
+
+        // Named locks are held by the connection that acquired them, so keep a
+        // dedicated connection checked out for the duration of the reset.
+        let mut lock_conn = self.pool.get_conn().await?;
+        let acquired: Option<i64> = lock_conn
+            .exec_first("SELECT GET_LOCK(?, ?)", (RESET_DB_LOCK_NAME, 60))
+            .await?;
+
+        if acquired != Some(1) {
+            anyhow::bail!("timed out waiting for the `{RESET_DB_LOCK_NAME}` lock");
+        }
+
+        let mut res = Ok(());
+
+        for table in &schema.tables {
+            res = self.drop_table(schema, table, true).await;
+            if res.is_err() {
+                break;
+            }
+
+            res = self.create_table(schema, table).await;
+            if res.is_err() {
+                break;
+            }
+        }
 
+        lock_conn
+            .exec_drop("SELECT RELEASE_LOCK(?)", (RESET_DB_LOCK_NAME,))
+            .await?;
+
+        res
+    }
+}
+
+impl MySQL {
+    /// Roll back the transactions dropped since the last operation, returning
+    /// their connections to the pool
+    async fn release_abandoned(&self) -> Result<()> {
+        let abandoned = std::mem::take(&mut *self.abandoned.lock().unwrap());
+
+        // Locking runs the rollbacks; the connection goes back to the pool
+        // once the last reference drops here
+        for conn in abandoned {
+            drop(conn.lock().await?);
+        }
+
+        Ok(())
+    }
+
+    async fn exec_op(conn: &mut Conn, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
         let (sql, ret): (sql::Statement, _) = match op {
             // Operation::Insert(stmt) => stmt.into(),
             Operation::QuerySql(op) => (op.stmt.into(), op.ret),
-            Operation::Transaction(Transaction::Start) => {
-                conn.query_drop("START TRANSACTION").await?;
-                return Ok(Response::count(0));
-            }
-            Operation::Transaction(Transaction::Commit) => {
-                conn.query_drop("COMMIT").await?;
-                return Ok(Response::count(0));
-            }
-            Operation::Transaction(Transaction::Rollback) => {
-                conn.query_drop("ROLLBACK").await?;
-                return Ok(Response::count(0));
+            Operation::Transaction(_) => {
+                anyhow::bail!("transactions are opened with `Driver::begin`")
             }
+            Operation::RawSql(op) => return Self::exec_raw_sql(conn, op).await,
             op => todo!("op={:#?}", op),
         };
 
         let mut params = Vec::new();
 
-        let sql_as_str = sql::Serializer::mysql(schema).serialize(&sql, &mut params);
+        let sql_as_str = sql::Serializer::mysql(schema).serialize(&sql, &mut params)?;
 
         let params = params.into_iter().map(Value::from).collect::<Vec<_>>();
         let args = params
@@ -203,15 +325,130 @@ impl Driver for MySQL {
             }
         }
     }
+}
 
-    async fn reset_db(&self, schema: &Schema) -> Result<()> {
-        for table in &schema.tables {
-            self.drop_table(schema, table, true).await?;
-            self.create_table(schema, table).await?;
+/// The connection a transaction keeps until it ends, shared with the
+/// savepoints nested in it
+#[derive(Debug)]
+struct TransactionConn {
+    conn: Mutex<Conn>,
+
+    /// Statements ending transactions that were dropped before they ended.
+    /// `Drop` can't wait on the connection, so they run before its next
+    /// operation, in the order the transactions were dropped.
+    rollbacks: std::sync::Mutex<Vec<String>>,
+}
+
+impl TransactionConn {
+    /// Lock the connection, first running any pending rollbacks
+    async fn lock(&self) -> Result<MutexGuard<'_, Conn>> {
+        let mut conn = self.conn.lock().await;
+        let rollbacks = std::mem::take(&mut *self.rollbacks.lock().unwrap());
+
+        for sql in rollbacks {
+            conn.query_drop(sql.as_str()).await?;
         }
 
+        Ok(conn)
+    }
+}
+
+/// A transaction opened by [`MySQL`], or a savepoint nested in one, on a
+/// connection it keeps until it ends.
+#[derive(Debug)]
+struct MySQLTransaction {
+    conn: Arc<TransactionConn>,
+
+    /// Where the outermost transaction leaves its connection when dropped
+    /// unfinished
+    abandoned: Arc<std::sync::Mutex<Vec<Arc<TransactionConn>>>>,
+
+    /// Number of transactions this one is nested in
+    depth: usize,
+
+    /// Set once committed or rolled back
+    finished: AtomicBool,
+}
+
+impl MySQLTransaction {
+    async fn open(
+        conn: Arc<TransactionConn>,
+        abandoned: Arc<std::sync::Mutex<Vec<Arc<TransactionConn>>>>,
+        depth: usize,
+    ) -> Result<Self> {
+        let tx = Self {
+            conn,
+            abandoned,
+            depth,
+            finished: AtomicBool::new(false),
+        };
+        let sql = tx.sql(Transaction::Start);
+        tx.conn.lock().await?.query_drop(sql.as_str()).await?;
+        Ok(tx)
+    }
+
+    fn sql(&self, op: Transaction) -> String {
+        let savepoint = format!("toasty_{}", self.depth);
+        op.sql((self.depth > 0).then_some(&savepoint[..]))
+    }
+}
+
+#[toasty_core::async_trait]
+impl Driver for MySQLTransaction {
+    fn capability(&self) -> &Capability {
+        &Capability::MYSQL
+    }
+
+    async fn register_schema(&mut self, _schema: &Schema) -> Result<()> {
         Ok(())
     }
+
+    async fn exec(&self, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
+        if self.finished.load(Ordering::SeqCst) {
+            anyhow::bail!("the transaction has already ended");
+        }
+
+        // Savepoints nested in this transaction and dropped since its last
+        // statement are rolled back first
+        let mut conn = self.conn.lock().await?;
+
+        match op {
+            Operation::Transaction(Transaction::Start) => {
+                anyhow::bail!("the transaction is already open; nest one with `Driver::begin`")
+            }
+            Operation::Transaction(op) => {
+                // Left unfinished if this fails, so dropping rolls back
+                conn.query_drop(self.sql(op).as_str()).await?;
+                self.finished.store(true, Ordering::SeqCst);
+                Ok(Response::count(0))
+            }
+            op => MySQL::exec_op(&mut conn, schema, op).await,
+        }
+    }
+
+    async fn begin(&self) -> Result<Box<dyn Driver>> {
+        let tx = MySQLTransaction::open(self.conn.clone(), self.abandoned.clone(), self.depth + 1)
+            .await?;
+        Ok(Box::new(tx))
+    }
+}
+
+impl Drop for MySQLTransaction {
+    fn drop(&mut self) {
+        if self.finished.load(Ordering::SeqCst) {
+            return;
+        }
+
+        // The connection's next operation rolls back first. For a savepoint
+        // that is the parent's; the outermost transaction's connection is
+        // kept from the pool until the driver's next operation.
+        let sql = self.sql(Transaction::Rollback);
+        self.conn.rollbacks.lock().unwrap().push(sql);
+
+        if self.depth == 0 {
+            self.abandoned.lock().unwrap().push(self.conn.clone());
+        }
+    }
 }
 
 fn mysql_to_toasty(
@@ -227,7 +464,9 @@ fn mysql_to_toasty(
 
         MYSQL_TYPE_VARCHAR | MYSQL_TYPE_VAR_STRING | MYSQL_TYPE_STRING | MYSQL_TYPE_BLOB => {
             match ty {
-                stmt::Type::String => extract_or_null(row, i, stmt::Value::String),
+                stmt::Type::String | stmt::Type::Unknown => {
+                    extract_or_null(row, i, stmt::Value::String)
+                }
                 stmt::Type::Uuid => extract_or_null(row, i, stmt::Value::Uuid),
                 stmt::Type::Bytes => extract_or_null(row, i, stmt::Value::Bytes),
                 _ => todo!("ty={ty:#?}"),
@@ -240,7 +479,7 @@ fn mysql_to_toasty(
             stmt::Type::I8 => extract_or_null(row, i, stmt::Value::I8),
             stmt::Type::I16 => extract_or_null(row, i, stmt::Value::I16),
             stmt::Type::I32 => extract_or_null(row, i, stmt::Value::I32),
-            stmt::Type::I64 => extract_or_null(row, i, stmt::Value::I64),
+            stmt::Type::I64 | stmt::Type::Unknown => extract_or_null(row, i, stmt::Value::I64),
             stmt::Type::U8 => extract_or_null(row, i, stmt::Value::U8),
             stmt::Type::U16 => extract_or_null(row, i, stmt::Value::U16),
             stmt::Type::U32 => extract_or_null(row, i, stmt::Value::U32),
@@ -6,28 +6,49 @@ use postgres::{
     types::{ToSql, Type},
     Column, Row, Socket,
 };
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use toasty_core::{
-    driver::{Capability, Operation, Response},
+    driver::{
+        operation::{RawSql, Transaction},
+        Capability, Operation, Response,
+    },
     schema::db::{Schema, Table},
     stmt,
     stmt::ValueRecord,
     Driver, Result,
 };
 use toasty_sql as sql;
+use tokio::sync::{Mutex, OwnedRwLockWriteGuard, RwLock};
 use tokio_postgres::{Client, Config};
 use url::Url;
 
+/// Advisory lock key held while `reset_db` runs.
+const RESET_DB_LOCK_KEY: i64 = 0x746f_6173_7479; // "toasty"
+
 #[derive(Debug)]
 pub struct PostgreSQL {
     /// The PostgreSQL client.
-    client: Client,
+    client: Arc<Client>,
+
+    /// Read-locked by each operation and write-locked by an open transaction
+    /// until it ends, so other operations don't run inside it.
+    gate: Arc<RwLock<()>>,
+
+    /// Rollbacks of dropped transactions, run before the next operation
+    rollbacks: Arc<PendingRollbacks>,
 }
 
 impl PostgreSQL {
     /// Initialize a Toasty PostgreSQL driver using an initialized connection.
     pub fn new(connection: Client) -> Self {
-        Self { client: connection }
+        Self {
+            client: Arc::new(connection),
+            gate: Arc::new(RwLock::new(())),
+            rollbacks: Arc::default(),
+        }
     }
 
     /// Connects to a PostgreSQL database using a connection string.
@@ -100,7 +121,7 @@ impl PostgreSQL {
         let sql = serializer.serialize(
             &sql::Statement::create_table(table, &Capability::POSTGRESQL),
             &mut params,
-        );
+        )?;
 
         assert!(
             params.is_empty(),
@@ -116,7 +137,7 @@ impl PostgreSQL {
                 continue;
             }
 
-            let sql = serializer.serialize(&sql::Statement::create_index(index), &mut params);
+            let sql = serializer.serialize(&sql::Statement::create_index(index), &mut params)?;
 
             assert!(
                 params.is_empty(),
@@ -135,9 +156,9 @@ impl PostgreSQL {
         let mut params = Vec::new();
 
         let sql = if if_exists {
-            serializer.serialize(&sql::Statement::drop_table_if_exists(table), &mut params)
+            serializer.serialize(&sql::Statement::drop_table_if_exists(table), &mut params)?
         } else {
-            serializer.serialize(&sql::Statement::drop_table(table), &mut params)
+            serializer.serialize(&sql::Statement::drop_table(table), &mut params)?
         };
 
         assert!(
@@ -148,11 +169,61 @@ impl PostgreSQL {
         self.client.execute(&sql, &[]).await?;
         Ok(())
     }
+
+    async fn exec_raw_sql(client: &Client, op: RawSql) -> Result<Response> {
+        let params = op.params.into_iter().map(Value::from).collect::<Vec<_>>();
+
+        let Some(ret_tys) = op.ret else {
+            if params.is_empty() {
+                client.batch_execute(&op.sql).await?;
+                return Ok(Response::count(0));
+            }
+
+            let args = params
+                .iter()
+                .map(|param| param as &(dyn ToSql + Sync))
+                .collect::<Vec<_>>();
+            let count = client.execute(&op.sql, &args).await?;
+            return Ok(Response::count(count));
+        };
+
+        let args = params
+            .iter()
+            .map(|param| {
+                (
+                    param as &(dyn ToSql + Sync),
+                    postgres_ty_for_value(&param.0),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let rows = client.query_typed(&op.sql, &args).await?;
+        let columns = rows
+            .first()
+            .map(|row| {
+                row.columns()
+                    .iter()
+                    .map(|column| column.name().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let results = rows.into_iter().map(move |row| {
+            let mut results = Vec::new();
+            for (i, column) in row.columns().iter().enumerate() {
+                let ty = ret_tys.get(i).unwrap_or(&stmt::Type::Unknown);
+                results.push(postgres_to_toasty(i, &row, column, ty));
+            }
+
+            Ok(ValueRecord::from_vec(results))
+        });
+
+        Ok(Response::value_stream(stmt::ValueStream::from_iter(results)).with_columns(columns))
+    }
 }
 
 impl From<Client> for PostgreSQL {
     fn from(client: Client) -> Self {
-        Self { client }
+        Self::new(client)
     }
 }
 
@@ -167,16 +238,72 @@ impl Driver for PostgreSQL {
     }
 
     async fn exec(&self, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
+        let _gate = self.gate.read().await;
+        self.rollbacks.run(&self.client).await?;
+        Self::exec_op(&self.client, schema, op).await
+    }
+
+    async fn begin(&self) -> Result<Box<dyn Driver>> {
+        let gate = self.gate.clone().write_owned().await;
+        self.rollbacks.run(&self.client).await?;
+        let tx = PostgreSQLTransaction::open(
+            self.client.clone(),
+            Arc::new(gate),
+            self.rollbacks.clone(),
+            0,
+        )
+        .await?;
+        Ok(Box::new(tx))
+    }
+
+    async fn reset_db(&self, schema: &Schema) -> Result<()> {
+        let _gate = self.gate.read().await;
+        self.rollbacks.run(&self.client).await?;
+
+        // Serialize resets across connections with a session-level advisory
+        // lock so parallel test runs sharing a database don't interleave.
+        self.client
+            .execute("SELECT pg_advisory_lock($1)", &[&RESET_DB_LOCK_KEY])
+            .await?;
+
+        let mut res = Ok(());
+
+        for table in &schema.tables {
+            res = self.drop_table(schema, table, true).await;
+            if res.is_err() {
+                break;
+            }
+
+            res = self.create_table(schema, table).await;
+            if res.is_err() {
+                break;
+            }
+        }
+
+        self.client
+            .execute("SELECT pg_advisory_unlock($1)", &[&RESET_DB_LOCK_KEY])
+            .await?;
+
+        res
+    }
+}
+
+impl PostgreSQL {
+    async fn exec_op(client: &Client, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
         let (sql, ret_tys): (sql::Statement, _) = match op {
             Operation::Insert(op) => (op.stmt.into(), None),
             Operation::QuerySql(query) => (query.stmt.into(), query.ret),
+            Operation::Transaction(_) => {
+                anyhow::bail!("transactions are opened with `Driver::begin`")
+            }
+            Operation::RawSql(op) => return Self::exec_raw_sql(client, op).await,
             op => todo!("op={:#?}", op),
         };
 
         let width = sql.returning_len();
 
         let mut params = Vec::new();
-        let sql_as_str = sql::Serializer::postgresql(schema).serialize(&sql, &mut params);
+        let sql_as_str = sql::Serializer::postgresql(schema).serialize(&sql, &mut params)?;
 
         let params = params.into_iter().map(Value::from).collect::<Vec<_>>();
 
@@ -185,7 +312,7 @@ impl Driver for PostgreSQL {
                 .iter()
                 .map(|param| param as &(dyn ToSql + Sync))
                 .collect::<Vec<_>>();
-            let count = self.client.execute(&sql_as_str, &args).await?;
+            let count = client.execute(&sql_as_str, &args).await?;
             return Ok(Response::count(count));
         }
 
@@ -199,7 +326,7 @@ impl Driver for PostgreSQL {
             })
             .collect::<Vec<_>>();
 
-        let rows = self.client.query_typed(&sql_as_str, &args).await?;
+        let rows = client.query_typed(&sql_as_str, &args).await?;
 
         if width.is_none() {
             let [row] = &rows[..] else { todo!() };
@@ -227,17 +354,140 @@ impl Driver for PostgreSQL {
             )))
         }
     }
+}
 
-    async fn reset_db(&self, schema: &Schema) -> Result<()> {
-        for table in &schema.tables {
-            self.drop_table(schema, table, true).await?;
-            self.create_table(schema, table).await?;
+/// Statements ending transactions that were dropped before they ended.
+/// `Drop` can't wait on the connection, so they run before its next
+/// operation, in the order the transactions were dropped.
+#[derive(Debug, Default)]
+struct PendingRollbacks {
+    statements: std::sync::Mutex<Vec<String>>,
+
+    /// Held while the statements run, so operations started meanwhile wait
+    /// for them
+    running: Mutex<()>,
+}
+
+impl PendingRollbacks {
+    fn push(&self, sql: String) {
+        self.statements.lock().unwrap().push(sql);
+    }
+
+    async fn run(&self, client: &Client) -> Result<()> {
+        let _running = self.running.lock().await;
+        let statements = std::mem::take(&mut *self.statements.lock().unwrap());
+
+        for sql in statements {
+            client.batch_execute(&sql).await?;
         }
 
         Ok(())
     }
 }
 
+/// A transaction opened by [`PostgreSQL`], or a savepoint nested in one. Other
+/// operations on the connection wait until it ends.
+#[derive(Debug)]
+struct PostgreSQLTransaction {
+    client: Arc<Client>,
+
+    /// Released once this transaction and any nested in it are dropped
+    gate: Arc<OwnedRwLockWriteGuard<()>>,
+
+    /// Shared with the connection, which runs the rollback when this is
+    /// dropped unfinished
+    rollbacks: Arc<PendingRollbacks>,
+
+    /// Number of transactions this one is nested in
+    depth: usize,
+
+    /// Set once committed or rolled back
+    finished: AtomicBool,
+}
+
+impl PostgreSQLTransaction {
+    async fn open(
+        client: Arc<Client>,
+        gate: Arc<OwnedRwLockWriteGuard<()>>,
+        rollbacks: Arc<PendingRollbacks>,
+        depth: usize,
+    ) -> Result<Self> {
+        let tx = Self {
+            client,
+            gate,
+            rollbacks,
+            depth,
+            finished: AtomicBool::new(false),
+        };
+        tx.client.batch_execute(&tx.sql(Transaction::Start)).await?;
+        Ok(tx)
+    }
+
+    fn sql(&self, op: Transaction) -> String {
+        let savepoint = format!("toasty_{}", self.depth);
+        op.sql((self.depth > 0).then_some(&savepoint[..]))
+    }
+}
+
+#[toasty_core::async_trait]
+impl Driver for PostgreSQLTransaction {
+    fn capability(&self) -> &Capability {
+        &Capability::POSTGRESQL
+    }
+
+    async fn register_schema(&mut self, _schema: &Schema) -> Result<()> {
+        Ok(())
+    }
+
+    async fn exec(&self, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
+        if self.finished.load(Ordering::SeqCst) {
+            anyhow::bail!("the transaction has already ended");
+        }
+
+        // Savepoints nested in this transaction and dropped since its last
+        // statement are rolled back first
+        self.rollbacks.run(&self.client).await?;
+
+        match op {
+            Operation::Transaction(Transaction::Start) => {
+                anyhow::bail!("the transaction is already open; nest one with `Driver::begin`")
+            }
+            Operation::Transaction(op) => {
+                // Left unfinished if this fails, so dropping rolls back
+                self.client.batch_execute(&self.sql(op)).await?;
+                self.finished.store(true, Ordering::SeqCst);
+                Ok(Response::count(0))
+            }
+            op => PostgreSQL::exec_op(&self.client, schema, op).await,
+        }
+    }
+
+    async fn begin(&self) -> Result<Box<dyn Driver>> {
+        self.rollbacks.run(&self.client).await?;
+        let tx = PostgreSQLTransaction::open(
+            self.client.clone(),
+            self.gate.clone(),
+            self.rollbacks.clone(),
+            self.depth + 1,
+        )
+        .await?;
+        Ok(Box::new(tx))
+    }
+}
+
+impl Drop for PostgreSQLTransaction {
+    fn drop(&mut self) {
+        if self.finished.load(Ordering::SeqCst) {
+            return;
+        }
+
+        // The connection's next operation rolls back first, whether it comes
+        // from the parent transaction or, once the gate is released, from
+        // outside
+        self.rollbacks.push(self.sql(Transaction::Rollback));
+    }
+}
+
 /// Converts a PostgreSQL value within a row to a [`toasty_core::stmt::Value`].
 fn postgres_to_toasty(
     index: usize,
@@ -271,6 +521,7 @@ fn postgres_to_toasty(
                     u8::try_from(v).unwrap_or_else(|_| panic!("u8 value out of range: {v}")),
                 ),
                 stmt::Type::U16 => stmt::Value::U16(v as u16),
+                stmt::Type::Unknown => stmt::Value::I16(v),
                 _ => panic!("unexpected type for INT2: {expected_ty:#?}"),
             })
             .unwrap_or(stmt::Value::Null)
@@ -310,7 +561,7 @@ fn postgres_to_toasty(
         row.get::<usize, Option<Vec<u8>>>(index)
             .map(|v| match expected_ty {
                 stmt::Type::Uuid => stmt::Value::Uuid(v.try_into().expect("invalid uuid bytes")),
-                stmt::Type::Bytes => stmt::Value::Bytes(v),
+                stmt::Type::Bytes | stmt::Type::Unknown => stmt::Value::Bytes(v),
                 _ => todo!(
                     "unsupported conversion from {:#?} to {expected_ty:?}",
                     column.type_()
@@ -1,22 +1,28 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, TransactionBehavior};
 use std::{
     path::Path,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 use toasty_core::{
     driver::{
-        operation::{Operation, Transaction},
+        operation::{Operation, RawSql, Transaction},
         Capability, Driver, Response,
     },
     schema::db::{Schema, Table},
     stmt, Result,
 };
 use toasty_sql as sql;
+use tokio::sync::OwnedMutexGuard;
 use url::Url;
 
 #[derive(Debug)]
 pub struct Sqlite {
-    connection: Mutex<Connection>,
+    /// Locked for each operation, and by an open transaction until it ends,
+    /// so other operations don't run inside it.
+    connection: Arc<tokio::sync::Mutex<Connection>>,
 }
 
 impl Sqlite {
@@ -40,14 +46,14 @@ impl Sqlite {
         let connection = Connection::open_in_memory().unwrap();
 
         Self {
-            connection: Mutex::new(connection),
+            connection: Arc::new(tokio::sync::Mutex::new(connection)),
         }
     }
 
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
         let connection = Connection::open(path)?;
         let sqlite = Self {
-            connection: Mutex::new(connection),
+            connection: Arc::new(tokio::sync::Mutex::new(connection)),
         };
         Ok(sqlite)
     }
@@ -64,28 +70,48 @@ impl Driver for Sqlite {
     }
 
     async fn exec(&self, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
-        let connection = self.connection.lock().unwrap();
+        let connection = self.connection.lock().await;
+        Self::exec_op(&connection, schema, op)
+    }
+
+    async fn begin(&self) -> Result<Box<dyn Driver>> {
+        let connection = self.connection.clone().lock_owned().await;
+        let tx = SqliteTransaction::open(Arc::new(Mutex::new(connection)), 0)?;
+        Ok(Box::new(tx))
+    }
+
+    async fn reset_db(&self, schema: &Schema) -> Result<()> {
+        let mut connection = self.connection.lock().await;
+
+        // Take the database write lock up-front so concurrent resets against
+        // the same file serialize rather than interleaving drops and creates.
+        let tx = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        for table in &schema.tables {
+            Self::drop_table(&tx, schema, table)?;
+            Self::create_table(&tx, schema, table)?;
+        }
 
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+impl Sqlite {
+    fn exec_op(connection: &Connection, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
         let (sql, ret_tys): (sql::Statement, _) = match op {
             Operation::QuerySql(op) => (op.stmt.into(), op.ret),
             // Operation::Insert(op) => op.stmt.into(),
-            Operation::Transaction(Transaction::Start) => {
-                connection.execute("BEGIN", [])?;
-                return Ok(Response::count(0));
-            }
-            Operation::Transaction(Transaction::Commit) => {
-                connection.execute("COMMIT", [])?;
-                return Ok(Response::count(0));
-            }
-            Operation::Transaction(Transaction::Rollback) => {
-                connection.execute("ROLLBACK", [])?;
-                return Ok(Response::count(0));
+            Operation::Transaction(_) => {
+                anyhow::bail!("transactions are opened with `Driver::begin`")
             }
+            Operation::RawSql(op) => return Self::exec_raw_sql(connection, op),
             _ => todo!("op={:#?}", op),
         };
 
         let mut params = vec![];
-        let sql_str = sql::Serializer::sqlite(schema).serialize(&sql, &mut params);
+        let sql_str = sql::Serializer::sqlite(schema).serialize(&sql, &mut params)?;
 
         let mut stmt = connection.prepare(&sql_str).unwrap();
 
@@ -154,26 +180,59 @@ impl Driver for Sqlite {
         Ok(Response::value_stream(stmt::ValueStream::from_vec(ret)))
     }
 
-    async fn reset_db(&self, schema: &Schema) -> Result<()> {
-        for table in &schema.tables {
-            self.create_table(schema, table)?;
+    fn exec_raw_sql(connection: &Connection, op: RawSql) -> Result<Response> {
+        let params = rusqlite::params_from_iter(op.params.iter().map(value_from_param));
+
+        let Some(ret_tys) = op.ret else {
+            if op.params.is_empty() {
+                connection.execute_batch(&op.sql)?;
+                return Ok(Response::count(connection.changes()));
+            }
+
+            let count = connection.execute(&op.sql, params)?;
+            return Ok(Response::count(count as _));
+        };
+
+        let mut stmt = connection.prepare(&op.sql)?;
+        let width = stmt.column_count();
+        let columns = stmt.column_names().into_iter().map(String::from).collect();
+        let mut rows = stmt.query(params)?;
+        let mut ret = vec![];
+
+        while let Some(row) = rows.next()? {
+            let items = (0..width)
+                .map(|index| {
+                    let ty = ret_tys.get(index).unwrap_or(&stmt::Type::Unknown);
+                    sqlite_to_toasty(row, index, ty)
+                })
+                .collect();
+
+            ret.push(stmt::ValueRecord::from_vec(items).into());
         }
 
-        Ok(())
+        Ok(Response::value_stream(stmt::ValueStream::from_vec(ret)).with_columns(columns))
     }
-}
 
-impl Sqlite {
-    fn create_table(&self, schema: &Schema, table: &Table) -> Result<()> {
+    fn drop_table(connection: &Connection, schema: &Schema, table: &Table) -> Result<()> {
         let serializer = sql::Serializer::sqlite(schema);
 
-        let connection = self.connection.lock().unwrap();
+        let mut params = vec![];
+        let stmt =
+            serializer.serialize(&sql::Statement::drop_table_if_exists(table), &mut params)?;
+        assert!(params.is_empty());
+
+        connection.execute(&stmt, [])?;
+        Ok(())
+    }
+
+    fn create_table(connection: &Connection, schema: &Schema, table: &Table) -> Result<()> {
+        let serializer = sql::Serializer::sqlite(schema);
 
         let mut params = vec![];
         let stmt = serializer.serialize(
             &sql::Statement::create_table(table, &Capability::SQLITE),
             &mut params,
-        );
+        )?;
         assert!(params.is_empty());
 
         connection.execute(&stmt, [])?;
@@ -185,7 +244,7 @@ impl Sqlite {
                 continue;
             }
 
-            let stmt = serializer.serialize(&sql::Statement::create_index(index), &mut params);
+            let stmt = serializer.serialize(&sql::Statement::create_index(index), &mut params)?;
             assert!(params.is_empty());
 
             connection.execute(&stmt, [])?;
@@ -194,6 +253,82 @@ impl Sqlite {
     }
 }
 
+/// A transaction opened by [`Sqlite`], or a savepoint nested in one. It
+/// holds the connection until it ends.
+#[derive(Debug)]
+struct SqliteTransaction {
+    connection: Arc<Mutex<OwnedMutexGuard<Connection>>>,
+
+    /// Number of transactions this one is nested in
+    depth: usize,
+
+    /// Set once committed or rolled back
+    finished: AtomicBool,
+}
+
+impl SqliteTransaction {
+    fn open(connection: Arc<Mutex<OwnedMutexGuard<Connection>>>, depth: usize) -> Result<Self> {
+        let tx = Self {
+            connection,
+            depth,
+            finished: AtomicBool::new(false),
+        };
+        tx.run(Transaction::Start)?;
+        Ok(tx)
+    }
+
+    fn run(&self, op: Transaction) -> Result<()> {
+        let savepoint = format!("toasty_{}", self.depth);
+        let sql = op.sql((self.depth > 0).then_some(&savepoint[..]));
+
+        self.connection.lock().unwrap().execute_batch(&sql)?;
+        Ok(())
+    }
+}
+
+#[toasty_core::async_trait]
+impl Driver for SqliteTransaction {
+    fn capability(&self) -> &Capability {
+        &Capability::SQLITE
+    }
+
+    async fn register_schema(&mut self, _schema: &Schema) -> Result<()> {
+        Ok(())
+    }
+
+    async fn exec(&self, schema: &Arc<Schema>, op: Operation) -> Result<Response> {
+        if self.finished.load(Ordering::SeqCst) {
+            anyhow::bail!("the transaction has already ended");
+        }
+
+        match op {
+            Operation::Transaction(Transaction::Start) => {
+                anyhow::bail!("the transaction is already open; nest one with `Driver::begin`")
+            }
+            Operation::Transaction(op) => {
+                // Left unfinished if this fails, so dropping rolls back
+                self.run(op)?;
+                self.finished.store(true, Ordering::SeqCst);
+                Ok(Response::count(0))
+            }
+            op => Sqlite::exec_op(&self.connection.lock().unwrap(), schema, op),
+        }
+    }
+
+    async fn begin(&self) -> Result<Box<dyn Driver>> {
+        let tx = SqliteTransaction::open(self.connection.clone(), self.depth + 1)?;
+        Ok(Box::new(tx))
+    }
+}
+
+impl Drop for SqliteTransaction {
+    fn drop(&mut self) {
+        if !self.finished.load(Ordering::SeqCst) {
+            let _ = self.run(Transaction::Rollback);
+        }
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize)]
 enum V {
     Bool(bool),
@@ -262,6 +397,7 @@ fn sqlite_to_toasty(row: &rusqlite::Row, index: usize, ty: &stmt::Type) -> stmt:
             stmt::Type::U16 => stmt::Value::U16(value as u16),
             stmt::Type::U32 => stmt::Value::U32(value as u32),
             stmt::Type::U64 => stmt::Value::U64(value as u64),
+            stmt::Type::Unknown => stmt::Value::I64(value),
             _ => todo!("ty={ty:#?}"),
         },
         Some(SqlValue::Text(value)) => match ty {
@@ -269,7 +405,7 @@ fn sqlite_to_toasty(row: &rusqlite::Row, index: usize, ty: &stmt::Type) -> stmt:
             _ => stmt::Value::String(value),
         },
         Some(SqlValue::Blob(value)) => match ty {
-            stmt::Type::Bytes => stmt::Value::Bytes(value),
+            stmt::Type::Bytes | stmt::Type::Unknown => stmt::Value::Bytes(value),
             _ => todo!("value={value:#?}"),
         },
         None => stmt::Value::Null,
@@ -5,7 +5,22 @@ use quote::quote;
 
 #[proc_macro_derive(
     Model,
-    attributes(key, auto, column, index, unique, table, has_many, has_one, belongs_to)
+    attributes(
+        key,
+        auto,
+        column,
+        index,
+        unique,
+        table,
+        table_name,
+        has_many,
+        has_one,
+        belongs_to,
+        table_options,
+        partition,
+        soft_delete,
+        collation
+    )
 )]
 pub fn derive_model(input: TokenStream) -> TokenStream {
     match toasty_codegen::generate(input.into()) {
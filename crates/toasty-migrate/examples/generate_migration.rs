@@ -1,5 +1,5 @@
-use toasty_migrate::*;
 use anyhow::Result;
+use toasty_migrate::*;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -63,17 +63,30 @@ fn create_example_old_schema() -> SchemaSnapshot {
         version: "1.0".to_string(),
         timestamp: "2025-01-17T00:00:00Z".to_string(),
         tables: vec![TableSnapshot {
+            schema: None,
             name: "users".to_string(),
             columns: vec![
                 ColumnSnapshot {
                     name: "id".to_string(),
                     ty: "Id".to_string(),
                     nullable: false,
+                    generated: None,
+                    stored: false,
+                    default: None,
+                    auto: false,
+                    collation: None,
+                    comment: None,
                 },
                 ColumnSnapshot {
                     name: "name".to_string(),
                     ty: "String".to_string(),
                     nullable: false,
+                    generated: None,
+                    stored: false,
+                    default: None,
+                    auto: false,
+                    collation: None,
+                    comment: None,
                 },
             ],
             indices: vec![IndexSnapshot {
@@ -81,8 +94,14 @@ fn create_example_old_schema() -> SchemaSnapshot {
                 columns: vec!["id".to_string()],
                 unique: true,
                 primary_key: true,
+                include: vec![],
+                predicate: None,
             }],
             primary_key: vec!["id".to_string()],
+            comment: None,
+            foreign_keys: vec![],
+            options: Default::default(),
+            partition_by: None,
         }],
     }
 }
@@ -94,23 +113,42 @@ fn create_example_new_schema() -> SchemaSnapshot {
         version: "1.0".to_string(),
         timestamp: "2025-01-17T01:00:00Z".to_string(),
         tables: vec![TableSnapshot {
+            schema: None,
             name: "users".to_string(),
             columns: vec![
                 ColumnSnapshot {
                     name: "id".to_string(),
                     ty: "Id".to_string(),
                     nullable: false,
+                    generated: None,
+                    stored: false,
+                    default: None,
+                    auto: false,
+                    collation: None,
+                    comment: None,
                 },
                 ColumnSnapshot {
                     name: "name".to_string(),
                     ty: "String".to_string(),
                     nullable: false,
+                    generated: None,
+                    stored: false,
+                    default: None,
+                    auto: false,
+                    collation: None,
+                    comment: None,
                 },
                 // NEW: Email field added
                 ColumnSnapshot {
                     name: "email".to_string(),
                     ty: "String".to_string(),
                     nullable: false,
+                    generated: None,
+                    stored: false,
+                    default: None,
+                    auto: false,
+                    collation: None,
+                    comment: None,
                 },
             ],
             indices: vec![
@@ -119,6 +157,8 @@ fn create_example_new_schema() -> SchemaSnapshot {
                     columns: vec!["id".to_string()],
                     unique: true,
                     primary_key: true,
+                    include: vec![],
+                    predicate: None,
                 },
                 // NEW: Unique email index
                 IndexSnapshot {
@@ -126,9 +166,15 @@ fn create_example_new_schema() -> SchemaSnapshot {
                     columns: vec!["email".to_string()],
                     unique: true,
                     primary_key: false,
+                    include: vec![],
+                    predicate: None,
                 },
             ],
             primary_key: vec!["id".to_string()],
+            comment: None,
+            foreign_keys: vec![],
+            options: Default::default(),
+            partition_by: None,
         }],
     }
 }
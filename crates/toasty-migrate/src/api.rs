@@ -0,0 +1,46 @@
+use crate::{AppliedMigration, MigrationFileInfo, MigrationLoader, MigrationTracker};
+use anyhow::Result;
+use std::time::Duration;
+
+/// Read-only view of a database's migration state, combining the migration
+/// files on disk with the versions recorded in `_toasty_migrations`.
+///
+/// Nothing is applied; this is for reporting, e.g. exposing the schema
+/// version from a health check.
+pub struct MigrationApi {
+    url: String,
+    loader: MigrationLoader,
+}
+
+impl MigrationApi {
+    pub fn new(url: impl Into<String>, migrations_dir: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            url: url.into(),
+            loader: MigrationLoader::new(migrations_dir),
+        }
+    }
+
+    /// Migration files that have not been applied to the database yet, in
+    /// version order
+    pub async fn pending(&self) -> Result<Vec<MigrationFileInfo>> {
+        let migrations = self.loader.discover_migrations()?;
+        let tracker = MigrationTracker::load_from_database(&self.url).await?;
+
+        Ok(tracker.pending(&migrations).into_iter().cloned().collect())
+    }
+
+    /// Migrations recorded as applied, in version order. The database
+    /// doesn't record how long each took, so `duration` is zero.
+    pub async fn applied(&self) -> Result<Vec<AppliedMigration>> {
+        let tracker = MigrationTracker::load_from_database(&self.url).await?;
+
+        Ok(tracker
+            .applied_migrations()
+            .into_iter()
+            .map(|version| AppliedMigration {
+                version,
+                duration: Duration::ZERO,
+            })
+            .collect())
+    }
+}
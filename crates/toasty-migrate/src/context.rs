@@ -1,10 +1,42 @@
-use crate::{ColumnDef, IndexDef, MigrationContext};
+use crate::snapshot::{
+    split_table_name, ColumnSnapshot, ForeignKeySnapshot, IndexSnapshot, PartitionBy,
+    SchemaSnapshot, TableOptions, TableSnapshot,
+};
+use crate::{
+    ColumnDef, ConstraintDef, ConstraintKind, IndexDef, MigrationContext, TruncateOptions,
+};
 use anyhow::Result;
 
+/// Table that audited migrations record their structural changes in
+pub const SCHEMA_LOG_TABLE: &str = "_toasty_schema_log";
+
 /// SQL-based migration context for SQLite, PostgreSQL, MySQL
 pub struct SqlMigrationContext {
     statements: Vec<String>,
+    /// The table each statement creates, drops or alters, in step with
+    /// `statements`
+    steps: Vec<Option<(String, TableStep)>>,
     flavor: SqlFlavor,
+    /// Schema as of the current statement, when auditing or given with
+    /// [`schema`](Self::schema)
+    audit: Option<SchemaSnapshot>,
+    /// Whether changes go to `_toasty_schema_log`
+    log: bool,
+    schema_log_created: bool,
+    idempotent: bool,
+}
+
+/// What a statement does to its table, to keep a table's statements after
+/// its `CREATE TABLE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TableStep {
+    Create,
+    Drop,
+    /// Adds a column, index or constraint, so it can move after a
+    /// `CREATE TABLE` that came later
+    Extend,
+    /// Changes or removes something the table already has
+    Change,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -14,123 +46,1517 @@ pub enum SqlFlavor {
     MySQL,
 }
 
+impl SqlFlavor {
+    /// The flavor for a connection URL, or `None` for non-SQL databases
+    pub fn from_url(url: &str) -> Option<Self> {
+        let scheme = url.split(':').next().unwrap_or_default();
+
+        match scheme {
+            "sqlite" => Some(SqlFlavor::Sqlite),
+            "postgresql" | "postgres" => Some(SqlFlavor::PostgreSQL),
+            "mysql" => Some(SqlFlavor::MySQL),
+            _ => None,
+        }
+    }
+}
+
 impl SqlMigrationContext {
     pub fn new(flavor: SqlFlavor) -> Self {
         Self {
             statements: Vec::new(),
+            steps: Vec::new(),
             flavor,
+            audit: None,
+            log: false,
+            schema_log_created: false,
+            idempotent: false,
         }
     }
 
+    /// Record every structural change in `_toasty_schema_log` along with the
+    /// definition before and after it. `schema` is the database's schema
+    /// before the migration runs and is where dropped definitions come from.
+    ///
+    /// The log rows are written by the same statements list, so they commit
+    /// or roll back together with the migration.
+    pub fn audit(mut self, schema: SchemaSnapshot) -> Self {
+        self.audit = Some(schema);
+        self.log = true;
+        self
+    }
+
+    /// Track `schema` like [`audit`](Self::audit) does, for statements that
+    /// restate a table's definition (MySQL's `MODIFY COLUMN`, SQLite table
+    /// rebuilds), but without logging the changes.
+    pub fn schema(mut self, schema: SchemaSnapshot) -> Self {
+        self.audit = Some(schema);
+        self
+    }
+
+    /// Only add a column if the table doesn't have it yet, so a migration
+    /// that was partially applied can be run again.
+    ///
+    /// PostgreSQL uses `ADD COLUMN IF NOT EXISTS`. MySQL lacks the clause, so
+    /// the `ALTER TABLE` runs through a prepared statement only when
+    /// `information_schema` has no such column. SQLite can't branch in SQL at
+    /// all and checks the audited schema instead.
+    pub fn idempotent(mut self, enabled: bool) -> Self {
+        self.idempotent = enabled;
+        self
+    }
+
+    /// The schema given to [`audit`](Self::audit) or
+    /// [`schema`](Self::schema), with the changes made so far applied to it
+    pub fn tracked_schema(&self) -> Option<&SchemaSnapshot> {
+        self.audit.as_ref()
+    }
+
     pub fn statements(&self) -> &[String] {
         &self.statements
     }
 
     fn add_statement(&mut self, sql: String) {
         self.statements.push(sql);
-    }
-}
-
-impl MigrationContext for SqlMigrationContext {
-    fn execute_sql(&mut self, sql: &str) -> Result<()> {
-        self.add_statement(sql.to_string());
-        Ok(())
+        self.steps.push(None);
     }
 
-    fn create_table(&mut self, name: &str, columns: Vec<ColumnDef>) -> Result<()> {
-        let column_defs: Vec<String> = columns
+    /// Add `sql`, which does `step` to `table`. Returns `false` if it exactly
+    /// repeats an earlier statement and was left out.
+    ///
+    /// Columns, indexes and constraints added to a table before its
+    /// `CREATE TABLE` are moved after it, so tables can be created in any
+    /// order. Creating a table twice, or changing it before it's created,
+    /// is an error.
+    fn add_table_statement(&mut self, table: &str, step: TableStep, sql: String) -> Result<bool> {
+        // A drop ends the table's history; it may be created again after
+        let since = self
+            .steps
             .iter()
-            .map(|col| {
-                let mut def = format!("{} {}", col.name, col.ty);
-                if !col.nullable {
-                    def.push_str(" NOT NULL");
+            .rposition(|s| matches!(s, Some((t, TableStep::Drop)) if t == table))
+            .map_or(0, |i| i + 1);
+        let earlier: Vec<usize> = (since..self.steps.len())
+            .filter(|&i| matches!(&self.steps[i], Some((t, _)) if t == table))
+            .collect();
+        let step_at = |i: usize| self.steps[i].as_ref().map(|(_, step)| *step);
+
+        match step {
+            TableStep::Create => {
+                if let Some(&i) = earlier
+                    .iter()
+                    .find(|&&i| step_at(i) == Some(TableStep::Create))
+                {
+                    if self.statements[i] == sql {
+                        return Ok(false);
+                    }
+                    return Err(anyhow::anyhow!("table {} is created twice", table));
                 }
-                if let Some(default) = &col.default {
-                    def.push_str(&format!(" DEFAULT {}", default));
+                if let Some(&i) = earlier
+                    .iter()
+                    .find(|&&i| step_at(i) == Some(TableStep::Change))
+                {
+                    return Err(anyhow::anyhow!(
+                        "`{}` changes table {} before it is created",
+                        self.statements[i],
+                        table
+                    ));
                 }
-                def
+
+                let moved: Vec<_> = earlier
+                    .iter()
+                    .rev()
+                    .map(|&i| (self.statements.remove(i), self.steps.remove(i)))
+                    .collect();
+
+                self.statements.push(sql);
+                self.steps.push(Some((table.to_string(), step)));
+                for (sql, step) in moved.into_iter().rev() {
+                    self.statements.push(sql);
+                    self.steps.push(step);
+                }
+                return Ok(true);
+            }
+            TableStep::Extend => {
+                // The same addition again is a duplicate, unless something
+                // in between may have removed the first one
+                for &i in earlier.iter().rev() {
+                    match step_at(i) {
+                        Some(TableStep::Change) => break,
+                        Some(TableStep::Extend) if self.statements[i] == sql => return Ok(false),
+                        _ => {}
+                    }
+                }
+            }
+            TableStep::Drop | TableStep::Change => {}
+        }
+
+        self.statements.push(sql);
+        self.steps.push(Some((table.to_string(), step)));
+        Ok(true)
+    }
+
+    fn audited_table(&mut self, table: &str) -> Option<&mut TableSnapshot> {
+        self.audit
+            .as_mut()?
+            .tables
+            .iter_mut()
+            .find(|t| t.qualified_name() == table)
+    }
+
+    /// Append a `_toasty_schema_log` row for the statement just added
+    fn log_change(
+        &mut self,
+        operation: &str,
+        table: &str,
+        object: Option<&str>,
+        before: Option<String>,
+        after: Option<String>,
+    ) {
+        if self.audit.is_none() || !self.log {
+            return;
+        }
+
+        if !self.schema_log_created {
+            let id = match self.flavor {
+                SqlFlavor::Sqlite => "INTEGER PRIMARY KEY AUTOINCREMENT",
+                SqlFlavor::PostgreSQL => "BIGSERIAL PRIMARY KEY",
+                SqlFlavor::MySQL => "BIGINT AUTO_INCREMENT PRIMARY KEY",
+            };
+
+            self.add_statement(format!(
+                "CREATE TABLE IF NOT EXISTS {} (\n  \
+                 id {},\n  \
+                 applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,\n  \
+                 operation VARCHAR(64) NOT NULL,\n  \
+                 table_name VARCHAR(255) NOT NULL,\n  \
+                 object_name VARCHAR(255),\n  \
+                 before_definition TEXT,\n  \
+                 after_definition TEXT\n);",
+                SCHEMA_LOG_TABLE, id
+            ));
+            self.schema_log_created = true;
+        }
+
+        let literal = |value: Option<&str>| value.map_or("NULL".to_string(), quote_literal);
+
+        self.add_statement(format!(
+            "INSERT INTO {} (operation, table_name, object_name, before_definition, after_definition) \
+             VALUES ({}, {}, {}, {}, {});",
+            SCHEMA_LOG_TABLE,
+            quote_literal(operation),
+            quote_literal(table),
+            literal(object),
+            literal(before.as_deref()),
+            literal(after.as_deref())
+        ));
+    }
+
+    /// `CREATE TABLE`, partitioned when the flavor supports it
+    fn create_table_sql(
+        &mut self,
+        name: &str,
+        columns: Vec<ColumnDef>,
+        partition_by: Option<PartitionBy>,
+    ) -> Result<()> {
+        let mut column_defs = columns
+            .iter()
+            .map(|col| self.column_sql(col))
+            .collect::<Result<Vec<_>>>()?;
+
+        let primary_key: Vec<String> = columns
+            .iter()
+            .filter(|col| col.primary_key)
+            .map(|col| col.name.clone())
+            .collect();
+        if !primary_key.is_empty() {
+            column_defs.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+        }
+
+        // Every flavor accepts foreign keys in the table body, and SQLite
+        // can't add them any other way
+        let foreign_keys: Vec<ForeignKeySnapshot> = columns
+            .iter()
+            .filter_map(|col| {
+                let reference = col.references.as_ref()?;
+                column_defs.push(format!(
+                    "FOREIGN KEY ({}) REFERENCES {} ({}){}",
+                    col.name,
+                    reference.table,
+                    reference.column,
+                    reference.on_delete.on_delete_sql()
+                ));
+                Some(ForeignKeySnapshot {
+                    column: col.name.clone(),
+                    references_table: reference.table.clone(),
+                    references_column: reference.column.clone(),
+                })
             })
             .collect();
 
+        let partition_clause = match (&partition_by, self.flavor) {
+            (None, _) => String::new(),
+            (Some(partition_by), SqlFlavor::PostgreSQL) => format!(
+                " PARTITION BY {} ({})",
+                partition_by.strategy.sql(),
+                partition_by.columns.join(", ")
+            ),
+            (Some(_), flavor) => {
+                let database = match flavor {
+                    SqlFlavor::Sqlite => "SQLite",
+                    _ => "MySQL",
+                };
+                self.add_statement(format!(
+                    "-- {}: PARTITION BY is not supported, {} will not be partitioned",
+                    database, name
+                ));
+                String::new()
+            }
+        };
+
         let sql = format!(
-            "CREATE TABLE {} (\n  {}\n);",
+            "CREATE TABLE {} (\n  {}\n){};",
             name,
-            column_defs.join(",\n  ")
+            column_defs.join(",\n  "),
+            partition_clause
         );
 
-        self.add_statement(sql);
+        if !self.add_table_statement(name, TableStep::Create, sql)? {
+            return Ok(());
+        }
+
+        if let Some(schema) = &mut self.audit {
+            let (table_schema, table_name) = split_table_name(name);
+            let table = TableSnapshot {
+                schema: table_schema.map(str::to_string),
+                name: table_name.to_string(),
+                columns: columns.iter().map(column_snapshot).collect(),
+                indices: vec![],
+                primary_key,
+                comment: None,
+                foreign_keys,
+                options: Default::default(),
+                partition_by,
+            };
+            let after = describe_table(&table);
+            schema.tables.push(table);
+            self.log_change("create_table", name, None, None, Some(after));
+        }
+
         Ok(())
     }
 
-    fn drop_table(&mut self, name: &str) -> Result<()> {
-        self.add_statement(format!("DROP TABLE {};", name));
+    /// Add the statement `sql` renders from the column's audited definition,
+    /// then apply `change` to that definition and log it
+    fn alter_column(
+        &mut self,
+        operation: &str,
+        table: &str,
+        column: &str,
+        sql: impl FnOnce(Option<&ColumnSnapshot>) -> Result<String>,
+        change: impl FnOnce(&mut ColumnSnapshot),
+    ) -> Result<()> {
+        let audited = self
+            .audited_table(table)
+            .and_then(|snapshot| snapshot.columns.iter().find(|c| c.name == column))
+            .cloned();
+
+        let statement = sql(audited.as_ref())?;
+        self.add_statement(statement);
+
+        if let Some(before) = audited {
+            let after = self
+                .audited_table(table)
+                .and_then(|snapshot| snapshot.columns.iter_mut().find(|c| c.name == column))
+                .map(|col| {
+                    change(col);
+                    describe_column(col)
+                });
+            self.log_change(
+                operation,
+                table,
+                Some(column),
+                Some(describe_column(&before)),
+                after,
+            );
+        }
+
         Ok(())
     }
 
-    fn add_column(&mut self, table: &str, column: ColumnDef) -> Result<()> {
-        let mut def = format!("{} {}", column.name, column.ty);
-        if !column.nullable {
+    /// Recreate a SQLite table as `rebuilt` under a temporary name, copy the
+    /// rows over with `value` giving each column's value, and swap it in
+    fn sqlite_rebuild(
+        &mut self,
+        table: &str,
+        rebuilt: &TableSnapshot,
+        value: impl Fn(&ColumnSnapshot) -> String,
+    ) -> Result<()> {
+        let temp = match split_table_name(table) {
+            (Some(schema), name) => format!("{}._toasty_rebuild_{}", schema, name),
+            (None, name) => format!("_toasty_rebuild_{}", name),
+        };
+
+        let mut column_defs = rebuilt
+            .columns
+            .iter()
+            .map(|col| {
+                self.column_sql(&ColumnDef {
+                    name: col.name.clone(),
+                    ty: col.ty.clone(),
+                    nullable: col.nullable,
+                    default: col.default.clone(),
+                    generated: col.generated.clone(),
+                    stored: col.stored,
+                    collation: col.collation.clone(),
+                    primary_key: false,
+                    references: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if !rebuilt.primary_key.is_empty() {
+            column_defs.push(format!("PRIMARY KEY ({})", rebuilt.primary_key.join(", ")));
+        }
+        for fk in &rebuilt.foreign_keys {
+            column_defs.push(format!(
+                "FOREIGN KEY ({}) REFERENCES {} ({})",
+                fk.column, fk.references_table, fk.references_column
+            ));
+        }
+        self.add_statement(format!(
+            "CREATE TABLE {} (\n  {}\n);",
+            temp,
+            column_defs.join(",\n  ")
+        ));
+
+        // Generated columns compute their own values
+        let copied: Vec<_> = rebuilt
+            .columns
+            .iter()
+            .filter(|col| col.generated.is_none())
+            .collect();
+        self.add_statement(format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {};",
+            temp,
+            copied
+                .iter()
+                .map(|col| col.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            copied
+                .iter()
+                .map(|col| value(col))
+                .collect::<Vec<_>>()
+                .join(", "),
+            table
+        ));
+        self.add_table_statement(table, TableStep::Drop, format!("DROP TABLE {};", table))?;
+        self.add_statement(format!("ALTER TABLE {} RENAME TO {};", temp, rebuilt.name));
+
+        // Indexes went with the old table
+        for index in &rebuilt.indices {
+            if !index.primary_key && !index.columns.is_empty() {
+                let unique = if index.unique { "UNIQUE " } else { "" };
+                self.add_statement(format!(
+                    "CREATE {}INDEX {} ON {} ({});",
+                    unique,
+                    qualified_index(table, &index.name),
+                    rebuilt.name,
+                    index.columns.join(", ")
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// SQLite can't change a column's type, so rebuild the table with the
+    /// new type, which needs its definition
+    fn sqlite_modify_column(
+        &mut self,
+        table: &str,
+        column: &str,
+        old_ty: &str,
+        new_ty: &str,
+    ) -> Result<()> {
+        let Some(mut rebuilt) = self.audited_table(table).cloned() else {
+            self.add_statement(format!(
+                "-- SQLite: Cannot change {}.{} from {} to {}. Requires table recreation",
+                table, column, old_ty, new_ty
+            ));
+            return Ok(());
+        };
+        let Some(col) = rebuilt.columns.iter_mut().find(|c| c.name == column) else {
+            return Err(anyhow::anyhow!("Table {} has no column {}", table, column));
+        };
+        let before = describe_column(col);
+        col.ty = new_ty.to_string();
+        let after = describe_column(col);
+
+        self.sqlite_rebuild(table, &rebuilt, |col| {
+            if col.name == column {
+                format!("CAST({} AS {})", column, new_ty)
+            } else {
+                col.name.clone()
+            }
+        })?;
+
+        if let Some(snapshot) = self.audited_table(table) {
+            *snapshot = rebuilt;
+        }
+        self.log_change(
+            "modify_column",
+            table,
+            Some(column),
+            Some(before),
+            Some(after),
+        );
+
+        Ok(())
+    }
+
+    /// Render a column definition for `CREATE TABLE` / `ADD COLUMN`
+    fn column_sql(&self, col: &ColumnDef) -> Result<String> {
+        let mut def = format!("{} {}", col.name, col.ty);
+
+        if let Some(collation) = &col.collation {
+            let collation = match self.flavor {
+                // PostgreSQL collation names are case-sensitive identifiers
+                SqlFlavor::PostgreSQL => format!("\"{}\"", collation.replace('"', "\"\"")),
+                SqlFlavor::Sqlite | SqlFlavor::MySQL => collation.clone(),
+            };
+            def.push_str(&format!(" COLLATE {}", collation));
+        }
+
+        if let Some(expr) = &col.generated {
+            if !col.stored && matches!(self.flavor, SqlFlavor::PostgreSQL) {
+                return Err(anyhow::anyhow!(
+                    "PostgreSQL only supports STORED generated columns ({})",
+                    col.name
+                ));
+            }
+
+            if col.default.is_some() {
+                return Err(anyhow::anyhow!(
+                    "generated column {} cannot have a default",
+                    col.name
+                ));
+            }
+
+            let kind = if col.stored { "STORED" } else { "VIRTUAL" };
+            def.push_str(&format!(" GENERATED ALWAYS AS ({}) {}", expr, kind));
+        }
+
+        if !col.nullable {
             def.push_str(" NOT NULL");
         }
-        if let Some(default) = &column.default {
+        if let Some(default) = &col.default {
+            let default = match self.flavor {
+                SqlFlavor::MySQL => mysql_default(&col.ty, default),
+                SqlFlavor::Sqlite | SqlFlavor::PostgreSQL => default.clone(),
+            };
             def.push_str(&format!(" DEFAULT {}", default));
         }
 
-        let sql = match self.flavor {
-            SqlFlavor::Sqlite => {
-                // SQLite has limited ALTER TABLE support
-                format!("ALTER TABLE {} ADD COLUMN {};", table, def)
+        Ok(def)
+    }
+}
+
+/// Quote a string as a SQL literal
+fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// The current definition of a column, which MySQL's `MODIFY COLUMN` has to
+/// restate in full
+fn mysql_column(
+    table: &str,
+    column: &str,
+    audited: Option<&ColumnSnapshot>,
+) -> Result<ColumnSnapshot> {
+    audited.cloned().ok_or_else(|| {
+        anyhow::anyhow!(
+            "MySQL can't change {}.{} without knowing its definition; audit the migration",
+            table,
+            column
+        )
+    })
+}
+
+/// A default for a MySQL column of type `ty`. MySQL rejects literal defaults
+/// on TEXT, BLOB, JSON and geometry columns, but accepts them written as an
+/// expression: `DEFAULT ('')`.
+fn mysql_default(ty: &str, default: &str) -> String {
+    let base = ty
+        .split('(')
+        .next()
+        .unwrap_or(ty)
+        .trim()
+        .to_ascii_lowercase();
+    let expression_only = base.ends_with("text")
+        || base.ends_with("blob")
+        || matches!(
+            base.as_str(),
+            "json" | "geometry" | "point" | "linestring" | "polygon"
+        );
+
+    if expression_only && !default.starts_with('(') {
+        format!("({})", default)
+    } else {
+        default.to_string()
+    }
+}
+
+/// `ALTER TABLE ... MODIFY COLUMN` restating `col` in full. The comment
+/// must be restated too, or MySQL drops it.
+fn mysql_modify_column(table: &str, col: &ColumnSnapshot) -> String {
+    let mut col = col.clone();
+    col.default = col.default.map(|default| mysql_default(&col.ty, &default));
+    let comment = match &col.comment {
+        Some(comment) => format!(" COMMENT {}", quote_literal(comment)),
+        None => String::new(),
+    };
+    format!(
+        "ALTER TABLE {} MODIFY COLUMN {}{};",
+        table,
+        describe_column(&col),
+        comment
+    )
+}
+
+/// Add `comment` to `statement` if it is the `CREATE TABLE` or `ADD COLUMN`
+/// that just created `table.column`
+fn mysql_fold_column_comment(
+    statement: &mut String,
+    table: &str,
+    column: &str,
+    comment: &str,
+) -> bool {
+    let comment = format!(" COMMENT {}", quote_literal(comment));
+
+    if statement.starts_with(&format!("ALTER TABLE {} ADD COLUMN {} ", table, column))
+        && statement.ends_with(';')
+    {
+        statement.insert_str(statement.len() - 1, &comment);
+        return true;
+    }
+
+    if !statement.starts_with(&format!("CREATE TABLE {} (", table)) {
+        return false;
+    }
+    let Some(start) = statement.find(&format!("\n  {} ", column)) else {
+        return false;
+    };
+    let end = statement[start + 1..]
+        .find('\n')
+        .map_or(statement.len(), |i| start + 1 + i);
+    let at = if statement[..end].ends_with(',') {
+        end - 1
+    } else {
+        end
+    };
+    statement.insert_str(at, &comment);
+    true
+}
+
+/// Describe a column as it would appear in a `CREATE TABLE`, for the audit log
+fn describe_column(col: &ColumnSnapshot) -> String {
+    let mut def = format!("{} {}", col.name, col.ty);
+
+    if let Some(collation) = &col.collation {
+        def.push_str(&format!(" COLLATE {}", collation));
+    }
+
+    if let Some(expr) = &col.generated {
+        let kind = if col.stored { "STORED" } else { "VIRTUAL" };
+        def.push_str(&format!(" GENERATED ALWAYS AS ({}) {}", expr, kind));
+    }
+    if !col.nullable {
+        def.push_str(" NOT NULL");
+    }
+    if let Some(default) = &col.default {
+        def.push_str(&format!(" DEFAULT {}", default));
+    }
+
+    def
+}
+
+fn describe_table(table: &TableSnapshot) -> String {
+    let columns: Vec<String> = table.columns.iter().map(describe_column).collect();
+    format!("{} ({})", table.qualified_name(), columns.join(", "))
+}
+
+/// Table options as written after a MySQL `CREATE TABLE`
+fn describe_options(options: &TableOptions) -> String {
+    let mut parts = Vec::new();
+    if let Some(engine) = &options.engine {
+        parts.push(format!("ENGINE = {}", engine));
+    }
+    if let Some(charset) = &options.charset {
+        parts.push(format!("DEFAULT CHARSET = {}", charset));
+    }
+    parts.join(" ")
+}
+
+/// Indexes live in their table's schema, and PostgreSQL and SQLite look them
+/// up by index name alone, so qualify it the same way as the table
+fn qualified_index(table: &str, index_name: &str) -> String {
+    match split_table_name(table) {
+        (Some(schema), _) => format!("{}.{}", schema, index_name),
+        (None, _) => index_name.to_string(),
+    }
+}
+
+fn describe_index(table: &str, index: &IndexSnapshot) -> String {
+    let unique = if index.unique { "UNIQUE " } else { "" };
+    let mut description = format!(
+        "{}INDEX {} ON {} ({})",
+        unique,
+        index.name,
+        table,
+        index.columns.join(", ")
+    );
+    if !index.include.is_empty() {
+        description.push_str(&format!(" INCLUDE ({})", index.include.join(", ")));
+    }
+    if let Some(predicate) = &index.predicate {
+        description.push_str(&format!(" WHERE {}", predicate));
+    }
+    description
+}
+
+/// The definition of a constraint after its name, as in `ADD CONSTRAINT`
+fn constraint_body(kind: &ConstraintKind) -> String {
+    match kind {
+        ConstraintKind::ForeignKey {
+            columns,
+            references_table,
+            references_columns,
+        } => format!(
+            "FOREIGN KEY ({}) REFERENCES {} ({})",
+            columns.join(", "),
+            references_table,
+            references_columns.join(", ")
+        ),
+        ConstraintKind::Check { expr } => format!("CHECK ({})", expr),
+    }
+}
+
+fn column_snapshot(col: &ColumnDef) -> ColumnSnapshot {
+    ColumnSnapshot {
+        name: col.name.clone(),
+        ty: col.ty.clone(),
+        nullable: col.nullable,
+        generated: col.generated.clone(),
+        stored: col.stored,
+        default: col.default.clone(),
+        auto: false,
+        collation: col.collation.clone(),
+        comment: None,
+    }
+}
+
+impl MigrationContext for SqlMigrationContext {
+    fn execute_sql(&mut self, sql: &str) -> Result<()> {
+        self.add_statement(sql.to_string());
+        Ok(())
+    }
+
+    fn create_table(&mut self, name: &str, columns: Vec<ColumnDef>) -> Result<()> {
+        self.create_table_sql(name, columns, None)
+    }
+
+    fn create_partitioned_table(
+        &mut self,
+        name: &str,
+        columns: Vec<ColumnDef>,
+        partition_by: PartitionBy,
+    ) -> Result<()> {
+        self.create_table_sql(name, columns, Some(partition_by))
+    }
+
+    fn create_partition(&mut self, parent: &str, name: &str, from: &str, to: &str) -> Result<()> {
+        let bounds = format!("FROM ({}) TO ({})", from, to);
+
+        match self.flavor {
+            SqlFlavor::PostgreSQL => self.add_statement(format!(
+                "CREATE TABLE {} PARTITION OF {} FOR VALUES {};",
+                name, parent, bounds
+            )),
+            SqlFlavor::Sqlite | SqlFlavor::MySQL => {
+                let database = match self.flavor {
+                    SqlFlavor::Sqlite => "SQLite",
+                    _ => "MySQL",
+                };
+                self.add_statement(format!(
+                    "-- {}: {} is not partitioned, skipping partition {}",
+                    database, parent, name
+                ));
+                return Ok(());
             }
-            SqlFlavor::PostgreSQL | SqlFlavor::MySQL => {
-                format!("ALTER TABLE {} ADD COLUMN {};", table, def)
+        }
+
+        self.log_change("create_partition", parent, Some(name), None, Some(bounds));
+        Ok(())
+    }
+
+    fn drop_table(&mut self, name: &str) -> Result<()> {
+        self.add_table_statement(name, TableStep::Drop, format!("DROP TABLE {};", name))?;
+
+        if let Some(schema) = &mut self.audit {
+            let before = schema
+                .tables
+                .iter()
+                .position(|t| t.qualified_name() == name)
+                .map(|i| describe_table(&schema.tables.remove(i)));
+            self.log_change("drop_table", name, None, before, None);
+        }
+
+        Ok(())
+    }
+
+    fn rename_table(&mut self, from: &str, to: &str) -> Result<()> {
+        let to = to.rsplit_once('.').map_or(to, |(_, name)| name);
+        self.add_statement(format!("ALTER TABLE {} RENAME TO {};", from, to));
+
+        let change = self.audited_table(from).map(|snapshot| {
+            let before = describe_table(snapshot);
+            snapshot.name = to.to_string();
+            (before, describe_table(snapshot))
+        });
+        let (before, after) = change.unzip();
+        self.log_change("rename_table", from, Some(to), before, after);
+
+        Ok(())
+    }
+
+    fn truncate_table_with(&mut self, table: &str, options: TruncateOptions) -> Result<()> {
+        let sql = match self.flavor {
+            // SQLite has no TRUNCATE; an unfiltered DELETE is optimized to
+            // the same thing
+            SqlFlavor::Sqlite => format!("DELETE FROM {};", table),
+            SqlFlavor::PostgreSQL => {
+                let mut sql = format!("TRUNCATE TABLE {}", table);
+                if options.restart_identity {
+                    sql.push_str(" RESTART IDENTITY");
+                }
+                if options.cascade {
+                    sql.push_str(" CASCADE");
+                }
+                sql.push(';');
+                sql
             }
+            SqlFlavor::MySQL => format!("TRUNCATE TABLE {};", table),
         };
 
         self.add_statement(sql);
         Ok(())
     }
 
+    fn add_column(&mut self, table: &str, column: ColumnDef) -> Result<()> {
+        if column.generated.is_some() && column.stored && matches!(self.flavor, SqlFlavor::Sqlite) {
+            return Err(anyhow::anyhow!(
+                "SQLite cannot add a STORED generated column ({}) to an existing table",
+                column.name
+            ));
+        }
+
+        let def = self.column_sql(&column)?;
+
+        match self.flavor {
+            SqlFlavor::Sqlite if self.idempotent => {
+                let Some(snapshot) = self.audited_table(table) else {
+                    return Err(anyhow::anyhow!(
+                        "SQLite can't add column {}.{} only if it is missing without \
+                         knowing the table's columns; audit the migration",
+                        table,
+                        column.name
+                    ));
+                };
+
+                if snapshot.columns.iter().any(|c| c.name == column.name) {
+                    self.add_statement(format!(
+                        "-- {}.{} already exists, not adding it again",
+                        table, column.name
+                    ));
+                    return Ok(());
+                }
+
+                let sql = format!("ALTER TABLE {} ADD COLUMN {};", table, def);
+                if !self.add_table_statement(table, TableStep::Extend, sql)? {
+                    return Ok(());
+                }
+            }
+            SqlFlavor::PostgreSQL if self.idempotent => {
+                let sql = format!("ALTER TABLE {} ADD COLUMN IF NOT EXISTS {};", table, def);
+                if !self.add_table_statement(table, TableStep::Extend, sql)? {
+                    return Ok(());
+                }
+            }
+            SqlFlavor::MySQL if self.idempotent => {
+                // A qualified table lives in the named database rather than
+                // the connection's
+                let (database, table_name) = match split_table_name(table) {
+                    (Some(database), name) => (quote_literal(database), name),
+                    (None, name) => ("DATABASE()".to_string(), name),
+                };
+                self.add_statement(format!(
+                    "SET @toasty_add_column = IF((SELECT COUNT(*) FROM information_schema.columns \
+                     WHERE table_schema = {} AND table_name = {} AND column_name = {}) = 0, \
+                     {}, 'DO 0');",
+                    database,
+                    quote_literal(table_name),
+                    quote_literal(&column.name),
+                    quote_literal(&format!("ALTER TABLE {} ADD COLUMN {}", table, def))
+                ));
+                self.add_statement(
+                    "PREPARE toasty_add_column FROM @toasty_add_column;".to_string(),
+                );
+                self.add_statement("EXECUTE toasty_add_column;".to_string());
+                self.add_statement("DEALLOCATE PREPARE toasty_add_column;".to_string());
+            }
+            SqlFlavor::Sqlite | SqlFlavor::PostgreSQL | SqlFlavor::MySQL => {
+                let sql = format!("ALTER TABLE {} ADD COLUMN {};", table, def);
+                if !self.add_table_statement(table, TableStep::Extend, sql)? {
+                    return Ok(());
+                }
+            }
+        }
+
+        let after = describe_column(&column_snapshot(&column));
+        if let Some(snapshot) = self.audited_table(table) {
+            if !snapshot.columns.iter().any(|c| c.name == column.name) {
+                snapshot.columns.push(column_snapshot(&column));
+            }
+        }
+        self.log_change("add_column", table, Some(&column.name), None, Some(after));
+
+        Ok(())
+    }
+
     fn drop_column(&mut self, table: &str, column: &str) -> Result<()> {
-        let sql = match self.flavor {
+        match self.flavor {
             SqlFlavor::Sqlite => {
                 // SQLite doesn't support DROP COLUMN directly
                 // Need to recreate table
-                format!("-- SQLite: Cannot DROP COLUMN {}. Requires table recreation", column)
+                self.add_statement(format!(
+                    "-- SQLite: Cannot DROP COLUMN {}. Requires table recreation",
+                    column
+                ));
             }
             SqlFlavor::PostgreSQL | SqlFlavor::MySQL => {
-                format!("ALTER TABLE {} DROP COLUMN {};", table, column)
+                let sql = format!("ALTER TABLE {} DROP COLUMN {};", table, column);
+                self.add_table_statement(table, TableStep::Change, sql)?;
+            }
+        }
+
+        let before = self.audited_table(table).and_then(|snapshot| {
+            let i = snapshot.columns.iter().position(|c| c.name == column)?;
+            Some(describe_column(&snapshot.columns.remove(i)))
+        });
+        self.log_change("drop_column", table, Some(column), before, None);
+
+        Ok(())
+    }
+
+    fn rename_column(&mut self, table: &str, from: &str, to: &str) -> Result<()> {
+        let sql = format!("ALTER TABLE {} RENAME COLUMN {} TO {};", table, from, to);
+        self.add_table_statement(table, TableStep::Change, sql)?;
+
+        let renamed = |name: &mut String| {
+            if name == from {
+                *name = to.to_string();
             }
         };
+        let change = self.audited_table(table).and_then(|snapshot| {
+            snapshot.primary_key.iter_mut().for_each(renamed);
+            for index in &mut snapshot.indices {
+                index
+                    .columns
+                    .iter_mut()
+                    .chain(&mut index.include)
+                    .for_each(renamed);
+            }
+
+            let column = snapshot.columns.iter_mut().find(|c| c.name == from)?;
+            let before = describe_column(column);
+            column.name = to.to_string();
+            Some((before, describe_column(column)))
+        });
+        let (before, after) = change.unzip();
+        self.log_change("rename_column", table, Some(to), before, after);
+
+        Ok(())
+    }
+
+    fn modify_column(
+        &mut self,
+        table: &str,
+        column: &str,
+        old_ty: &str,
+        new_ty: &str,
+    ) -> Result<()> {
+        let change = |col: &mut ColumnSnapshot| col.ty = new_ty.to_string();
+
+        match self.flavor {
+            SqlFlavor::Sqlite => self.sqlite_modify_column(table, column, old_ty, new_ty),
+            // Casting explicitly also converts between types PostgreSQL has
+            // no implicit cast for, like text to integer
+            SqlFlavor::PostgreSQL => self.alter_column(
+                "modify_column",
+                table,
+                column,
+                |_| {
+                    Ok(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{};",
+                        table, column, new_ty, column, new_ty
+                    ))
+                },
+                change,
+            ),
+            SqlFlavor::MySQL => self.alter_column(
+                "modify_column",
+                table,
+                column,
+                |audited| {
+                    let mut col = mysql_column(table, column, audited)?;
+                    col.ty = new_ty.to_string();
+                    Ok(mysql_modify_column(table, &col))
+                },
+                change,
+            ),
+        }
+    }
+
+    fn make_not_null(&mut self, table: &str, column: &str, default: &str) -> Result<()> {
+        let audited = self
+            .audited_table(table)
+            .and_then(|snapshot| snapshot.columns.iter().find(|c| c.name == column))
+            .cloned();
+
+        let set_not_null = match self.flavor {
+            SqlFlavor::PostgreSQL => {
+                format!(
+                    "ALTER TABLE {} ALTER COLUMN {} SET NOT NULL;",
+                    table, column
+                )
+            }
+            SqlFlavor::MySQL => {
+                // MODIFY COLUMN restates the whole definition
+                let Some(mut col) = audited.clone() else {
+                    return Err(anyhow::anyhow!(
+                        "MySQL can't make {}.{} NOT NULL without knowing its definition; \
+                         audit the migration",
+                        table,
+                        column
+                    ));
+                };
+                col.nullable = false;
+                mysql_modify_column(table, &col)
+            }
+            SqlFlavor::Sqlite => format!(
+                "-- SQLite: Cannot SET NOT NULL on {}.{}. Requires table recreation",
+                table, column
+            ),
+        };
+
+        self.add_statement(format!(
+            "UPDATE {} SET {} = {} WHERE {} IS NULL;",
+            table, column, default, column
+        ));
+        self.add_statement(set_not_null);
+
+        if let Some(before) = audited {
+            let after = self
+                .audited_table(table)
+                .and_then(|snapshot| snapshot.columns.iter_mut().find(|c| c.name == column))
+                .map(|col| {
+                    col.nullable = false;
+                    describe_column(col)
+                });
+            self.log_change(
+                "make_not_null",
+                table,
+                Some(column),
+                Some(describe_column(&before)),
+                after,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn backfill_column(
+        &mut self,
+        table: &str,
+        column: &str,
+        value: &str,
+        batch_size: usize,
+    ) -> Result<()> {
+        let update = format!("UPDATE {} SET {} = {}", table, column, value);
+
+        match self.flavor {
+            // Each batch commits, so row locks are released as it goes
+            SqlFlavor::PostgreSQL => self.add_statement(format!(
+                "DO $$ BEGIN LOOP {} WHERE ctid IN (SELECT ctid FROM {} WHERE {} IS NULL LIMIT {}); \
+                 EXIT WHEN NOT FOUND; COMMIT; END LOOP; END $$;",
+                update,
+                table,
+                column,
+                batch_size.max(1)
+            )),
+            // Without a procedure there is no loop to batch in
+            SqlFlavor::MySQL | SqlFlavor::Sqlite => {
+                self.add_statement(format!("{} WHERE {} IS NULL;", update, column))
+            }
+        }
 
-        self.add_statement(sql);
         Ok(())
     }
 
+    fn drop_not_null(&mut self, table: &str, column: &str) -> Result<()> {
+        let flavor = self.flavor;
+        let sql = |audited: Option<&ColumnSnapshot>| {
+            Ok(match flavor {
+                SqlFlavor::PostgreSQL => {
+                    format!(
+                        "ALTER TABLE {} ALTER COLUMN {} DROP NOT NULL;",
+                        table, column
+                    )
+                }
+                SqlFlavor::MySQL => {
+                    let mut col = mysql_column(table, column, audited)?;
+                    col.nullable = true;
+                    mysql_modify_column(table, &col)
+                }
+                SqlFlavor::Sqlite => format!(
+                    "-- SQLite: Cannot DROP NOT NULL on {}.{}. Requires table recreation",
+                    table, column
+                ),
+            })
+        };
+
+        self.alter_column("drop_not_null", table, column, sql, |col| {
+            col.nullable = true
+        })
+    }
+
+    fn set_column_default(
+        &mut self,
+        table: &str,
+        column: &str,
+        default: Option<&str>,
+    ) -> Result<()> {
+        let flavor = self.flavor;
+        let sql = |audited: Option<&ColumnSnapshot>| {
+            // MySQL spells this the same way as PostgreSQL, but needs the
+            // column's type to know whether the default must be an expression
+            let default = match (flavor, default, audited) {
+                (SqlFlavor::MySQL, Some(default), Some(col)) => {
+                    Some(mysql_default(&col.ty, default))
+                }
+                (_, default, _) => default.map(str::to_string),
+            };
+            let alter = match default {
+                Some(default) => format!("SET DEFAULT {}", default),
+                None => "DROP DEFAULT".to_string(),
+            };
+
+            Ok(match flavor {
+                SqlFlavor::PostgreSQL | SqlFlavor::MySQL => {
+                    format!("ALTER TABLE {} ALTER COLUMN {} {};", table, column, alter)
+                }
+                SqlFlavor::Sqlite => format!(
+                    "-- SQLite: Cannot change the default of {}.{}. Requires table recreation",
+                    table, column
+                ),
+            })
+        };
+
+        self.alter_column("set_column_default", table, column, sql, |col| {
+            col.default = default.map(str::to_string)
+        })
+    }
+
     fn create_index(&mut self, table: &str, index: IndexDef) -> Result<()> {
         let unique = if index.unique { "UNIQUE " } else { "" };
         let columns = index.columns.join(", ");
 
+        let include = match self.flavor {
+            _ if index.include.is_empty() => String::new(),
+            SqlFlavor::PostgreSQL => format!(" INCLUDE ({})", index.include.join(", ")),
+            SqlFlavor::Sqlite | SqlFlavor::MySQL => {
+                let database = match self.flavor {
+                    SqlFlavor::Sqlite => "SQLite",
+                    _ => "MySQL",
+                };
+                self.add_statement(format!(
+                    "-- {}: INCLUDE is not supported, index {} will not cover ({})",
+                    database,
+                    index.name,
+                    index.include.join(", ")
+                ));
+                String::new()
+            }
+        };
+
+        let predicate = match (&index.predicate, self.flavor) {
+            (None, _) => String::new(),
+            (Some(predicate), SqlFlavor::PostgreSQL | SqlFlavor::Sqlite) => {
+                format!(" WHERE {}", predicate)
+            }
+            (Some(predicate), SqlFlavor::MySQL) => {
+                self.add_statement(format!(
+                    "-- MySQL: partial indexes are not supported, index {} will cover all rows, \
+                     not just those WHERE {}",
+                    index.name, predicate
+                ));
+                String::new()
+            }
+        };
+
         let sql = format!(
-            "CREATE {}INDEX {} ON {} ({});",
-            unique, index.name, table, columns
+            "CREATE {}INDEX {} ON {} ({}){}{};",
+            unique, index.name, table, columns, include, predicate
         );
 
-        self.add_statement(sql);
+        if !self.add_table_statement(table, TableStep::Extend, sql)? {
+            return Ok(());
+        }
+
+        let snapshot = IndexSnapshot {
+            name: index.name.clone(),
+            columns: index.columns.clone(),
+            unique: index.unique,
+            primary_key: false,
+            include: index.include.clone(),
+            predicate: index.predicate.clone(),
+        };
+        let after = describe_index(table, &snapshot);
+        if let Some(table) = self.audited_table(table) {
+            table.indices.push(snapshot);
+        }
+        self.log_change("create_index", table, Some(&index.name), None, Some(after));
+
         Ok(())
     }
 
-    fn drop_index(&mut self, _table: &str, index_name: &str) -> Result<()> {
+    fn drop_index(&mut self, table: &str, index_name: &str) -> Result<()> {
         let sql = match self.flavor {
             SqlFlavor::Sqlite | SqlFlavor::PostgreSQL => {
-                format!("DROP INDEX {};", index_name)
+                format!("DROP INDEX {};", qualified_index(table, index_name))
             }
             SqlFlavor::MySQL => {
                 // MySQL requires table name
-                format!("DROP INDEX {} ON {};", index_name, _table)
+                format!("DROP INDEX {} ON {};", index_name, table)
             }
         };
 
-        self.add_statement(sql);
+        self.add_table_statement(table, TableStep::Change, sql)?;
+
+        let before = self.audited_table(table).and_then(|snapshot| {
+            let i = snapshot
+                .indices
+                .iter()
+                .position(|idx| idx.name == index_name)?;
+            Some(describe_index(table, &snapshot.indices.remove(i)))
+        });
+        self.log_change("drop_index", table, Some(index_name), before, None);
+
+        Ok(())
+    }
+
+    fn rename_index(&mut self, table: &str, from: &str, to: &str) -> Result<()> {
+        let index = self
+            .audited_table(table)
+            .and_then(|snapshot| snapshot.indices.iter_mut().find(|idx| idx.name == from));
+
+        let before = index.as_ref().map(|index| describe_index(table, index));
+        let after = index.map(|index| {
+            index.name = to.to_string();
+            index.clone()
+        });
+
+        match self.flavor {
+            SqlFlavor::PostgreSQL => {
+                self.add_statement(format!(
+                    "ALTER INDEX {} RENAME TO {};",
+                    qualified_index(table, from),
+                    to
+                ));
+            }
+            SqlFlavor::MySQL => {
+                self.add_statement(format!(
+                    "ALTER TABLE {} RENAME INDEX {} TO {};",
+                    table, from, to
+                ));
+            }
+            SqlFlavor::Sqlite => {
+                // SQLite can't rename an index, so rebuild it under the new
+                // name from its definition in the audited schema
+                let Some(index) = &after else {
+                    return Err(anyhow::anyhow!(
+                        "SQLite can't rename index {} without knowing its definition; \
+                         drop and recreate it, or audit the migration",
+                        from
+                    ));
+                };
+
+                let unique = if index.unique { "UNIQUE " } else { "" };
+                self.add_statement(format!("DROP INDEX {};", qualified_index(table, from)));
+                self.add_statement(format!(
+                    "CREATE {}INDEX {} ON {} ({});",
+                    unique,
+                    to,
+                    table,
+                    index.columns.join(", ")
+                ));
+            }
+        }
+
+        let after = after.map(|index| describe_index(table, &index));
+        self.log_change("rename_index", table, Some(to), before, after);
+
+        Ok(())
+    }
+
+    fn add_constraint(&mut self, table: &str, constraint: ConstraintDef) -> Result<()> {
+        let body = constraint_body(&constraint.kind);
+
+        let sql = match self.flavor {
+            SqlFlavor::Sqlite => {
+                return Err(anyhow::anyhow!(
+                    "SQLite cannot add constraint {} to an existing table",
+                    constraint.name
+                ));
+            }
+            SqlFlavor::PostgreSQL => {
+                let not_valid = if constraint.not_valid {
+                    " NOT VALID"
+                } else {
+                    ""
+                };
+                format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} {}{};",
+                    table, constraint.name, body, not_valid
+                )
+            }
+            SqlFlavor::MySQL => {
+                // MySQL always validates existing rows
+                format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} {};",
+                    table, constraint.name, body
+                )
+            }
+        };
+
+        if !self.add_table_statement(table, TableStep::Extend, sql)? {
+            return Ok(());
+        }
+        self.log_change(
+            "add_constraint",
+            table,
+            Some(&constraint.name),
+            None,
+            Some(body),
+        );
+
+        Ok(())
+    }
+
+    fn validate_constraint(&mut self, table: &str, name: &str) -> Result<()> {
+        // Only PostgreSQL has unvalidated constraints
+        if let SqlFlavor::PostgreSQL = self.flavor {
+            self.add_statement(format!(
+                "ALTER TABLE {} VALIDATE CONSTRAINT {};",
+                table, name
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn drop_constraint(&mut self, table: &str, constraint: ConstraintDef) -> Result<()> {
+        let sql = match (self.flavor, &constraint.kind) {
+            (SqlFlavor::Sqlite, _) => {
+                return Err(anyhow::anyhow!(
+                    "SQLite cannot drop constraint {} from an existing table",
+                    constraint.name
+                ));
+            }
+            (SqlFlavor::PostgreSQL, _) => {
+                format!("ALTER TABLE {} DROP CONSTRAINT {};", table, constraint.name)
+            }
+            (SqlFlavor::MySQL, ConstraintKind::ForeignKey { .. }) => {
+                format!(
+                    "ALTER TABLE {} DROP FOREIGN KEY {};",
+                    table, constraint.name
+                )
+            }
+            (SqlFlavor::MySQL, ConstraintKind::Check { .. }) => {
+                format!("ALTER TABLE {} DROP CHECK {};", table, constraint.name)
+            }
+        };
+
+        self.add_table_statement(table, TableStep::Change, sql)?;
+        self.log_change(
+            "drop_constraint",
+            table,
+            Some(&constraint.name),
+            Some(constraint_body(&constraint.kind)),
+            None,
+        );
+
+        Ok(())
+    }
+
+    fn comment_on_table(&mut self, table: &str, comment: &str) -> Result<()> {
+        match self.flavor {
+            SqlFlavor::Sqlite => {
+                // SQLite has no table comments
+            }
+            SqlFlavor::PostgreSQL => {
+                self.add_statement(format!(
+                    "COMMENT ON TABLE {} IS {};",
+                    table,
+                    quote_literal(comment)
+                ));
+            }
+            SqlFlavor::MySQL => {
+                let option = format!("COMMENT {}", quote_literal(comment));
+                let create_prefix = format!("CREATE TABLE {} (", table);
+
+                // Fold the comment into the table's CREATE statement when it
+                // was just emitted, otherwise alter the existing table.
+                match self.statements.last_mut() {
+                    Some(last) if last.starts_with(&create_prefix) && last.ends_with(';') => {
+                        last.truncate(last.len() - 1);
+                        last.push_str(&format!(" {};", option));
+                    }
+                    _ => self.add_statement(format!("ALTER TABLE {} {};", table, option)),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn comment_on_column(
+        &mut self,
+        table: &str,
+        column: &str,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        let mysql = match self.flavor {
+            // SQLite has no column comments, so only the tracked schema
+            // keeps it
+            SqlFlavor::Sqlite => {
+                if let Some(col) = self
+                    .audited_table(table)
+                    .and_then(|snapshot| snapshot.columns.iter_mut().find(|c| c.name == column))
+                {
+                    col.comment = comment.map(str::to_string);
+                }
+                return Ok(());
+            }
+            SqlFlavor::PostgreSQL => false,
+            SqlFlavor::MySQL => true,
+        };
+
+        // A column that was just created takes the comment in the statement
+        // that created it, which has its definition at hand
+        if let (true, Some(text)) = (mysql, comment) {
+            let folded = self
+                .statements
+                .last_mut()
+                .is_some_and(|last| mysql_fold_column_comment(last, table, column, text));
+            if folded {
+                if let Some(col) = self
+                    .audited_table(table)
+                    .and_then(|snapshot| snapshot.columns.iter_mut().find(|c| c.name == column))
+                {
+                    col.comment = Some(text.to_string());
+                }
+                return Ok(());
+            }
+        }
+
+        let sql = |audited: Option<&ColumnSnapshot>| {
+            if mysql {
+                let mut col = mysql_column(table, column, audited)?;
+                col.comment = comment.map(str::to_string);
+                return Ok(mysql_modify_column(table, &col));
+            }
+
+            Ok(format!(
+                "COMMENT ON COLUMN {}.{} IS {};",
+                table,
+                column,
+                comment.map_or("NULL".to_string(), quote_literal)
+            ))
+        };
+
+        self.alter_column("comment_on_column", table, column, sql, |col| {
+            col.comment = comment.map(str::to_string)
+        })
+    }
+
+    fn alter_table_options(&mut self, table: &str, options: TableOptions) -> Result<()> {
+        if options.is_empty() {
+            return Ok(());
+        }
+
+        let database = match self.flavor {
+            SqlFlavor::MySQL => None,
+            SqlFlavor::PostgreSQL => Some("PostgreSQL"),
+            SqlFlavor::Sqlite => Some("SQLite"),
+        };
+        if let Some(database) = database {
+            self.add_statement(format!(
+                "-- {}: Tables have no engine or charset, {} is left as is",
+                database, table
+            ));
+            return Ok(());
+        }
+
+        let create_prefix = format!("CREATE TABLE {} (", table);
+        match self.statements.last_mut() {
+            // A table that was just created gets the options in its CREATE
+            // statement, as defaults rather than a conversion
+            Some(last) if last.starts_with(&create_prefix) && last.ends_with(';') => {
+                last.truncate(last.len() - 1);
+                last.push_str(&format!(" {};", describe_options(&options)));
+            }
+            _ => {
+                let mut specs = Vec::new();
+                if let Some(engine) = &options.engine {
+                    specs.push(format!("ENGINE = {}", engine));
+                }
+                // CONVERT re-encodes existing text columns, not just the
+                // default for new ones
+                if let Some(charset) = &options.charset {
+                    specs.push(format!("CONVERT TO CHARACTER SET {}", charset));
+                }
+                self.add_statement(format!("ALTER TABLE {} {};", table, specs.join(", ")));
+            }
+        }
+
+        if let Some(snapshot) = self.audited_table(table) {
+            let before = snapshot.options.clone();
+            if options.engine.is_some() {
+                snapshot.options.engine = options.engine;
+            }
+            if options.charset.is_some() {
+                snapshot.options.charset = options.charset;
+            }
+            let after = snapshot.options.clone();
+            self.log_change(
+                "alter_table_options",
+                table,
+                None,
+                Some(describe_options(&before)),
+                Some(describe_options(&after)),
+            );
+        }
+
         Ok(())
     }
 }
@@ -142,10 +1568,32 @@ pub struct NoSqlMigrationContext {
 
 #[derive(Debug, Clone)]
 pub enum NoSqlOperation {
-    CreateCollection { name: String },
-    DropCollection { name: String },
-    CreateIndex { collection: String, index: IndexDef },
-    DropIndex { collection: String, index_name: String },
+    CreateCollection {
+        name: String,
+    },
+    DropCollection {
+        name: String,
+    },
+    RenameCollection {
+        from: String,
+        to: String,
+    },
+    CreateIndex {
+        collection: String,
+        index: IndexDef,
+    },
+    DropIndex {
+        collection: String,
+        index_name: String,
+    },
+    /// Delete every document in the collection (`deleteMany({})`)
+    DeleteMany {
+        collection: String,
+    },
+    /// Raw database command, passed through as a JSON document
+    RunCommand {
+        command: serde_json::Value,
+    },
     // NoSQL doesn't need ADD/DROP column - documents are flexible
 }
 
@@ -159,11 +1607,90 @@ impl NoSqlMigrationContext {
     pub fn operations(&self) -> &[NoSqlOperation] {
         &self.operations
     }
+
+    /// Run the recorded operations against the MongoDB database `db`, in the
+    /// order they were recorded
+    #[cfg(feature = "mongodb_introspect")]
+    pub async fn apply(&self, db: &mongodb::Database) -> Result<()> {
+        use mongodb::bson::{doc, Bson, Document};
+        use mongodb::{options::IndexOptions, IndexModel};
+
+        for operation in &self.operations {
+            match operation {
+                NoSqlOperation::CreateCollection { name } => db.create_collection(name).await?,
+                NoSqlOperation::DropCollection { name } => {
+                    db.collection::<Document>(name).drop().await?
+                }
+                NoSqlOperation::RenameCollection { from, to } => {
+                    // Only the admin database renames, given full namespaces
+                    let namespace = |name: &str| format!("{}.{}", db.name(), name);
+                    db.client()
+                        .database("admin")
+                        .run_command(doc! {
+                            "renameCollection": namespace(from),
+                            "to": namespace(to),
+                        })
+                        .await?;
+                }
+                NoSqlOperation::CreateIndex { collection, index } => {
+                    if index.predicate.is_some() {
+                        return Err(anyhow::anyhow!(
+                            "Index {} on {} has a SQL condition, which MongoDB can't apply",
+                            index.name,
+                            collection
+                        ));
+                    }
+
+                    let keys: Document = index
+                        .columns
+                        .iter()
+                        .map(|column| (column.clone(), Bson::Int32(1)))
+                        .collect();
+                    let options = IndexOptions::builder()
+                        .name(index.name.clone())
+                        .unique(index.unique)
+                        .build();
+                    db.collection::<Document>(collection)
+                        .create_index(IndexModel::builder().keys(keys).options(options).build())
+                        .await?;
+                }
+                NoSqlOperation::DropIndex {
+                    collection,
+                    index_name,
+                } => {
+                    db.collection::<Document>(collection)
+                        .drop_index(index_name)
+                        .await?
+                }
+                NoSqlOperation::DeleteMany { collection } => {
+                    db.collection::<Document>(collection)
+                        .delete_many(doc! {})
+                        .await?;
+                }
+                NoSqlOperation::RunCommand { command } => {
+                    db.run_command(mongodb::bson::to_document(command)?).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl MigrationContext for NoSqlMigrationContext {
-    fn execute_sql(&mut self, _sql: &str) -> Result<()> {
-        Err(anyhow::anyhow!("SQL execution not supported in NoSQL context"))
+    fn execute_sql(&mut self, command: &str) -> Result<()> {
+        let command: serde_json::Value = serde_json::from_str(command)
+            .map_err(|e| anyhow::anyhow!("NoSQL context expects a JSON command document: {}", e))?;
+
+        if !command.is_object() {
+            return Err(anyhow::anyhow!(
+                "NoSQL command must be a JSON object, got: {}",
+                command
+            ));
+        }
+
+        self.operations.push(NoSqlOperation::RunCommand { command });
+        Ok(())
     }
 
     fn create_table(&mut self, name: &str, _columns: Vec<ColumnDef>) -> Result<()> {
@@ -173,6 +1700,26 @@ impl MigrationContext for NoSqlMigrationContext {
         Ok(())
     }
 
+    fn create_partitioned_table(
+        &mut self,
+        name: &str,
+        columns: Vec<ColumnDef>,
+        _partition_by: PartitionBy,
+    ) -> Result<()> {
+        // Collections are sharded by the server, not partitioned
+        self.create_table(name, columns)
+    }
+
+    fn create_partition(
+        &mut self,
+        _parent: &str,
+        _name: &str,
+        _from: &str,
+        _to: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     fn drop_table(&mut self, name: &str) -> Result<()> {
         self.operations.push(NoSqlOperation::DropCollection {
             name: name.to_string(),
@@ -180,6 +1727,21 @@ impl MigrationContext for NoSqlMigrationContext {
         Ok(())
     }
 
+    fn rename_table(&mut self, from: &str, to: &str) -> Result<()> {
+        self.operations.push(NoSqlOperation::RenameCollection {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        Ok(())
+    }
+
+    fn truncate_table_with(&mut self, table: &str, _options: TruncateOptions) -> Result<()> {
+        self.operations.push(NoSqlOperation::DeleteMany {
+            collection: table.to_string(),
+        });
+        Ok(())
+    }
+
     fn add_column(&mut self, _table: &str, _column: ColumnDef) -> Result<()> {
         // NoSQL databases don't require schema changes for adding fields
         // Documents can have different fields
@@ -191,6 +1753,83 @@ impl MigrationContext for NoSqlMigrationContext {
         Ok(())
     }
 
+    fn rename_column(&mut self, table: &str, from: &str, to: &str) -> Result<()> {
+        // Documents keep their own field names, so rename the field in each
+        self.operations.push(NoSqlOperation::RunCommand {
+            command: serde_json::json!({
+                "update": table,
+                "updates": [{
+                    "q": {},
+                    "u": { "$rename": { from: to } },
+                    "multi": true,
+                }],
+            }),
+        });
+        Ok(())
+    }
+
+    fn modify_column(
+        &mut self,
+        _table: &str,
+        _column: &str,
+        _old_ty: &str,
+        _new_ty: &str,
+    ) -> Result<()> {
+        // Fields have no declared type; each document keeps the values it has
+        Ok(())
+    }
+
+    fn make_not_null(&mut self, table: &str, column: &str, default: &str) -> Result<()> {
+        // Documents have no NOT NULL; only the backfill applies
+        self.backfill_column(table, column, default, 0)
+    }
+
+    fn backfill_column(
+        &mut self,
+        table: &str,
+        column: &str,
+        value: &str,
+        _batch_size: usize,
+    ) -> Result<()> {
+        // The value has to be JSON to be stored in documents, and the server
+        // batches a multi-document update itself
+        let value: serde_json::Value = serde_json::from_str(value).map_err(|e| {
+            anyhow::anyhow!(
+                "Collection {} needs a JSON default to backfill {}: {}",
+                table,
+                column,
+                e
+            )
+        })?;
+
+        self.operations.push(NoSqlOperation::RunCommand {
+            command: serde_json::json!({
+                "update": table,
+                "updates": [{
+                    "q": { column: null },
+                    "u": { "$set": { column: value } },
+                    "multi": true,
+                }],
+            }),
+        });
+        Ok(())
+    }
+
+    fn drop_not_null(&mut self, _table: &str, _column: &str) -> Result<()> {
+        // Any field can be missing or null in a document
+        Ok(())
+    }
+
+    fn set_column_default(
+        &mut self,
+        _table: &str,
+        _column: &str,
+        _default: Option<&str>,
+    ) -> Result<()> {
+        // Documents have no defaults; the application fills them in
+        Ok(())
+    }
+
     fn create_index(&mut self, table: &str, index: IndexDef) -> Result<()> {
         self.operations.push(NoSqlOperation::CreateIndex {
             collection: table.to_string(),
@@ -206,4 +1845,45 @@ impl MigrationContext for NoSqlMigrationContext {
         });
         Ok(())
     }
+
+    fn rename_index(&mut self, table: &str, from: &str, to: &str) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Collection {} can't rename index {} in place; drop it and create {} instead",
+            table,
+            from,
+            to
+        ))
+    }
+
+    fn add_constraint(&mut self, _table: &str, _constraint: ConstraintDef) -> Result<()> {
+        // Documents have no relational constraints
+        Ok(())
+    }
+
+    fn validate_constraint(&mut self, _table: &str, _name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn drop_constraint(&mut self, _table: &str, _constraint: ConstraintDef) -> Result<()> {
+        Ok(())
+    }
+
+    fn comment_on_table(&mut self, _table: &str, _comment: &str) -> Result<()> {
+        // Collections have no comments
+        Ok(())
+    }
+
+    fn comment_on_column(
+        &mut self,
+        _table: &str,
+        _column: &str,
+        _comment: Option<&str>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn alter_table_options(&mut self, _table: &str, _options: TableOptions) -> Result<()> {
+        // Collections have no engine or charset
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
-use crate::snapshot::{SchemaSnapshot, TableSnapshot, ColumnSnapshot, IndexSnapshot};
+use crate::snapshot::{ColumnSnapshot, IndexSnapshot, SchemaSnapshot, TableOptions, TableSnapshot};
+use crate::{ConstraintDef, SqlFlavor};
 use anyhow::Result;
 
 #[derive(Debug, Clone)]
@@ -10,25 +11,89 @@ pub struct SchemaDiff {
 pub enum SchemaChange {
     // Table changes
     CreateTable(TableSnapshot),
-    DropTable(String),
+    /// The dropped table as it was, so it can be recreated
+    DropTable(TableSnapshot),
+    /// Only reported with [`DiffOptions::detect_renames`]; otherwise a
+    /// renamed table is a drop and a create. Both names are qualified.
+    RenameTable {
+        from: String,
+        to: String,
+    },
 
     // Column changes
-    AddColumn { table: String, column: ColumnSnapshot },
-    DropColumn { table: String, column: String },
-    ModifyColumn { table: String, old: ColumnSnapshot, new: ColumnSnapshot },
+    AddColumn {
+        table: String,
+        column: ColumnSnapshot,
+    },
+    /// `column` is the dropped column as it was, so it can be restored
+    DropColumn {
+        table: String,
+        column: ColumnSnapshot,
+    },
+    ModifyColumn {
+        table: String,
+        old: ColumnSnapshot,
+        new: ColumnSnapshot,
+    },
+    /// Produced from a confirmed rename, or by the differ when
+    /// [`DiffOptions::detect_renames`] is set
+    RenameColumn {
+        table: String,
+        from: String,
+        to: String,
+    },
+    /// Only the column's documentation changed; `None` has none
+    ChangeColumnComment {
+        table: String,
+        column: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+
+    /// The key columns changed. Primary key indexes are never diffed by
+    /// name, since the database names its own (`users_pkey`).
+    ChangePrimaryKey {
+        table: String,
+        old: Vec<String>,
+        new: Vec<String>,
+    },
 
     // Index changes
-    CreateIndex { table: String, index: IndexSnapshot },
-    DropIndex { table: String, index_name: String },
+    CreateIndex {
+        table: String,
+        index: IndexSnapshot,
+    },
+    /// `index` is the dropped index's definition, so it can be recreated
+    DropIndex {
+        table: String,
+        index: IndexSnapshot,
+    },
+
+    // Constraint changes
+    AddConstraint {
+        table: String,
+        constraint: ConstraintDef,
+    },
+
+    /// Storage options changed. `new` holds only the options that differ;
+    /// `old` is what the table had before.
+    AlterTableOptions {
+        table: String,
+        old: TableOptions,
+        new: TableOptions,
+    },
 }
 
 impl SchemaChange {
+    /// Whether the change loses data. Renames keep it, so they are neither
+    /// destructive nor additive.
     pub fn is_destructive(&self) -> bool {
         matches!(
             self,
             SchemaChange::DropTable(_)
                 | SchemaChange::DropColumn { .. }
                 | SchemaChange::ModifyColumn { .. }
+                | SchemaChange::ChangePrimaryKey { .. }
         )
     }
 
@@ -38,49 +103,409 @@ impl SchemaChange {
             SchemaChange::CreateTable(_)
                 | SchemaChange::AddColumn { .. }
                 | SchemaChange::CreateIndex { .. }
+                | SchemaChange::AddConstraint { .. }
         )
     }
 }
 
+/// Options for [`detect_changes_with`]
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    detect_renames: bool,
+}
+
+impl DiffOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Guess renames instead of reporting a drop and an add, which would
+    /// lose the data. A table with exactly one dropped and one added column
+    /// of the same type has that column renamed, and a dropped and a created
+    /// table with the same columns are one table renamed. Ambiguous pairings
+    /// are left as they are.
+    pub fn detect_renames(mut self, detect: bool) -> Self {
+        self.detect_renames = detect;
+        self
+    }
+}
+
 pub fn detect_changes(old: &SchemaSnapshot, new: &SchemaSnapshot) -> Result<SchemaDiff> {
+    detect_changes_with(old, new, &DiffOptions::default())
+}
+
+pub fn detect_changes_with(
+    old: &SchemaSnapshot,
+    new: &SchemaSnapshot,
+    options: &DiffOptions,
+) -> Result<SchemaDiff> {
     let mut changes = Vec::new();
 
-    // Build maps for quick lookup
-    let old_tables: std::collections::HashMap<_, _> =
-        old.tables.iter().map(|t| (&t.name, t)).collect();
-    let new_tables: std::collections::HashMap<_, _> =
-        new.tables.iter().map(|t| (&t.name, t)).collect();
+    // Build maps for quick lookup. Tables are keyed by schema too, so the
+    // same name in two schemas is two different tables.
+    let old_tables: std::collections::HashMap<_, _> = old
+        .tables
+        .iter()
+        .map(|t| ((t.schema.as_deref(), t.name.as_str()), t))
+        .collect();
+    let new_tables: std::collections::HashMap<_, _> = new
+        .tables
+        .iter()
+        .map(|t| ((t.schema.as_deref(), t.name.as_str()), t))
+        .collect();
+
+    let dropped: Vec<_> = old
+        .tables
+        .iter()
+        .filter(|t| !new_tables.contains_key(&(t.schema.as_deref(), t.name.as_str())))
+        .collect();
+    let created: Vec<_> = new
+        .tables
+        .iter()
+        .filter(|t| !old_tables.contains_key(&(t.schema.as_deref(), t.name.as_str())))
+        .collect();
+
+    // A dropped and a created table that only match each other
+    let renamed_to = |from: &TableSnapshot| {
+        let mut candidates = created.iter().filter(|to| same_columns(from, to));
+        let to = candidates.next()?;
+        let unique = candidates.next().is_none()
+            && dropped
+                .iter()
+                .filter(|other| same_columns(other, to))
+                .count()
+                == 1;
+        unique.then_some(*to)
+    };
+    let renames: Vec<_> = if options.detect_renames {
+        dropped
+            .iter()
+            .filter_map(|from| Some((*from, renamed_to(from)?)))
+            .collect()
+    } else {
+        vec![]
+    };
+    let is_renamed = |table: &TableSnapshot| {
+        renames
+            .iter()
+            .any(|(from, to)| std::ptr::eq(*from, table) || std::ptr::eq(*to, table))
+    };
 
     // Detect dropped tables
-    for (table_name, _table) in &old_tables {
-        if !new_tables.contains_key(table_name) {
-            changes.push(SchemaChange::DropTable((*table_name).clone()));
+    for table in &dropped {
+        if !is_renamed(table) {
+            changes.push(SchemaChange::DropTable((*table).clone()));
         }
     }
 
     // Detect new tables
-    for (table_name, table) in &new_tables {
-        if !old_tables.contains_key(table_name) {
+    for table in &created {
+        if !is_renamed(table) {
             changes.push(SchemaChange::CreateTable((*table).clone()));
         }
     }
 
+    // Renamed tables can still differ in their indexes
+    for (from, to) in &renames {
+        let table_name = to.qualified_name();
+        changes.push(SchemaChange::RenameTable {
+            from: from.qualified_name(),
+            to: table_name.clone(),
+        });
+        detect_table_changes(&mut changes, &table_name, from, to, options);
+    }
+
     // Detect column and index changes within existing tables
-    for (table_name, new_table) in &new_tables {
-        if let Some(old_table) = old_tables.get(table_name) {
-            detect_table_changes(&mut changes, table_name, old_table, new_table);
+    for (key, new_table) in &new_tables {
+        if let Some(old_table) = old_tables.get(key) {
+            let table_name = new_table.qualified_name();
+            detect_table_changes(&mut changes, &table_name, old_table, new_table, options);
         }
     }
 
     Ok(SchemaDiff { changes })
 }
 
+/// Normalize a column default expression so that equivalent spellings compare
+/// equal: type casts (`'active'::text`) and redundant outer parentheses are
+/// stripped, whitespace is collapsed and everything outside string literals
+/// and quoted identifiers is lowercased.
+pub fn normalize_default(expr: &str) -> String {
+    let mut expr = expr.trim();
+
+    // Strip parentheses wrapping the whole expression, e.g. SQLite's `('a')`
+    while expr.starts_with('(') && expr.ends_with(')') && wraps_whole(expr) {
+        expr = expr[1..expr.len() - 1].trim();
+    }
+
+    let mut out = String::with_capacity(expr.len());
+    let mut chars = expr.chars().peekable();
+    let mut quote = None;
+
+    while let Some(ch) = chars.next() {
+        if let Some(q) = quote {
+            out.push(ch);
+            if ch == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match ch {
+            '\'' | '"' => {
+                quote = Some(ch);
+                out.push(ch);
+            }
+            ':' if chars.peek() == Some(&':') => {
+                // Skip the cast's type name, e.g. `::character varying(255)`
+                chars.next();
+                let mut skipped_space = false;
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || matches!(next, '_' | '"' | '.' | '[' | ']') {
+                        skipped_space = false;
+                    } else if next == ' ' {
+                        skipped_space = true;
+                    } else if next == '(' {
+                        // Type modifiers like `(255)`
+                        for next in chars.by_ref() {
+                            if next == ')' {
+                                break;
+                            }
+                        }
+                        skipped_space = false;
+                        continue;
+                    } else {
+                        break;
+                    }
+                    chars.next();
+                }
+                if skipped_space {
+                    out.push(' ');
+                }
+            }
+            ch if ch.is_whitespace() => {
+                if !out.ends_with(' ') && !out.is_empty() {
+                    out.push(' ');
+                }
+            }
+            ch => out.extend(ch.to_lowercase()),
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+/// Whether the opening parenthesis at the start of `expr` closes at its end
+fn wraps_whole(expr: &str) -> bool {
+    let mut depth = 0;
+    let mut in_string = false;
+
+    for (i, ch) in expr.char_indices() {
+        match ch {
+            '\'' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return i == expr.len() - 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Normalize a column type so that spellings of the same type compare equal:
+/// case and whitespace are folded and aliases map to one name, e.g. `TEXT`
+/// and `text`, or `character varying(255)` and `varchar(255)`. MySQL's
+/// integer display widths are dropped and its `tinyint(1)` is `boolean`.
+pub fn normalize_type(ty: &str, flavor: SqlFlavor) -> String {
+    let ty = ty
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    // Split off the parameters, e.g. `(255)` or `(10, 2)`
+    let (base, params) = match ty.find('(') {
+        Some(i) => (ty[..i].trim_end(), ty[i..].replace(' ', "")),
+        None => (ty.as_str(), String::new()),
+    };
+
+    if let SqlFlavor::MySQL = flavor {
+        match base {
+            "tinyint" if params == "(1)" => return "boolean".to_string(),
+            "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" => {
+                return normalize_type(base, SqlFlavor::PostgreSQL)
+            }
+            _ => {}
+        }
+    }
+
+    let base = match base {
+        "character varying" => "varchar",
+        "character" | "bpchar" => "char",
+        "int" | "int4" => "integer",
+        "int8" => "bigint",
+        "int2" => "smallint",
+        "bool" => "boolean",
+        "float8" | "double" => "double precision",
+        "float4" => "real",
+        "decimal" => "numeric",
+        "timestamp without time zone" => "timestamp",
+        "timestamp with time zone" => "timestamptz",
+        "time without time zone" => "time",
+        "time with time zone" => "timetz",
+        base => base,
+    };
+
+    format!("{}{}", base, params)
+}
+
+/// Whether two column types are the same. Snapshots don't record which
+/// database they came from, so a type matches if any dialect reads both
+/// spellings as the same type.
+fn same_type(a: &str, b: &str) -> bool {
+    [SqlFlavor::PostgreSQL, SqlFlavor::MySQL, SqlFlavor::Sqlite]
+        .into_iter()
+        .any(|flavor| normalize_type(a, flavor) == normalize_type(b, flavor))
+}
+
+/// The default to compare for a column. An explicit `NULL` default is the
+/// same as none, and sequence-backed defaults are ignored for `#[auto]`
+/// columns, since the database fills those in itself.
+pub(crate) fn comparable_default(column: &ColumnSnapshot, auto: bool) -> Option<String> {
+    let default = normalize_default(column.default.as_deref()?);
+
+    if default == "null" || (auto && default.starts_with("nextval(")) {
+        None
+    } else {
+        Some(default)
+    }
+}
+
+/// The default to diff a column on. Generated migrations create required
+/// columns with a `''` default, which entities don't declare, so on a
+/// `NOT NULL` column that default is the same as none.
+fn diffed_default(column: &ColumnSnapshot, auto: bool) -> Option<String> {
+    comparable_default(column, auto).filter(|default| column.nullable || default != "''")
+}
+
+/// The collation to compare for a column, without identifier quoting.
+/// Names are matched case-insensitively, as MySQL and SQLite do.
+fn comparable_collation(column: &ColumnSnapshot) -> Option<String> {
+    let collation = column.collation.as_deref()?;
+    Some(collation.trim_matches(['"', '`']).to_lowercase())
+}
+
+/// Whether two indexes are the same apart from their names. The database
+/// names the index behind an inline `UNIQUE` itself (`users_email_key`), and
+/// hand-written migrations use their own conventions (`idx_users_email`),
+/// so indexes whose names don't match are matched by definition instead.
+fn same_definition(a: &IndexSnapshot, b: &IndexSnapshot) -> bool {
+    a.columns == b.columns
+        && a.unique == b.unique
+        && a.include == b.include
+        && a.predicate == b.predicate
+}
+
+/// Whether two tables in the same schema have the same columns, by name,
+/// type and nullability
+fn same_columns(a: &TableSnapshot, b: &TableSnapshot) -> bool {
+    a.schema == b.schema
+        && !a.columns.is_empty()
+        && a.columns.len() == b.columns.len()
+        && a.columns.iter().all(|col| {
+            b.columns.iter().any(|other| {
+                other.name == col.name
+                    && same_type(&other.ty, &col.ty)
+                    && other.nullable == col.nullable
+            })
+        })
+}
+
+/// The column a table's only dropped column was renamed to: the table's
+/// only added column, if it has the same type
+fn renamed_column<'a>(
+    old_table: &'a TableSnapshot,
+    new_table: &'a TableSnapshot,
+) -> Option<(&'a str, &'a str)> {
+    let mut dropped = old_table
+        .columns
+        .iter()
+        .filter(|c| !new_table.columns.iter().any(|n| n.name == c.name));
+    let mut added = new_table
+        .columns
+        .iter()
+        .filter(|c| !old_table.columns.iter().any(|o| o.name == c.name));
+
+    let (from, to) = (dropped.next()?, added.next()?);
+    if dropped.next().is_some() || added.next().is_some() || !same_type(&from.ty, &to.ty) {
+        return None;
+    }
+
+    Some((&from.name, &to.name))
+}
+
 fn detect_table_changes(
     changes: &mut Vec<SchemaChange>,
     table_name: &str,
     old_table: &TableSnapshot,
     new_table: &TableSnapshot,
+    options: &DiffOptions,
 ) {
+    // Compare the rest of the table as if the column had always had its new
+    // name, as the database's rename also renames it in keys and indexes
+    let renamed_table;
+    let old_table = match renamed_column(old_table, new_table).filter(|_| options.detect_renames) {
+        Some((from, to)) => {
+            changes.push(SchemaChange::RenameColumn {
+                table: table_name.to_string(),
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+
+            let mut table = old_table.clone();
+            let rename = |name: &mut String| {
+                if name == from {
+                    *name = to.to_string();
+                }
+            };
+            table.columns.iter_mut().for_each(|c| rename(&mut c.name));
+            table.primary_key.iter_mut().for_each(rename);
+            for index in &mut table.indices {
+                index
+                    .columns
+                    .iter_mut()
+                    .chain(&mut index.include)
+                    .for_each(rename);
+            }
+            renamed_table = table;
+            &renamed_table
+        }
+        None => old_table,
+    };
+
+    // Options the entity leaves unset keep whatever the table has.
+    // Engine and charset names are case-insensitive.
+    let changed = |old: &Option<String>, new: &Option<String>| match (old, new) {
+        (Some(old), Some(new)) if old.eq_ignore_ascii_case(new) => None,
+        (_, new) => new.clone(),
+    };
+    let options = TableOptions {
+        engine: changed(&old_table.options.engine, &new_table.options.engine),
+        charset: changed(&old_table.options.charset, &new_table.options.charset),
+    };
+    if !options.is_empty() {
+        changes.push(SchemaChange::AlterTableOptions {
+            table: table_name.to_string(),
+            old: old_table.options.clone(),
+            new: options,
+        });
+    }
+
     // Build column maps
     let old_columns: std::collections::HashMap<_, _> =
         old_table.columns.iter().map(|c| (&c.name, c)).collect();
@@ -88,11 +513,11 @@ fn detect_table_changes(
         new_table.columns.iter().map(|c| (&c.name, c)).collect();
 
     // Detect dropped columns
-    for (col_name, _col) in &old_columns {
+    for (col_name, col) in &old_columns {
         if !new_columns.contains_key(col_name) {
             changes.push(SchemaChange::DropColumn {
                 table: table_name.to_string(),
-                column: (*col_name).clone(),
+                column: (*col).clone(),
             });
         }
     }
@@ -110,50 +535,111 @@ fn detect_table_changes(
     // Detect modified columns
     for (col_name, new_col) in &new_columns {
         if let Some(old_col) = old_columns.get(col_name) {
-            // Normalize types for comparison (TEXT == text, INTEGER == integer, etc.)
-            let old_ty_normalized = old_col.ty.to_uppercase();
-            let new_ty_normalized = new_col.ty.to_uppercase();
+            // Introspected columns don't know they are `#[auto]`, so take it
+            // from whichever side does
+            let auto = old_col.auto || new_col.auto;
 
-            // Only detect as modified if types are actually different or nullable changed
-            if old_ty_normalized != new_ty_normalized || old_col.nullable != new_col.nullable {
+            // Only detect as modified if types are actually different, nullable
+            // changed, a generated column's expression changed, or the default
+            // changed beyond how it is spelled
+            if !same_type(&old_col.ty, &new_col.ty)
+                || old_col.nullable != new_col.nullable
+                || old_col.generated != new_col.generated
+                || old_col.stored != new_col.stored
+                || diffed_default(old_col, auto) != diffed_default(new_col, auto)
+                || comparable_collation(old_col) != comparable_collation(new_col)
+            {
                 changes.push(SchemaChange::ModifyColumn {
                     table: table_name.to_string(),
                     old: (*old_col).clone(),
                     new: (*new_col).clone(),
                 });
             }
+
+            if old_col.comment != new_col.comment {
+                changes.push(SchemaChange::ChangeColumnComment {
+                    table: table_name.to_string(),
+                    column: (*col_name).clone(),
+                    old: old_col.comment.clone(),
+                    new: new_col.comment.clone(),
+                });
+            }
         }
     }
 
+    // The database names primary key indexes itself, so the key is compared
+    // by its columns and its index is left out of the index comparison. A
+    // table created without a key but with a unique index on the key
+    // columns already enforces it.
+    let same_key = old_table.primary_key == new_table.primary_key
+        || (old_table.primary_key.is_empty()
+            && old_table
+                .indices
+                .iter()
+                .any(|idx| idx.unique && idx.columns == new_table.primary_key));
+    if !same_key {
+        changes.push(SchemaChange::ChangePrimaryKey {
+            table: table_name.to_string(),
+            old: old_table.primary_key.clone(),
+            new: new_table.primary_key.clone(),
+        });
+    }
+
     // Detect index changes
-    let old_indices: std::collections::HashMap<_, _> =
-        old_table.indices.iter().map(|i| (&i.name, i)).collect();
-    let new_indices: std::collections::HashMap<_, _> =
-        new_table.indices.iter().map(|i| (&i.name, i)).collect();
-
-    // Dropped indices
-    for (idx_name, _idx) in &old_indices {
-        if !new_indices.contains_key(idx_name) {
+    let old_indices: std::collections::HashMap<_, _> = old_table
+        .indices
+        .iter()
+        .filter(|i| !i.primary_key)
+        .map(|i| (&i.name, i))
+        .collect();
+    let new_indices: std::collections::HashMap<_, _> = new_table
+        .indices
+        .iter()
+        .filter(|i| !i.primary_key)
+        .map(|i| (&i.name, i))
+        .collect();
+
+    // Dropped indices: neither the name nor the definition is left. A
+    // unique index on exactly the key columns enforces the key and is kept.
+    for (idx_name, idx) in &old_indices {
+        let same_primary_key = idx.unique && idx.columns == new_table.primary_key;
+        let equivalent = new_indices
+            .values()
+            .any(|new_idx| same_definition(idx, new_idx));
+
+        if !new_indices.contains_key(idx_name) && !same_primary_key && !equivalent {
             changes.push(SchemaChange::DropIndex {
                 table: table_name.to_string(),
-                index_name: (*idx_name).clone(),
+                index: (*idx).clone(),
             });
         }
     }
 
     // New indices - check both by name AND by columns to avoid duplicates
     for (idx_name, new_idx) in &new_indices {
-        // Check if index already exists by name
-        if old_indices.contains_key(idx_name) {
+        // Check if index already exists by name. Covered columns and the
+        // predicate can't be altered in place, so a change there rebuilds
+        // the index.
+        if let Some(old_idx) = old_indices.get(idx_name) {
+            if old_idx.include != new_idx.include || old_idx.predicate != new_idx.predicate {
+                changes.push(SchemaChange::DropIndex {
+                    table: table_name.to_string(),
+                    index: (*old_idx).clone(),
+                });
+                changes.push(SchemaChange::CreateIndex {
+                    table: table_name.to_string(),
+                    index: (*new_idx).clone(),
+                });
+            }
             continue;
         }
 
         // Also check if an index with same columns exists (different name)
-        let columns_match = old_indices.values().any(|old_idx| {
-            old_idx.columns == new_idx.columns && old_idx.unique == new_idx.unique
-        });
+        let equivalent = old_indices
+            .values()
+            .any(|old_idx| same_definition(old_idx, new_idx));
 
-        if !columns_match {
+        if !equivalent {
             changes.push(SchemaChange::CreateIndex {
                 table: table_name.to_string(),
                 index: (*new_idx).clone(),
@@ -0,0 +1,194 @@
+use crate::snapshot::{split_table_name, ColumnSnapshot, SchemaSnapshot, TableSnapshot};
+use std::fmt::Write;
+
+/// Diagram languages a [`SchemaSnapshot`] can be rendered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagramFormat {
+    /// DBML, as read by dbdiagram.io
+    Dbml,
+    /// PlantUML's entity-relationship diagrams
+    PlantUml,
+}
+
+impl DiagramFormat {
+    /// The format for a `--format` value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "dbml" => Some(DiagramFormat::Dbml),
+            "plantuml" | "puml" => Some(DiagramFormat::PlantUml),
+            _ => None,
+        }
+    }
+}
+
+impl SchemaSnapshot {
+    /// Render the tables, columns and foreign keys as an ER diagram
+    pub fn to_diagram(&self, format: DiagramFormat) -> String {
+        match format {
+            DiagramFormat::Dbml => self.to_dbml(),
+            DiagramFormat::PlantUml => self.to_plantuml(),
+        }
+    }
+
+    /// Render the schema in DBML, one `Table` block per table followed by a
+    /// `Ref` per foreign key column
+    pub fn to_dbml(&self) -> String {
+        let mut out = String::new();
+
+        for table in &self.tables {
+            writeln!(out, "Table {} {{", dbml_table_name(table)).unwrap();
+
+            for column in &table.columns {
+                let mut settings = vec![];
+                if table.primary_key.len() == 1 && table.primary_key[0] == column.name {
+                    settings.push("pk".to_string());
+                } else if !column.nullable {
+                    settings.push("not null".to_string());
+                }
+                if column.auto {
+                    settings.push("increment".to_string());
+                }
+                if let Some(default) = &column.default {
+                    settings.push(format!("default: `{}`", default));
+                }
+
+                write!(out, "  {} {}", dbml_ident(&column.name), dbml_type(column)).unwrap();
+                if !settings.is_empty() {
+                    write!(out, " [{}]", settings.join(", ")).unwrap();
+                }
+                out.push('\n');
+            }
+
+            // A composite key can't be marked on a single column
+            if table.primary_key.len() > 1 {
+                let columns: Vec<_> = table.primary_key.iter().map(|c| dbml_ident(c)).collect();
+                writeln!(
+                    out,
+                    "\n  indexes {{\n    ({}) [pk]\n  }}",
+                    columns.join(", ")
+                )
+                .unwrap();
+            }
+
+            if let Some(comment) = &table.comment {
+                writeln!(
+                    out,
+                    "\n  Note: '{}'",
+                    comment.replace('\\', "\\\\").replace('\'', "\\'")
+                )
+                .unwrap();
+            }
+
+            out.push_str("}\n\n");
+        }
+
+        for table in &self.tables {
+            for fk in &table.foreign_keys {
+                writeln!(
+                    out,
+                    "Ref: {}.{} > {}.{}",
+                    dbml_table_name(table),
+                    dbml_ident(&fk.column),
+                    dbml_qualified_name(&fk.references_table),
+                    dbml_ident(&fk.references_column),
+                )
+                .unwrap();
+            }
+        }
+
+        out.trim_end().to_string() + "\n"
+    }
+
+    /// Render the schema as a PlantUML ER diagram: an `entity` per table with
+    /// its key columns above the line, and a crow's foot link per foreign key
+    pub fn to_plantuml(&self) -> String {
+        let mut out = String::from("@startuml\nhide circle\nskinparam linetype ortho\n\n");
+
+        for table in &self.tables {
+            writeln!(
+                out,
+                "entity \"{}\" as {} {{",
+                table.qualified_name(),
+                plantuml_alias(&table.qualified_name())
+            )
+            .unwrap();
+
+            let (keys, rest): (Vec<_>, Vec<_>) = table
+                .columns
+                .iter()
+                .partition(|column| table.primary_key.contains(&column.name));
+
+            for column in &keys {
+                writeln!(out, "  * {} : {} <<PK>>", column.name, column.ty).unwrap();
+            }
+            out.push_str("  --\n");
+            for column in &rest {
+                let required = if column.nullable { "" } else { "* " };
+                let fk = if table.foreign_keys.iter().any(|fk| fk.column == column.name) {
+                    " <<FK>>"
+                } else {
+                    ""
+                };
+                writeln!(out, "  {}{} : {}{}", required, column.name, column.ty, fk).unwrap();
+            }
+
+            out.push_str("}\n\n");
+        }
+
+        for table in &self.tables {
+            for fk in &table.foreign_keys {
+                let nullable = table
+                    .columns
+                    .iter()
+                    .any(|column| column.name == fk.column && column.nullable);
+
+                writeln!(
+                    out,
+                    "{} }}o--{} {} : {}",
+                    plantuml_alias(&table.qualified_name()),
+                    if nullable { "o|" } else { "||" },
+                    plantuml_alias(&fk.references_table),
+                    fk.column,
+                )
+                .unwrap();
+            }
+        }
+
+        out.push_str("@enduml\n");
+        out
+    }
+}
+
+fn dbml_table_name(table: &TableSnapshot) -> String {
+    dbml_qualified_name(&table.qualified_name())
+}
+
+fn dbml_qualified_name(name: &str) -> String {
+    match split_table_name(name) {
+        (Some(schema), table) => format!("{}.{}", dbml_ident(schema), dbml_ident(table)),
+        (None, table) => dbml_ident(table),
+    }
+}
+
+/// Quote names DBML wouldn't read as a bare identifier
+fn dbml_ident(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\\\""))
+    }
+}
+
+/// Multi-word types like `timestamp with time zone` must be quoted
+fn dbml_type(column: &ColumnSnapshot) -> String {
+    if column.ty.contains(' ') {
+        format!("\"{}\"", column.ty)
+    } else {
+        column.ty.clone()
+    }
+}
+
+/// PlantUML aliases can't contain the `.` of a schema-qualified name
+fn plantuml_alias(name: &str) -> String {
+    name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_")
+}
@@ -1,108 +1,990 @@
-use crate::diff::{SchemaChange, SchemaDiff};
+use crate::diff::{comparable_default, detect_changes, SchemaChange, SchemaDiff};
+use crate::snapshot::{
+    ColumnSnapshot, IndexSnapshot, PartitionBy, PartitionStrategy, TableOptions, TableSnapshot,
+};
+use crate::source::SchemaSource;
+use crate::{
+    ColumnDef, ConstraintDef, ConstraintKind, IndexDef, MigrationContext, SqlFlavor,
+    SqlMigrationContext,
+};
 use anyhow::Result;
 
+/// Render a `Vec<String>` literal for generated migration code
+fn string_vec_code(items: &[String]) -> String {
+    let items = items
+        .iter()
+        .map(|item| format!("\"{}\".into()", item))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("vec![{}]", items)
+}
+
+/// Render a `ConstraintDef { .. }` literal for generated migration code
+fn constraint_def_code(constraint: &ConstraintDef, not_valid: bool) -> String {
+    let kind = match &constraint.kind {
+        ConstraintKind::ForeignKey {
+            columns,
+            references_table,
+            references_columns,
+        } => format!(
+            "ConstraintKind::ForeignKey {{ columns: {}, references_table: \"{}\".into(), references_columns: {} }}",
+            string_vec_code(columns),
+            references_table,
+            string_vec_code(references_columns)
+        ),
+        ConstraintKind::Check { expr } => {
+            format!("ConstraintKind::Check {{ expr: {:?}.into() }}", expr)
+        }
+    };
+
+    format!(
+        "ConstraintDef {{ name: \"{}\".into(), kind: {}, not_valid: {} }}",
+        constraint.name, kind, not_valid
+    )
+}
+
+/// The `ColumnDef` a generated migration builds for `col`
+fn column_def(col: &ColumnSnapshot, default: Option<String>) -> ColumnDef {
+    ColumnDef {
+        name: col.name.clone(),
+        ty: col.ty.clone(),
+        nullable: col.nullable,
+        default: if col.generated.is_some() {
+            None
+        } else {
+            default
+        },
+        generated: col.generated.clone(),
+        stored: col.stored,
+        collation: col.collation.clone(),
+        primary_key: false,
+        references: None,
+    }
+}
+
+fn index_def(index: &IndexSnapshot) -> IndexDef {
+    IndexDef {
+        name: index.name.clone(),
+        columns: index.columns.clone(),
+        unique: index.unique,
+        include: index.include.clone(),
+        predicate: index.predicate.clone(),
+    }
+}
+
+/// Render an `IndexDef { .. }` literal for generated migration code
+fn index_def_code(index: &IndexSnapshot) -> String {
+    let quoted = |columns: &[String]| {
+        columns
+            .iter()
+            .map(|c| format!("\"{}\".into()", c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut fields = format!(
+        "name: \"{}\".into(), columns: vec![{}], unique: {}",
+        index.name,
+        quoted(&index.columns),
+        index.unique
+    );
+
+    if !index.include.is_empty() {
+        fields.push_str(&format!(", include: vec![{}]", quoted(&index.include)));
+    }
+    if let Some(predicate) = &index.predicate {
+        fields.push_str(&format!(", predicate: Some({:?}.into())", predicate));
+    }
+
+    format!("IndexDef {{ {}, ..Default::default() }}", fields)
+}
+
+/// A `db.comment_on_column(...)` call for generated migration code
+fn comment_on_column_code(table: &str, column: &str, comment: Option<&str>) -> String {
+    let comment = match comment {
+        Some(comment) => format!("Some({:?})", comment),
+        None => "None".to_string(),
+    };
+    format!(
+        "db.comment_on_column(\"{}\", \"{}\", {})?;",
+        table, column, comment
+    )
+}
+
+/// Render a `TableOptions { .. }` literal for generated migration code
+fn table_options_code(options: &TableOptions) -> String {
+    let mut fields = Vec::new();
+    if let Some(engine) = &options.engine {
+        fields.push(format!("engine: Some({:?}.into())", engine));
+    }
+    if let Some(charset) = &options.charset {
+        fields.push(format!("charset: Some({:?}.into())", charset));
+    }
+    fields.push("..Default::default()".to_string());
+
+    format!("TableOptions {{ {} }}", fields.join(", "))
+}
+
+/// Render a `PartitionBy { .. }` literal for generated migration code
+fn partition_by_code(partition_by: &PartitionBy) -> String {
+    format!(
+        "PartitionBy {{ strategy: PartitionStrategy::{:?}, columns: {} }}",
+        partition_by.strategy,
+        string_vec_code(&partition_by.columns)
+    )
+}
+
+/// The options to set to undo `new`: each changed option goes back to its
+/// `old` value, where there was one
+fn reverted_options(old: &TableOptions, new: &TableOptions) -> TableOptions {
+    TableOptions {
+        engine: new.engine.as_ref().and(old.engine.clone()),
+        charset: new.charset.as_ref().and(old.charset.clone()),
+    }
+}
+
+/// Comments asking for a primary key change from `old` to `new` columns to
+/// be written by hand
+fn primary_key_change_code(table: &str, old: &[String], new: &[String]) -> Vec<String> {
+    vec![
+        format!(
+            "// Change primary key of {} from ({}) to ({})",
+            table,
+            old.join(", "),
+            new.join(", ")
+        ),
+        "// TODO: Drop the old key and add the new one; existing rows must be unique on the new columns"
+            .to_string(),
+    ]
+}
+
+/// Create `table` with its columns, options, comment and indices. With
+/// `restore`, columns get their recorded defaults, as when a dropped table is
+/// recreated; otherwise required columns default to `''`.
+fn create_table(db: &mut dyn MigrationContext, table: &TableSnapshot, restore: bool) -> Result<()> {
+    let columns = table
+        .columns
+        .iter()
+        .map(|col| {
+            // Key columns always get a value; a default would only hide a
+            // missing one
+            let primary_key = table.primary_key.contains(&col.name);
+            let default = if primary_key {
+                None
+            } else if restore {
+                col.default.clone()
+            } else {
+                (!col.nullable).then(|| "''".to_string())
+            };
+            ColumnDef {
+                primary_key,
+                nullable: col.nullable && !primary_key,
+                ..column_def(col, default)
+            }
+        })
+        .collect();
+    let name = table.qualified_name();
+    match &table.partition_by {
+        Some(partition_by) => db.create_partitioned_table(&name, columns, partition_by.clone())?,
+        None => db.create_table(&name, columns)?,
+    }
+
+    if !table.options.is_empty() {
+        db.alter_table_options(&name, table.options.clone())?;
+    }
+
+    if let Some(comment) = &table.comment {
+        db.comment_on_table(&name, comment)?;
+    }
+
+    for col in &table.columns {
+        if let Some(comment) = &col.comment {
+            db.comment_on_column(&name, &col.name, Some(comment))?;
+        }
+    }
+
+    for index in &table.indices {
+        if !index.primary_key && !index.columns.is_empty() {
+            db.create_index(&name, index_def(index))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Generated code for [`create_table`]
+fn create_table_code(table: &TableSnapshot, restore: bool) -> Vec<String> {
+    let mut statements = Vec::new();
+
+    // Generate column definitions
+    let mut column_defs = Vec::new();
+    for col in &table.columns {
+        // Key columns are always required and never defaulted
+        let primary_key = table.primary_key.contains(&col.name);
+        let col = ColumnSnapshot {
+            nullable: col.nullable && !primary_key,
+            ..col.clone()
+        };
+        let default_val = match &col.default {
+            _ if primary_key => "None".to_string(),
+            Some(default) if restore => format!("Some({:?}.into())", default),
+            _ if col.nullable || restore => "None".to_string(),
+            _ => "Some(\"''\".into())".to_string(),
+        };
+        column_defs.push(format!(
+            "            {}",
+            column_def_code(&col, &default_val, primary_key)
+        ));
+    }
+
+    let columns_str = if column_defs.is_empty() {
+        "vec![]".to_string()
+    } else {
+        format!("vec![\n{}\n        ]", column_defs.join(",\n"))
+    };
+
+    match &table.partition_by {
+        Some(partition_by) => {
+            statements.push(format!(
+                "db.create_partitioned_table(\"{}\", {}, {})?;",
+                table.qualified_name(),
+                columns_str,
+                partition_by_code(partition_by)
+            ));
+            // Rows need a partition to go to
+            if partition_by.strategy == PartitionStrategy::Range {
+                statements.push("// Add partitions before inserting rows, e.g.:".to_string());
+                statements.push(format!(
+                    "// db.create_partition(\"{0}\", \"{0}_2025_01\", \"'2025-01-01'\", \"'2025-02-01'\")?;",
+                    table.qualified_name()
+                ));
+            }
+        }
+        None => statements.push(format!(
+            "db.create_table(\"{}\", {})?;",
+            table.qualified_name(),
+            columns_str
+        )),
+    }
+
+    if !table.options.is_empty() {
+        statements.push(format!(
+            "db.alter_table_options(\"{}\", {})?;",
+            table.qualified_name(),
+            table_options_code(&table.options)
+        ));
+    }
+
+    if let Some(comment) = &table.comment {
+        statements.push(format!(
+            "db.comment_on_table(\"{}\", {:?})?;",
+            table.qualified_name(),
+            comment
+        ));
+    }
+
+    for col in &table.columns {
+        if let Some(comment) = &col.comment {
+            let name = table.qualified_name();
+            statements.push(comment_on_column_code(&name, &col.name, Some(comment)));
+        }
+    }
+
+    // Generate index definitions
+    for index in &table.indices {
+        if !index.primary_key && !index.columns.is_empty() {
+            statements.push(format!(
+                "db.create_index(\"{}\", {})?;",
+                table.qualified_name(),
+                index_def_code(index)
+            ));
+        }
+    }
+
+    statements
+}
+
+/// Render a `ColumnDef { .. }` literal for generated migration code
+fn column_def_code(col: &ColumnSnapshot, default: &str, primary_key: bool) -> String {
+    let mut fields = format!(
+        "name: \"{}\".into(), ty: \"{}\".into(), nullable: {}",
+        col.name, col.ty, col.nullable
+    );
+
+    if let Some(expr) = &col.generated {
+        fields.push_str(&format!(
+            ", generated: Some({:?}.into()), stored: {}",
+            expr, col.stored
+        ));
+    } else {
+        fields.push_str(&format!(", default: {}", default));
+    }
+
+    if let Some(collation) = &col.collation {
+        fields.push_str(&format!(", collation: Some({:?}.into())", collation));
+    }
+
+    if primary_key {
+        fields.push_str(", primary_key: true");
+    }
+
+    format!("ColumnDef {{ {}, ..Default::default() }}", fields)
+}
+
+/// One part of changing a column in place. A modify touching several of
+/// type, nullability and default runs its steps in the order listed: the old
+/// default may not cast to the new type, and `NULL`s are backfilled with the
+/// new default.
+enum ColumnStep<'a> {
+    DropDefault,
+    DropNotNull,
+    ChangeType {
+        from: &'a str,
+        to: &'a str,
+    },
+    /// A change of generated expression or collation, which needs a
+    /// hand-written conversion
+    Convert,
+    SetDefault(&'a str),
+    MakeNotNull(&'a str),
+    /// `NOT NULL` with no default to backfill `NULL`s with
+    RequireNotNull,
+}
+
+/// The steps that turn column `old` into `new`, leaving out any part that
+/// didn't change
+fn column_steps<'a>(old: &'a ColumnSnapshot, new: &'a ColumnSnapshot) -> Vec<ColumnStep<'a>> {
+    let auto = old.auto || new.auto;
+    let retype = !old.ty.eq_ignore_ascii_case(&new.ty);
+    let convert = old.generated != new.generated
+        || old.stored != new.stored
+        || old.collation != new.collation;
+    let rewrite = retype || convert;
+
+    let old_default = comparable_default(old, auto);
+    let new_default = new
+        .default
+        .as_deref()
+        .filter(|_| comparable_default(new, auto).is_some());
+    let default_changed = rewrite || old_default != comparable_default(new, auto);
+
+    let mut steps = Vec::new();
+    if default_changed && old_default.is_some() && (rewrite || new_default.is_none()) {
+        steps.push(ColumnStep::DropDefault);
+    }
+    if !old.nullable && new.nullable {
+        steps.push(ColumnStep::DropNotNull);
+    }
+    if retype {
+        steps.push(ColumnStep::ChangeType {
+            from: &old.ty,
+            to: &new.ty,
+        });
+    }
+    if convert {
+        steps.push(ColumnStep::Convert);
+    }
+    if let Some(default) = new_default.filter(|_| default_changed) {
+        steps.push(ColumnStep::SetDefault(default));
+    }
+    if old.nullable && !new.nullable {
+        steps.push(match new_default {
+            Some(default) => ColumnStep::MakeNotNull(default),
+            None => ColumnStep::RequireNotNull,
+        });
+    }
+
+    steps
+}
+
+/// Generated migration code turning column `old` into `new`
+fn column_steps_code(table: &str, old: &ColumnSnapshot, new: &ColumnSnapshot) -> Vec<String> {
+    let column = &new.name;
+    let mut statements = Vec::new();
+
+    for step in column_steps(old, new) {
+        match step {
+            ColumnStep::DropDefault => statements.push(format!(
+                "db.set_column_default(\"{}\", \"{}\", None)?;",
+                table, column
+            )),
+            ColumnStep::DropNotNull => {
+                statements.push(format!("db.drop_not_null(\"{}\", \"{}\")?;", table, column))
+            }
+            ColumnStep::ChangeType { from, to } => statements.push(format!(
+                "db.modify_column(\"{}\", \"{}\", {:?}, {:?})?;",
+                table, column, from, to
+            )),
+            ColumnStep::Convert => {
+                statements.push(format!("// Modify column {}.{}", table, column));
+                statements.push(
+                    "// TODO: Implement column modification with data conversion".to_string(),
+                );
+            }
+            ColumnStep::SetDefault(default) => statements.push(format!(
+                "db.set_column_default(\"{}\", \"{}\", Some({:?}))?;",
+                table, column, default
+            )),
+            ColumnStep::MakeNotNull(default) => statements.push(format!(
+                "db.make_not_null(\"{}\", \"{}\", {:?})?;",
+                table, column, default
+            )),
+            ColumnStep::RequireNotNull => {
+                statements.push(format!("// Make column {}.{} NOT NULL", table, column));
+                statements.push(
+                    "// TODO: Backfill NULLs first; the column has no default to fill them with"
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    statements
+}
+
+/// Run the steps turning column `old` into `new` on `db`. Conversions other
+/// than a type change are left out, as generated code leaves them to be
+/// written by hand.
+fn apply_column_steps(
+    db: &mut dyn MigrationContext,
+    table: &str,
+    old: &ColumnSnapshot,
+    new: &ColumnSnapshot,
+) -> Result<()> {
+    let column = &new.name;
+
+    for step in column_steps(old, new) {
+        match step {
+            ColumnStep::DropDefault => db.set_column_default(table, column, None)?,
+            ColumnStep::DropNotNull => db.drop_not_null(table, column)?,
+            ColumnStep::ChangeType { from, to } => db.modify_column(table, column, from, to)?,
+            ColumnStep::SetDefault(default) => {
+                db.set_column_default(table, column, Some(default))?
+            }
+            ColumnStep::MakeNotNull(default) => db.make_not_null(table, column, default)?,
+            ColumnStep::Convert | ColumnStep::RequireNotNull => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Default rows per batch when backfilling an added column
+const BACKFILL_BATCH_SIZE: usize = 10_000;
+
+/// Whether a default expression can give each row a different value, which
+/// makes PostgreSQL rewrite the table to add a column with it
+fn is_volatile_default(expr: &str) -> bool {
+    const VOLATILE: &[&str] = &[
+        "random(",
+        "gen_random_uuid(",
+        "uuid_generate_v",
+        "clock_timestamp(",
+        "timeofday(",
+        "nextval(",
+    ];
+
+    let expr = expr.to_ascii_lowercase();
+    VOLATILE.iter().any(|f| expr.contains(f))
+}
+
+/// How an added column gets its values for existing rows
+enum AddColumnPlan<'a> {
+    /// Add the column as described, without a default
+    Plain,
+    /// Add the column with its default in one statement
+    WithDefault(&'a str),
+    /// Add the column as nullable with the default, then backfill existing
+    /// rows (in batches when `batched`) and make it `NOT NULL` in
+    /// `followup()`
+    Split { default: &'a str, batched: bool },
+}
+
+/// Turn a migration description into the slug used in its version and file
+/// name: lowercase `[a-z0-9_]` with runs of anything else collapsed into a
+/// single `_`
+pub fn migration_slug(description: &str) -> Result<String> {
+    let mut slug = String::new();
+
+    for ch in description.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
+        }
+    }
+
+    let slug = slug.trim_end_matches('_');
+    if slug.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Migration description {:?} has no letters or digits to name the migration with",
+            description
+        ));
+    }
+
+    Ok(slug.to_string())
+}
+
+/// Whether `name` can be used as a Rust identifier
+fn is_rust_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let starts_ok = matches!(chars.next(), Some(ch) if ch.is_ascii_alphabetic() || ch == '_');
+    starts_ok && name != "_" && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
 pub struct MigrationGenerator {
     pub migration_dir: std::path::PathBuf,
+    safe_constraints: bool,
+    safe_add_column: bool,
+    postgres_version: Option<u32>,
+    batch_size: usize,
+    sql_down: Option<SqlFlavor>,
+    template_dir: Option<std::path::PathBuf>,
 }
 
 impl MigrationGenerator {
     pub fn new(migration_dir: impl Into<std::path::PathBuf>) -> Self {
         Self {
             migration_dir: migration_dir.into(),
+            safe_constraints: false,
+            safe_add_column: false,
+            postgres_version: None,
+            batch_size: BACKFILL_BATCH_SIZE,
+            sql_down: None,
+            template_dir: None,
         }
     }
 
+    /// Add constraints as `NOT VALID` in `up()` and validate them in
+    /// `followup()`, which runs outside the migration transaction. This
+    /// avoids holding a table lock while existing rows are checked.
+    pub fn safe_constraints(mut self, enabled: bool) -> Self {
+        self.safe_constraints = enabled;
+        self
+    }
+
+    /// Add `NOT NULL` columns with a default without rewriting the table.
+    /// PostgreSQL 11+ does this itself for defaults that are the same for
+    /// every row; otherwise the column is added as nullable and existing rows
+    /// are backfilled in batches before `followup()` makes it `NOT NULL`.
+    /// Without a `postgres_version`, every such column is split.
+    pub fn safe_add_column(mut self, enabled: bool) -> Self {
+        self.safe_add_column = enabled;
+        self
+    }
+
+    /// Rows updated per batch when backfilling a column added with
+    /// [`safe_add_column`](Self::safe_add_column). Defaults to 10,000.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// The PostgreSQL server the migration targets, as its
+    /// `server_version_num` (e.g. `160002`)
+    pub fn postgres_version(mut self, version: u32) -> Self {
+        self.postgres_version = Some(version);
+        self
+    }
+
+    /// Write `down()` as literal `db.execute_sql(...)` calls in the given
+    /// dialect instead of structural calls like `db.drop_table(...)`, so the
+    /// rollback can be reviewed and edited as SQL. Generation fails when a
+    /// change can't be reversed in SQL, like a primary key change or a
+    /// column conversion.
+    pub fn sql_down(mut self, flavor: SqlFlavor) -> Self {
+        self.sql_down = Some(flavor);
+        self
+    }
+
+    /// Render migration files from `migration.rs.tmpl` in `dir` instead of
+    /// the built-in template. Placeholders are `{{version}}`,
+    /// `{{struct_name}}`, `{{up}}`, `{{down}}` and `{{followup}}`, which
+    /// expands to the whole `followup()` method when there is one.
+    pub fn template_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.template_dir = Some(dir.into());
+        self
+    }
+
     pub fn generate(&self, diff: &SchemaDiff, description: &str) -> Result<MigrationFile> {
         let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
-        let version = format!("{}_{}", timestamp, description.replace(' ', "_"));
-        let filename = format!("{}.rs", version);
+        let version = format!("{}_{}", timestamp, migration_slug(description)?);
+
+        self.generate_version(diff, &version)
+    }
 
+    /// Generate a migration for `diff` under an existing `version`, e.g. to
+    /// rewrite a migration that has not been applied yet
+    pub fn generate_version(&self, diff: &SchemaDiff, version: &str) -> Result<MigrationFile> {
         let migration = MigrationFile {
-            version: version.clone(),
-            filename,
+            version: version.to_string(),
+            filename: format!("{}.rs", version),
             up_statements: self.generate_up_statements(&diff.changes)?,
             down_statements: self.generate_down_statements(&diff.changes)?,
+            followup_statements: self.generate_followup_statements(&diff.changes),
         };
 
         Ok(migration)
     }
 
+    /// Diff the schema from `current` against `desired` and generate a
+    /// migration for the changes, or `None` when they already match.
+    pub async fn generate_from(
+        &self,
+        current: &dyn SchemaSource,
+        desired: &dyn SchemaSource,
+        description: &str,
+    ) -> Result<Option<MigrationFile>> {
+        // Catch what would only fail on apply, like two indexes sharing a name
+        let desired = desired.snapshot().await?;
+        desired.validate()?;
+
+        let diff = detect_changes(&current.snapshot().await?, &desired)?;
+
+        if diff.changes.is_empty() {
+            return Ok(None);
+        }
+
+        self.generate(&diff, description).map(Some)
+    }
+
+    /// Perform the operations the generated migration's `up()` and
+    /// `followup()` would, against `db`. With a `SqlMigrationContext` this
+    /// renders the migration's SQL without compiling or applying it.
+    pub fn apply(&self, diff: &SchemaDiff, db: &mut dyn MigrationContext) -> Result<()> {
+        for change in &diff.changes {
+            match change {
+                SchemaChange::CreateTable(table) => create_table(db, table, false)?,
+                SchemaChange::DropTable(table) => db.drop_table(&table.qualified_name())?,
+                SchemaChange::RenameTable { from, to } => db.rename_table(from, to)?,
+                SchemaChange::AddColumn { table, column } => match self.add_column_plan(column) {
+                    AddColumnPlan::Plain => db.add_column(table, column_def(column, None))?,
+                    AddColumnPlan::WithDefault(default) => {
+                        db.add_column(table, column_def(column, Some(default.to_string())))?
+                    }
+                    AddColumnPlan::Split { default, .. } => {
+                        let column = ColumnSnapshot {
+                            nullable: true,
+                            ..column.clone()
+                        };
+                        db.add_column(table, column_def(&column, None))?;
+                        db.set_column_default(table, &column.name, Some(default))?;
+                    }
+                },
+                SchemaChange::DropColumn { table, column } => {
+                    db.drop_column(table, &column.name)?
+                }
+                SchemaChange::RenameColumn { table, from, to } => {
+                    db.rename_column(table, from, to)?
+                }
+                SchemaChange::ChangeColumnComment {
+                    table, column, new, ..
+                } => db.comment_on_column(table, column, new.as_deref())?,
+                SchemaChange::ModifyColumn { table, old, new } => {
+                    apply_column_steps(db, table, old, new)?
+                }
+                SchemaChange::CreateIndex { table, index } => {
+                    db.create_index(table, index_def(index))?
+                }
+                SchemaChange::DropIndex { table, index } => db.drop_index(table, &index.name)?,
+                SchemaChange::AddConstraint { table, constraint } => {
+                    let mut constraint = constraint.clone();
+                    constraint.not_valid = self.safe_constraints;
+                    db.add_constraint(table, constraint)?;
+                }
+                SchemaChange::AlterTableOptions { table, new, .. } => {
+                    db.alter_table_options(table, new.clone())?
+                }
+                // Left to be written by hand, like column conversions
+                SchemaChange::ChangePrimaryKey { .. } => {}
+            }
+
+            if let SchemaChange::AddColumn { table, column } = change {
+                if let Some(comment) = &column.comment {
+                    db.comment_on_column(table, &column.name, Some(comment))?;
+                }
+            }
+        }
+
+        for change in &diff.changes {
+            match change {
+                SchemaChange::AddColumn { table, column } => {
+                    if let AddColumnPlan::Split { default, batched } = self.add_column_plan(column)
+                    {
+                        if batched {
+                            db.backfill_column(table, &column.name, default, self.batch_size)?;
+                        }
+                        db.make_not_null(table, &column.name, default)?;
+                    }
+                }
+                SchemaChange::AddConstraint { table, constraint } if self.safe_constraints => {
+                    db.validate_constraint(table, &constraint.name)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_column_plan<'a>(&self, column: &'a ColumnSnapshot) -> AddColumnPlan<'a> {
+        let default = match &column.default {
+            Some(default)
+                if self.safe_add_column && !column.nullable && column.generated.is_none() =>
+            {
+                default
+            }
+            _ => return AddColumnPlan::Plain,
+        };
+
+        // Older servers rewrite the table for any default
+        let fast_default = self.postgres_version.is_some_and(|v| v >= 110_000);
+        if fast_default && !is_volatile_default(default) {
+            return AddColumnPlan::WithDefault(default);
+        }
+
+        AddColumnPlan::Split {
+            default,
+            // Committing between batches needs PostgreSQL 11+
+            batched: self.postgres_version.is_none_or(|v| v >= 110_000),
+        }
+    }
+
     fn generate_up_statements(&self, changes: &[SchemaChange]) -> Result<Vec<String>> {
         let mut statements = Vec::new();
 
         for change in changes {
             match change {
                 SchemaChange::CreateTable(table) => {
-                    // Generate column definitions
-                    let mut column_defs = Vec::new();
-                    for col in &table.columns {
-                        let default_val = col.nullable.then(|| "None".to_string())
-                            .or(Some("Some(\"''\".into())".to_string()))
-                            .unwrap();
-                        column_defs.push(format!(
-                            "            ColumnDef {{ name: \"{}\".into(), ty: \"{}\".into(), nullable: {}, default: {} }}",
-                            col.name, col.ty, col.nullable, default_val
+                    statements.extend(create_table_code(table, false))
+                }
+                SchemaChange::DropTable(table) => {
+                    statements.push(format!("db.drop_table(\"{}\")?;", table.qualified_name()));
+                }
+                SchemaChange::RenameTable { from, to } => {
+                    statements.push(format!("db.rename_table(\"{}\", \"{}\")?;", from, to));
+                }
+                SchemaChange::AddColumn { table, column } => match self.add_column_plan(column) {
+                    AddColumnPlan::Plain => statements.push(format!(
+                        "db.add_column(\"{}\", {})?;",
+                        table,
+                        column_def_code(column, "None", false)
+                    )),
+                    AddColumnPlan::WithDefault(default) => statements.push(format!(
+                        "db.add_column(\"{}\", {})?;",
+                        table,
+                        column_def_code(column, &format!("Some({:?}.into())", default), false)
+                    )),
+                    AddColumnPlan::Split { default, .. } => {
+                        let nullable = ColumnSnapshot {
+                            nullable: true,
+                            ..column.clone()
+                        };
+                        statements.push(format!(
+                            "// Add {}.{} as nullable; followup() backfills it and makes it NOT NULL",
+                            table, column.name
+                        ));
+                        statements.push(format!(
+                            "db.add_column(\"{}\", {})?;",
+                            table,
+                            column_def_code(&nullable, "None", false)
+                        ));
+                        statements.push(format!(
+                            "db.set_column_default(\"{}\", \"{}\", Some({:?}))?;",
+                            table, column.name, default
                         ));
                     }
-
-                    let columns_str = if column_defs.is_empty() {
-                        "vec![]".to_string()
-                    } else {
-                        format!("vec![\n{}\n        ]", column_defs.join(",\n"))
-                    };
-
+                },
+                SchemaChange::DropColumn { table, column } => {
+                    statements.push(format!(
+                        "db.drop_column(\"{}\", \"{}\")?;",
+                        table, column.name
+                    ));
+                }
+                SchemaChange::RenameColumn { table, from, to } => {
+                    statements.push(format!(
+                        "db.rename_column(\"{}\", \"{}\", \"{}\")?;",
+                        table, from, to
+                    ));
+                }
+                SchemaChange::ChangeColumnComment {
+                    table, column, new, ..
+                } => {
+                    statements.push(comment_on_column_code(table, column, new.as_deref()));
+                }
+                SchemaChange::ModifyColumn { table, old, new } => {
+                    statements.extend(column_steps_code(table, old, new));
+                }
+                SchemaChange::CreateIndex { table, index } => {
                     statements.push(format!(
-                        "db.create_table(\"{}\", {})?;",
-                        table.name, columns_str
+                        "db.create_index(\"{}\", {})?;",
+                        table,
+                        index_def_code(index)
                     ));
+                }
+                SchemaChange::DropIndex { table, index } => {
+                    statements.push(format!(
+                        "db.drop_index(\"{}\", \"{}\")?;",
+                        table, index.name
+                    ));
+                }
+                SchemaChange::AddConstraint { table, constraint } => {
+                    statements.push(format!(
+                        "db.add_constraint(\"{}\", {})?;",
+                        table,
+                        constraint_def_code(constraint, self.safe_constraints)
+                    ));
+                }
+                SchemaChange::AlterTableOptions { table, new, .. } => {
+                    statements.push(format!(
+                        "db.alter_table_options(\"{}\", {})?;",
+                        table,
+                        table_options_code(new)
+                    ));
+                }
+                SchemaChange::ChangePrimaryKey { table, old, new } => {
+                    statements.extend(primary_key_change_code(table, old, new));
+                }
+            }
+
+            if let SchemaChange::AddColumn { table, column } = change {
+                if let Some(comment) = &column.comment {
+                    statements.push(comment_on_column_code(table, &column.name, Some(comment)));
+                }
+            }
+        }
+
+        Ok(statements)
+    }
+
+    fn generate_followup_statements(&self, changes: &[SchemaChange]) -> Vec<String> {
+        let mut statements = Vec::new();
 
-                    // Generate index definitions
-                    for index in &table.indices {
-                        if !index.primary_key && !index.columns.is_empty() {
-                            let columns_str = index.columns.iter()
-                                .map(|c| format!("\"{}\".into()", c))
-                                .collect::<Vec<_>>()
-                                .join(", ");
+        for change in changes {
+            match change {
+                SchemaChange::AddColumn { table, column } => {
+                    if let AddColumnPlan::Split { default, batched } = self.add_column_plan(column)
+                    {
+                        if batched {
                             statements.push(format!(
-                                "db.create_index(\"{}\", IndexDef {{ name: \"{}\".into(), columns: vec![{}], unique: {} }})?;",
-                                table.name, index.name, columns_str, index.unique
+                                "db.backfill_column(\"{}\", \"{}\", {:?}, {})?;",
+                                table, column.name, default, self.batch_size
                             ));
                         }
+                        statements.push(format!(
+                            "db.make_not_null(\"{}\", \"{}\", {:?})?;",
+                            table, column.name, default
+                        ));
                     }
                 }
-                SchemaChange::DropTable(name) => {
-                    statements.push(format!("db.drop_table(\"{}\")?;", name));
+                SchemaChange::AddConstraint { table, constraint } if self.safe_constraints => {
+                    statements.push(format!(
+                        "db.validate_constraint(\"{}\", \"{}\")?;",
+                        table, constraint.name
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        statements
+    }
+
+    fn generate_down_statements(&self, changes: &[SchemaChange]) -> Result<Vec<String>> {
+        if let Some(flavor) = self.sql_down {
+            return self.generate_sql_down_statements(changes, flavor);
+        }
+
+        let mut statements = Vec::new();
+
+        // Reverse the changes
+        for change in changes.iter().rev() {
+            match change {
+                SchemaChange::CreateTable(table) => {
+                    statements.push(format!("db.drop_table(\"{}\")?;", table.qualified_name()));
+                }
+                SchemaChange::DropTable(table) => {
+                    // The table comes back empty; its rows are gone
+                    statements.extend(create_table_code(table, true));
+                }
+                SchemaChange::RenameTable { from, to } => {
+                    statements.push(format!("db.rename_table(\"{}\", \"{}\")?;", to, from));
                 }
                 SchemaChange::AddColumn { table, column } => {
                     statements.push(format!(
-                        "db.add_column(\"{}\", ColumnDef {{ name: \"{}\".into(), ty: \"{}\".into(), nullable: {} }})?;",
-                        table, column.name, column.ty, column.nullable
+                        "db.drop_column(\"{}\", \"{}\")?;",
+                        table, column.name
                     ));
                 }
                 SchemaChange::DropColumn { table, column } => {
-                    statements.push(format!("db.drop_column(\"{}\", \"{}\")?;", table, column));
+                    // The column comes back empty; its data is gone
+                    if !column.nullable && column.default.is_none() && column.generated.is_none() {
+                        statements.push(format!(
+                            "// {}.{} is NOT NULL without a default, so existing rows need a value first",
+                            table, column.name
+                        ));
+                    }
+                    let default = match &column.default {
+                        Some(default) => format!("Some({:?}.into())", default),
+                        None => "None".to_string(),
+                    };
+                    statements.push(format!(
+                        "db.add_column(\"{}\", {})?;",
+                        table,
+                        column_def_code(column, &default, false)
+                    ));
+                }
+                SchemaChange::RenameColumn { table, from, to } => {
+                    statements.push(format!(
+                        "db.rename_column(\"{}\", \"{}\", \"{}\")?;",
+                        table, to, from
+                    ));
+                }
+                SchemaChange::ChangeColumnComment {
+                    table, column, old, ..
+                } => {
+                    statements.push(comment_on_column_code(table, column, old.as_deref()));
                 }
                 SchemaChange::ModifyColumn { table, old, new } => {
+                    statements.extend(column_steps_code(table, new, old));
+                }
+                SchemaChange::CreateIndex { table, index } => {
                     statements.push(format!(
-                        "// Modify column {}.{}: {} -> {}",
-                        table, old.name, old.ty, new.ty
+                        "db.drop_index(\"{}\", \"{}\")?;",
+                        table, index.name
                     ));
+                }
+                SchemaChange::DropIndex { table, index } => {
                     statements.push(format!(
-                        "// TODO: Implement column modification with data conversion"
+                        "db.create_index(\"{}\", {})?;",
+                        table,
+                        index_def_code(index)
                     ));
                 }
-                SchemaChange::CreateIndex { table, index } => {
-                    let columns_str = index.columns.iter()
-                        .map(|c| format!("\"{}\".into()", c))
-                        .collect::<Vec<_>>()
-                        .join(", ");
+                SchemaChange::AddConstraint { table, constraint } => {
                     statements.push(format!(
-                        "db.create_index(\"{}\", IndexDef {{ name: \"{}\".into(), columns: vec![{}], unique: {} }})?;",
-                        table, index.name, columns_str, index.unique
+                        "db.drop_constraint(\"{}\", {})?;",
+                        table,
+                        constraint_def_code(constraint, false)
                     ));
                 }
-                SchemaChange::DropIndex { table, index_name } => {
-                    statements.push(format!("db.drop_index(\"{}\", \"{}\")?;", table, index_name));
+                SchemaChange::AlterTableOptions { table, old, new } => {
+                    let reverted = reverted_options(old, new);
+                    if reverted.is_empty() {
+                        statements.push(format!(
+                            "// Cannot automatically restore the previous options of {}",
+                            table
+                        ));
+                    } else {
+                        statements.push(format!(
+                            "db.alter_table_options(\"{}\", {})?;",
+                            table,
+                            table_options_code(&reverted)
+                        ));
+                    }
+                }
+                SchemaChange::ChangePrimaryKey { table, old, new } => {
+                    statements.extend(primary_key_change_code(table, new, old));
                 }
             }
         }
@@ -110,33 +992,75 @@ impl MigrationGenerator {
         Ok(statements)
     }
 
-    fn generate_down_statements(&self, changes: &[SchemaChange]) -> Result<Vec<String>> {
+    fn generate_sql_down_statements(
+        &self,
+        changes: &[SchemaChange],
+        flavor: SqlFlavor,
+    ) -> Result<Vec<String>> {
         let mut statements = Vec::new();
 
-        // Reverse the changes
         for change in changes.iter().rev() {
+            let mut db = SqlMigrationContext::new(flavor);
+
             match change {
-                SchemaChange::CreateTable(table) => {
-                    statements.push(format!("db.drop_table(\"{}\")?;", table.name));
+                SchemaChange::CreateTable(table) => db.drop_table(&table.qualified_name())?,
+                SchemaChange::RenameTable { from, to } => db.rename_table(to, from)?,
+                SchemaChange::AddColumn { table, column } => db.drop_column(table, &column.name)?,
+                SchemaChange::DropColumn { table, column } => {
+                    db.add_column(table, column_def(column, column.default.clone()))?
                 }
-                SchemaChange::DropTable(name) => {
-                    statements.push(format!("// Cannot automatically recreate dropped table: {}", name));
-                    statements.push(format!("// Manual intervention required"));
+                SchemaChange::RenameColumn { table, from, to } => {
+                    db.rename_column(table, to, from)?
                 }
-                SchemaChange::AddColumn { table, column } => {
-                    statements.push(format!("db.drop_column(\"{}\", \"{}\")?;", table, column.name));
+                SchemaChange::ChangeColumnComment {
+                    table, column, old, ..
+                } => db.comment_on_column(table, column, old.as_deref())?,
+                SchemaChange::CreateIndex { table, index } => db.drop_index(table, &index.name)?,
+                SchemaChange::AddConstraint { table, constraint } => {
+                    db.drop_constraint(table, constraint.clone())?
                 }
-                SchemaChange::DropColumn { table, column } => {
-                    statements.push(format!("// Cannot automatically restore dropped column: {}.{}", table, column));
+                SchemaChange::AlterTableOptions { table, old, new } => {
+                    let reverted = reverted_options(old, new);
+                    if reverted.is_empty() {
+                        return Err(anyhow::anyhow!(
+                            "Cannot generate SQL restoring the previous options of {}",
+                            table
+                        ));
+                    }
+                    db.alter_table_options(table, reverted)?;
+                }
+                SchemaChange::ChangePrimaryKey { table, .. } => {
+                    return Err(anyhow::anyhow!(
+                        "Cannot generate SQL restoring the primary key of {}",
+                        table
+                    ));
                 }
-                SchemaChange::ModifyColumn { table, old, new: _ } => {
-                    statements.push(format!("// Restore column {}.{} to original type", table, old.name));
+                SchemaChange::DropTable(table) => create_table(&mut db, table, true)?,
+                SchemaChange::ModifyColumn { table, old, new } => {
+                    let reversible = column_steps(new, old).iter().all(|step| {
+                        !matches!(step, ColumnStep::Convert | ColumnStep::RequireNotNull)
+                    });
+                    if !reversible {
+                        return Err(anyhow::anyhow!(
+                            "Cannot generate SQL restoring column {}.{}; \
+                             it needs a hand-written conversion",
+                            table,
+                            old.name
+                        ));
+                    }
+                    apply_column_steps(&mut db, table, new, old)?;
                 }
-                SchemaChange::CreateIndex { table, index } => {
-                    statements.push(format!("db.drop_index(\"{}\", \"{}\")?;", table, index.name));
+                SchemaChange::DropIndex { table, index } => {
+                    db.create_index(table, index_def(index))?
                 }
-                SchemaChange::DropIndex { table, index_name } => {
-                    statements.push(format!("// Recreate dropped index: {}.{}", table, index_name));
+            }
+
+            for sql in db.statements() {
+                // Dialect notes (e.g. SQLite's missing DROP COLUMN) come back
+                // as SQL comments
+                match sql.strip_prefix("--") {
+                    Some(note) => statements.push(format!("//{}", note)),
+                    None => statements.push(format!("db.execute_sql({:?})?;", sql)),
                 }
             }
         }
@@ -155,40 +1079,149 @@ impl MigrationGenerator {
     }
 
     fn generate_migration_code(&self, migration: &MigrationFile) -> Result<String> {
-        let struct_name = migration
-            .version
-            .chars()
-            .filter(|c| c.is_alphanumeric() || *c == '_')
-            .collect::<String>();
+        let struct_name = format!("Migration_{}", migration.version);
+        if !is_rust_identifier(&struct_name) {
+            return Err(anyhow::anyhow!(
+                "Migration version {:?} does not make a valid Rust struct name",
+                migration.version
+            ));
+        }
 
-        let up_code = migration.up_statements.join("\n        ");
-        let down_code = migration.down_statements.join("\n        ");
+        let followup = if migration.followup_statements.is_empty() {
+            vec![]
+        } else {
+            vec![
+                String::new(),
+                format!(
+                    "fn followup(&self, db: &mut dyn MigrationContext) -> Result<()> {{\n        {}\n        Ok(())\n    }}",
+                    migration.followup_statements.join("\n        ")
+                ),
+            ]
+        };
 
-        Ok(format!(
-            r#"use toasty_migrate::{{Migration, MigrationContext, ColumnDef, IndexDef}};
+        let (template, source) = match &self.template_dir {
+            Some(dir) => {
+                let path = dir.join(MIGRATION_TEMPLATE_FILE);
+                let template = std::fs::read_to_string(&path).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Failed to read migration template {}: {}",
+                        path.display(),
+                        e
+                    )
+                })?;
+                (template, path.display().to_string())
+            }
+            None => (
+                DEFAULT_MIGRATION_TEMPLATE.to_string(),
+                "built-in template".to_string(),
+            ),
+        };
+
+        render_template(
+            &template,
+            &[
+                ("version", vec![migration.version.clone()]),
+                ("struct_name", vec![struct_name]),
+                ("up", migration.up_statements.clone()),
+                ("down", migration.down_statements.clone()),
+                ("followup", followup),
+            ],
+        )
+        .map_err(|e| anyhow::anyhow!("Migration template {}: {}", source, e))
+    }
+}
+
+/// File read from `MigrationGenerator::template_dir`
+pub const MIGRATION_TEMPLATE_FILE: &str = "migration.rs.tmpl";
+
+/// Template used when no template directory is configured
+const DEFAULT_MIGRATION_TEMPLATE: &str = r#"use toasty_migrate::prelude::*;
 use anyhow::Result;
 
-pub struct Migration_{};
+pub struct {{struct_name}};
 
-impl Migration for Migration_{} {{
-    fn version(&self) -> &str {{
-        "{}"
-    }}
+impl Migration for {{struct_name}} {
+    fn version(&self) -> &str {
+        "{{version}}"
+    }
 
-    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {{
-        {}
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        {{up}}
         Ok(())
-    }}
+    }
 
-    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {{
-        {}
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        {{down}}
         Ok(())
-    }}
-}}
-"#,
-            struct_name, struct_name, migration.version, up_code, down_code
-        ))
     }
+    {{followup}}
+}
+"#;
+
+/// Substitute `{{name}}` placeholders in a migration template.
+///
+/// A placeholder alone on its line expands to one line per value, each
+/// indented like the placeholder, and the line is dropped when there are no
+/// values. Elsewhere the values are joined with newlines. `up`, `down` and
+/// `followup` must appear so no part of the migration is silently lost.
+fn render_template(template: &str, values: &[(&str, Vec<String>)]) -> Result<String> {
+    let lookup = |name: &str| values.iter().find(|(key, _)| *key == name).map(|(_, v)| v);
+
+    let mut seen = std::collections::HashSet::new();
+    let mut lines = Vec::new();
+
+    for line in template.split('\n') {
+        let trimmed = line.trim_start();
+        let standalone = trimmed
+            .strip_prefix("{{")
+            .and_then(|rest| rest.strip_suffix("}}"))
+            .filter(|name| !name.contains("{{") && !name.contains("}}"));
+
+        if let Some(name) = standalone {
+            let items = lookup(name)
+                .ok_or_else(|| anyhow::anyhow!("unknown placeholder {{{{{}}}}}", name))?;
+            seen.insert(name.to_string());
+
+            let indent = &line[..line.len() - trimmed.len()];
+            for item in items {
+                if item.is_empty() {
+                    lines.push(String::new());
+                } else {
+                    lines.push(format!("{}{}", indent, item));
+                }
+            }
+            continue;
+        }
+
+        let mut rendered = String::new();
+        let mut rest = line;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let name = &rest[start + 2..start + 2 + len];
+            let items = lookup(name)
+                .ok_or_else(|| anyhow::anyhow!("unknown placeholder {{{{{}}}}}", name))?;
+            seen.insert(name.to_string());
+
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(&items.join("\n"));
+            rest = &rest[start + 2 + len + 2..];
+        }
+        rendered.push_str(rest);
+        lines.push(rendered);
+    }
+
+    for required in ["up", "down", "followup"] {
+        if !seen.contains(required) {
+            return Err(anyhow::anyhow!(
+                "missing the {{{{{}}}}} placeholder",
+                required
+            ));
+        }
+    }
+
+    Ok(lines.join("\n"))
 }
 
 #[derive(Debug, Clone)]
@@ -197,10 +1230,50 @@ pub struct MigrationFile {
     pub filename: String,
     pub up_statements: Vec<String>,
     pub down_statements: Vec<String>,
+    /// Statements for `followup()`, run after `up()` outside its transaction
+    pub followup_statements: Vec<String>,
 }
 
+#[async_trait::async_trait]
 pub trait Migration: Send + Sync {
     fn version(&self) -> &str;
+
+    /// Versions of migrations that must be applied before this one,
+    /// regardless of how the versions sort.
+    fn requires(&self) -> Vec<&str> {
+        vec![]
+    }
+
     fn up(&self, db: &mut dyn crate::MigrationContext) -> Result<()>;
     fn down(&self, db: &mut dyn crate::MigrationContext) -> Result<()>;
+
+    /// Statements that must run after `up()` has committed, outside the
+    /// migration transaction (e.g. `VALIDATE CONSTRAINT`).
+    fn followup(&self, _db: &mut dyn crate::MigrationContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Data changes made over a live connection, e.g. reading rows,
+    /// transforming them in Rust and writing them back. Runs after `up()`'s
+    /// statements and before `followup()`'s, and only when the runner has a
+    /// connection ([`MigrationRunner::run_pending_live`]).
+    ///
+    /// [`MigrationRunner::run_pending_live`]: crate::MigrationRunner::run_pending_live
+    async fn up_async(&self, _db: &mut dyn crate::AsyncMigrationContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Undo `up_async()`. Runs before `down()`'s statements.
+    async fn down_async(&self, _db: &mut dyn crate::AsyncMigrationContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Live counterpart of `followup()`, run after its statements and
+    /// outside the migration transaction, so it can commit as it goes (see
+    /// [`AsyncMigrationContext::backfill`]).
+    ///
+    /// [`AsyncMigrationContext::backfill`]: crate::AsyncMigrationContext::backfill
+    async fn followup_async(&self, _db: &mut dyn crate::AsyncMigrationContext) -> Result<()> {
+        Ok(())
+    }
 }
@@ -1,5 +1,304 @@
+use crate::context::SCHEMA_LOG_TABLE;
 use crate::snapshot::*;
 use anyhow::Result;
+use toasty_core::driver::redact_url;
+
+/// Parse PostgreSQL's `pg_get_partkeydef` output, e.g. `RANGE (created_at)`
+#[cfg(feature = "postgresql")]
+fn parse_partition_key(def: &str) -> Option<PartitionBy> {
+    let (strategy, columns) = def.split_once(' ')?;
+    let columns = columns.trim().strip_prefix('(')?.strip_suffix(')')?;
+
+    Some(PartitionBy {
+        strategy: PartitionStrategy::from_name(strategy)?,
+        columns: columns.split(',').map(|c| c.trim().to_string()).collect(),
+    })
+}
+
+/// Run a parameterless query, yielding rows as the server sends them
+/// instead of buffering the whole result like `Client::query`
+#[cfg(feature = "postgresql")]
+async fn query_stream(
+    client: &tokio_postgres::Client,
+    query: &str,
+) -> Result<impl futures::TryStream<Ok = tokio_postgres::Row, Error = anyhow::Error>> {
+    use futures::TryStreamExt;
+
+    let rows = client.query_raw(query, std::iter::empty::<&str>()).await?;
+    Ok(rows.map_err(anyhow::Error::from))
+}
+
+/// A column from an `information_schema.columns` row
+#[cfg(feature = "postgresql")]
+fn postgresql_column(row: &tokio_postgres::Row) -> ColumnSnapshot {
+    let col_name: String = row.get(0);
+    let data_type: String = row.get(1);
+    let is_nullable: String = row.get(2);
+    let generation_expression: Option<String> = row.get(3);
+    let column_default: Option<String> = row.get(4);
+    let collation: Option<String> = row.get(5);
+    let is_identity: String = row.get(6);
+    let comment: Option<String> = row.get(7);
+
+    // PostgreSQL generated columns are always stored
+    let generated = generation_expression.filter(|expr| !expr.is_empty());
+    let stored = generated.is_some();
+
+    // SERIAL columns default to the next value of their owned sequence; that
+    // default is what `#[auto]` means, not one the entity declares
+    let serial = column_default
+        .as_deref()
+        .is_some_and(|default| default.starts_with("nextval("));
+    let auto = serial || is_identity == "YES";
+    let default = if serial { None } else { column_default };
+
+    ColumnSnapshot {
+        name: col_name,
+        ty: crate::normalize_type(&data_type, crate::SqlFlavor::PostgreSQL),
+        nullable: is_nullable == "YES",
+        generated,
+        stored,
+        default,
+        auto,
+        collation,
+        comment,
+    }
+}
+
+/// An index from a row of the index query in `introspect_postgresql_table`
+#[cfg(feature = "postgresql")]
+fn postgresql_index(row: &tokio_postgres::Row) -> IndexSnapshot {
+    let mut columns: Vec<String> = row.get(3);
+    let key_columns: i32 = row.get(4);
+
+    // `indkey` lists the key columns first, then the INCLUDE columns
+    let include = columns.split_off((key_columns as usize).min(columns.len()));
+
+    IndexSnapshot {
+        name: row.get(0),
+        columns,
+        unique: row.get(1),
+        primary_key: row.get(2),
+        include,
+        predicate: row
+            .get::<_, Option<String>>(5)
+            .map(|predicate| strip_parens(&predicate)),
+    }
+}
+
+/// PostgreSQL wraps a partial index's predicate in parentheses, e.g.
+/// `(deleted_at IS NULL)`; drop them so it matches the entity's condition
+#[cfg(feature = "postgresql")]
+fn strip_parens(expr: &str) -> String {
+    let mut expr = expr.trim();
+
+    while let Some(inner) = expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) {
+        // Only when the first parenthesis closes at the very end, not in
+        // `(a) OR (b)`
+        let mut depth = 0;
+        let wraps = inner.chars().all(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            depth >= 0
+        });
+        if !wraps {
+            break;
+        }
+        expr = inner.trim();
+    }
+
+    expr.to_string()
+}
+
+/// The `WHERE` condition of a SQLite `CREATE INDEX` statement: whatever
+/// follows the indexed column list
+#[cfg(feature = "sqlite")]
+fn sqlite_index_predicate(create_sql: &str) -> Option<String> {
+    let open = create_sql.find('(')?;
+    let mut depth = 0;
+    let close = create_sql[open..].char_indices().find_map(|(i, c)| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        (depth == 0).then_some(open + i)
+    })?;
+
+    let rest = create_sql[close + 1..].trim_start();
+    let keyword = rest.get(..5)?;
+    if !keyword.eq_ignore_ascii_case("where") {
+        return None;
+    }
+
+    Some(
+        rest[5..]
+            .trim()
+            .trim_end_matches(';')
+            .trim_end()
+            .to_string(),
+    )
+}
+
+/// Walk `sql`, calling `f` with the byte offset, character and parenthesis
+/// depth of each character outside quoted strings and identifiers. Stops
+/// early when `f` returns `true` and returns that offset.
+#[cfg(feature = "sqlite")]
+fn sqlite_scan(sql: &str, mut f: impl FnMut(usize, char, usize) -> bool) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (i, ch) in sql.char_indices() {
+        match (quote, ch) {
+            (Some(q), _) if ch == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(ch),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => {
+                depth += 1;
+                if f(i, ch, depth) {
+                    return Some(i);
+                }
+            }
+            (None, ')') => {
+                if f(i, ch, depth) {
+                    return Some(i);
+                }
+                depth = depth.saturating_sub(1);
+            }
+            (None, _) => {
+                if f(i, ch, depth) {
+                    return Some(i);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The definition of `column` in a SQLite `CREATE TABLE` statement, from its
+/// name up to the comma or parenthesis that ends it
+#[cfg(feature = "sqlite")]
+fn sqlite_column_def<'a>(create_sql: &'a str, column: &str) -> Option<&'a str> {
+    // Split the parenthesized body at its top-level commas
+    let open = sqlite_scan(create_sql, |_, ch, _| ch == '(')?;
+    let mut defs = vec![];
+    let mut start = open + 1;
+    sqlite_scan(&create_sql[start..], |i, ch, depth| match ch {
+        ',' if depth == 0 => {
+            defs.push(&create_sql[start..open + 1 + i]);
+            start = open + 2 + i;
+            false
+        }
+        ')' if depth == 0 => {
+            defs.push(&create_sql[start..open + 1 + i]);
+            true
+        }
+        _ => false,
+    })?;
+
+    defs.into_iter().map(str::trim).find(|def| {
+        let name = match def.chars().next() {
+            Some(q @ ('"' | '`')) => def[1..].split(q).next(),
+            Some('[') => def[1..].split(']').next(),
+            _ => def.split(char::is_whitespace).next(),
+        };
+        name.is_some_and(|name| name.eq_ignore_ascii_case(column))
+    })
+}
+
+/// The offset just past the keyword `word` in `def`, skipping quoted text and
+/// matches inside longer words or parentheses
+#[cfg(feature = "sqlite")]
+fn sqlite_keyword_end(def: &str, word: &str) -> Option<usize> {
+    let is_ident = |ch: char| ch.is_ascii_alphanumeric() || ch == '_';
+    let at = sqlite_scan(def, |i, ch, depth| {
+        depth == 0
+            && ch != '('
+            && def[i..]
+                .get(..word.len())
+                .is_some_and(|w| w.eq_ignore_ascii_case(word))
+            && !def[..i].ends_with(is_ident)
+            && !def[i + word.len()..].starts_with(is_ident)
+    })?;
+    Some(at + word.len())
+}
+
+/// Extract the `GENERATED ALWAYS AS (<expr>)` expression for `column` from a
+/// SQLite `CREATE TABLE` statement.
+#[cfg(feature = "sqlite")]
+fn sqlite_generated_expr(create_sql: &str, column: &str) -> Option<String> {
+    let def = sqlite_column_def(create_sql, column)?;
+    let rest = &def[sqlite_keyword_end(def, "AS")?..];
+    let open = rest.len() - rest.trim_start().len();
+    if !rest[open..].starts_with('(') {
+        return None;
+    }
+
+    let close = sqlite_scan(&rest[open..], |_, ch, depth| ch == ')' && depth == 1)?;
+    Some(rest[open + 1..open + close].trim().to_string())
+}
+
+/// Extract the `COLLATE <name>` collation for `column` from a SQLite
+/// `CREATE TABLE` statement. SQLite only keeps it in the original SQL.
+#[cfg(feature = "sqlite")]
+fn sqlite_collation(create_sql: &str, column: &str) -> Option<String> {
+    let def = sqlite_column_def(create_sql, column)?;
+    let name = def[sqlite_keyword_end(def, "COLLATE")?..]
+        .split_whitespace()
+        .next()?;
+    Some(name.trim_matches(['"', '`']).to_string())
+}
+
+/// Split a `sqlite:` URL into the database path and the `PRAGMA` settings in
+/// its query string, e.g. `sqlite:app.db?journal_mode=WAL&synchronous=NORMAL`
+pub fn sqlite_url_parts(url: &str) -> Result<(&str, Vec<(&str, &str)>)> {
+    let url = url.trim_start_matches("sqlite:");
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    let is_word = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+
+    let pragmas = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) if is_word(name) && is_word(value) => Ok((name, value)),
+            _ => Err(anyhow::anyhow!(
+                "Invalid SQLite PRAGMA setting in URL: {}",
+                pair
+            )),
+        })
+        .collect::<Result<_>>()?;
+
+    Ok((path, pragmas))
+}
+
+/// Apply `PRAGMA` settings to a fresh connection. This must happen before
+/// any transaction starts, since some (like `journal_mode`) can't change
+/// inside one.
+#[cfg(feature = "sqlite")]
+pub fn apply_sqlite_pragmas(conn: &rusqlite::Connection, pragmas: &[(&str, &str)]) -> Result<()> {
+    for (name, value) in pragmas {
+        conn.pragma_update(None, name, value)?;
+    }
+    Ok(())
+}
+
+/// Open the database a `sqlite:` URL points at, applying any `PRAGMA`
+/// settings from its query string
+#[cfg(feature = "sqlite")]
+pub fn open_sqlite(url: &str) -> Result<rusqlite::Connection> {
+    let (path, pragmas) = sqlite_url_parts(url)?;
+    let conn = rusqlite::Connection::open(path)?;
+    apply_sqlite_pragmas(&conn, &pragmas)?;
+    Ok(conn)
+}
 
 /// Introspect database schema to create a snapshot
 /// This allows generating migrations based on current database state
@@ -11,11 +310,29 @@ pub trait SchemaIntrospector: Send + Sync {
 /// SQL database introspection (works for PostgreSQL, MySQL, SQLite)
 pub struct SqlIntrospector {
     connection_url: String,
+    parallel: usize,
 }
 
 impl SqlIntrospector {
     pub fn new(connection_url: String) -> Self {
-        Self { connection_url }
+        Self {
+            connection_url,
+            parallel: 1,
+        }
+    }
+
+    /// Introspect up to `n` tables at a time, each worker on its own
+    /// connection. Tables are still returned sorted by name, so the
+    /// snapshot is identical to a sequential run.
+    pub fn parallel(mut self, n: usize) -> Self {
+        self.parallel = n.max(1);
+        self
+    }
+
+    /// Tables worth introspecting, skipping Toasty's own bookkeeping
+    #[cfg(any(feature = "postgresql", feature = "sqlite"))]
+    fn is_user_table(table_name: &str) -> bool {
+        table_name != "_toasty_migrations" && table_name != SCHEMA_LOG_TABLE
     }
 
     /// Introspect schema from database
@@ -30,13 +347,48 @@ impl SqlIntrospector {
             #[cfg(feature = "mysql")]
             "mysql" => self.introspect_mysql().await,
             scheme => Err(anyhow::anyhow!(
-                "Unsupported database for introspection: {}. Enable feature flag.", scheme
+                "Unsupported database for introspection: {}. Enable feature flag.",
+                scheme
             )),
         }
     }
 
+    /// Introspect a single table, e.g. to refresh one entry of a cached
+    /// snapshot with [`SchemaSnapshot::merge`]
+    pub async fn introspect_table(&self, name: &str) -> Result<TableSnapshot> {
+        let url = url::Url::parse(&self.connection_url)?;
+
+        let table: Result<TableSnapshot> = match url.scheme() {
+            #[cfg(feature = "postgresql")]
+            "postgresql" | "postgres" => {
+                let (client, connection) =
+                    tokio_postgres::connect(&self.connection_url, tokio_postgres::NoTls).await?;
+
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        eprintln!("connection error: {}", e);
+                    }
+                });
+
+                Self::introspect_postgresql_table(&client, name).await
+            }
+            #[cfg(feature = "sqlite")]
+            "sqlite" => {
+                let conn = open_sqlite(&self.connection_url)?;
+                Self::introspect_sqlite_table(&conn, name)
+            }
+            scheme => Err(anyhow::anyhow!(
+                "Unsupported database for introspection: {}. Enable feature flag.",
+                scheme
+            )),
+        };
+
+        table.map_err(|e| anyhow::anyhow!("Failed to introspect table {}: {}", name, e))
+    }
+
     #[cfg(feature = "postgresql")]
     async fn introspect_postgresql(&self) -> Result<SchemaSnapshot> {
+        use futures::TryStreamExt;
         use tokio_postgres::NoTls;
 
         println!("🔍 Introspecting PostgreSQL schema...");
@@ -50,27 +402,40 @@ impl SqlIntrospector {
             }
         });
 
-        let mut tables = Vec::new();
-
-        // Query tables
-        let rows = client.query(
+        // Query tables. Partitions belong to their parent table rather than
+        // the schema the entities describe.
+        let rows = query_stream(
+            &client,
             "SELECT table_name FROM information_schema.tables
              WHERE table_schema = 'public' AND table_type = 'BASE TABLE'
+               AND table_name NOT IN (
+                 SELECT c.relname FROM pg_class c
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 WHERE n.nspname = 'public' AND c.relispartition
+               )
              ORDER BY table_name",
-            &[],
-        ).await?;
-
-        for row in rows {
-            let table_name: String = row.get(0);
-
-            // Skip migration tracking table
-            if table_name == "_toasty_migrations" {
-                continue;
+        )
+        .await?;
+
+        // Skip migration tracking and audit tables
+        let table_names: Vec<String> = rows
+            .try_filter_map(|row| async move {
+                let name: String = row.get(0);
+                Ok(Self::is_user_table(&name).then_some(name))
+            })
+            .try_collect()
+            .await?;
+
+        let mut tables = if self.parallel > 1 && table_names.len() > 1 {
+            self.introspect_postgresql_parallel(table_names).await?
+        } else {
+            let mut tables = Vec::new();
+            for table_name in &table_names {
+                tables.push(Self::introspect_postgresql_table(&client, table_name).await?);
             }
-
-            let table = self.introspect_postgresql_table(&client, &table_name).await?;
-            tables.push(table);
-        }
+            tables
+        };
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
 
         println!("✅ Found {} table(s)", tables.len());
 
@@ -81,36 +446,75 @@ impl SqlIntrospector {
         })
     }
 
+    /// Introspect tables concurrently over a pool of `self.parallel`
+    /// connections. Each worker takes the next table from a shared queue.
     #[cfg(feature = "postgresql")]
-    async fn introspect_postgresql_table(
+    async fn introspect_postgresql_parallel(
         &self,
+        table_names: Vec<String>,
+    ) -> Result<Vec<TableSnapshot>> {
+        use std::sync::{Arc, Mutex};
+        use tokio_postgres::NoTls;
+
+        let workers = self.parallel.min(table_names.len());
+        let queue = Arc::new(Mutex::new(table_names));
+        let mut tasks = tokio::task::JoinSet::new();
+
+        for _ in 0..workers {
+            let (client, connection) = tokio_postgres::connect(&self.connection_url, NoTls).await?;
+
+            tokio::spawn(async move {
+                if let Err(e) = connection.await {
+                    eprintln!("connection error: {}", e);
+                }
+            });
+
+            let queue = queue.clone();
+            tasks.spawn(async move {
+                let mut tables = Vec::new();
+                loop {
+                    let next = queue.lock().unwrap().pop();
+                    let Some(table_name) = next else { break };
+                    tables.push(Self::introspect_postgresql_table(&client, &table_name).await?);
+                }
+                Ok::<_, anyhow::Error>(tables)
+            });
+        }
+
+        let mut tables = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            tables.extend(result??);
+        }
+
+        Ok(tables)
+    }
+
+    /// Introspect one table. Catalog rows are streamed and turned into
+    /// snapshot entries as they arrive, so a table with thousands of columns
+    /// or indexes never holds its whole result set in memory.
+    #[cfg(feature = "postgresql")]
+    async fn introspect_postgresql_table(
         client: &tokio_postgres::Client,
         table_name: &str,
     ) -> Result<TableSnapshot> {
-        let mut columns = Vec::new();
-        let mut primary_key_cols = Vec::new();
+        use futures::TryStreamExt;
 
-        // Get columns - use simple_query to avoid parameter issues
+        // Get columns
         let query = format!(
-            "SELECT column_name, data_type, is_nullable
+            "SELECT column_name, data_type, is_nullable, generation_expression, column_default,
+                    collation_name, is_identity,
+                    col_description(format('%I.%I', table_schema, table_name)::regclass,
+                                    ordinal_position::int)
              FROM information_schema.columns
              WHERE table_name = '{}' AND table_schema = 'public'
              ORDER BY ordinal_position",
             table_name
         );
-        let rows = client.query(&query, &[]).await?;
-
-        for row in rows {
-            let col_name: String = row.get(0);
-            let data_type: String = row.get(1);
-            let is_nullable: String = row.get(2);
-
-            columns.push(ColumnSnapshot {
-                name: col_name,
-                ty: data_type,
-                nullable: is_nullable == "YES",
-            });
-        }
+        let columns = query_stream(client, &query)
+            .await?
+            .map_ok(|row| postgresql_column(&row))
+            .try_collect()
+            .await?;
 
         // Get primary key
         let pk_query = format!(
@@ -120,93 +524,111 @@ impl SqlIntrospector {
              WHERE i.indrelid = '{}'::regclass AND i.indisprimary",
             table_name
         );
-        let pk_rows = client.query(&pk_query, &[]).await?;
-
-        for row in pk_rows {
-            let col_name: String = row.get(0);
-            primary_key_cols.push(col_name);
-        }
+        let primary_key_cols = query_stream(client, &pk_query)
+            .await?
+            .map_ok(|row| row.get::<_, String>(0))
+            .try_collect()
+            .await?;
 
         // Get indexes with column information
-        let mut indices = Vec::new();
         let idx_query = format!(
             "SELECT
                 i.indexname,
-                i.indexdef,
                 ix.indisunique,
                 ix.indisprimary,
-                ARRAY_AGG(a.attname ORDER BY array_position(ix.indkey, a.attnum)) as index_columns
+                ARRAY_AGG(a.attname ORDER BY array_position(ix.indkey, a.attnum)) as index_columns,
+                ix.indnkeyatts::int4,
+                pg_get_expr(ix.indpred, ix.indrelid)
              FROM pg_indexes i
              JOIN pg_class c ON c.relname = i.indexname
              JOIN pg_index ix ON ix.indexrelid = c.oid
              JOIN pg_attribute a ON a.attrelid = ix.indrelid AND a.attnum = ANY(ix.indkey)
              WHERE i.tablename = '{}' AND i.schemaname = 'public'
-             GROUP BY i.indexname, i.indexdef, ix.indisunique, ix.indisprimary",
+             GROUP BY i.indexname, ix.indisunique, ix.indisprimary, ix.indnkeyatts,
+                ix.indpred, ix.indrelid",
             table_name
         );
-        let idx_rows = client.query(&idx_query, &[]).await?;
+        let indices = query_stream(client, &idx_query)
+            .await?
+            .map_ok(|row| postgresql_index(&row))
+            .try_collect()
+            .await?;
 
-        for row in idx_rows {
-            let idx_name: String = row.get(0);
-            let _idx_def: String = row.get(1);
-            let is_unique: bool = row.get(2);
-            let is_primary: bool = row.get(3);
-            let columns: Vec<String> = row.get(4);
-
-            indices.push(IndexSnapshot {
-                name: idx_name,
-                columns,
-                unique: is_unique,
-                primary_key: is_primary,
-            });
-        }
+        // `RANGE (created_at)` for a partitioned table, no row otherwise
+        let query = format!(
+            "SELECT pg_get_partkeydef(c.oid)
+             FROM pg_class c
+             JOIN pg_namespace n ON n.oid = c.relnamespace
+             WHERE c.relname = '{}' AND n.nspname = 'public' AND c.relkind = 'p'",
+            table_name
+        );
+        let partition_by = client
+            .query_opt(&query, &[])
+            .await?
+            .and_then(|row| parse_partition_key(&row.get::<_, String>(0)));
 
         Ok(TableSnapshot {
+            schema: None,
             name: table_name.to_string(),
             columns,
             indices,
             primary_key: primary_key_cols,
+            comment: None,
+            foreign_keys: vec![],
+            options: Default::default(),
+            partition_by,
         })
     }
 
     #[cfg(not(feature = "postgresql"))]
     async fn introspect_postgresql(&self) -> Result<SchemaSnapshot> {
-        Err(anyhow::anyhow!("PostgreSQL introspection requires 'postgresql' feature"))
+        Err(anyhow::anyhow!(
+            "PostgreSQL introspection requires 'postgresql' feature"
+        ))
     }
 
     #[cfg(feature = "sqlite")]
     async fn introspect_sqlite(&self) -> Result<SchemaSnapshot> {
-        use rusqlite::Connection;
-
         println!("🔍 Introspecting SQLite schema...");
 
-        // Parse SQLite URL (sqlite:path or sqlite::memory:)
-        let db_path = self.connection_url.trim_start_matches("sqlite:");
+        // Parse SQLite URL (sqlite:path or sqlite::memory:, plus PRAGMAs)
+        let conn = open_sqlite(&self.connection_url)?;
 
-        let conn = Connection::open(db_path)?;
+        let table_names = Self::sqlite_table_names(&conn)?;
 
-        let mut tables = Vec::new();
+        // Each `:memory:` connection is its own database, so it can't be
+        // shared between workers
+        let in_memory = sqlite_url_parts(&self.connection_url)?.0 == ":memory:";
 
-        // Query tables
-        let mut stmt = conn.prepare(
-            "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
-        )?;
+        let mut tables = if self.parallel > 1 && table_names.len() > 1 && !in_memory {
+            self.introspect_sqlite_parallel(table_names)?
+        } else {
+            table_names
+                .iter()
+                .map(|table_name| Self::introspect_sqlite_table(&conn, table_name))
+                .collect::<Result<Vec<_>>>()?
+        };
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
 
-        let table_names: Vec<String> = stmt
-            .query_map([], |row| row.get(0))?
-            .collect::<Result<Vec<_>, _>>()?;
-
-        for table_name in table_names {
-            // Skip migration tracking table
-            if table_name == "_toasty_migrations" {
-                continue;
-            }
+        println!("✅ Found {} table(s)", tables.len());
 
-            let table = self.introspect_sqlite_table(&conn, &table_name)?;
-            tables.push(table);
-        }
+        Ok(SchemaSnapshot {
+            version: "1.0".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            tables,
+        })
+    }
 
-        println!("✅ Found {} table(s)", tables.len());
+    /// Introspect an already open SQLite connection. An in-memory database
+    /// only exists on the connections sharing it, so reconnecting by URL
+    /// would find it empty.
+    #[cfg(feature = "sqlite")]
+    pub fn introspect_sqlite_connection(conn: &rusqlite::Connection) -> Result<SchemaSnapshot> {
+        let mut tables = Self::sqlite_table_names(conn)?
+            .iter()
+            .map(|table_name| Self::introspect_sqlite_table(conn, table_name))
+            .collect::<Result<Vec<_>>>()?;
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
 
         Ok(SchemaSnapshot {
             version: "1.0".to_string(),
@@ -215,33 +637,105 @@ impl SqlIntrospector {
         })
     }
 
+    /// User tables in a SQLite database, skipping migration tracking and
+    /// audit tables
+    #[cfg(feature = "sqlite")]
+    fn sqlite_table_names(conn: &rusqlite::Connection) -> Result<Vec<String>> {
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name"
+        )?;
+
+        let mut table_names: Vec<String> = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        table_names.retain(|name| Self::is_user_table(name));
+
+        Ok(table_names)
+    }
+
+    /// Introspect tables on `self.parallel` threads, each with its own
+    /// connection, taking the next table from a shared queue
+    #[cfg(feature = "sqlite")]
+    fn introspect_sqlite_parallel(&self, table_names: Vec<String>) -> Result<Vec<TableSnapshot>> {
+        let workers = self.parallel.min(table_names.len());
+        let queue = std::sync::Mutex::new(table_names);
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let conn = open_sqlite(&self.connection_url)?;
+                        let mut tables = Vec::new();
+                        loop {
+                            let next = queue.lock().unwrap().pop();
+                            let Some(table_name) = next else { break };
+                            tables.push(Self::introspect_sqlite_table(&conn, &table_name)?);
+                        }
+                        Ok::<_, anyhow::Error>(tables)
+                    })
+                })
+                .collect();
+
+            let mut tables = Vec::new();
+            for handle in handles {
+                let result = handle
+                    .join()
+                    .map_err(|_| anyhow::anyhow!("SQLite introspection worker panicked"))?;
+                tables.extend(result?);
+            }
+            Ok(tables)
+        })
+    }
+
     #[cfg(feature = "sqlite")]
     fn introspect_sqlite_table(
-        &self,
         conn: &rusqlite::Connection,
         table_name: &str,
     ) -> Result<TableSnapshot> {
         let mut columns = Vec::new();
         let mut primary_key_cols = Vec::new();
 
-        // Get columns using PRAGMA table_info
-        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
+        // The generation expressions are only available from the original
+        // CREATE TABLE statement
+        let create_sql: String = conn.query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [table_name],
+            |row| row.get(0),
+        )?;
+
+        // Get columns using PRAGMA table_xinfo, which includes generated
+        // columns (hidden = 2 for virtual, 3 for stored)
+        let mut stmt = conn.prepare(&format!("PRAGMA table_xinfo({})", table_name))?;
         let rows = stmt.query_map([], |row| {
             Ok((
-                row.get::<_, String>(1)?, // name
-                row.get::<_, String>(2)?, // type
-                row.get::<_, i32>(3)?,    // notnull
-                row.get::<_, i32>(5)?,    // pk
+                row.get::<_, String>(1)?,         // name
+                row.get::<_, String>(2)?,         // type
+                row.get::<_, i32>(3)?,            // notnull
+                row.get::<_, Option<String>>(4)?, // dflt_value
+                row.get::<_, i32>(5)?,            // pk
+                row.get::<_, i32>(6)?,            // hidden
             ))
         })?;
 
         for row in rows {
-            let (col_name, col_type, not_null, is_pk) = row?;
+            let (col_name, col_type, not_null, default, is_pk, hidden) = row?;
+
+            let generated = if hidden == 2 || hidden == 3 {
+                sqlite_generated_expr(&create_sql, &col_name)
+            } else {
+                None
+            };
 
             columns.push(ColumnSnapshot {
                 name: col_name.clone(),
-                ty: col_type,
+                ty: crate::normalize_type(&col_type, crate::SqlFlavor::Sqlite),
                 nullable: not_null == 0,
+                generated,
+                stored: hidden == 3,
+                default,
+                auto: false,
+                collation: sqlite_collation(&create_sql, &col_name),
+                comment: None,
             });
 
             if is_pk > 0 {
@@ -256,11 +750,26 @@ impl SqlIntrospector {
             Ok((
                 row.get::<_, String>(1)?, // name
                 row.get::<_, i32>(2)?,    // unique
+                row.get::<_, String>(3)?, // origin
+                row.get::<_, i32>(4)?,    // partial
             ))
         })?;
 
         for idx_row in idx_rows {
-            let (idx_name, is_unique) = idx_row?;
+            let (idx_name, is_unique, origin, partial) = idx_row?;
+
+            // SQLite keeps the `CREATE INDEX` as written, so the predicate
+            // reads back exactly as the migration created it
+            let predicate = if partial == 1 {
+                let sql: String = conn.query_row(
+                    "SELECT sql FROM sqlite_master WHERE type = 'index' AND name = ?1",
+                    [&idx_name],
+                    |row| row.get(0),
+                )?;
+                sqlite_index_predicate(&sql)
+            } else {
+                None
+            };
 
             // Get index columns
             let mut col_stmt = conn.prepare(&format!("PRAGMA index_info({})", idx_name))?;
@@ -274,55 +783,209 @@ impl SqlIntrospector {
                 name: idx_name.clone(),
                 columns: idx_columns,
                 unique: is_unique == 1,
-                primary_key: idx_name.contains("pk") || idx_name.ends_with("_pkey"),
+                primary_key: origin == "pk"
+                    || idx_name.contains("pk")
+                    || idx_name.ends_with("_pkey"),
+                include: vec![],
+                predicate,
             });
         }
 
         Ok(TableSnapshot {
+            schema: None,
             name: table_name.to_string(),
             columns,
             indices,
             primary_key: primary_key_cols,
+            comment: None,
+            foreign_keys: vec![],
+            options: Default::default(),
+            partition_by: None,
         })
     }
 
     #[cfg(not(feature = "sqlite"))]
     #[allow(dead_code)]
     async fn introspect_sqlite(&self) -> Result<SchemaSnapshot> {
-        Err(anyhow::anyhow!("SQLite introspection requires 'sqlite' feature"))
+        Err(anyhow::anyhow!(
+            "SQLite introspection requires 'sqlite' feature"
+        ))
     }
 
     #[cfg(not(feature = "mysql"))]
     #[allow(dead_code)]
     async fn introspect_mysql(&self) -> Result<SchemaSnapshot> {
-        Err(anyhow::anyhow!("MySQL introspection requires 'mysql' feature"))
+        Err(anyhow::anyhow!(
+            "MySQL introspection requires 'mysql' feature"
+        ))
     }
 }
 
 /// MongoDB schema introspection
 pub struct MongoDbIntrospector {
     connection_url: String,
+    sample_size: usize,
 }
 
 impl MongoDbIntrospector {
     pub fn new(connection_url: String) -> Self {
-        Self { connection_url }
+        Self {
+            connection_url,
+            sample_size: 100,
+        }
+    }
+
+    /// How many documents of each collection to read when inferring its
+    /// fields. Defaults to 100.
+    pub fn sample_size(mut self, n: usize) -> Self {
+        self.sample_size = n;
+        self
     }
 
     /// Introspect MongoDB schema (collections and indexes)
+    ///
+    /// Collections have no declared fields, so the columns are the top-level
+    /// fields found in a sample of documents, all nullable. The `_id` index
+    /// is the primary key.
+    #[cfg(feature = "mongodb_introspect")]
     pub async fn introspect_schema(&self) -> Result<SchemaSnapshot> {
-        // TODO: Implement MongoDB introspection
-        // 1. Connect to database
-        // 2. List collections
-        // 3. Get indexes for each collection
-        // 4. Infer schema from sample documents (optional)
+        println!(
+            "🔍 Introspecting MongoDB schema from: {}",
+            redact_url(&self.connection_url)
+        );
+
+        let client = mongodb::Client::with_uri_str(&self.connection_url).await?;
+        let db = client.default_database().ok_or_else(|| {
+            anyhow::anyhow!(
+                "MongoDB URL has no database: {}",
+                redact_url(&self.connection_url)
+            )
+        })?;
+
+        let mut names = db.list_collection_names().await?;
+        names.retain(|name| !name.starts_with("system."));
+        names.sort();
 
-        println!("🔍 Introspecting MongoDB schema from: {}", self.connection_url);
+        let mut tables = Vec::new();
+        for name in names {
+            let collection = db.collection::<mongodb::bson::Document>(&name);
+            tables.push(self.introspect_collection(&collection).await?);
+        }
 
         Ok(SchemaSnapshot {
             version: "1.0".to_string(),
             timestamp: chrono::Utc::now().to_rfc3339(),
-            tables: vec![],
+            tables,
         })
     }
+
+    #[cfg(not(feature = "mongodb_introspect"))]
+    pub async fn introspect_schema(&self) -> Result<SchemaSnapshot> {
+        Err(anyhow::anyhow!(
+            "MongoDB introspection of {} requires 'mongodb_introspect' feature",
+            redact_url(&self.connection_url)
+        ))
+    }
+
+    #[cfg(feature = "mongodb_introspect")]
+    async fn introspect_collection(
+        &self,
+        collection: &mongodb::Collection<mongodb::bson::Document>,
+    ) -> Result<TableSnapshot> {
+        let mut indices = Vec::new();
+        let mut primary_key = Vec::new();
+
+        let mut cursor = collection.list_indexes().await?;
+        while cursor.advance().await? {
+            let index = cursor.deserialize_current()?;
+            let columns: Vec<String> = index.keys.keys().cloned().collect();
+            let is_primary = columns == ["_id"];
+            let options = index.options.unwrap_or_default();
+
+            if is_primary {
+                primary_key = columns.clone();
+            }
+            indices.push(IndexSnapshot {
+                name: options
+                    .name
+                    .unwrap_or_else(|| format!("{}_1", columns.join("_1_"))),
+                columns,
+                // The `_id` index is always unique, without saying so
+                unique: is_primary || options.unique.unwrap_or(false),
+                primary_key: is_primary,
+                include: vec![],
+                predicate: None,
+            });
+        }
+
+        // Union the fields of the sampled documents, in the order first seen
+        let mut columns: Vec<ColumnSnapshot> = Vec::new();
+        let mut cursor = collection
+            .find(mongodb::bson::doc! {})
+            .limit(self.sample_size as i64)
+            .await?;
+        while cursor.advance().await? {
+            let document = cursor.deserialize_current()?;
+            for (field, value) in &document {
+                let ty = bson_type_name(value);
+                // A null says nothing about the type of the field's values
+                match columns.iter_mut().find(|column| &column.name == field) {
+                    Some(column) if column.ty == "null" => column.ty = ty.to_string(),
+                    Some(column) if ty != "null" && column.ty != ty => {
+                        column.ty = "mixed".to_string()
+                    }
+                    Some(_) => {}
+                    None => columns.push(ColumnSnapshot {
+                        name: field.clone(),
+                        ty: ty.to_string(),
+                        nullable: true,
+                        generated: None,
+                        stored: false,
+                        default: None,
+                        auto: false,
+                        collation: None,
+                        comment: None,
+                    }),
+                }
+            }
+        }
+
+        Ok(TableSnapshot {
+            schema: None,
+            name: collection.name().to_string(),
+            columns,
+            indices,
+            primary_key,
+            comment: None,
+            foreign_keys: vec![],
+            options: Default::default(),
+            partition_by: None,
+        })
+    }
+}
+
+/// The `$type` alias MongoDB uses for a value's type, e.g. `string` or
+/// `objectId`. Types without an alias here are `mixed`, as are fields whose
+/// sampled values differ in type.
+#[cfg(feature = "mongodb_introspect")]
+fn bson_type_name(value: &mongodb::bson::Bson) -> &'static str {
+    use mongodb::bson::Bson;
+
+    match value {
+        Bson::Double(_) => "double",
+        Bson::String(_) => "string",
+        Bson::Document(_) => "object",
+        Bson::Array(_) => "array",
+        Bson::Binary(_) => "binData",
+        Bson::ObjectId(_) => "objectId",
+        Bson::Boolean(_) => "bool",
+        Bson::DateTime(_) => "date",
+        Bson::Null => "null",
+        Bson::RegularExpression(_) => "regex",
+        Bson::Int32(_) => "int",
+        Bson::Timestamp(_) => "timestamp",
+        Bson::Int64(_) => "long",
+        Bson::Decimal128(_) => "decimal",
+        _ => "mixed",
+    }
 }
@@ -1,60 +1,287 @@
-pub mod snapshot;
+pub mod api;
+pub mod context;
 pub mod diff;
+pub mod export;
 pub mod generator;
-pub mod tracker;
-pub mod runner;
-pub mod loader;
-pub mod context;
 pub mod introspect;
+pub mod live;
+pub mod loader;
+pub mod order;
 pub mod parser;
+pub mod prelude;
+pub mod renames;
+pub mod runner;
+pub mod script;
+pub mod snapshot;
+pub mod source;
+pub mod tracker;
 
-pub use snapshot::{SchemaSnapshot, save_snapshot, load_snapshot};
-pub use diff::{SchemaChange, SchemaDiff, detect_changes};
-pub use generator::{Migration, MigrationGenerator, MigrationFile};
+pub use api::MigrationApi;
+pub use context::{
+    NoSqlMigrationContext, NoSqlOperation, SqlFlavor, SqlMigrationContext, SCHEMA_LOG_TABLE,
+};
+pub use diff::{
+    detect_changes, detect_changes_with, normalize_default, normalize_type, DiffOptions,
+    SchemaChange, SchemaDiff,
+};
+pub use export::DiagramFormat;
+pub use generator::{
+    migration_slug, Migration, MigrationFile, MigrationGenerator, MIGRATION_TEMPLATE_FILE,
+};
+#[cfg(feature = "sqlite")]
+pub use introspect::{apply_sqlite_pragmas, open_sqlite};
+pub use introspect::{sqlite_url_parts, MongoDbIntrospector, SchemaIntrospector, SqlIntrospector};
+#[cfg(feature = "postgresql")]
+pub use live::PostgresMigrationConnection;
+#[cfg(feature = "sqlite")]
+pub use live::SqliteMigrationConnection;
+pub use live::{connect, AsyncMigrationContext, Row};
+pub use loader::{MigrationFileInfo, MigrationLoader, MigrationRegistry};
+pub use order::dependency_order;
+pub use parser::{EntityParser, ParseWarning};
+pub use renames::{apply_renames, rename_candidates, ColumnRename, RenameHints, RENAMES_FILE};
+pub use runner::{
+    AppliedMigration, MigrationRunner, MigrationStatus, RunReport, SCHEMA_HISTORY_DIR,
+};
+pub use script::MigrationScript;
+pub use snapshot::{
+    load_snapshot, load_validated_snapshot, save_snapshot, PartitionBy, PartitionStrategy,
+    SchemaSnapshot, TableOptions,
+};
+pub use source::{FileSchemaSource, SchemaSource};
 pub use tracker::MigrationTracker;
-pub use runner::{MigrationRunner, MigrationStatus};
-pub use loader::{MigrationLoader, MigrationFileInfo};
-pub use context::{SqlMigrationContext, NoSqlMigrationContext, SqlFlavor, NoSqlOperation};
-pub use introspect::{SchemaIntrospector, SqlIntrospector, MongoDbIntrospector};
-pub use parser::EntityParser;
 
 use anyhow::Result;
 
 /// Migration context provides database operations for migrations
 pub trait MigrationContext {
-    /// Execute a raw SQL statement (SQL databases only)
+    /// Execute a raw statement
+    ///
+    /// This is the supported escape hatch for hand-written migrations that
+    /// need DDL or DML the structured methods don't cover. The statement is
+    /// recorded alongside the structured operations and runs inside the same
+    /// migration transaction, so it is tracked like any other change.
+    ///
+    /// SQL contexts accept a SQL statement. The NoSQL context accepts a JSON
+    /// command document (e.g. `{"collMod": "users", ...}` for MongoDB).
     fn execute_sql(&mut self, sql: &str) -> Result<()>;
 
     /// Create a table
     fn create_table(&mut self, name: &str, columns: Vec<ColumnDef>) -> Result<()>;
 
+    /// Create a table split into partitions by `partition_by`. Rows can only
+    /// be inserted once a partition covers them; see `create_partition`.
+    ///
+    /// Only PostgreSQL partitions tables; other backends create a plain one.
+    fn create_partitioned_table(
+        &mut self,
+        name: &str,
+        columns: Vec<ColumnDef>,
+        partition_by: PartitionBy,
+    ) -> Result<()>;
+
+    /// Create partition `name` of the range-partitioned table `parent`,
+    /// holding keys from `from` up to but excluding `to`. Both bounds are
+    /// SQL expressions, e.g. `'2025-01-01'`.
+    fn create_partition(&mut self, parent: &str, name: &str, from: &str, to: &str) -> Result<()>;
+
     /// Drop a table
     fn drop_table(&mut self, name: &str) -> Result<()>;
 
+    /// Rename a table, keeping its data. `to` stays in the table's schema,
+    /// so a schema prefix on it is ignored.
+    fn rename_table(&mut self, from: &str, to: &str) -> Result<()>;
+
+    /// Delete every row from a table, keeping the table itself
+    fn truncate_table(&mut self, table: &str) -> Result<()> {
+        self.truncate_table_with(table, TruncateOptions::default())
+    }
+
+    /// Delete every row from a table, with PostgreSQL's `RESTART IDENTITY`
+    /// and `CASCADE` options
+    fn truncate_table_with(&mut self, table: &str, options: TruncateOptions) -> Result<()>;
+
     /// Add a column to a table
     fn add_column(&mut self, table: &str, column: ColumnDef) -> Result<()>;
 
     /// Drop a column from a table
     fn drop_column(&mut self, table: &str, column: &str) -> Result<()>;
 
+    /// Rename a column, keeping its data
+    fn rename_column(&mut self, table: &str, from: &str, to: &str) -> Result<()>;
+
+    /// Change a column's type from `old_ty` to `new_ty`, converting the
+    /// values it holds. SQLite can't alter a column, so the table is rebuilt.
+    fn modify_column(
+        &mut self,
+        table: &str,
+        column: &str,
+        old_ty: &str,
+        new_ty: &str,
+    ) -> Result<()>;
+
+    /// Make a nullable column `NOT NULL`, first setting existing `NULL`s to
+    /// `default` (a SQL expression) so the constraint can be added
+    fn make_not_null(&mut self, table: &str, column: &str, default: &str) -> Result<()>;
+
+    /// Set existing `NULL`s in `column` to `value` (a SQL expression)
+    /// `batch_size` rows at a time, so no single statement holds locks on the
+    /// whole table. PostgreSQL commits after each batch, which needs
+    /// PostgreSQL 11+ and has to run outside a transaction, e.g. in
+    /// `followup()`.
+    fn backfill_column(
+        &mut self,
+        table: &str,
+        column: &str,
+        value: &str,
+        batch_size: usize,
+    ) -> Result<()>;
+
+    /// Allow `NULL` in a `NOT NULL` column
+    fn drop_not_null(&mut self, table: &str, column: &str) -> Result<()>;
+
+    /// Set a column's default to a SQL expression, or remove it with `None`.
+    /// Existing rows keep their values.
+    fn set_column_default(
+        &mut self,
+        table: &str,
+        column: &str,
+        default: Option<&str>,
+    ) -> Result<()>;
+
     /// Create an index
     fn create_index(&mut self, table: &str, index: IndexDef) -> Result<()>;
 
     /// Drop an index
     fn drop_index(&mut self, table: &str, index_name: &str) -> Result<()>;
+
+    /// Rename an index, keeping it in place where the database allows
+    fn rename_index(&mut self, table: &str, from: &str, to: &str) -> Result<()>;
+
+    /// Add a constraint to an existing table
+    ///
+    /// When `constraint.not_valid` is set, PostgreSQL adds the constraint
+    /// without checking existing rows; run `validate_constraint` afterwards,
+    /// outside the migration transaction.
+    fn add_constraint(&mut self, table: &str, constraint: ConstraintDef) -> Result<()>;
+
+    /// Check existing rows against a constraint added as `NOT VALID`
+    fn validate_constraint(&mut self, table: &str, name: &str) -> Result<()>;
+
+    /// Drop a constraint added with `add_constraint`. MySQL drops foreign
+    /// keys and checks with different statements, hence the full definition.
+    fn drop_constraint(&mut self, table: &str, constraint: ConstraintDef) -> Result<()>;
+
+    /// Attach a documentation comment to a table
+    ///
+    /// Backends without table comments (SQLite, NoSQL) ignore this.
+    fn comment_on_table(&mut self, table: &str, comment: &str) -> Result<()>;
+
+    /// Attach a documentation comment to a column, or remove it with `None`
+    ///
+    /// SQLite and NoSQL backends ignore this. MySQL restates the column's
+    /// definition, so it needs the schema to be audited or given.
+    fn comment_on_column(&mut self, table: &str, column: &str, comment: Option<&str>)
+        -> Result<()>;
+
+    /// Set a table's storage options, leaving those that are `None` as they
+    /// are
+    ///
+    /// Only MySQL has table options; other backends ignore this.
+    fn alter_table_options(&mut self, table: &str, options: TableOptions) -> Result<()>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ColumnDef {
     pub name: String,
     pub ty: String,
     pub nullable: bool,
     pub default: Option<String>,
+    /// Makes this a generated column computed from the given expression
+    pub generated: Option<String>,
+    /// Store the generated value instead of computing it on read
+    pub stored: bool,
+    /// Collation for comparing and sorting text, e.g. `"C"` or
+    /// `"utf8mb4_unicode_ci"`
+    pub collation: Option<String>,
+    /// Part of the table's primary key. Only used by `create_table`.
+    pub primary_key: bool,
+    /// Foreign key to a column of another table. Only used by
+    /// `create_table`, which adds it to the table body.
+    pub references: Option<ColumnReference>,
+}
+
+/// The column a foreign key column points at, for `ColumnDef::references`
+#[derive(Debug, Clone, Default)]
+pub struct ColumnReference {
+    pub table: String,
+    pub column: String,
+    /// What happens to referencing rows when the referenced row is deleted
+    pub on_delete: ReferentialAction,
+}
+
+/// `ON DELETE` behavior of a foreign key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReferentialAction {
+    /// Reject the delete while rows still reference it
+    #[default]
+    NoAction,
+    Restrict,
+    Cascade,
+    SetNull,
+}
+
+impl ReferentialAction {
+    /// The `ON DELETE` clause, empty for the default `NO ACTION`
+    pub(crate) fn on_delete_sql(self) -> &'static str {
+        match self {
+            ReferentialAction::NoAction => "",
+            ReferentialAction::Restrict => " ON DELETE RESTRICT",
+            ReferentialAction::Cascade => " ON DELETE CASCADE",
+            ReferentialAction::SetNull => " ON DELETE SET NULL",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstraintDef {
+    pub name: String,
+    pub kind: ConstraintKind,
+    /// Skip checking existing rows when adding the constraint (PostgreSQL)
+    pub not_valid: bool,
 }
 
 #[derive(Debug, Clone)]
+pub enum ConstraintKind {
+    ForeignKey {
+        columns: Vec<String>,
+        references_table: String,
+        references_columns: Vec<String>,
+    },
+    Check {
+        expr: String,
+    },
+}
+
+/// Options for `MigrationContext::truncate_table_with`. Only PostgreSQL
+/// supports them; other backends ignore them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TruncateOptions {
+    /// Reset sequences owned by the table's columns
+    pub restart_identity: bool,
+    /// Also truncate tables with foreign keys referencing this one
+    pub cascade: bool,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct IndexDef {
     pub name: String,
     pub columns: Vec<String>,
     pub unique: bool,
+    /// Non-key columns stored in the index so queries can be answered from
+    /// it alone (`INCLUDE`, PostgreSQL only)
+    pub include: Vec<String>,
+    /// Only index rows matching this SQL condition (partial index, `WHERE`;
+    /// PostgreSQL and SQLite)
+    pub predicate: Option<String>,
 }
@@ -0,0 +1,420 @@
+use crate::{SqlFlavor, SqlMigrationContext};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+use toasty_core::stmt::Value;
+
+/// A row returned by [`AsyncMigrationContext::query`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// Column names, shared by all rows of a result
+    columns: Arc<[String]>,
+    values: Vec<Value>,
+}
+
+impl Row {
+    pub fn new(columns: Arc<[String]>, values: Vec<Value>) -> Self {
+        Self { columns, values }
+    }
+
+    /// The value of the column at `index`, in select-list order
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.values.get(index)
+    }
+
+    /// The value of the first column named `name`
+    pub fn get_by_name(&self, name: &str) -> Option<&Value> {
+        let index = self.columns.iter().position(|column| column == name)?;
+        self.values.get(index)
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn into_values(self) -> Vec<Value> {
+        self.values
+    }
+}
+
+/// A migration context over a live connection. Unlike [`MigrationContext`],
+/// which only buffers statements, each call runs against the database right
+/// away, so a data migration can read rows, transform them in Rust and write
+/// them back. See [`Migration::up_async`].
+///
+/// Statements take their parameters as `$1`, `$2`, ... on PostgreSQL and
+/// `?1`, `?2`, ... on SQLite.
+///
+/// [`MigrationContext`]: crate::MigrationContext
+/// [`Migration::up_async`]: crate::Migration::up_async
+#[async_trait]
+pub trait AsyncMigrationContext: Send {
+    /// The dialect of the connected database
+    fn flavor(&self) -> SqlFlavor;
+
+    /// Run a statement, returning the number of rows it changed
+    async fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64>;
+
+    /// Run a query and return all of its rows
+    async fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>>;
+
+    /// Run one or more statements without parameters, e.g. generated DDL
+    async fn execute_batch(&mut self, sql: &str) -> Result<()>;
+
+    /// Run the statements buffered in `context`. Dialect notes (`-- ...`)
+    /// are skipped.
+    async fn execute_all(&mut self, context: &SqlMigrationContext) -> Result<()> {
+        for sql in context.statements() {
+            if !sql.starts_with("--") {
+                self.execute_batch(sql).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn begin(&mut self) -> Result<()> {
+        self.execute_batch("BEGIN").await
+    }
+
+    async fn commit(&mut self) -> Result<()> {
+        self.execute_batch("COMMIT").await
+    }
+
+    async fn rollback(&mut self) -> Result<()> {
+        self.execute_batch("ROLLBACK").await
+    }
+
+    /// Run `UPDATE table SET set_sql` on the rows matching `where_sql`,
+    /// `batch_size` rows at a time, committing after each batch so no
+    /// transaction holds locks on the whole table. Returns the number of rows
+    /// updated.
+    ///
+    /// Batches repeat until none match, so `set_sql` must make a row stop
+    /// matching `where_sql` (e.g. `email_domain IS NULL`). Each batch is its
+    /// own transaction, so call this outside the migration's, from
+    /// [`Migration::followup_async`].
+    ///
+    /// [`Migration::followup_async`]: crate::Migration::followup_async
+    async fn backfill(
+        &mut self,
+        table: &str,
+        set_sql: &str,
+        where_sql: &str,
+        batch_size: usize,
+    ) -> Result<u64> {
+        let limit = batch_size.max(1);
+        let sql = match self.flavor() {
+            // Row locators avoid needing to know the table's key
+            SqlFlavor::PostgreSQL => format!(
+                "UPDATE {0} SET {1} WHERE ctid IN (SELECT ctid FROM {0} WHERE {2} LIMIT {3})",
+                table, set_sql, where_sql, limit
+            ),
+            SqlFlavor::Sqlite => format!(
+                "UPDATE {0} SET {1} WHERE rowid IN (SELECT rowid FROM {0} WHERE {2} LIMIT {3})",
+                table, set_sql, where_sql, limit
+            ),
+            SqlFlavor::MySQL => format!(
+                "UPDATE {} SET {} WHERE {} LIMIT {}",
+                table, set_sql, where_sql, limit
+            ),
+        };
+
+        let mut total = 0;
+        loop {
+            self.begin().await?;
+            let updated = match self.execute(&sql, &[]).await {
+                Ok(updated) => updated,
+                Err(e) => {
+                    self.rollback().await.ok();
+                    return Err(e);
+                }
+            };
+            self.commit().await?;
+
+            if updated == 0 {
+                return Ok(total);
+            }
+            total += updated;
+        }
+    }
+}
+
+/// Connect to the database at `url`, whichever kind it is
+pub async fn connect(url: &str) -> Result<Box<dyn AsyncMigrationContext>> {
+    match SqlFlavor::from_url(url) {
+        #[cfg(feature = "sqlite")]
+        Some(SqlFlavor::Sqlite) => Ok(Box::new(SqliteMigrationConnection::open(url)?)),
+        #[cfg(feature = "postgresql")]
+        Some(SqlFlavor::PostgreSQL) => {
+            Ok(Box::new(PostgresMigrationConnection::connect(url).await?))
+        }
+        _ => Err(anyhow::anyhow!(
+            "Migration connections are not supported for {}",
+            url.split(':').next().unwrap_or(url)
+        )),
+    }
+}
+
+/// [`AsyncMigrationContext`] over a SQLite connection
+#[cfg(feature = "sqlite")]
+pub struct SqliteMigrationConnection {
+    conn: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteMigrationConnection {
+    pub fn new(conn: rusqlite::Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Open the database at a `sqlite:` URL, see [`crate::open_sqlite`]
+    pub fn open(url: &str) -> Result<Self> {
+        Ok(Self::new(crate::open_sqlite(url)?))
+    }
+
+    pub fn connection(&self) -> &rusqlite::Connection {
+        &self.conn
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_param(value: &Value) -> Result<rusqlite::types::Value> {
+    use rusqlite::types::Value as Sqlite;
+
+    Ok(match value {
+        Value::Null => Sqlite::Null,
+        Value::Bool(v) => Sqlite::Integer(*v as i64),
+        Value::String(v) => Sqlite::Text(v.clone()),
+        Value::Bytes(v) => Sqlite::Blob(v.clone()),
+        Value::Uuid(v) => Sqlite::Text(v.to_string()),
+        value => Sqlite::Integer(integer(value)?),
+    })
+}
+
+#[cfg(feature = "sqlite")]
+#[async_trait]
+impl AsyncMigrationContext for SqliteMigrationConnection {
+    fn flavor(&self) -> SqlFlavor {
+        SqlFlavor::Sqlite
+    }
+
+    async fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64> {
+        let params = params
+            .iter()
+            .map(sqlite_param)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.conn.execute(sql, rusqlite::params_from_iter(params))? as u64)
+    }
+
+    async fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+        use rusqlite::types::ValueRef;
+
+        let params = params
+            .iter()
+            .map(sqlite_param)
+            .collect::<Result<Vec<_>>>()?;
+        let mut stmt = self.conn.prepare(sql)?;
+        let columns: Arc<[String]> = stmt.column_names().into_iter().map(String::from).collect();
+        let mut rows = stmt.query(rusqlite::params_from_iter(params))?;
+        let mut out = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            let values = (0..columns.len())
+                .map(|i| {
+                    Ok(match row.get_ref(i)? {
+                        ValueRef::Null => Value::Null,
+                        ValueRef::Integer(n) => Value::I64(n),
+                        // Toasty values have no floating point variant
+                        ValueRef::Real(n) => Value::String(n.to_string()),
+                        ValueRef::Text(text) => {
+                            Value::String(String::from_utf8_lossy(text).into_owned())
+                        }
+                        ValueRef::Blob(bytes) => Value::Bytes(bytes.to_vec()),
+                    })
+                })
+                .collect::<Result<_>>()?;
+            out.push(Row::new(columns.clone(), values));
+        }
+
+        Ok(out)
+    }
+
+    async fn execute_batch(&mut self, sql: &str) -> Result<()> {
+        Ok(self.conn.execute_batch(sql)?)
+    }
+
+    /// Takes the write lock up front rather than upgrading mid-transaction,
+    /// so a concurrent writer makes this wait out the busy timeout instead
+    /// of failing with "database is locked" halfway through
+    async fn begin(&mut self) -> Result<()> {
+        self.execute_batch("BEGIN IMMEDIATE").await
+    }
+}
+
+/// [`AsyncMigrationContext`] over a PostgreSQL connection
+#[cfg(feature = "postgresql")]
+pub struct PostgresMigrationConnection {
+    client: tokio_postgres::Client,
+}
+
+#[cfg(feature = "postgresql")]
+impl PostgresMigrationConnection {
+    pub fn new(client: tokio_postgres::Client) -> Self {
+        Self { client }
+    }
+
+    /// Connect to the database at a `postgresql:` URL
+    pub async fn connect(url: &str) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(url, tokio_postgres::NoTls).await?;
+
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("connection error: {}", e);
+            }
+        });
+
+        Ok(Self::new(client))
+    }
+
+    pub fn client(&self) -> &tokio_postgres::Client {
+        &self.client
+    }
+}
+
+/// A value bound to a PostgreSQL statement. Integers are narrowed or
+/// widened to the parameter's type.
+#[cfg(feature = "postgresql")]
+#[derive(Debug)]
+struct PostgresParam<'a>(&'a Value);
+
+#[cfg(feature = "postgresql")]
+impl tokio_postgres::types::ToSql for PostgresParam<'_> {
+    fn to_sql(
+        &self,
+        ty: &tokio_postgres::types::Type,
+        out: &mut tokio_postgres::types::private::BytesMut,
+    ) -> std::result::Result<tokio_postgres::types::IsNull, Box<dyn std::error::Error + Sync + Send>>
+    {
+        use tokio_postgres::types::{IsNull, Type};
+
+        match self.0 {
+            Value::Null => Ok(IsNull::Yes),
+            Value::Bool(v) => v.to_sql(ty, out),
+            Value::String(v) => v.to_sql(ty, out),
+            Value::Bytes(v) => v.to_sql(ty, out),
+            Value::Uuid(v) => v.to_string().to_sql(ty, out),
+            value => {
+                let n = integer(value)?;
+                match *ty {
+                    Type::INT2 => i16::try_from(n)?.to_sql(ty, out),
+                    Type::INT4 => i32::try_from(n)?.to_sql(ty, out),
+                    _ => n.to_sql(ty, out),
+                }
+            }
+        }
+    }
+
+    fn accepts(_ty: &tokio_postgres::types::Type) -> bool {
+        true
+    }
+
+    tokio_postgres::types::to_sql_checked!();
+}
+
+#[cfg(feature = "postgresql")]
+#[async_trait]
+impl AsyncMigrationContext for PostgresMigrationConnection {
+    fn flavor(&self) -> SqlFlavor {
+        SqlFlavor::PostgreSQL
+    }
+
+    async fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64> {
+        let params: Vec<_> = params.iter().map(PostgresParam).collect();
+        Ok(self.client.execute_raw(sql, params).await?)
+    }
+
+    async fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+        use tokio_postgres::types::ToSql;
+
+        let params: Vec<_> = params.iter().map(PostgresParam).collect();
+        let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p as _).collect();
+        let stmt = self.client.prepare(sql).await?;
+        let columns: Arc<[String]> = stmt
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+
+        self.client
+            .query(&stmt, &params)
+            .await?
+            .iter()
+            .map(|row| {
+                let values = (0..row.len())
+                    .map(|i| postgresql_value(row, i))
+                    .collect::<Result<_>>()?;
+                Ok(Row::new(columns.clone(), values))
+            })
+            .collect()
+    }
+
+    async fn execute_batch(&mut self, sql: &str) -> Result<()> {
+        Ok(self.client.batch_execute(sql).await?)
+    }
+}
+
+#[cfg(feature = "postgresql")]
+fn postgresql_value(row: &tokio_postgres::Row, i: usize) -> Result<Value> {
+    use tokio_postgres::types::Type;
+
+    let column = &row.columns()[i];
+    let value = match *column.type_() {
+        Type::BOOL => row.try_get::<_, Option<bool>>(i)?.map(Value::Bool),
+        Type::INT2 => row.try_get::<_, Option<i16>>(i)?.map(Value::I16),
+        Type::INT4 => row.try_get::<_, Option<i32>>(i)?.map(Value::I32),
+        Type::INT8 => row.try_get::<_, Option<i64>>(i)?.map(Value::I64),
+        Type::FLOAT4 => row
+            .try_get::<_, Option<f32>>(i)?
+            .map(|n| Value::String(n.to_string())),
+        Type::FLOAT8 => row
+            .try_get::<_, Option<f64>>(i)?
+            .map(|n| Value::String(n.to_string())),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => {
+            row.try_get::<_, Option<String>>(i)?.map(Value::String)
+        }
+        Type::BYTEA => row.try_get::<_, Option<Vec<u8>>>(i)?.map(Value::Bytes),
+        ref ty => {
+            return Err(anyhow::anyhow!(
+                "Column {} has unsupported type {}; cast it to text in the query",
+                column.name(),
+                ty
+            ))
+        }
+    };
+
+    Ok(value.unwrap_or(Value::Null))
+}
+
+/// An integer parameter as `i64`
+#[cfg(any(feature = "sqlite", feature = "postgresql"))]
+fn integer(value: &Value) -> Result<i64> {
+    Ok(match value {
+        Value::I8(v) => (*v).into(),
+        Value::I16(v) => (*v).into(),
+        Value::I32(v) => (*v).into(),
+        Value::I64(v) => *v,
+        Value::U8(v) => (*v).into(),
+        Value::U16(v) => (*v).into(),
+        Value::U32(v) => (*v).into(),
+        Value::U64(v) => i64::try_from(*v)?,
+        value => return Err(anyhow::anyhow!("Unsupported parameter: {:?}", value)),
+    })
+}
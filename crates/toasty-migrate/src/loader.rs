@@ -1,4 +1,5 @@
-use anyhow::Result;
+use crate::{Migration, MigrationScript};
+use anyhow::{bail, Result};
 
 /// Loads migration files from a directory
 pub struct MigrationLoader {
@@ -45,15 +46,127 @@ impl MigrationLoader {
         Ok(migrations)
     }
 
+    /// Discover and parse all migration files, in version order, so they
+    /// can be run without being compiled in. See [`MigrationScript`].
+    pub fn load_migrations(&self) -> Result<Vec<Box<dyn Migration>>> {
+        Ok(self.collect_migrations()?.into_vec())
+    }
+
+    /// Discover and parse all migration files into a registry, so their
+    /// `up()` can build the statements they run
+    pub fn collect_migrations(&self) -> Result<MigrationRegistry> {
+        let mut registry = MigrationRegistry::new();
+        for file in self.discover_migrations()? {
+            registry.register(Box::new(MigrationScript::load(&file.path)?))?;
+        }
+        Ok(registry)
+    }
+
     /// Get path to schema snapshot file
     pub fn snapshot_path(&self) -> std::path::PathBuf {
         self.migration_dir.join(".schema.json")
     }
 }
 
+/// Migrations kept in version order, whether parsed from their files by
+/// [`MigrationLoader::collect_migrations`] or compiled into the app.
+///
+/// A migration crate can list its migrations in one place:
+///
+/// ```ignore
+/// pub fn all_migrations() -> anyhow::Result<MigrationRegistry> {
+///     let mut registry = MigrationRegistry::new();
+///     registry.register(Box::new(Migration_20250101_000000_create_users))?;
+///     registry.register(Box::new(Migration_20250102_000000_add_bio))?;
+///     Ok(registry)
+/// }
+/// ```
+#[derive(Default)]
+pub struct MigrationRegistry {
+    migrations: Vec<Box<dyn Migration>>,
+}
+
+impl MigrationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a migration in its place by version. Two migrations can't share
+    /// a version.
+    pub fn register(&mut self, migration: Box<dyn Migration>) -> Result<()> {
+        let i = self
+            .migrations
+            .partition_point(|m| m.version() < migration.version());
+        if self
+            .migrations
+            .get(i)
+            .is_some_and(|m| m.version() == migration.version())
+        {
+            bail!("Migration {} is registered twice", migration.version());
+        }
+
+        self.migrations.insert(i, migration);
+        Ok(())
+    }
+
+    /// The migration with this version, if registered
+    pub fn get(&self, version: &str) -> Option<&dyn Migration> {
+        self.iter().find(|m| m.version() == version)
+    }
+
+    /// Migrations in version order
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Migration> {
+        self.migrations.iter().map(|m| m.as_ref())
+    }
+
+    pub fn versions(&self) -> Vec<&str> {
+        self.iter().map(|m| m.version()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.migrations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.migrations.is_empty()
+    }
+
+    /// The migrations in version order, e.g. for [`MigrationRunner::pending`]
+    ///
+    /// [`MigrationRunner::pending`]: crate::MigrationRunner::pending
+    pub fn into_vec(self) -> Vec<Box<dyn Migration>> {
+        self.migrations
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MigrationFileInfo {
     pub version: String,
     pub path: std::path::PathBuf,
     pub filename: String,
 }
+
+impl MigrationFileInfo {
+    /// Hash of the file's content as 16 hex digits, for telling whether a
+    /// migration changed after it was applied. Runs of whitespace count as a
+    /// single space, so reformatting the file keeps its hash.
+    ///
+    /// The file is read on each call; discovering migrations doesn't hash
+    /// them.
+    pub fn content_hash(&self) -> Result<String> {
+        let content = std::fs::read_to_string(&self.path)?;
+
+        // FNV-1a: stable across Rust versions and platforms, unlike
+        // `DefaultHasher`
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for (i, word) in content.split_whitespace().enumerate() {
+            let separator: &[u8] = if i == 0 { b"" } else { b" " };
+            for byte in separator.iter().chain(word.as_bytes()) {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+
+        Ok(format!("{:016x}", hash))
+    }
+}
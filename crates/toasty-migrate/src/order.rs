@@ -0,0 +1,66 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// Order tables so each comes after the tables it references, i.e. the
+/// order to create them in. Reverse it to drop children before parents.
+///
+/// `references` holds `(table, referenced_table)` pairs, one per foreign
+/// key. Self-references and references to tables not in `tables` are
+/// ignored. Tables caught in a reference cycle can't be ordered and are
+/// appended at the end in name order.
+pub fn dependency_order(tables: &[String], references: &[(String, String)]) -> Vec<String> {
+    let index: HashMap<&str, usize> = tables
+        .iter()
+        .enumerate()
+        .map(|(i, table)| (table.as_str(), i))
+        .collect();
+
+    // Number of unordered parents per table, and the reverse edges used to
+    // release children once a parent is ordered
+    let mut blocked_on = vec![0; tables.len()];
+    let mut children: Vec<Vec<usize>> = vec![vec![]; tables.len()];
+    let mut seen = BTreeSet::new();
+
+    for (table, parent) in references {
+        let (Some(&child), Some(&parent)) = (index.get(table.as_str()), index.get(parent.as_str()))
+        else {
+            continue;
+        };
+
+        // A table with several foreign keys to the same parent only waits
+        // on it once
+        if child != parent && seen.insert((child, parent)) {
+            blocked_on[child] += 1;
+            children[parent].push(child);
+        }
+    }
+
+    let mut ready: BTreeSet<(&str, usize)> = tables
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| blocked_on[*i] == 0)
+        .map(|(i, table)| (table.as_str(), i))
+        .collect();
+    let mut order = Vec::with_capacity(tables.len());
+
+    while let Some((table, i)) = ready.pop_first() {
+        order.push(table.to_string());
+
+        for &child in &children[i] {
+            blocked_on[child] -= 1;
+            if blocked_on[child] == 0 {
+                ready.insert((tables[child].as_str(), child));
+            }
+        }
+    }
+
+    let mut cyclic: Vec<&String> = tables
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| blocked_on[*i] > 0)
+        .map(|(_, table)| table)
+        .collect();
+    cyclic.sort();
+    order.extend(cyclic.into_iter().cloned());
+
+    order
+}
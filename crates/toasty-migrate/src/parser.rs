@@ -1,6 +1,21 @@
+use crate::context::SqlFlavor;
 use crate::snapshot::*;
 use anyhow::Result;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Column `#[soft_delete]` adds, set when a row is deleted
+const SOFT_DELETE_COLUMN: &str = "deleted_at";
+
+/// The type of [`SOFT_DELETE_COLUMN`] on each database. MySQL has no
+/// timestamp with a time zone, and its `timestamp` ends in 2038.
+fn soft_delete_type(flavor: Option<SqlFlavor>) -> &'static str {
+    match flavor {
+        Some(SqlFlavor::MySQL) => "datetime(6)",
+        Some(SqlFlavor::Sqlite) => "timestamp",
+        Some(SqlFlavor::PostgreSQL) | None => "timestamptz",
+    }
+}
 
 /// Convert PascalCase to snake_case
 fn to_snake_case(s: &str) -> String {
@@ -18,21 +33,373 @@ fn to_snake_case(s: &str) -> String {
     result
 }
 
+/// Pluralize a snake_case name by its last word: `category` → `categories`,
+/// `address` → `addresses`, `user_role` → `user_roles`
+fn pluralize(name: &str) -> String {
+    let consonant_y = name.strip_suffix('y').filter(|stem| {
+        stem.chars()
+            .last()
+            .is_some_and(|ch| ch.is_ascii_alphabetic() && !"aeiou".contains(ch))
+    });
+
+    if let Some(stem) = consonant_y {
+        format!("{}ies", stem)
+    } else if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|end| name.ends_with(end))
+    {
+        format!("{}es", name)
+    } else {
+        format!("{}s", name)
+    }
+}
+
+/// Extract the foreign key columns from a `#[belongs_to(...)]` attribute,
+/// falling back to the `<field>_id` convention for a bare `#[belongs_to]`,
+/// along with the columns they reference, `id` unless `references` is given.
+fn belongs_to_keys(
+    attr: &syn::Attribute,
+    field_name: &str,
+) -> syn::Result<(Vec<String>, Vec<String>)> {
+    let mut keys = Vec::new();
+    let mut references = Vec::new();
+
+    if let syn::Meta::List(_) = attr.meta {
+        attr.parse_nested_meta(|meta| {
+            let value: syn::Expr = meta.value()?.parse()?;
+            let columns = if meta.path.is_ident("key") {
+                &mut keys
+            } else if meta.path.is_ident("references") {
+                &mut references
+            } else {
+                return Ok(());
+            };
+
+            // `key = user_id` or `key = [org_id, user_id]`
+            let paths: Vec<syn::Expr> = match value {
+                syn::Expr::Array(array) => array.elems.into_iter().collect(),
+                value => vec![value],
+            };
+            for path in paths {
+                if let syn::Expr::Path(path) = path {
+                    if let Some(ident) = path.path.get_ident() {
+                        columns.push(ident.to_string());
+                    }
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    if keys.is_empty() {
+        keys.push(format!("{}_id", field_name));
+    }
+    if references.is_empty() {
+        references.push("id".to_string());
+    }
+    if references.len() != keys.len() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "#[belongs_to] needs one `references` column per `key` column",
+        ));
+    }
+    Ok((keys, references))
+}
+
+/// Parse a `#[generated("<expr>")]` or `#[generated("<expr>", stored)]`
+/// attribute into the expression and whether it is stored.
+fn parse_generated(attr: &syn::Attribute) -> syn::Result<(String, bool)> {
+    let args = attr.parse_args_with(
+        syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+    )?;
+    let mut args = args.into_iter();
+
+    let expr = match args.next() {
+        Some(syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(expr),
+            ..
+        })) => expr.value(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "expected #[generated(\"<expr>\")]",
+            ))
+        }
+    };
+    let stored =
+        args.any(|arg| matches!(arg, syn::Expr::Path(path) if path.path.is_ident("stored")));
+
+    Ok((expr, stored))
+}
+
+/// Parse a `#[<attr> = "<name>"]` attribute, such as `#[collation = "C"]`,
+/// into the name
+fn parse_name_value(attr: &syn::Attribute) -> syn::Result<String> {
+    match &attr.meta.require_name_value()?.value {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(name),
+            ..
+        }) => Ok(name.value()),
+        value => Err(syn::Error::new_spanned(
+            value,
+            "expected a string, e.g. `= \"name\"`",
+        )),
+    }
+}
+
+/// The `name` given in `#[index(name = "...")]` or `#[unique(name = "...")]`
+fn index_name(attr: &syn::Attribute) -> syn::Result<Option<String>> {
+    let mut name = None;
+
+    if let syn::Meta::List(_) = attr.meta {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `name = \"...\"`"))
+            }
+        })?;
+    }
+
+    Ok(name)
+}
+
+/// The options given in `#[table_options(engine = "...", charset = "...")]`
+fn table_options(attrs: &[syn::Attribute]) -> syn::Result<TableOptions> {
+    let mut options = TableOptions::default();
+
+    for attr in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("table_options"))
+    {
+        attr.parse_nested_meta(|meta| {
+            let value =
+                || -> syn::Result<String> { Ok(meta.value()?.parse::<syn::LitStr>()?.value()) };
+
+            if meta.path.is_ident("engine") {
+                options.engine = Some(value()?);
+            } else if meta.path.is_ident("charset") {
+                options.charset = Some(value()?);
+            } else {
+                return Err(meta.error("expected `engine = \"...\"` or `charset = \"...\"`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(options)
+}
+
+/// The partitioning given in `#[partition(by = "range", key = "created_at")]`.
+/// `key` may list several columns, separated by commas.
+fn partition_by(attrs: &[syn::Attribute]) -> syn::Result<Option<PartitionBy>> {
+    let Some(attr) = attrs.iter().find(|attr| attr.path().is_ident("partition")) else {
+        return Ok(None);
+    };
+
+    let mut strategy = None;
+    let mut columns = Vec::new();
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("by") {
+            let by = meta.value()?.parse::<syn::LitStr>()?;
+            strategy = Some(PartitionStrategy::from_name(&by.value()).ok_or_else(|| {
+                syn::Error::new_spanned(&by, "expected \"range\", \"list\" or \"hash\"")
+            })?);
+        } else if meta.path.is_ident("key") {
+            let key = meta.value()?.parse::<syn::LitStr>()?.value();
+            columns = key
+                .split(',')
+                .map(|column| column.trim().to_string())
+                .filter(|column| !column.is_empty())
+                .collect();
+        } else {
+            return Err(meta.error("expected `by = \"...\"` or `key = \"...\"`"));
+        }
+        Ok(())
+    })?;
+
+    match strategy {
+        Some(strategy) if !columns.is_empty() => Ok(Some(PartitionBy { strategy, columns })),
+        _ => Err(syn::Error::new_spanned(
+            attr,
+            "expected `#[partition(by = \"...\", key = \"...\")]`",
+        )),
+    }
+}
+
+/// Join the `///` doc comment lines among `attrs`
+fn doc_comment(attrs: &[syn::Attribute]) -> Option<String> {
+    let docs: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta.require_name_value().ok()?.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(doc),
+                ..
+            }) => Some(doc.value()),
+            _ => None,
+        })
+        .map(|doc| doc.strip_prefix(' ').map(str::to_string).unwrap_or(doc))
+        .collect();
+
+    let comment = docs.join("\n").trim().to_string();
+    (!comment.is_empty()).then_some(comment)
+}
+
+/// Whether `attrs` derive `toasty::Model`
+fn derives_model(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+    let mut model = false;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        attr.parse_nested_meta(|meta| {
+            let segments: Vec<_> = meta
+                .path
+                .segments
+                .iter()
+                .map(|s| s.ident.to_string())
+                .collect();
+            model |= segments == ["toasty", "Model"];
+            Ok(())
+        })?;
+    }
+
+    Ok(model)
+}
+
+/// The last path segment of `ty` and its generic type arguments, e.g.
+/// `Option` and `[Id<User>]` for `Option<Id<User>>`
+fn type_segment(ty: &syn::Type) -> Option<(String, Vec<&syn::Type>)> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+
+    let args = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+
+    Some((segment.ident.to_string(), args))
+}
+
+/// `ty` without an outer `Option`, and whether it had one
+fn strip_option(ty: &syn::Type) -> (&syn::Type, bool) {
+    match type_segment(ty) {
+        Some((name, args)) if name == "Option" && args.len() == 1 => (args[0], true),
+        _ => (ty, false),
+    }
+}
+
+/// Whether `ty` is one of Toasty's relation types rather than a column
+fn is_relation_type(ty: &syn::Type) -> bool {
+    matches!(
+        type_segment(strip_option(ty).0),
+        Some((name, _)) if matches!(name.as_str(), "HasMany" | "HasOne" | "BelongsTo")
+    )
+}
+
+/// The table for a model: `#[table_name = "..."]`, or Toasty's own
+/// `#[table = "..."]`, overrides the name derived from the struct
+fn table_name(item: &syn::ItemStruct) -> syn::Result<String> {
+    match item
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("table_name") || attr.path().is_ident("table"))
+    {
+        Some(attr) => parse_name_value(attr),
+        // Convert to snake_case and pluralize
+        None => Ok(pluralize(&to_snake_case(&item.ident.to_string()))),
+    }
+}
+
+/// Collect the models among `items`, including those in inline modules
+fn collect_models<'a>(
+    items: &'a [syn::Item],
+    models: &mut Vec<&'a syn::ItemStruct>,
+) -> syn::Result<()> {
+    for item in items {
+        match item {
+            syn::Item::Struct(item) if derives_model(&item.attrs)? => models.push(item),
+            syn::Item::Mod(syn::ItemMod {
+                content: Some((_, items)),
+                ..
+            }) => collect_models(items, models)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// A field the parser could only partly understand. The schema is still
+/// built, but may not match what Toasty itself derives from the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub table: String,
+    pub field: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}: {}", self.table, self.field, self.message)
+    }
+}
+
 /// Parse Rust entity files to extract schema
 pub struct EntityParser {
     entity_dir: std::path::PathBuf,
+    auto_fk_index: bool,
+    flavor: Option<SqlFlavor>,
 }
 
 impl EntityParser {
     pub fn new(entity_dir: impl Into<std::path::PathBuf>) -> Self {
         Self {
             entity_dir: entity_dir.into(),
+            auto_fk_index: true,
+            flavor: None,
         }
     }
 
+    /// The database the schema is for, which picks the types of the columns
+    /// the parser adds itself, like `#[soft_delete]`'s `deleted_at`. They
+    /// get PostgreSQL's types without one.
+    pub fn flavor(mut self, flavor: Option<SqlFlavor>) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    /// Whether to add an index on each `belongs_to` key column that is not
+    /// already covered by one. Enabled by default.
+    pub fn auto_fk_index(mut self, enabled: bool) -> Self {
+        self.auto_fk_index = enabled;
+        self
+    }
+
     /// Parse entity files and build schema snapshot
     pub fn parse_entities(&self) -> Result<SchemaSnapshot> {
-        println!("📖 Parsing entity files from: {}", self.entity_dir.display());
+        let (snapshot, warnings) = self.parse_entities_with_warnings()?;
+        for warning in warnings {
+            println!("⚠️  {}", warning);
+        }
+        Ok(snapshot)
+    }
+
+    /// Parse entity files, returning the warnings for fields that couldn't
+    /// be fully handled instead of printing them
+    pub fn parse_entities_with_warnings(&self) -> Result<(SchemaSnapshot, Vec<ParseWarning>)> {
+        println!(
+            "📖 Parsing entity files from: {}",
+            self.entity_dir.display()
+        );
 
         let src_dir = self.entity_dir.join("src");
         if !src_dir.exists() {
@@ -42,20 +409,45 @@ impl EntityParser {
             ));
         }
 
-        // Find all .rs files recursively
-        let mut all_tables = Vec::new();
-        self.scan_directory(&src_dir, &mut all_tables)?;
+        // Read every file before parsing models, so a relation can find the
+        // table of a model defined in another file
+        let mut files = Vec::new();
+        self.scan_directory(&src_dir, &mut files)?;
+
+        let mut models = Vec::new();
+        for (path, file) in &files {
+            let mut file_models = Vec::new();
+            collect_models(&file.items, &mut file_models)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+            models.extend(file_models.into_iter().map(|item| (path, item)));
+        }
+
+        let mut table_names = HashMap::new();
+        for (path, item) in &models {
+            let table = table_name(item)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+            table_names.insert(item.ident.to_string(), table);
+        }
 
+        let mut all_tables = Vec::new();
+        let mut warnings = Vec::new();
+        for (path, item) in models {
+            let table = self
+                .parse_struct(item, &table_names, &mut warnings)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+            all_tables.push(table);
+        }
         println!("✅ Parsed {} model(s) from entity files", all_tables.len());
 
-        Ok(SchemaSnapshot {
+        let snapshot = SchemaSnapshot {
             version: "1.0".to_string(),
             timestamp: chrono::Utc::now().to_rfc3339(),
             tables: all_tables,
-        })
+        };
+        Ok((snapshot, warnings))
     }
 
-    fn scan_directory(&self, dir: &Path, tables: &mut Vec<TableSnapshot>) -> Result<()> {
+    fn scan_directory(&self, dir: &Path, files: &mut Vec<(PathBuf, syn::File)>) -> Result<()> {
         for entry in std::fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
@@ -63,189 +455,311 @@ impl EntityParser {
             if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("rs") {
                 // Parse this Rust file
                 let content = std::fs::read_to_string(&path)?;
-                let mut file_tables = self.parse_models_from_content(&content)?;
-                tables.append(&mut file_tables);
+                let file = syn::parse_file(&content)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+                files.push((path, file));
             } else if path.is_dir() {
                 // Recursively scan subdirectories
-                self.scan_directory(&path, tables)?;
+                self.scan_directory(&path, files)?;
             }
         }
         Ok(())
     }
 
-    fn parse_models_from_content(&self, content: &str) -> Result<Vec<TableSnapshot>> {
-        let mut tables = Vec::new();
-
-        // Simple regex-based parsing (for MVP - could use syn for full parsing)
-        let lines: Vec<&str> = content.lines().collect();
-        let mut i = 0;
-
-        while i < lines.len() {
-            let line = lines[i].trim();
-
-            // Look for #[derive(Debug, toasty::Model)] or #[derive(toasty::Model)]
-            if line.contains("#[derive") && line.contains("toasty::Model") {
-                // Next lines should have the struct
-                i += 1;
-                while i < lines.len() {
-                    let struct_line = lines[i].trim();
-                    if struct_line.starts_with("pub struct ") {
-                        if let Some(table) = self.parse_struct(&lines, i)? {
-                            tables.push(table);
-                        }
-                        break;
-                    }
-                    i += 1;
-                }
-            }
-            i += 1;
-        }
-
-        Ok(tables)
-    }
-
-    fn parse_struct(&self, lines: &[&str], start: usize) -> Result<Option<TableSnapshot>> {
-        // Extract struct name
-        let struct_line = lines[start].trim();
-        let parts: Vec<&str> = struct_line.split_whitespace().collect();
-        if parts.len() < 3 {
-            return Ok(None);
-        }
-
-        let struct_name = parts[2].trim_end_matches(" {").to_string();
-
-        // Convert to snake_case and pluralize
-        let table_name = to_snake_case(&struct_name) + "s";
+    fn parse_struct(
+        &self,
+        item: &syn::ItemStruct,
+        table_names: &HashMap<String, String>,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> syn::Result<TableSnapshot> {
+        let table_name = table_name(item)?;
 
         let mut columns = Vec::new();
         let mut indices = Vec::new();
         let mut primary_key = Vec::new();
-        let mut has_key = false;
+        let mut foreign_keys = Vec::new();
+        let mut key_columns = Vec::new();
+        let mut optional_keys = Vec::new();
+        // Where each index came from, for reporting name collisions
+        let mut index_sources = Vec::new();
+
+        for field in &item.fields {
+            let Some(ident) = &field.ident else {
+                continue;
+            };
+            let field_name = ident.to_string();
 
-        // Parse fields
-        let mut i = start + 1;
-        while i < lines.len() {
-            let line = lines[i].trim();
-
-            if line == "}" {
-                break;
-            }
-
-            // Check for attributes in previous lines
             let mut is_key = false;
             let mut is_unique = false;
             let mut is_index = false;
-            let mut is_relation = false;
-
-            // Look back for attributes on previous line only
-            if i > 0 {
-                let prev_line = lines[i - 1].trim();
-                if prev_line.contains("#[key]") {
+            let mut explicit_index_name = None;
+            let mut is_auto = false;
+            let mut relation = None;
+            let mut generated = None;
+            let mut collation = None;
+
+            // Other attributes, such as doc comments and `cfg`, don't affect
+            // the column
+            for attr in &field.attrs {
+                let path = attr.path();
+                if path.is_ident("key") {
                     is_key = true;
-                }
-                if prev_line.contains("#[unique]") {
+                } else if path.is_ident("unique") {
                     is_unique = true;
-                }
-                if prev_line.contains("#[index]") {
+                    explicit_index_name = index_name(attr)?.or(explicit_index_name);
+                } else if path.is_ident("index") {
                     is_index = true;
-                }
-                if prev_line.contains("#[has_many]") || prev_line.contains("#[belongs_to]") {
-                    is_relation = true;
+                    explicit_index_name = index_name(attr)?.or(explicit_index_name);
+                } else if path.is_ident("auto") {
+                    is_auto = true;
+                } else if path.is_ident("belongs_to")
+                    || path.is_ident("has_many")
+                    || path.is_ident("has_one")
+                {
+                    relation = Some(attr);
+                } else if path.is_ident("generated") {
+                    generated = Some(parse_generated(attr)?);
+                } else if path.is_ident("collation") {
+                    collation = Some(parse_name_value(attr)?);
                 }
             }
 
-            // Parse field: pub name: Type,
-            if line.starts_with("pub ") && line.contains(":") {
-                // Quick check: skip if this is obviously a relationship type
-                if line.contains("HasMany<") || line.contains("BelongsTo<") {
-                    i += 1;
-                    continue;
+            let (ty, nullable) = strip_option(&field.ty);
+
+            // Remember belongs_to keys so they can be indexed below
+            if let Some(attr) = relation.filter(|attr| attr.path().is_ident("belongs_to")) {
+                let (keys, references) = belongs_to_keys(attr, &field_name)?;
+
+                // The referenced table is the one of the model in
+                // `BelongsTo<Model>`, which may be defined in another file
+                let references_table = match type_segment(ty) {
+                    Some((name, args)) if name == "BelongsTo" && args.len() == 1 => {
+                        type_segment(args[0]).map(|(model, _)| {
+                            table_names
+                                .get(&model)
+                                .cloned()
+                                .unwrap_or_else(|| pluralize(&to_snake_case(&model)))
+                        })
+                    }
+                    _ => None,
+                };
+                if let Some(references_table) = references_table {
+                    for (column, references_column) in keys.iter().zip(&references) {
+                        foreign_keys.push(ForeignKeySnapshot {
+                            column: column.clone(),
+                            references_table: references_table.clone(),
+                            references_column: references_column.clone(),
+                        });
+                    }
                 }
 
-                // Skip if previous line has relationship attribute
-                if is_relation {
-                    i += 1;
-                    continue;
+                // An optional relation can be unset, which its key columns
+                // can only record if they are `Option`s too
+                if nullable {
+                    optional_keys.extend(keys.iter().map(|key| (field_name.clone(), key.clone())));
                 }
+                key_columns.extend(keys);
+            }
 
-                let field_parts: Vec<&str> = line.split(':').collect();
-                if field_parts.len() >= 2 {
-                    let field_name = field_parts[0]
-                        .trim()
-                        .trim_start_matches("pub ")
-                        .to_string();
-
-                    let field_type = field_parts[1]
-                        .trim()
-                        .trim_end_matches(',')
-                        .to_string();
-
-                    // Skip if this field name is a duplicate (already added as key)
-                    let is_duplicate = columns.iter().any(|c: &ColumnSnapshot| c.name == field_name);
-                    if is_duplicate {
-                        i += 1;
-                        continue;
-                    }
+            let mut warn = |message: String| {
+                warnings.push(ParseWarning {
+                    table: table_name.clone(),
+                    field: field_name.clone(),
+                    message,
+                })
+            };
 
-                    // Determine nullable and type
-                    let (nullable, clean_type) = if field_type.starts_with("Option<") {
-                        let inner = field_type
-                            .trim_start_matches("Option<")
-                            .trim_end_matches('>')
-                            .to_string();
-                        (true, inner)
-                    } else {
-                        (false, field_type)
-                    };
-
-                    // Map Rust types to SQL types
-                    let sql_type = match clean_type.as_str() {
-                        "String" => "text",
-                        "i32" => "integer",
-                        "i64" => "bigint",
-                        t if t.starts_with("Id<") => "text",
-                        _ => "text", // Default
-                    };
-
-                    columns.push(ColumnSnapshot {
-                        name: field_name.clone(),
-                        ty: sql_type.to_string(),
-                        nullable,
-                    });
+            if relation.is_some() {
+                continue;
+            }
+            if is_relation_type(&field.ty) {
+                warn(
+                    "relation type without #[belongs_to], #[has_many] or #[has_one]; skipped"
+                        .into(),
+                );
+                continue;
+            }
 
-                    if is_key {
-                        primary_key.push(field_name.clone());
-                        has_key = true;
+            // Map Rust types to SQL types, falling back to text
+            let sql_type = match type_segment(ty) {
+                Some((name, _)) => match name.as_str() {
+                    "String" => "text",
+                    "i32" => "integer",
+                    "i64" => "bigint",
+                    "Id" => "text",
+                    name => {
+                        warn(format!("no SQL type for `{}`; using text", name));
+                        "text"
                     }
+                },
+                None => {
+                    warn("unsupported field type; using text".into());
+                    "text"
+                }
+            };
+
+            let (generated, stored) = match generated {
+                Some((expr, stored)) => (Some(expr), stored),
+                None => (None, false),
+            };
+
+            columns.push(ColumnSnapshot {
+                name: field_name.clone(),
+                ty: sql_type.to_string(),
+                nullable,
+                generated,
+                stored,
+                default: None,
+                auto: is_auto,
+                collation,
+                comment: doc_comment(&field.attrs),
+            });
+
+            if is_key {
+                primary_key.push(field_name.clone());
+            }
 
-                    if is_unique || is_key {
-                        indices.push(IndexSnapshot {
-                            name: format!("index_{}_by_{}", table_name, field_name),
-                            columns: vec![field_name.clone()],
-                            unique: true,
-                            primary_key: is_key,
-                        });
-                    } else if is_index {
-                        indices.push(IndexSnapshot {
-                            name: format!("index_{}_by_{}", table_name, field_name),
-                            columns: vec![field_name],
-                            unique: false,
-                            primary_key: false,
-                        });
-                    }
+            // The primary key already makes a key column unique, so
+            // `#[unique]` or `#[index]` on it adds no second index
+            if (is_unique || is_index) && !is_key {
+                let attr = if is_unique { "#[unique]" } else { "#[index]" };
+                index_sources.push(format!("{} on `{}`", attr, field_name));
+                indices.push(IndexSnapshot {
+                    name: explicit_index_name
+                        .unwrap_or_else(|| format!("index_{}_by_{}", table_name, field_name)),
+                    columns: vec![field_name],
+                    unique: is_unique,
+                    primary_key: false,
+                    include: vec![],
+                    predicate: None,
+                });
+            }
+        }
+
+        for (field, key) in optional_keys {
+            if columns.iter().any(|c| c.name == key && !c.nullable) {
+                warnings.push(ParseWarning {
+                    table: table_name.clone(),
+                    field,
+                    message: format!(
+                        "optional relation with a non-Option key `{}`; the column is NOT NULL",
+                        key
+                    ),
+                });
+            }
+        }
+
+        // `#[soft_delete]` marks rows deleted instead of removing them, so
+        // uniqueness only holds among the rows that aren't
+        if item
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("soft_delete"))
+        {
+            if columns
+                .iter()
+                .any(|column| column.name == SOFT_DELETE_COLUMN)
+            {
+                return Err(syn::Error::new_spanned(
+                    &item.ident,
+                    format!(
+                        "#[soft_delete] adds `{}`; remove the field",
+                        SOFT_DELETE_COLUMN
+                    ),
+                ));
+            }
+
+            columns.push(ColumnSnapshot {
+                name: SOFT_DELETE_COLUMN.to_string(),
+                ty: soft_delete_type(self.flavor).to_string(),
+                nullable: true,
+                generated: None,
+                stored: false,
+                default: None,
+                auto: false,
+                collation: None,
+                comment: None,
+            });
+
+            for index in indices.iter_mut().filter(|index| index.unique) {
+                index.predicate = Some(format!("{} IS NULL", SOFT_DELETE_COLUMN));
+            }
+        }
+
+        // Index foreign key columns unless an existing index already leads
+        // with them
+        if self.auto_fk_index {
+            for column in key_columns {
+                let covered = primary_key.first() == Some(&column)
+                    || indices
+                        .iter()
+                        .any(|index| index.columns.first() == Some(&column));
+
+                if !covered && columns.iter().any(|c| c.name == column) {
+                    index_sources.push(format!("the belongs_to key `{}`", column));
+                    indices.push(IndexSnapshot {
+                        name: format!("index_{}_by_{}", table_name, column),
+                        columns: vec![column],
+                        unique: false,
+                        primary_key: false,
+                        include: vec![],
+                        predicate: None,
+                    });
                 }
             }
+        }
 
-            i += 1;
+        // Two indexes with one name would fail as the second is created
+        for (i, index) in indices.iter().enumerate() {
+            if let Some(first) = indices[..i]
+                .iter()
+                .position(|other| other.name == index.name)
+            {
+                return Err(syn::Error::new_spanned(
+                    &item.ident,
+                    format!(
+                        "index {} on {} comes from both {} and {}",
+                        index.name, table_name, index_sources[first], index_sources[i]
+                    ),
+                ));
+            }
+        }
+
+        let partition_by = partition_by(&item.attrs)?;
+        if let Some(partition_by) = &partition_by {
+            for column in &partition_by.columns {
+                if !columns.iter().any(|c| &c.name == column) {
+                    return Err(syn::Error::new_spanned(
+                        &item.ident,
+                        format!(
+                            "partition key `{}` is not a field of {}",
+                            column, item.ident
+                        ),
+                    ));
+                }
+                // PostgreSQL rejects the CREATE TABLE otherwise
+                if !primary_key.contains(column) {
+                    warnings.push(ParseWarning {
+                        table: table_name.clone(),
+                        field: column.clone(),
+                        message: "partition key is not part of the primary key".into(),
+                    });
+                }
+            }
         }
 
         // Don't add default id - models should always have #[key] field
 
-        Ok(Some(TableSnapshot {
+        Ok(TableSnapshot {
+            schema: None,
             name: table_name,
             columns,
             indices,
             primary_key,
-        }))
+            comment: doc_comment(&item.attrs),
+            foreign_keys,
+            options: table_options(&item.attrs)?,
+            partition_by,
+        })
     }
 }
@@ -0,0 +1,50 @@
+//! The types migrations and tools built on Toasty's migrations use most.
+//! Generated migration files import this.
+//!
+//! ```
+//! use toasty_migrate::prelude::*;
+//!
+//! struct CreateUsers;
+//!
+//! impl Migration for CreateUsers {
+//!     fn version(&self) -> &str {
+//!         "20250101_000000_create_users"
+//!     }
+//!
+//!     fn up(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+//!         db.create_table("users", vec![
+//!             ColumnDef { name: "id".into(), ty: "text".into(), primary_key: true, ..Default::default() },
+//!             ColumnDef { name: "email".into(), ty: "text".into(), ..Default::default() },
+//!         ])?;
+//!         db.create_index("users", IndexDef {
+//!             name: "index_users_by_email".into(),
+//!             columns: vec!["email".into()],
+//!             unique: true,
+//!             ..Default::default()
+//!         })
+//!     }
+//!
+//!     fn down(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+//!         db.drop_table("users")
+//!     }
+//! }
+//!
+//! let mut db = SqlMigrationContext::new(SqlFlavor::Sqlite);
+//! CreateUsers.up(&mut db).unwrap();
+//! assert!(db.statements()[0].starts_with("CREATE TABLE users ("));
+//!
+//! let schema: SchemaSnapshot = serde_json::from_str(
+//!     r#"{"version": "1.0", "timestamp": "2025-01-01T00:00:00Z", "tables": []}"#,
+//! ).unwrap();
+//! assert!(schema.tables.is_empty());
+//! ```
+
+pub use crate::generator::Migration;
+pub use crate::live::{AsyncMigrationContext, Row};
+pub use crate::snapshot::SchemaSnapshot;
+pub use crate::{
+    ColumnDef, ColumnReference, ConstraintDef, ConstraintKind, IndexDef, MigrationContext,
+    PartitionBy, PartitionStrategy, ReferentialAction, SqlFlavor, SqlMigrationContext,
+    TableOptions, TruncateOptions,
+};
+pub use async_trait::async_trait;
@@ -0,0 +1,110 @@
+use crate::diff::{SchemaChange, SchemaDiff};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File in the migrations directory recording confirmed column renames, so
+/// regenerating a migration turns the same drop and add into a rename again
+pub const RENAMES_FILE: &str = ".renames.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnRename {
+    pub table: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// Column renames confirmed so far, as stored in [`RENAMES_FILE`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RenameHints {
+    pub columns: Vec<ColumnRename>,
+}
+
+impl RenameHints {
+    /// Load hints from `path`. A missing file has no hints.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid rename hints {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Record a rename, unless it is already known
+    pub fn add(&mut self, rename: ColumnRename) {
+        if !self.columns.contains(&rename) {
+            self.columns.push(rename);
+        }
+    }
+}
+
+/// Dropped and added columns on the same table that could be one column
+/// renamed: both have the same type and nullability
+pub fn rename_candidates(diff: &SchemaDiff) -> Vec<ColumnRename> {
+    let mut candidates = Vec::new();
+
+    for change in &diff.changes {
+        let SchemaChange::DropColumn {
+            table,
+            column: dropped,
+        } = change
+        else {
+            continue;
+        };
+
+        for change in &diff.changes {
+            if let SchemaChange::AddColumn {
+                table: t,
+                column: added,
+            } = change
+            {
+                if t == table
+                    && added.ty.eq_ignore_ascii_case(&dropped.ty)
+                    && added.nullable == dropped.nullable
+                {
+                    candidates.push(ColumnRename {
+                        table: table.clone(),
+                        from: dropped.name.clone(),
+                        to: added.name.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Replace each rename's drop and add in `diff` with a single
+/// `RenameColumn`. Renames whose columns aren't both in the diff, such as
+/// hints for migrations already generated, are ignored.
+pub fn apply_renames(diff: &mut SchemaDiff, renames: &[ColumnRename]) {
+    for rename in renames {
+        let drop = diff.changes.iter().position(|change| {
+            matches!(change, SchemaChange::DropColumn { table, column }
+                if *table == rename.table && column.name == rename.from)
+        });
+        let add = diff.changes.iter().position(|change| {
+            matches!(change, SchemaChange::AddColumn { table, column }
+                if *table == rename.table && column.name == rename.to)
+        });
+
+        if let (Some(drop), Some(add)) = (drop, add) {
+            diff.changes[drop] = SchemaChange::RenameColumn {
+                table: rename.table.clone(),
+                from: rename.from.clone(),
+                to: rename.to.clone(),
+            };
+            diff.changes.remove(add);
+        }
+    }
+}
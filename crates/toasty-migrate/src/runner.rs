@@ -1,72 +1,484 @@
-use crate::{Migration, MigrationContext, MigrationTracker};
+use crate::{
+    save_snapshot, AsyncMigrationContext, Migration, MigrationContext, MigrationTracker,
+    SchemaSnapshot, SqlIntrospector, SqlMigrationContext,
+};
 use anyhow::Result;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Where `toasty migrate:up --dump-schema-on-apply` keeps the schema
+/// snapshot taken after each migration
+pub const SCHEMA_HISTORY_DIR: &str = ".toasty/history";
+
+/// Callback run with a migration's version, see [`MigrationRunner::on_before`]
+type Hook = Box<dyn Fn(&str) + Send + Sync>;
+
+/// `println!` unless the runner is [`quiet`](MigrationRunner::quiet)
+macro_rules! say {
+    ($runner:expr, $($arg:tt)*) => {
+        if !$runner.quiet {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Where a run sends migrations' statements
+enum Target<'a> {
+    /// Buffered for the caller to execute, with the followups kept apart so
+    /// they can run after `context` has committed. The `*_async()` methods
+    /// can't run without a connection and are skipped, and recording the
+    /// migration in `_toasty_migrations` is left to the caller.
+    Buffered {
+        context: &'a mut dyn MigrationContext,
+        followup: Option<&'a mut dyn MigrationContext>,
+    },
+
+    /// Executed on a live connection as each migration runs, one
+    /// transaction per migration that also records it in
+    /// `_toasty_migrations`
+    Live(&'a mut dyn AsyncMigrationContext),
+}
+
+impl Target<'_> {
+    /// `schema` is the database's schema before the migration, for
+    /// [`MigrationRunner::audit`]
+    async fn up(
+        &mut self,
+        migration: &dyn Migration,
+        tracker: &MigrationTracker,
+        schema: Option<SchemaSnapshot>,
+    ) -> Result<()> {
+        match self {
+            Target::Buffered { context, followup } => {
+                migration.up(&mut **context)?;
+                match followup {
+                    Some(followup) => migration.followup(&mut **followup),
+                    None => Ok(()),
+                }
+            }
+            Target::Live(db) => {
+                // `up()` and `up_async()` commit together; the followups run
+                // after, outside the transaction
+                db.begin().await?;
+                if let Err(e) = live_up(&mut **db, migration, tracker, schema).await {
+                    db.rollback().await.ok();
+                    return Err(e);
+                }
+                db.commit().await?;
+
+                let mut statements = SqlMigrationContext::new(db.flavor());
+                migration.followup(&mut statements)?;
+                db.execute_all(&statements).await?;
+                migration.followup_async(&mut **db).await
+            }
+        }
+    }
+
+    async fn down(&mut self, migration: &dyn Migration, tracker: &MigrationTracker) -> Result<()> {
+        match self {
+            Target::Buffered { context, .. } => migration.down(&mut **context),
+            Target::Live(db) => {
+                db.begin().await?;
+                if let Err(e) = live_down(&mut **db, migration, tracker).await {
+                    db.rollback().await.ok();
+                    return Err(e);
+                }
+                db.commit().await
+            }
+        }
+    }
+}
+
+async fn live_up(
+    db: &mut dyn AsyncMigrationContext,
+    migration: &dyn Migration,
+    tracker: &MigrationTracker,
+    schema: Option<SchemaSnapshot>,
+) -> Result<()> {
+    let mut statements = SqlMigrationContext::new(db.flavor());
+    if let Some(schema) = schema {
+        statements = statements.audit(schema);
+    }
+    migration.up(&mut statements)?;
+    db.execute_all(&statements).await?;
+
+    migration.up_async(db).await?;
+    tracker.persist_applied(db, migration.version()).await
+}
+
+async fn live_down(
+    db: &mut dyn AsyncMigrationContext,
+    migration: &dyn Migration,
+    tracker: &MigrationTracker,
+) -> Result<()> {
+    migration.down_async(db).await?;
+
+    let mut statements = SqlMigrationContext::new(db.flavor());
+    migration.down(&mut statements)?;
+    db.execute_all(&statements).await?;
+
+    tracker.persist_rolled_back(db, migration.version()).await
+}
 
 /// Executes migrations against a database
 pub struct MigrationRunner {
     tracker: MigrationTracker,
+    until: Option<String>,
+    audit: Option<SqlIntrospector>,
+    dump_schema: Option<(SqlIntrospector, PathBuf)>,
+    before: Vec<Hook>,
+    after: Vec<Hook>,
+    quiet: bool,
 }
 
 impl MigrationRunner {
     pub fn new(tracker: MigrationTracker) -> Self {
-        Self { tracker }
+        Self {
+            tracker,
+            until: None,
+            audit: None,
+            dump_schema: None,
+            before: Vec::new(),
+            after: Vec::new(),
+            quiet: false,
+        }
     }
 
-    /// Initialize the migration system (create tracking table)
-    pub async fn initialize(&mut self) -> Result<()> {
-        self.tracker.initialize().await?;
-        self.tracker.load_applied().await?;
-        Ok(())
+    /// Only apply migrations whose `YYYYMMDD_HHMMSS` version prefix is at or
+    /// before `cutoff`. Later migrations are left pending.
+    pub fn until(mut self, cutoff: impl Into<String>) -> Self {
+        self.until = Some(cutoff.into());
+        self
     }
 
-    /// Run all pending migrations
+    /// Before each migration, introspect the database at `url` and log the
+    /// migration's structural changes to `_toasty_schema_log`, see
+    /// [`SqlMigrationContext::audit`]. Like [`dump_schema`](Self::dump_schema),
+    /// this needs a live run.
+    pub fn audit(mut self, url: impl Into<String>) -> Self {
+        self.audit = Some(SqlIntrospector::new(url.into()));
+        self
+    }
+
+    /// After each migration is applied, introspect the database at `url` and
+    /// save its schema as `<dir>/<version>.json`, so schema state can be
+    /// diffed across migrations later.
+    ///
+    /// Only live runs ([`run_pending_live`](Self::run_pending_live)) can dump
+    /// the schema: a buffered run hasn't executed anything yet, so
+    /// [`run_pending`](Self::run_pending) fails when this is set.
+    pub fn dump_schema(mut self, url: impl Into<String>, dir: impl Into<PathBuf>) -> Self {
+        self.dump_schema = Some((SqlIntrospector::new(url.into()), dir.into()));
+        self
+    }
+
+    /// Call `hook` with the version of each migration about to be applied or
+    /// rolled back, e.g. to start a timer or log it
+    pub fn on_before(mut self, hook: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.before.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` with the version of each migration once it has been
+    /// applied or rolled back and the tracking table updated
+    pub fn on_after(mut self, hook: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.after.push(Box::new(hook));
+        self
+    }
+
+    /// Don't print progress, e.g. when the caller reports it through
+    /// [`on_before`](Self::on_before) / [`on_after`](Self::on_after) hooks
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
+    }
+
+    fn is_after_cutoff(&self, version: &str) -> bool {
+        match &self.until {
+            Some(cutoff) => version.get(..cutoff.len()).unwrap_or(version) > cutoff.as_str(),
+            None => false,
+        }
+    }
+
+    /// Create the tracking table on `db` if needed and load the migrations
+    /// it records as applied. Live runs do this themselves.
+    pub async fn initialize(&mut self, db: &mut dyn AsyncMigrationContext) -> Result<()> {
+        self.tracker.initialize(db).await?;
+        self.tracker.load_applied(db).await
+    }
+
+    /// The migrations a run would apply, in the order it would apply them:
+    /// those not applied yet and not after the [`until`](Self::until)
+    /// cutoff, each after the migrations it `requires`
+    pub fn pending(&self, migrations: Vec<Box<dyn Migration>>) -> Result<Vec<Box<dyn Migration>>> {
+        Ok(self
+            .order_by_dependencies(migrations)?
+            .into_iter()
+            .filter(|migration| {
+                !self.tracker.is_applied(migration.version())
+                    && !self.is_after_cutoff(migration.version())
+            })
+            .collect())
+    }
+
+    /// Versions of `migrations` not applied yet that a run leaves pending
+    /// because they are after the [`until`](Self::until) cutoff
+    pub fn deferred(&self, migrations: &[Box<dyn Migration>]) -> Vec<String> {
+        let mut versions: Vec<String> = migrations
+            .iter()
+            .map(|migration| migration.version())
+            .filter(|version| !self.tracker.is_applied(version) && self.is_after_cutoff(version))
+            .map(String::from)
+            .collect();
+        versions.sort();
+        versions
+    }
+
+    /// Run all pending migrations, buffering their statements in `context`
+    /// and their [`Migration::followup`] statements in `followup`. The
+    /// followups must run after `context`'s statements have committed, outside
+    /// their transaction.
     pub async fn run_pending(
         &mut self,
         migrations: Vec<Box<dyn Migration>>,
         context: &mut dyn MigrationContext,
-    ) -> Result<usize> {
-        let mut applied_count = 0;
+        followup: &mut dyn MigrationContext,
+    ) -> Result<RunReport> {
+        let target = Target::Buffered {
+            context,
+            followup: Some(followup),
+        };
+        self.run(migrations, target).await
+    }
+
+    /// Run all pending migrations on a live connection, including their
+    /// [`Migration::up_async`] data changes. Each migration is recorded in
+    /// `_toasty_migrations` in the same transaction as its changes. MySQL
+    /// commits each DDL statement on its own, so there only the data changes
+    /// and the record are undone when a migration fails.
+    pub async fn run_pending_live(
+        &mut self,
+        migrations: Vec<Box<dyn Migration>>,
+        db: &mut dyn AsyncMigrationContext,
+    ) -> Result<RunReport> {
+        self.run(migrations, Target::Live(db)).await
+    }
+
+    async fn run(
+        &mut self,
+        migrations: Vec<Box<dyn Migration>>,
+        mut target: Target<'_>,
+    ) -> Result<RunReport> {
+        if matches!(target, Target::Buffered { .. }) {
+            if self.dump_schema.is_some() {
+                return Err(anyhow::anyhow!(
+                    "Dumping the schema needs a live run; buffered statements haven't executed yet"
+                ));
+            }
+            if self.audit.is_some() {
+                return Err(anyhow::anyhow!(
+                    "Auditing needs a live run; buffered statements haven't executed yet"
+                ));
+            }
+        }
+
+        if let Target::Live(db) = &mut target {
+            self.initialize(&mut **db).await?;
+        }
+
+        let migrations = self.order_by_dependencies(migrations)?;
+        let mut report = RunReport::default();
 
         for migration in migrations {
             let version = migration.version();
 
             // Skip if already applied
             if self.tracker.is_applied(version) {
+                report.skipped.push(version.to_string());
+                continue;
+            }
+
+            if self.is_after_cutoff(version) {
+                report.deferred.push(version.to_string());
                 continue;
             }
 
-            println!("Applying migration: {}", version);
+            if let Some(required) = migration
+                .requires()
+                .into_iter()
+                .find(|required| report.deferred.iter().any(|d| d == required))
+            {
+                return Err(anyhow::anyhow!(
+                    "Migration {} requires {}, which is after the cutoff",
+                    version,
+                    required
+                ));
+            }
+
+            // A previous run stopped after recording some migrations; pick up
+            // from the first one it didn't record
+            if report.applied.is_empty() && !report.skipped.is_empty() {
+                say!(
+                    self,
+                    "Resuming at {} ({} migration(s) already applied)",
+                    version,
+                    report.skipped.len()
+                );
+            }
 
-            // Execute the up migration
-            migration.up(context)?;
+            say!(self, "Applying migration: {}", version);
+            self.before.iter().for_each(|hook| hook(version));
+            let started = Instant::now();
 
-            // Mark as applied
+            // Execute the up migration, then anything deferred until after it
+            let schema = match &self.audit {
+                Some(introspector) => Some(introspector.introspect_schema().await?),
+                None => None,
+            };
+            target.up(migration.as_ref(), &self.tracker, schema).await?;
             self.tracker.mark_applied(version.to_string());
-            self.tracker.persist_applied(version).await?;
 
-            applied_count += 1;
-            println!("  ✅ Applied: {}", version);
+            if let Some((introspector, dir)) = &self.dump_schema {
+                let snapshot = introspector.introspect_schema().await?;
+                std::fs::create_dir_all(dir)?;
+                save_snapshot(&snapshot, dir.join(format!("{}.json", version)))?;
+            }
+
+            report.applied.push(AppliedMigration {
+                version: version.to_string(),
+                duration: started.elapsed(),
+            });
+            self.after.iter().for_each(|hook| hook(version));
+            say!(self, "  ✅ Applied: {}", version);
         }
 
-        if applied_count == 0 {
-            println!("No pending migrations");
+        if report.applied_count() == 0 {
+            say!(self, "No pending migrations");
         } else {
-            println!("\n✅ Applied {} migration(s)", applied_count);
+            say!(self, "\n✅ Applied {} migration(s)", report.applied_count());
+        }
+
+        Ok(report)
+    }
+
+    /// Order migrations so each runs after the migrations it `requires`,
+    /// falling back to version order among migrations that are ready.
+    fn order_by_dependencies(
+        &self,
+        migrations: Vec<Box<dyn Migration>>,
+    ) -> Result<Vec<Box<dyn Migration>>> {
+        let versions: HashMap<String, usize> = migrations
+            .iter()
+            .enumerate()
+            .map(|(i, migration)| (migration.version().to_string(), i))
+            .collect();
+
+        // Number of unsatisfied prerequisites per migration, and the reverse
+        // edges used to release dependents once a migration is ordered.
+        let mut blocked_on = vec![0; migrations.len()];
+        let mut dependents: Vec<Vec<usize>> = vec![vec![]; migrations.len()];
+
+        for (i, migration) in migrations.iter().enumerate() {
+            for required in migration.requires() {
+                match versions.get(required) {
+                    Some(&dep) => {
+                        blocked_on[i] += 1;
+                        dependents[dep].push(i);
+                    }
+                    None if self.tracker.is_applied(required) => {}
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "Migration {} requires missing migration {}",
+                            migration.version(),
+                            required
+                        ));
+                    }
+                }
+            }
+        }
+
+        let mut ready: BTreeSet<(&str, usize)> = migrations
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| blocked_on[*i] == 0)
+            .map(|(i, migration)| (migration.version(), i))
+            .collect();
+        let mut order = Vec::with_capacity(migrations.len());
+
+        while let Some((_, i)) = ready.pop_first() {
+            order.push(i);
+
+            for &dependent in &dependents[i] {
+                blocked_on[dependent] -= 1;
+                if blocked_on[dependent] == 0 {
+                    ready.insert((migrations[dependent].version(), dependent));
+                }
+            }
+        }
+
+        if order.len() != migrations.len() {
+            let cycle: Vec<&str> = migrations
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| blocked_on[*i] > 0)
+                .map(|(_, migration)| migration.version())
+                .collect();
+
+            return Err(anyhow::anyhow!(
+                "Migration dependency cycle between: {}",
+                cycle.join(", ")
+            ));
         }
 
-        Ok(applied_count)
+        let mut slots: Vec<_> = migrations.into_iter().map(Some).collect();
+        Ok(order
+            .into_iter()
+            .map(|i| slots[i].take().unwrap())
+            .collect())
     }
 
-    /// Rollback the last N migrations
+    /// Rollback the last N migrations, buffering their statements in
+    /// `context`
     pub async fn rollback(
         &mut self,
         count: usize,
         migrations: Vec<Box<dyn Migration>>,
         context: &mut dyn MigrationContext,
     ) -> Result<usize> {
+        let target = Target::Buffered {
+            context,
+            followup: None,
+        };
+        self.roll_back(count, migrations, target).await
+    }
+
+    /// Rollback the last N migrations on a live connection, including their
+    /// [`Migration::down_async`] data changes. Each one's row is removed from
+    /// `_toasty_migrations` in the same transaction.
+    pub async fn rollback_live(
+        &mut self,
+        count: usize,
+        migrations: Vec<Box<dyn Migration>>,
+        db: &mut dyn AsyncMigrationContext,
+    ) -> Result<usize> {
+        self.roll_back(count, migrations, Target::Live(db)).await
+    }
+
+    async fn roll_back(
+        &mut self,
+        count: usize,
+        migrations: Vec<Box<dyn Migration>>,
+        mut target: Target<'_>,
+    ) -> Result<usize> {
+        if let Target::Live(db) = &mut target {
+            self.initialize(&mut **db).await?;
+        }
+
         let applied = self.tracker.applied_migrations();
 
         if applied.is_empty() {
-            println!("No migrations to rollback");
+            say!(self, "No migrations to rollback");
             return Ok(0);
         }
 
@@ -80,20 +492,19 @@ impl MigrationRunner {
                 .find(|m| m.version() == version)
                 .ok_or_else(|| anyhow::anyhow!("Migration not found: {}", version))?;
 
-            println!("Rolling back migration: {}", version);
+            say!(self, "Rolling back migration: {}", version);
+            self.before.iter().for_each(|hook| hook(version));
 
             // Execute the down migration
-            migration.down(context)?;
-
-            // Mark as rolled back
+            target.down(migration.as_ref(), &self.tracker).await?;
             self.tracker.mark_rolled_back(version);
-            self.tracker.persist_rolled_back(version).await?;
 
+            self.after.iter().for_each(|hook| hook(version));
             rolled_back_count += 1;
-            println!("  ✅ Rolled back: {}", version);
+            say!(self, "  ✅ Rolled back: {}", version);
         }
 
-        println!("\n✅ Rolled back {} migration(s)", rolled_back_count);
+        say!(self, "\n✅ Rolled back {} migration(s)", rolled_back_count);
         Ok(rolled_back_count)
     }
 
@@ -119,6 +530,36 @@ impl MigrationRunner {
     }
 }
 
+/// Outcome of [`MigrationRunner::run_pending`]
+#[derive(Debug, Default)]
+pub struct RunReport {
+    /// Migrations applied by this run, in the order they ran
+    pub applied: Vec<AppliedMigration>,
+
+    /// Versions that were already applied and therefore skipped
+    pub skipped: Vec<String>,
+
+    /// Pending versions left for later because they are after the
+    /// [`MigrationRunner::until`] cutoff
+    pub deferred: Vec<String>,
+}
+
+impl RunReport {
+    /// Number of migrations applied by this run
+    pub fn applied_count(&self) -> usize {
+        self.applied.len()
+    }
+}
+
+/// A migration applied during a run
+#[derive(Debug)]
+pub struct AppliedMigration {
+    pub version: String,
+
+    /// Time spent running the migration's `up` and `followup` steps
+    pub duration: Duration,
+}
+
 #[derive(Debug)]
 pub struct MigrationStatus {
     pub version: String,
@@ -0,0 +1,648 @@
+use crate::{
+    ColumnDef, ColumnReference, ConstraintDef, ConstraintKind, IndexDef, Migration,
+    MigrationContext, PartitionBy, PartitionStrategy, ReferentialAction, TableOptions,
+    TruncateOptions,
+};
+use anyhow::{anyhow, bail, Result};
+use std::path::Path;
+use syn::{punctuated::Punctuated, Expr, Token};
+
+/// One `db.*` call, replayed on whichever context the migration runs against
+type Step = Box<dyn Fn(&mut dyn MigrationContext) -> Result<()> + Send + Sync>;
+
+/// A migration file run without being compiled into the app, by replaying
+/// the `db.*` calls of its `up()`, `down()` and `followup()`.
+///
+/// Generated migrations only call `db` methods with literal arguments, and
+/// that's all a script may do. A file with other Rust code, or with
+/// `*_async()` data changes, fails to load; compile it into the app and run
+/// it with [`MigrationRunner::run_pending_live`] instead.
+///
+/// [`MigrationRunner::run_pending_live`]: crate::MigrationRunner::run_pending_live
+pub struct MigrationScript {
+    version: String,
+    requires: Vec<String>,
+    up: Vec<Step>,
+    down: Vec<Step>,
+    followup: Vec<Step>,
+}
+
+impl MigrationScript {
+    /// Read and parse the migration file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+
+        Self::parse(&content).map_err(|e| anyhow!("Migration {}: {}", path.display(), e))
+    }
+
+    /// Parse the source of a migration file
+    pub fn parse(content: &str) -> Result<Self> {
+        let file = syn::parse_file(content)?;
+
+        let block = file
+            .items
+            .iter()
+            .find_map(|item| match item {
+                syn::Item::Impl(block) if implements_migration(block) => Some(block),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("no `impl Migration for ...` block"))?;
+
+        let mut version = None;
+        let mut script = Self {
+            version: String::new(),
+            requires: vec![],
+            up: vec![],
+            down: vec![],
+            followup: vec![],
+        };
+
+        for item in &block.items {
+            let syn::ImplItem::Fn(f) = item else {
+                continue;
+            };
+            let name = f.sig.ident.to_string();
+
+            match name.as_str() {
+                "version" => version = Some(returned(&f.block, &name)?.string()?),
+                "requires" => script.requires = returned(&f.block, &name)?.strings()?,
+                "up" => script.up = steps(f, &name)?,
+                "down" => script.down = steps(f, &name)?,
+                "followup" => script.followup = steps(f, &name)?,
+                "up_async" | "down_async" | "followup_async" => {
+                    bail!(
+                        "`{}()` only runs with the migration compiled into the app",
+                        name
+                    )
+                }
+                _ => {}
+            }
+        }
+
+        script.version = version.ok_or_else(|| anyhow!("missing `fn version()`"))?;
+        Ok(script)
+    }
+}
+
+impl Migration for MigrationScript {
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn requires(&self) -> Vec<&str> {
+        self.requires.iter().map(String::as_str).collect()
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        self.up.iter().try_for_each(|step| step(db))
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        self.down.iter().try_for_each(|step| step(db))
+    }
+
+    fn followup(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        self.followup.iter().try_for_each(|step| step(db))
+    }
+}
+
+fn implements_migration(block: &syn::ItemImpl) -> bool {
+    block
+        .trait_
+        .as_ref()
+        .and_then(|(_, path, _)| path.segments.last())
+        .is_some_and(|segment| segment.ident == "Migration")
+}
+
+/// The value a function like `version()` returns
+fn returned(block: &syn::Block, name: &str) -> Result<Arg> {
+    match &block.stmts[..] {
+        [syn::Stmt::Expr(expr, None)] => Arg::parse(expr),
+        _ => Err(anyhow!("`{}()` must return a literal", name)),
+    }
+}
+
+/// The `db.*` calls making up the body of `up()`, `down()` or `followup()`
+fn steps(f: &syn::ImplItemFn, name: &str) -> Result<Vec<Step>> {
+    // The context parameter, `db` in generated migrations
+    let db = f
+        .sig
+        .inputs
+        .iter()
+        .find_map(|input| match input {
+            syn::FnArg::Typed(arg) => match &*arg.pat {
+                syn::Pat::Ident(pat) => Some(pat.ident.to_string()),
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        })
+        .unwrap_or_else(|| "db".to_string());
+
+    let mut steps = Vec::new();
+
+    for stmt in &f.block.stmts {
+        let syn::Stmt::Expr(expr, _) = stmt else {
+            bail!("`{}()` may only call `{}` methods", name, db);
+        };
+
+        let expr = match expr {
+            Expr::Try(expr) => &*expr.expr,
+            expr => expr,
+        };
+
+        match expr {
+            Expr::MethodCall(call) if is_ident(&call.receiver, &db) => {
+                let method = call.method.to_string();
+                let args = call.args.iter().map(Arg::parse).collect::<Result<Vec<_>>>();
+                let step = args.and_then(|args| step(&method, args));
+
+                steps.push(step.map_err(|e| anyhow!("`{}()`: `{}.{}`: {}", name, db, method, e))?);
+            }
+            // The trailing `Ok(())`
+            Expr::Call(call) if is_ident(&call.func, "Ok") => {}
+            _ => bail!("`{}()` may only call `{}` methods", name, db),
+        }
+    }
+
+    Ok(steps)
+}
+
+fn is_ident(expr: &Expr, ident: &str) -> bool {
+    matches!(expr, Expr::Path(path) if path.path.is_ident(ident))
+}
+
+/// Build the step for a call to `db.<method>(args)`
+fn step(method: &str, args: Vec<Arg>) -> Result<Step> {
+    let mut args = Args(args.into_iter());
+
+    let step: Step = match method {
+        "execute_sql" => {
+            let sql = args.string()?;
+            Box::new(move |db| db.execute_sql(&sql))
+        }
+        "create_table" => {
+            let (name, columns) = (args.string()?, args.columns()?);
+            Box::new(move |db| db.create_table(&name, columns.clone()))
+        }
+        "create_partitioned_table" => {
+            let (name, columns, partition_by) = (
+                args.string()?,
+                args.columns()?,
+                args.next()?.partition_by()?,
+            );
+            Box::new(move |db| {
+                db.create_partitioned_table(&name, columns.clone(), partition_by.clone())
+            })
+        }
+        "create_partition" => {
+            let (parent, name) = (args.string()?, args.string()?);
+            let (from, to) = (args.string()?, args.string()?);
+            Box::new(move |db| db.create_partition(&parent, &name, &from, &to))
+        }
+        "drop_table" => {
+            let name = args.string()?;
+            Box::new(move |db| db.drop_table(&name))
+        }
+        "rename_table" => {
+            let (from, to) = (args.string()?, args.string()?);
+            Box::new(move |db| db.rename_table(&from, &to))
+        }
+        "truncate_table" => {
+            let table = args.string()?;
+            Box::new(move |db| db.truncate_table(&table))
+        }
+        "truncate_table_with" => {
+            let (table, options) = (args.string()?, args.next()?.truncate_options()?);
+            Box::new(move |db| db.truncate_table_with(&table, options))
+        }
+        "add_column" => {
+            let (table, column) = (args.string()?, args.next()?.column_def()?);
+            Box::new(move |db| db.add_column(&table, column.clone()))
+        }
+        "drop_column" => {
+            let (table, column) = (args.string()?, args.string()?);
+            Box::new(move |db| db.drop_column(&table, &column))
+        }
+        "rename_column" => {
+            let (table, from, to) = (args.string()?, args.string()?, args.string()?);
+            Box::new(move |db| db.rename_column(&table, &from, &to))
+        }
+        "modify_column" => {
+            let (table, column) = (args.string()?, args.string()?);
+            let (old_ty, new_ty) = (args.string()?, args.string()?);
+            Box::new(move |db| db.modify_column(&table, &column, &old_ty, &new_ty))
+        }
+        "make_not_null" => {
+            let (table, column, default) = (args.string()?, args.string()?, args.string()?);
+            Box::new(move |db| db.make_not_null(&table, &column, &default))
+        }
+        "backfill_column" => {
+            let (table, column, value) = (args.string()?, args.string()?, args.string()?);
+            let batch_size = args.next()?.int()?;
+            Box::new(move |db| db.backfill_column(&table, &column, &value, batch_size))
+        }
+        "drop_not_null" => {
+            let (table, column) = (args.string()?, args.string()?);
+            Box::new(move |db| db.drop_not_null(&table, &column))
+        }
+        "set_column_default" => {
+            let (table, column) = (args.string()?, args.string()?);
+            let default = args.next()?.optional_string()?;
+            Box::new(move |db| db.set_column_default(&table, &column, default.as_deref()))
+        }
+        "create_index" => {
+            let (table, index) = (args.string()?, args.next()?.index_def()?);
+            Box::new(move |db| db.create_index(&table, index.clone()))
+        }
+        "drop_index" => {
+            let (table, name) = (args.string()?, args.string()?);
+            Box::new(move |db| db.drop_index(&table, &name))
+        }
+        "rename_index" => {
+            let (table, from, to) = (args.string()?, args.string()?, args.string()?);
+            Box::new(move |db| db.rename_index(&table, &from, &to))
+        }
+        "add_constraint" => {
+            let (table, constraint) = (args.string()?, args.next()?.constraint_def()?);
+            Box::new(move |db| db.add_constraint(&table, constraint.clone()))
+        }
+        "validate_constraint" => {
+            let (table, name) = (args.string()?, args.string()?);
+            Box::new(move |db| db.validate_constraint(&table, &name))
+        }
+        "drop_constraint" => {
+            let (table, constraint) = (args.string()?, args.next()?.constraint_def()?);
+            Box::new(move |db| db.drop_constraint(&table, constraint.clone()))
+        }
+        "comment_on_table" => {
+            let (table, comment) = (args.string()?, args.string()?);
+            Box::new(move |db| db.comment_on_table(&table, &comment))
+        }
+        "comment_on_column" => {
+            let (table, column) = (args.string()?, args.string()?);
+            let comment = args.next()?.optional_string()?;
+            Box::new(move |db| db.comment_on_column(&table, &column, comment.as_deref()))
+        }
+        "alter_table_options" => {
+            let (table, options) = (args.string()?, args.next()?.table_options()?);
+            Box::new(move |db| db.alter_table_options(&table, options.clone()))
+        }
+        _ => bail!("unknown method"),
+    };
+
+    if args.0.next().is_some() {
+        bail!("too many arguments");
+    }
+
+    Ok(step)
+}
+
+/// The arguments of a call, taken in order
+struct Args(std::vec::IntoIter<Arg>);
+
+impl Args {
+    fn next(&mut self) -> Result<Arg> {
+        self.0.next().ok_or_else(|| anyhow!("missing arguments"))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.next()?.string()
+    }
+
+    fn columns(&mut self) -> Result<Vec<ColumnDef>> {
+        self.next()?
+            .list()?
+            .into_iter()
+            .map(Arg::column_def)
+            .collect()
+    }
+}
+
+/// A literal argument in a migration file
+#[derive(Debug)]
+enum Arg {
+    String(String),
+    Bool(bool),
+    Int(u64),
+    None,
+    Some(Box<Arg>),
+    List(Vec<Arg>),
+    /// A struct, or struct-like enum variant, e.g. `IndexDef { .. }`. Fields
+    /// left out take their default.
+    Struct {
+        path: String,
+        fields: Vec<(String, Arg)>,
+    },
+    /// A unit enum variant, e.g. `PartitionStrategy::Range`
+    Path(String),
+}
+
+impl Arg {
+    fn parse(expr: &Expr) -> Result<Self> {
+        Ok(match expr {
+            Expr::Lit(lit) => match &lit.lit {
+                syn::Lit::Str(s) => Arg::String(s.value()),
+                syn::Lit::Bool(b) => Arg::Bool(b.value),
+                syn::Lit::Int(n) => Arg::Int(n.base10_parse()?),
+                _ => bail!("unsupported literal"),
+            },
+            // `"users".into()` and friends
+            Expr::MethodCall(call)
+                if call.args.is_empty()
+                    && ["into", "to_string", "to_owned"].contains(&&*call.method.to_string()) =>
+            {
+                Arg::parse(&call.receiver)?
+            }
+            Expr::Reference(expr) => Arg::parse(&expr.expr)?,
+            Expr::Paren(expr) => Arg::parse(&expr.expr)?,
+            Expr::Group(expr) => Arg::parse(&expr.expr)?,
+            Expr::Path(expr) if expr.path.is_ident("None") => Arg::None,
+            Expr::Path(expr) => Arg::Path(path_string(&expr.path)),
+            Expr::Call(call) if call.args.len() == 1 => {
+                let Expr::Path(func) = &*call.func else {
+                    bail!("unsupported argument");
+                };
+                let arg = Arg::parse(&call.args[0])?;
+
+                match &*path_string(&func.path) {
+                    "Some" => Arg::Some(Box::new(arg)),
+                    "String::from" => arg,
+                    _ => bail!("unsupported argument"),
+                }
+            }
+            Expr::Macro(expr) if expr.mac.path.is_ident("vec") => {
+                let items = expr
+                    .mac
+                    .parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+                Arg::List(items.iter().map(Arg::parse).collect::<Result<_>>()?)
+            }
+            Expr::Array(expr) => {
+                Arg::List(expr.elems.iter().map(Arg::parse).collect::<Result<_>>()?)
+            }
+            Expr::Struct(expr) => {
+                // Only `..Default::default()` may fill in the rest
+                if let Some(rest) = &expr.rest {
+                    match &**rest {
+                        Expr::Call(call)
+                            if call.args.is_empty()
+                                && is_ident_path(&call.func, "Default::default") => {}
+                        _ => bail!("unsupported struct update"),
+                    }
+                }
+
+                let fields = expr
+                    .fields
+                    .iter()
+                    .map(|field| match &field.member {
+                        syn::Member::Named(name) => {
+                            Ok((name.to_string(), Arg::parse(&field.expr)?))
+                        }
+                        syn::Member::Unnamed(_) => bail!("unsupported tuple struct"),
+                    })
+                    .collect::<Result<_>>()?;
+
+                Arg::Struct {
+                    path: path_string(&expr.path),
+                    fields,
+                }
+            }
+            _ => bail!("unsupported argument; only literals can run uncompiled"),
+        })
+    }
+
+    fn string(self) -> Result<String> {
+        match self {
+            Arg::String(s) => Ok(s),
+            arg => Err(anyhow!("expected a string, found {:?}", arg)),
+        }
+    }
+
+    fn bool(self) -> Result<bool> {
+        match self {
+            Arg::Bool(b) => Ok(b),
+            arg => Err(anyhow!("expected a bool, found {:?}", arg)),
+        }
+    }
+
+    fn int(self) -> Result<usize> {
+        match self {
+            Arg::Int(n) => Ok(usize::try_from(n)?),
+            arg => Err(anyhow!("expected an integer, found {:?}", arg)),
+        }
+    }
+
+    fn optional(self) -> Option<Arg> {
+        match self {
+            Arg::None => None,
+            Arg::Some(arg) => Some(*arg),
+            arg => Some(arg),
+        }
+    }
+
+    fn optional_string(self) -> Result<Option<String>> {
+        self.optional().map(Arg::string).transpose()
+    }
+
+    fn list(self) -> Result<Vec<Arg>> {
+        match self {
+            Arg::List(items) => Ok(items),
+            arg => Err(anyhow!("expected a list, found {:?}", arg)),
+        }
+    }
+
+    fn strings(self) -> Result<Vec<String>> {
+        self.list()?.into_iter().map(Arg::string).collect()
+    }
+
+    /// The last segment of a unit variant's path
+    fn variant(self) -> Result<String> {
+        match self {
+            Arg::Path(path) => Ok(path.rsplit("::").next().unwrap_or_default().to_string()),
+            arg => Err(anyhow!("expected an enum variant, found {:?}", arg)),
+        }
+    }
+
+    /// The fields of a `name { .. }` struct literal
+    fn fields(self, name: &str) -> Result<Vec<(String, Arg)>> {
+        match self {
+            Arg::Struct { path, fields } if path.rsplit("::").next() == Some(name) => Ok(fields),
+            arg => Err(anyhow!("expected `{} {{ .. }}`, found {:?}", name, arg)),
+        }
+    }
+
+    fn column_def(self) -> Result<ColumnDef> {
+        let mut column = ColumnDef::default();
+
+        for (field, value) in self.fields("ColumnDef")? {
+            match &*field {
+                "name" => column.name = value.string()?,
+                "ty" => column.ty = value.string()?,
+                "nullable" => column.nullable = value.bool()?,
+                "default" => column.default = value.optional_string()?,
+                "generated" => column.generated = value.optional_string()?,
+                "stored" => column.stored = value.bool()?,
+                "collation" => column.collation = value.optional_string()?,
+                "primary_key" => column.primary_key = value.bool()?,
+                "references" => {
+                    column.references = value.optional().map(Arg::column_reference).transpose()?
+                }
+                _ => bail!("unknown `ColumnDef` field `{}`", field),
+            }
+        }
+
+        Ok(column)
+    }
+
+    fn column_reference(self) -> Result<ColumnReference> {
+        let mut reference = ColumnReference::default();
+
+        for (field, value) in self.fields("ColumnReference")? {
+            match &*field {
+                "table" => reference.table = value.string()?,
+                "column" => reference.column = value.string()?,
+                "on_delete" => {
+                    reference.on_delete = match &*value.variant()? {
+                        "NoAction" => ReferentialAction::NoAction,
+                        "Restrict" => ReferentialAction::Restrict,
+                        "Cascade" => ReferentialAction::Cascade,
+                        "SetNull" => ReferentialAction::SetNull,
+                        action => bail!("unknown `ReferentialAction::{}`", action),
+                    }
+                }
+                _ => bail!("unknown `ColumnReference` field `{}`", field),
+            }
+        }
+
+        Ok(reference)
+    }
+
+    fn index_def(self) -> Result<IndexDef> {
+        let mut index = IndexDef::default();
+
+        for (field, value) in self.fields("IndexDef")? {
+            match &*field {
+                "name" => index.name = value.string()?,
+                "columns" => index.columns = value.strings()?,
+                "unique" => index.unique = value.bool()?,
+                "include" => index.include = value.strings()?,
+                "predicate" => index.predicate = value.optional_string()?,
+                _ => bail!("unknown `IndexDef` field `{}`", field),
+            }
+        }
+
+        Ok(index)
+    }
+
+    fn constraint_def(self) -> Result<ConstraintDef> {
+        let (mut name, mut kind, mut not_valid) = (None, None, false);
+
+        for (field, value) in self.fields("ConstraintDef")? {
+            match &*field {
+                "name" => name = Some(value.string()?),
+                "kind" => kind = Some(value.constraint_kind()?),
+                "not_valid" => not_valid = value.bool()?,
+                _ => bail!("unknown `ConstraintDef` field `{}`", field),
+            }
+        }
+
+        Ok(ConstraintDef {
+            name: name.ok_or_else(|| anyhow!("`ConstraintDef` is missing `name`"))?,
+            kind: kind.ok_or_else(|| anyhow!("`ConstraintDef` is missing `kind`"))?,
+            not_valid,
+        })
+    }
+
+    fn constraint_kind(self) -> Result<ConstraintKind> {
+        let Arg::Struct { path, fields } = self else {
+            bail!("expected a `ConstraintKind`, found {:?}", self);
+        };
+        let mut fields: std::collections::HashMap<_, _> = fields.into_iter().collect();
+        let mut take = |field: &str| {
+            fields
+                .remove(field)
+                .ok_or_else(|| anyhow!("`{}` is missing `{}`", path, field))
+        };
+
+        let kind = match path.rsplit("::").next() {
+            Some("ForeignKey") => ConstraintKind::ForeignKey {
+                columns: take("columns")?.strings()?,
+                references_table: take("references_table")?.string()?,
+                references_columns: take("references_columns")?.strings()?,
+            },
+            Some("Check") => ConstraintKind::Check {
+                expr: take("expr")?.string()?,
+            },
+            _ => bail!("unknown constraint kind `{}`", path),
+        };
+
+        if let Some(field) = fields.keys().next() {
+            bail!("unknown `{}` field `{}`", path, field);
+        }
+
+        Ok(kind)
+    }
+
+    fn partition_by(self) -> Result<PartitionBy> {
+        let (mut strategy, mut columns) = (None, vec![]);
+
+        for (field, value) in self.fields("PartitionBy")? {
+            match &*field {
+                "strategy" => {
+                    let name = value.variant()?;
+                    strategy = Some(
+                        PartitionStrategy::from_name(&name)
+                            .ok_or_else(|| anyhow!("unknown `PartitionStrategy::{}`", name))?,
+                    );
+                }
+                "columns" => columns = value.strings()?,
+                _ => bail!("unknown `PartitionBy` field `{}`", field),
+            }
+        }
+
+        Ok(PartitionBy {
+            strategy: strategy.ok_or_else(|| anyhow!("`PartitionBy` is missing `strategy`"))?,
+            columns,
+        })
+    }
+
+    fn table_options(self) -> Result<TableOptions> {
+        let mut options = TableOptions::default();
+
+        for (field, value) in self.fields("TableOptions")? {
+            match &*field {
+                "engine" => options.engine = value.optional_string()?,
+                "charset" => options.charset = value.optional_string()?,
+                _ => bail!("unknown `TableOptions` field `{}`", field),
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn truncate_options(self) -> Result<TruncateOptions> {
+        let mut options = TruncateOptions::default();
+
+        for (field, value) in self.fields("TruncateOptions")? {
+            match &*field {
+                "restart_identity" => options.restart_identity = value.bool()?,
+                "cascade" => options.cascade = value.bool()?,
+                _ => bail!("unknown `TruncateOptions` field `{}`", field),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn path_string(path: &syn::Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn is_ident_path(expr: &Expr, path: &str) -> bool {
+    matches!(expr, Expr::Path(expr) if path_string(&expr.path) == path)
+}
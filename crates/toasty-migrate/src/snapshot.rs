@@ -1,7 +1,8 @@
-use toasty_core::schema::db::Schema;
 use anyhow::Result;
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::Path;
+use toasty_core::schema::db::Schema;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaSnapshot {
@@ -12,10 +13,81 @@ pub struct SchemaSnapshot {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableSnapshot {
+    /// Database schema (namespace) the table lives in, e.g. `tenant`. `None`
+    /// is the connection's default schema.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
     pub name: String,
     pub columns: Vec<ColumnSnapshot>,
     pub indices: Vec<IndexSnapshot>,
     pub primary_key: Vec<String>,
+    /// Table-level documentation, taken from the model's doc comment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Columns referencing another table, from `#[belongs_to]` relations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub foreign_keys: Vec<ForeignKeySnapshot>,
+    /// Storage options, from `#[table_options(...)]`
+    #[serde(default, skip_serializing_if = "TableOptions::is_empty")]
+    pub options: TableOptions,
+    /// How the table is split into partitions, from `#[partition(...)]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_by: Option<PartitionBy>,
+}
+
+/// PostgreSQL declarative partitioning: rows go to the partition whose
+/// bounds hold their values for `columns`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartitionBy {
+    pub strategy: PartitionStrategy,
+    /// The partition key
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PartitionStrategy {
+    Range,
+    List,
+    Hash,
+}
+
+impl PartitionStrategy {
+    /// The strategy for a `#[partition(by = "...")]` value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "range" => Some(PartitionStrategy::Range),
+            "list" => Some(PartitionStrategy::List),
+            "hash" => Some(PartitionStrategy::Hash),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn sql(self) -> &'static str {
+        match self {
+            PartitionStrategy::Range => "RANGE",
+            PartitionStrategy::List => "LIST",
+            PartitionStrategy::Hash => "HASH",
+        }
+    }
+}
+
+/// Table-level storage options. Only MySQL has them; `None` leaves the
+/// database's default in place.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableOptions {
+    /// Storage engine, e.g. `InnoDB`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// Default character set, e.g. `utf8mb4`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charset: Option<String>,
+}
+
+impl TableOptions {
+    pub fn is_empty(&self) -> bool {
+        self.engine.is_none() && self.charset.is_none()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +95,33 @@ pub struct ColumnSnapshot {
     pub name: String,
     pub ty: String,
     pub nullable: bool,
+    /// Expression for a generated (computed) column
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated: Option<String>,
+    /// Whether a generated column is stored rather than computed on read
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stored: bool,
+    /// Default value expression, as written in SQL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Whether the value is generated by the database (`#[auto]`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto: bool,
+    /// Collation for comparing and sorting text (`#[collation = "..."]`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+    /// Column-level documentation, taken from the field's doc comment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// One column of a foreign key: `column` holds values of
+/// `references_table.references_column`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForeignKeySnapshot {
+    pub column: String,
+    pub references_table: String,
+    pub references_column: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +130,33 @@ pub struct IndexSnapshot {
     pub columns: Vec<String>,
     pub unique: bool,
     pub primary_key: bool,
+    /// Non-key columns covered by the index (`INCLUDE`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Condition limiting the index to matching rows (a partial index's
+    /// `WHERE`), e.g. `deleted_at IS NULL`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predicate: Option<String>,
+}
+
+impl TableSnapshot {
+    /// The name to use in DDL: `schema.name` when the table has a schema,
+    /// otherwise just `name`
+    pub fn qualified_name(&self) -> String {
+        match &self.schema {
+            Some(schema) => format!("{}.{}", schema, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Split a possibly schema-qualified table name (`tenant.users`) into its
+/// schema and bare name
+pub(crate) fn split_table_name(name: &str) -> (Option<&str>, &str) {
+    match name.split_once('.') {
+        Some((schema, table)) => (Some(schema), table),
+        None => (None, name),
+    }
 }
 
 impl SchemaSnapshot {
@@ -45,6 +171,12 @@ impl SchemaSnapshot {
                     name: column.name.clone(),
                     ty: format!("{:?}", column.ty),
                     nullable: column.nullable,
+                    generated: None,
+                    stored: false,
+                    default: None,
+                    auto: false,
+                    collation: None,
+                    comment: None,
                 });
             }
 
@@ -61,6 +193,8 @@ impl SchemaSnapshot {
                     columns: column_names,
                     unique: index.unique,
                     primary_key: index.primary_key,
+                    include: vec![],
+                    predicate: None,
                 });
             }
 
@@ -70,10 +204,15 @@ impl SchemaSnapshot {
                 .collect();
 
             tables.push(TableSnapshot {
+                schema: None,
                 name: table.name.clone(),
                 columns,
                 indices,
                 primary_key,
+                comment: None,
+                foreign_keys: vec![],
+                options: Default::default(),
+                partition_by: None,
             });
         }
 
@@ -83,6 +222,190 @@ impl SchemaSnapshot {
             tables,
         }
     }
+
+    /// Upsert `other`'s tables into this snapshot: a table with the same
+    /// schema and name is replaced, others are added. Tables stay sorted by
+    /// name, as introspection returns them, so snapshots of single tables
+    /// merge into the same snapshot a full introspection gives.
+    pub fn merge(&mut self, other: SchemaSnapshot) {
+        for table in other.tables {
+            let existing = self
+                .tables
+                .iter_mut()
+                .find(|t| t.schema == table.schema && t.name == table.name);
+
+            match existing {
+                Some(existing) => *existing = table,
+                None => self.tables.push(table),
+            }
+        }
+
+        self.tables.sort_by(|a, b| a.name.cmp(&b.name));
+        self.timestamp = other.timestamp;
+    }
+
+    /// Check that the snapshot is internally consistent: table names are
+    /// unique, and every primary key and index column names an existing
+    /// column of its table.
+    pub fn validate(&self) -> Result<()> {
+        let mut table_names = HashSet::new();
+
+        for table in &self.tables {
+            if !table_names.insert((table.schema.as_deref(), table.name.as_str())) {
+                anyhow::bail!(
+                    "Table {} appears more than once in the snapshot",
+                    table.qualified_name()
+                );
+            }
+
+            let has_column = |name: &String| table.columns.iter().any(|c| &c.name == name);
+
+            for column in &table.primary_key {
+                if !has_column(column) {
+                    anyhow::bail!(
+                        "Table {}: primary key references missing column {}",
+                        table.qualified_name(),
+                        column
+                    );
+                }
+            }
+
+            for (i, index) in table.indices.iter().enumerate() {
+                if let Some(other) = table.indices[..i].iter().find(|o| o.name == index.name) {
+                    anyhow::bail!(
+                        "Table {}: index {} is defined twice, on ({}) and on ({})",
+                        table.qualified_name(),
+                        index.name,
+                        other.columns.join(", "),
+                        index.columns.join(", ")
+                    );
+                }
+
+                for column in index.columns.iter().chain(&index.include) {
+                    if !has_column(column) {
+                        anyhow::bail!(
+                            "Table {}: index {} references missing column {}",
+                            table.qualified_name(),
+                            index.name,
+                            column
+                        );
+                    }
+                }
+            }
+
+            for fk in &table.foreign_keys {
+                if !has_column(&fk.column) {
+                    anyhow::bail!(
+                        "Table {}: foreign key on missing column {}",
+                        table.qualified_name(),
+                        fk.column
+                    );
+                }
+
+                let Some(referenced) = self
+                    .tables
+                    .iter()
+                    .find(|t| t.qualified_name() == fk.references_table)
+                else {
+                    anyhow::bail!(
+                        "Table {}: foreign key {} references missing table {}",
+                        table.qualified_name(),
+                        fk.column,
+                        fk.references_table
+                    );
+                };
+
+                if !referenced
+                    .columns
+                    .iter()
+                    .any(|c| c.name == fk.references_column)
+                {
+                    anyhow::bail!(
+                        "Table {}: foreign key {} references missing column {}.{}",
+                        table.qualified_name(),
+                        fk.column,
+                        fk.references_table,
+                        fk.references_column
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Render the schema as Markdown: a section per table listing its
+    /// columns, indices and foreign keys
+    pub fn to_markdown(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::from("# Schema\n");
+
+        for table in &self.tables {
+            let _ = write!(out, "\n## {}\n\n", table.qualified_name());
+            if let Some(comment) = &table.comment {
+                let _ = write!(out, "{}\n\n", comment);
+            }
+
+            out.push_str("| Column | Type | Nullable | Default |\n");
+            out.push_str("|--------|------|----------|---------|\n");
+            for column in &table.columns {
+                let key = if table.primary_key.contains(&column.name) {
+                    " (primary key)"
+                } else {
+                    ""
+                };
+                let default = match (&column.generated, &column.default) {
+                    (Some(expr), _) => format!("generated: `{}`", expr),
+                    (None, Some(default)) => format!("`{}`", default),
+                    (None, None) => String::new(),
+                };
+                let _ = writeln!(
+                    out,
+                    "| `{}`{} | {} | {} | {} |",
+                    column.name,
+                    key,
+                    column.ty,
+                    if column.nullable { "yes" } else { "no" },
+                    markdown_cell(&default)
+                );
+            }
+
+            let indices: Vec<_> = table.indices.iter().filter(|i| !i.primary_key).collect();
+            if !indices.is_empty() {
+                out.push_str("\n| Index | Columns | Unique |\n");
+                out.push_str("|-------|---------|--------|\n");
+                for index in indices {
+                    let _ = writeln!(
+                        out,
+                        "| `{}` | {} | {} |",
+                        index.name,
+                        index.columns.join(", "),
+                        if index.unique { "yes" } else { "no" }
+                    );
+                }
+            }
+
+            if !table.foreign_keys.is_empty() {
+                out.push_str("\n| Foreign key | References |\n");
+                out.push_str("|-------------|------------|\n");
+                for fk in &table.foreign_keys {
+                    let _ = writeln!(
+                        out,
+                        "| `{}` | `{}.{}` |",
+                        fk.column, fk.references_table, fk.references_column
+                    );
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Escape `text` for a Markdown table cell
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
 }
 
 pub fn save_snapshot(snapshot: &SchemaSnapshot, path: impl AsRef<Path>) -> Result<()> {
@@ -96,3 +419,14 @@ pub fn load_snapshot(path: impl AsRef<Path>) -> Result<SchemaSnapshot> {
     let snapshot = serde_json::from_str(&json)?;
     Ok(snapshot)
 }
+
+/// Load a snapshot and [`validate`](SchemaSnapshot::validate) it, so a
+/// hand-edited or corrupted file is reported before it is diffed against.
+pub fn load_validated_snapshot(path: impl AsRef<Path>) -> Result<SchemaSnapshot> {
+    let path = path.as_ref();
+    let snapshot = load_snapshot(path)?;
+    snapshot
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid snapshot {}: {}", path.display(), e))?;
+    Ok(snapshot)
+}
@@ -0,0 +1,59 @@
+use crate::introspect::{MongoDbIntrospector, SqlIntrospector};
+use crate::parser::EntityParser;
+use crate::snapshot::{load_snapshot, SchemaSnapshot};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+/// Anything that can produce a schema snapshot: a live database, entity
+/// files, a saved snapshot, or a fake in tests.
+#[async_trait]
+pub trait SchemaSource: Send + Sync {
+    async fn snapshot(&self) -> Result<SchemaSnapshot>;
+}
+
+/// Schema read from a JSON snapshot file, such as a migration directory's
+/// `.schema.json`. Useful for generating migrations without a database.
+pub struct FileSchemaSource {
+    path: PathBuf,
+}
+
+impl FileSchemaSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl SchemaSource for FileSchemaSource {
+    async fn snapshot(&self) -> Result<SchemaSnapshot> {
+        load_snapshot(&self.path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to read schema snapshot {}: {}",
+                self.path.display(),
+                e
+            )
+        })
+    }
+}
+
+#[async_trait]
+impl SchemaSource for SqlIntrospector {
+    async fn snapshot(&self) -> Result<SchemaSnapshot> {
+        self.introspect_schema().await
+    }
+}
+
+#[async_trait]
+impl SchemaSource for MongoDbIntrospector {
+    async fn snapshot(&self) -> Result<SchemaSnapshot> {
+        self.introspect_schema().await
+    }
+}
+
+#[async_trait]
+impl SchemaSource for EntityParser {
+    async fn snapshot(&self) -> Result<SchemaSnapshot> {
+        self.parse_entities()
+    }
+}
@@ -1,7 +1,21 @@
+use crate::{loader::MigrationFileInfo, AsyncMigrationContext, SqlFlavor};
 use anyhow::Result;
 use std::collections::HashSet;
+use toasty_core::stmt::Value;
+
+const CREATE_TRACKING_TABLE: &str = "CREATE TABLE IF NOT EXISTS _toasty_migrations (
+    version VARCHAR(255) PRIMARY KEY,
+    applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+)";
 
 /// Tracks which migrations have been applied to the database
+///
+/// The set lives in memory; [`initialize`](Self::initialize) and
+/// [`load_applied`](Self::load_applied) fill it from the `_toasty_migrations`
+/// table, and [`persist_applied`](Self::persist_applied) /
+/// [`persist_rolled_back`](Self::persist_rolled_back) write to that table on
+/// the connection given to them, so a migration and its tracking row commit
+/// in the same transaction.
 pub struct MigrationTracker {
     applied: HashSet<String>,
 }
@@ -13,25 +27,79 @@ impl MigrationTracker {
         }
     }
 
-    /// Initialize migration tracking table
-    /// SQL: CREATE TABLE IF NOT EXISTS _toasty_migrations (
-    ///         version VARCHAR(255) PRIMARY KEY,
-    ///         applied_at TIMESTAMP NOT NULL
-    ///      )
-    /// NoSQL: Create collection with version as primary key
-    pub async fn initialize(&mut self) -> Result<()> {
-        // TODO: Execute database-specific table/collection creation
-        // This will be implemented in the MigrationContext
-        Ok(())
+    /// Create the `_toasty_migrations` table if the database doesn't have
+    /// one yet
+    pub async fn initialize(&mut self, db: &mut dyn AsyncMigrationContext) -> Result<()> {
+        db.execute_batch(CREATE_TRACKING_TABLE).await
     }
 
-    /// Load applied migrations from database
-    pub async fn load_applied(&mut self) -> Result<()> {
-        // TODO: Query _toasty_migrations table/collection
-        // For now, returns empty set
+    /// Add the versions recorded in `_toasty_migrations` to the applied set
+    pub async fn load_applied(&mut self, db: &mut dyn AsyncMigrationContext) -> Result<()> {
+        let versions = Self::applied_versions(db).await?;
+        self.applied
+            .extend(versions.into_iter().map(|(version, _)| version));
         Ok(())
     }
 
+    /// Read the applied migrations from the `_toasty_migrations` table of the
+    /// database at `url`. A database that has never been migrated has no
+    /// such table and yields an empty tracker.
+    pub async fn load_from_database(url: &str) -> Result<Self> {
+        let mut db = crate::live::connect(url).await?;
+        let mut tracker = Self::new();
+        tracker.load_applied(&mut *db).await?;
+        Ok(tracker)
+    }
+
+    /// The migrations recorded in `_toasty_migrations` as
+    /// `(version, applied_at)`, in version order. Empty when the table
+    /// doesn't exist yet.
+    pub async fn applied_versions(
+        db: &mut dyn AsyncMigrationContext,
+    ) -> Result<Vec<(String, String)>> {
+        let (exists, select) = match db.flavor() {
+            SqlFlavor::PostgreSQL => (
+                "SELECT 1 FROM information_schema.tables WHERE table_name = '_toasty_migrations'",
+                "SELECT version, applied_at::text FROM _toasty_migrations ORDER BY version",
+            ),
+            SqlFlavor::Sqlite => (
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_toasty_migrations'",
+                "SELECT version, applied_at FROM _toasty_migrations ORDER BY version",
+            ),
+            SqlFlavor::MySQL => (
+                "SELECT 1 FROM information_schema.tables \
+                 WHERE table_schema = DATABASE() AND table_name = '_toasty_migrations'",
+                "SELECT version, CAST(applied_at AS CHAR) FROM _toasty_migrations ORDER BY version",
+            ),
+        };
+
+        if db.query(exists, &[]).await?.is_empty() {
+            return Ok(vec![]);
+        }
+
+        db.query(select, &[])
+            .await?
+            .into_iter()
+            .map(|row| match (row.get(0), row.get(1)) {
+                (Some(Value::String(version)), Some(Value::String(applied_at))) => {
+                    Ok((version.clone(), applied_at.clone()))
+                }
+                _ => Err(anyhow::anyhow!(
+                    "Unexpected row in _toasty_migrations: {:?}",
+                    row
+                )),
+            })
+            .collect()
+    }
+
+    /// The discovered migrations that have not been applied yet, in order
+    pub fn pending<'a>(&self, migrations: &'a [MigrationFileInfo]) -> Vec<&'a MigrationFileInfo> {
+        migrations
+            .iter()
+            .filter(|migration| !self.is_applied(&migration.version))
+            .collect()
+    }
+
     /// Check if a migration has been applied
     pub fn is_applied(&self, version: &str) -> bool {
         self.applied.contains(version)
@@ -54,15 +122,42 @@ impl MigrationTracker {
         migrations
     }
 
-    /// Persist applied migration to database
-    pub async fn persist_applied(&self, _version: &str) -> Result<()> {
-        // TODO: INSERT INTO _toasty_migrations (version, applied_at)
+    /// Record `version` in `_toasty_migrations`. Call this on the
+    /// connection running the migration, before its transaction commits.
+    pub async fn persist_applied(
+        &self,
+        db: &mut dyn AsyncMigrationContext,
+        version: &str,
+    ) -> Result<()> {
+        let sql = format!(
+            "INSERT INTO _toasty_migrations (version) VALUES ({})",
+            placeholder(db.flavor())
+        );
+        db.execute(&sql, &[version.into()]).await?;
         Ok(())
     }
 
-    /// Remove migration record from database
-    pub async fn persist_rolled_back(&self, _version: &str) -> Result<()> {
-        // TODO: DELETE FROM _toasty_migrations WHERE version = ?
+    /// Remove `version` from `_toasty_migrations`. Call this on the
+    /// connection rolling the migration back, before its transaction commits.
+    pub async fn persist_rolled_back(
+        &self,
+        db: &mut dyn AsyncMigrationContext,
+        version: &str,
+    ) -> Result<()> {
+        let sql = format!(
+            "DELETE FROM _toasty_migrations WHERE version = {}",
+            placeholder(db.flavor())
+        );
+        db.execute(&sql, &[version.into()]).await?;
         Ok(())
     }
 }
+
+/// The first statement parameter in `flavor`'s syntax
+fn placeholder(flavor: SqlFlavor) -> &'static str {
+    match flavor {
+        SqlFlavor::PostgreSQL => "$1",
+        SqlFlavor::Sqlite => "?1",
+        SqlFlavor::MySQL => "?",
+    }
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "sqlite")]
+
+use toasty_migrate::MigrationApi;
+
+/// Record `version` in the tracking table, as `toasty migrate:up` does
+fn record_applied(conn: &rusqlite::Connection, version: &str) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS _toasty_migrations (
+            version TEXT PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .unwrap();
+    conn.execute(
+        "INSERT INTO _toasty_migrations (version) VALUES (?1)",
+        [version],
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn pending_shrinks_and_applied_grows_after_up() {
+    let project = tempfile::tempdir().unwrap();
+    let migrations = project.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    for version in [
+        "20250101_000000_a",
+        "20250102_000000_b",
+        "20250103_000000_c",
+    ] {
+        std::fs::write(migrations.join(format!("{version}.rs")), "").unwrap();
+    }
+
+    let db = project.path().join("app.db");
+    let api = MigrationApi::new(format!("sqlite:{}", db.display()), &migrations);
+
+    // A fresh database has no tracking table yet
+    assert_eq!(api.pending().await.unwrap().len(), 3);
+    assert!(api.applied().await.unwrap().is_empty());
+
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    record_applied(&conn, "20250101_000000_a");
+    record_applied(&conn, "20250102_000000_b");
+
+    let pending: Vec<String> = api
+        .pending()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|file| file.version)
+        .collect();
+    assert_eq!(pending, ["20250103_000000_c"]);
+
+    let applied: Vec<String> = api
+        .applied()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+    assert_eq!(applied, ["20250101_000000_a", "20250102_000000_b"]);
+
+    // Querying never applies anything
+    assert_eq!(api.pending().await.unwrap().len(), 1);
+}
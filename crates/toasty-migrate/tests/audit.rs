@@ -0,0 +1,84 @@
+#![cfg(feature = "sqlite")]
+
+use toasty_migrate::{
+    ColumnDef, MigrationContext, SqlFlavor, SqlIntrospector, SqlMigrationContext,
+};
+
+#[tokio::test]
+async fn dropped_column_definition_is_logged() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let conn = rusqlite::Connection::open(file.path()).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (
+            id text NOT NULL,
+            status text NOT NULL DEFAULT 'active'
+        );",
+    )
+    .unwrap();
+
+    let introspector = SqlIntrospector::new(format!("sqlite:{}", file.path().display()));
+    let schema = introspector.introspect_schema().await.unwrap();
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite).audit(schema);
+    ctx.add_column(
+        "users",
+        ColumnDef {
+            name: "bio".into(),
+            ty: "text".into(),
+            nullable: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.drop_column("users", "status").unwrap();
+
+    let tx = conn.unchecked_transaction().unwrap();
+    for sql in ctx.statements() {
+        tx.execute_batch(sql).unwrap();
+    }
+    tx.commit().unwrap();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT operation, object_name, before_definition, after_definition
+             FROM _toasty_schema_log ORDER BY id",
+        )
+        .unwrap();
+    let rows: Vec<(String, String, Option<String>, Option<String>)> = stmt
+        .query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(
+        rows,
+        [
+            (
+                "add_column".to_string(),
+                "bio".to_string(),
+                None,
+                Some("bio text".to_string())
+            ),
+            (
+                "drop_column".to_string(),
+                "status".to_string(),
+                Some("status text NOT NULL DEFAULT 'active'".to_string()),
+                None
+            ),
+        ]
+    );
+
+    // The log table stays out of introspected schemas
+    let schema = introspector.introspect_schema().await.unwrap();
+    assert_eq!(schema.tables.len(), 1);
+}
+
+#[test]
+fn statements_unchanged_without_audit() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.drop_column("users", "status").unwrap();
+
+    assert_eq!(ctx.statements(), ["ALTER TABLE users DROP COLUMN status;"]);
+}
@@ -0,0 +1,968 @@
+use toasty_migrate::{
+    ColumnDef, ColumnReference, ConstraintDef, ConstraintKind, IndexDef, MigrationContext,
+    NoSqlMigrationContext, NoSqlOperation, PartitionBy, PartitionStrategy, ReferentialAction,
+    SchemaSnapshot, SqlFlavor, SqlMigrationContext, TruncateOptions,
+};
+
+#[test]
+fn execute_sql_is_recorded_in_order() {
+    for flavor in [SqlFlavor::Sqlite, SqlFlavor::PostgreSQL, SqlFlavor::MySQL] {
+        let mut ctx = SqlMigrationContext::new(flavor);
+
+        ctx.create_table(
+            "users",
+            vec![ColumnDef {
+                name: "id".into(),
+                ty: "text".into(),
+                nullable: false,
+                default: None,
+                ..Default::default()
+            }],
+        )
+        .unwrap();
+        ctx.execute_sql("UPDATE users SET id = lower(id);").unwrap();
+
+        let statements = ctx.statements();
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].starts_with("CREATE TABLE users"));
+        assert_eq!(statements[1], "UPDATE users SET id = lower(id);");
+    }
+}
+
+#[test]
+fn execute_sql_accepts_mongo_command() {
+    let mut ctx = NoSqlMigrationContext::new();
+
+    ctx.execute_sql(r#"{"collMod": "users", "validationLevel": "moderate"}"#)
+        .unwrap();
+
+    match ctx.operations() {
+        [NoSqlOperation::RunCommand { command }] => {
+            assert_eq!(command["collMod"], "users");
+            assert_eq!(command["validationLevel"], "moderate");
+        }
+        ops => panic!("unexpected operations: {:?}", ops),
+    }
+}
+
+#[test]
+fn execute_sql_rejects_non_json_mongo_command() {
+    let mut ctx = NoSqlMigrationContext::new();
+
+    assert!(ctx.execute_sql("DROP TABLE users").is_err());
+    assert!(ctx.execute_sql("[1, 2, 3]").is_err());
+    assert!(ctx.operations().is_empty());
+}
+
+#[test]
+fn comment_on_table_postgresql() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+
+    ctx.comment_on_table("users", "Registered users; see the owner's docs")
+        .unwrap();
+
+    assert_eq!(
+        ctx.statements(),
+        ["COMMENT ON TABLE users IS 'Registered users; see the owner''s docs';"]
+    );
+}
+
+#[test]
+fn comment_on_table_mysql() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+
+    ctx.create_table(
+        "users",
+        vec![ColumnDef {
+            name: "id".into(),
+            ty: "text".into(),
+            nullable: false,
+            default: None,
+            ..Default::default()
+        }],
+    )
+    .unwrap();
+    ctx.comment_on_table("users", "Registered users").unwrap();
+
+    // Folded into the CREATE TABLE that was just emitted
+    assert_eq!(
+        ctx.statements(),
+        ["CREATE TABLE users (\n  id text NOT NULL\n) COMMENT 'Registered users';"]
+    );
+
+    // Otherwise the existing table is altered
+    ctx.comment_on_table("posts", "Blog posts").unwrap();
+    assert_eq!(
+        ctx.statements()[1],
+        "ALTER TABLE posts COMMENT 'Blog posts';"
+    );
+}
+
+#[test]
+fn comment_on_table_is_skipped_for_sqlite() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    ctx.comment_on_table("users", "Registered users").unwrap();
+
+    assert!(ctx.statements().is_empty());
+}
+
+fn full_name() -> ColumnDef {
+    ColumnDef {
+        name: "full_name".into(),
+        ty: "text".into(),
+        nullable: true,
+        generated: Some("first_name || ' ' || last_name".into()),
+        stored: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn generated_column_per_flavor() {
+    for flavor in [SqlFlavor::Sqlite, SqlFlavor::PostgreSQL, SqlFlavor::MySQL] {
+        let mut ctx = SqlMigrationContext::new(flavor);
+
+        ctx.create_table("users", vec![full_name()]).unwrap();
+
+        assert_eq!(
+            ctx.statements(),
+            ["CREATE TABLE users (\n  full_name text GENERATED ALWAYS AS (first_name || ' ' || last_name) STORED\n);"]
+        );
+    }
+}
+
+#[test]
+fn virtual_generated_column() {
+    let column = ColumnDef {
+        stored: false,
+        ..full_name()
+    };
+
+    for flavor in [SqlFlavor::Sqlite, SqlFlavor::MySQL] {
+        let mut ctx = SqlMigrationContext::new(flavor);
+        ctx.add_column("users", column.clone()).unwrap();
+
+        assert_eq!(
+            ctx.statements(),
+            ["ALTER TABLE users ADD COLUMN full_name text GENERATED ALWAYS AS (first_name || ' ' || last_name) VIRTUAL;"]
+        );
+    }
+
+    // PostgreSQL only has stored generated columns
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    assert!(ctx.add_column("users", column).is_err());
+}
+
+#[test]
+fn sqlite_cannot_add_stored_generated_column() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    assert!(ctx.add_column("users", full_name()).is_err());
+    assert!(ctx.statements().is_empty());
+}
+
+fn user_fk(not_valid: bool) -> ConstraintDef {
+    ConstraintDef {
+        name: "fk_posts_user_id".into(),
+        kind: ConstraintKind::ForeignKey {
+            columns: vec!["user_id".into()],
+            references_table: "users".into(),
+            references_columns: vec!["id".into()],
+        },
+        not_valid,
+    }
+}
+
+#[test]
+fn add_constraint_not_valid_then_validate_postgresql() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+
+    ctx.add_constraint("posts", user_fk(true)).unwrap();
+    ctx.add_constraint(
+        "posts",
+        ConstraintDef {
+            name: "chk_posts_title".into(),
+            kind: ConstraintKind::Check {
+                expr: "length(title) > 0".into(),
+            },
+            not_valid: true,
+        },
+    )
+    .unwrap();
+    ctx.validate_constraint("posts", "fk_posts_user_id")
+        .unwrap();
+
+    assert_eq!(
+        ctx.statements(),
+        [
+            "ALTER TABLE posts ADD CONSTRAINT fk_posts_user_id FOREIGN KEY (user_id) REFERENCES users (id) NOT VALID;",
+            "ALTER TABLE posts ADD CONSTRAINT chk_posts_title CHECK (length(title) > 0) NOT VALID;",
+            "ALTER TABLE posts VALIDATE CONSTRAINT fk_posts_user_id;",
+        ]
+    );
+}
+
+#[test]
+fn add_constraint_other_flavors() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+    ctx.add_constraint("posts", user_fk(true)).unwrap();
+    ctx.validate_constraint("posts", "fk_posts_user_id")
+        .unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["ALTER TABLE posts ADD CONSTRAINT fk_posts_user_id FOREIGN KEY (user_id) REFERENCES users (id);"]
+    );
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    assert!(ctx.add_constraint("posts", user_fk(false)).is_err());
+}
+
+#[test]
+fn create_table_foreign_keys_per_flavor() {
+    let columns = vec![
+        ColumnDef {
+            name: "id".into(),
+            ty: "text".into(),
+            primary_key: true,
+            ..Default::default()
+        },
+        ColumnDef {
+            name: "user_id".into(),
+            ty: "text".into(),
+            references: Some(ColumnReference {
+                table: "users".into(),
+                column: "id".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        ColumnDef {
+            name: "editor_id".into(),
+            ty: "text".into(),
+            nullable: true,
+            references: Some(ColumnReference {
+                table: "users".into(),
+                column: "id".into(),
+                on_delete: ReferentialAction::SetNull,
+            }),
+            ..Default::default()
+        },
+    ];
+
+    // The constraints sit in the table body for every flavor
+    for flavor in [SqlFlavor::Sqlite, SqlFlavor::PostgreSQL, SqlFlavor::MySQL] {
+        let mut ctx = SqlMigrationContext::new(flavor);
+        ctx.create_table("posts", columns.clone()).unwrap();
+
+        assert_eq!(
+            ctx.statements(),
+            ["CREATE TABLE posts (\n  \
+                id text NOT NULL,\n  \
+                user_id text NOT NULL,\n  \
+                editor_id text,\n  \
+                PRIMARY KEY (id),\n  \
+                FOREIGN KEY (user_id) REFERENCES users (id),\n  \
+                FOREIGN KEY (editor_id) REFERENCES users (id) ON DELETE SET NULL\n);"],
+            "{flavor:?}"
+        );
+    }
+}
+
+#[test]
+fn collation_clause_per_flavor() {
+    let column = ColumnDef {
+        name: "name".into(),
+        ty: "text".into(),
+        nullable: false,
+        ..Default::default()
+    };
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.add_column(
+        "tags",
+        ColumnDef {
+            collation: Some("C".into()),
+            ..column.clone()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["ALTER TABLE tags ADD COLUMN name text COLLATE \"C\" NOT NULL;"]
+    );
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+    ctx.add_column(
+        "tags",
+        ColumnDef {
+            ty: "varchar(255)".into(),
+            collation: Some("utf8mb4_unicode_ci".into()),
+            ..column
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["ALTER TABLE tags ADD COLUMN name varchar(255) COLLATE utf8mb4_unicode_ci NOT NULL;"]
+    );
+}
+
+#[test]
+fn truncate_table_per_flavor() {
+    let cases = [
+        (SqlFlavor::Sqlite, "DELETE FROM cache;"),
+        (SqlFlavor::PostgreSQL, "TRUNCATE TABLE cache;"),
+        (SqlFlavor::MySQL, "TRUNCATE TABLE cache;"),
+    ];
+
+    for (flavor, expected) in cases {
+        let mut ctx = SqlMigrationContext::new(flavor);
+        ctx.truncate_table("cache").unwrap();
+        assert_eq!(ctx.statements(), [expected]);
+    }
+}
+
+#[test]
+fn truncate_table_options_apply_to_postgres_only() {
+    let options = TruncateOptions {
+        restart_identity: true,
+        cascade: true,
+    };
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.truncate_table_with("cache", options).unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["TRUNCATE TABLE cache RESTART IDENTITY CASCADE;"]
+    );
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+    ctx.truncate_table_with("cache", options).unwrap();
+    assert_eq!(ctx.statements(), ["TRUNCATE TABLE cache;"]);
+}
+
+#[test]
+fn nosql_truncate_deletes_all_documents() {
+    let mut ctx = NoSqlMigrationContext::new();
+    ctx.truncate_table("cache").unwrap();
+
+    match ctx.operations() {
+        [NoSqlOperation::DeleteMany { collection }] => assert_eq!(collection, "cache"),
+        ops => panic!("unexpected operations: {:?}", ops),
+    }
+}
+
+fn covering_index() -> IndexDef {
+    IndexDef {
+        name: "index_users_by_email".into(),
+        columns: vec!["email".into()],
+        unique: true,
+        include: vec!["name".into(), "created_at".into()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn postgres_index_includes_covered_columns() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.create_index("users", covering_index()).unwrap();
+
+    assert_eq!(
+        ctx.statements(),
+        ["CREATE UNIQUE INDEX index_users_by_email ON users (email) INCLUDE (name, created_at);"]
+    );
+}
+
+#[test]
+fn include_is_dropped_with_a_note_without_postgres() {
+    for flavor in [SqlFlavor::Sqlite, SqlFlavor::MySQL] {
+        let mut ctx = SqlMigrationContext::new(flavor);
+        ctx.create_index("users", covering_index()).unwrap();
+
+        let statements = ctx.statements();
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].starts_with("-- "), "{:?}", statements);
+        assert!(statements[0].contains("INCLUDE is not supported"));
+        assert_eq!(
+            statements[1],
+            "CREATE UNIQUE INDEX index_users_by_email ON users (email);"
+        );
+    }
+}
+
+#[test]
+fn rename_index_keeps_the_index_in_place() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.rename_index("users", "users_email_idx", "index_users_by_email")
+        .unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["ALTER INDEX users_email_idx RENAME TO index_users_by_email;"]
+    );
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+    ctx.rename_index("users", "users_email_idx", "index_users_by_email")
+        .unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["ALTER TABLE users RENAME INDEX users_email_idx TO index_users_by_email;"]
+    );
+}
+
+#[test]
+fn sqlite_rename_index_rebuilds_from_audited_definition() {
+    // Without the index definition there is nothing to rebuild from
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    assert!(ctx
+        .rename_index("users", "users_email_idx", "index_users_by_email")
+        .is_err());
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite).audit(SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![],
+    });
+    ctx.create_table(
+        "users",
+        vec![ColumnDef {
+            name: "email".into(),
+            ty: "text".into(),
+            ..Default::default()
+        }],
+    )
+    .unwrap();
+    ctx.create_index(
+        "users",
+        IndexDef {
+            name: "users_email_idx".into(),
+            columns: vec!["email".into()],
+            unique: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.rename_index("users", "users_email_idx", "index_users_by_email")
+        .unwrap();
+
+    let statements: Vec<_> = ctx
+        .statements()
+        .iter()
+        .filter(|sql| !sql.contains("_toasty_schema_log"))
+        .collect();
+    assert_eq!(
+        &statements[statements.len() - 2..],
+        [
+            "DROP INDEX users_email_idx;",
+            "CREATE UNIQUE INDEX index_users_by_email ON users (email);",
+        ]
+    );
+}
+
+fn nickname() -> ColumnDef {
+    ColumnDef {
+        name: "nickname".into(),
+        ty: "text".into(),
+        nullable: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn idempotent_add_column_postgres_and_mysql() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL).idempotent(true);
+    ctx.add_column("users", nickname()).unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["ALTER TABLE users ADD COLUMN IF NOT EXISTS nickname text;"]
+    );
+
+    // MySQL has no IF NOT EXISTS, so the ALTER only runs when the catalog
+    // doesn't list the column
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL).idempotent(true);
+    ctx.add_column("users", nickname()).unwrap();
+    assert_eq!(
+        ctx.statements(),
+        [
+            "SET @toasty_add_column = IF((SELECT COUNT(*) FROM information_schema.columns \
+             WHERE table_schema = DATABASE() AND table_name = 'users' AND column_name = 'nickname') = 0, \
+             'ALTER TABLE users ADD COLUMN nickname text', 'DO 0');",
+            "PREPARE toasty_add_column FROM @toasty_add_column;",
+            "EXECUTE toasty_add_column;",
+            "DEALLOCATE PREPARE toasty_add_column;",
+        ]
+    );
+
+    // Off by default
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.add_column("users", nickname()).unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["ALTER TABLE users ADD COLUMN nickname text;"]
+    );
+}
+
+#[test]
+fn mysql_create_and_drop_index() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+    ctx.create_index(
+        "users",
+        IndexDef {
+            name: "index_users_by_email".into(),
+            columns: vec!["email".into()],
+            unique: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.create_index(
+        "app.users",
+        IndexDef {
+            name: "index_users_by_name_and_age".into(),
+            columns: vec!["name".into(), "age".into()],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.drop_index("users", "index_users_by_email").unwrap();
+    ctx.drop_index("app.users", "index_users_by_name_and_age")
+        .unwrap();
+
+    // MySQL indexes belong to their table, so DROP INDEX names it and the
+    // index name is never qualified
+    assert_eq!(
+        ctx.statements(),
+        [
+            "CREATE UNIQUE INDEX index_users_by_email ON users (email);",
+            "CREATE INDEX index_users_by_name_and_age ON app.users (name, age);",
+            "DROP INDEX index_users_by_email ON users;",
+            "DROP INDEX index_users_by_name_and_age ON app.users;",
+        ]
+    );
+}
+
+#[test]
+fn mysql_add_and_drop_column() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+    ctx.add_column("users", nickname()).unwrap();
+    ctx.add_column(
+        "users",
+        ColumnDef {
+            name: "name".into(),
+            ty: "text".into(),
+            nullable: false,
+            default: Some("''".into()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.add_column(
+        "users",
+        ColumnDef {
+            name: "age".into(),
+            ty: "integer".into(),
+            nullable: false,
+            default: Some("0".into()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.add_column(
+        "users",
+        ColumnDef {
+            name: "code".into(),
+            ty: "varchar(16)".into(),
+            nullable: false,
+            default: Some("''".into()),
+            collation: Some("utf8mb4_bin".into()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.drop_column("users", "age").unwrap();
+    ctx.drop_column("app.users", "code").unwrap();
+
+    // TEXT columns only take a default written as an expression
+    assert_eq!(
+        ctx.statements(),
+        [
+            "ALTER TABLE users ADD COLUMN nickname text;",
+            "ALTER TABLE users ADD COLUMN name text NOT NULL DEFAULT ('');",
+            "ALTER TABLE users ADD COLUMN age integer NOT NULL DEFAULT 0;",
+            "ALTER TABLE users ADD COLUMN code varchar(16) COLLATE utf8mb4_bin NOT NULL DEFAULT '';",
+            "ALTER TABLE users DROP COLUMN age;",
+            "ALTER TABLE app.users DROP COLUMN code;",
+        ]
+    );
+}
+
+#[test]
+fn mysql_idempotent_add_column_checks_the_named_database() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL).idempotent(true);
+    ctx.add_column("app.users", nickname()).unwrap();
+
+    assert_eq!(
+        ctx.statements()[0],
+        "SET @toasty_add_column = IF((SELECT COUNT(*) FROM information_schema.columns \
+         WHERE table_schema = 'app' AND table_name = 'users' AND column_name = 'nickname') = 0, \
+         'ALTER TABLE app.users ADD COLUMN nickname text', 'DO 0');"
+    );
+}
+
+#[test]
+fn idempotent_add_column_sqlite_checks_audited_schema() {
+    // Nothing to check against
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite).idempotent(true);
+    assert!(ctx.add_column("users", nickname()).is_err());
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite)
+        .audit(SchemaSnapshot {
+            version: "1.0".to_string(),
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+            tables: vec![],
+        })
+        .idempotent(true);
+    ctx.create_table(
+        "users",
+        vec![ColumnDef {
+            name: "id".into(),
+            ty: "text".into(),
+            ..Default::default()
+        }],
+    )
+    .unwrap();
+    ctx.add_column("users", nickname()).unwrap();
+    // A re-run finds the column already there
+    ctx.add_column("users", nickname()).unwrap();
+
+    let statements: Vec<_> = ctx
+        .statements()
+        .iter()
+        .filter(|sql| !sql.contains("_toasty_schema_log"))
+        .skip(1)
+        .collect();
+    assert_eq!(
+        statements,
+        [
+            "ALTER TABLE users ADD COLUMN nickname text;",
+            "-- users.nickname already exists, not adding it again",
+        ]
+    );
+}
+
+#[test]
+fn make_not_null_backfills_then_constrains() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.make_not_null("users", "nickname", "''").unwrap();
+    assert_eq!(
+        ctx.statements(),
+        [
+            "UPDATE users SET nickname = '' WHERE nickname IS NULL;",
+            "ALTER TABLE users ALTER COLUMN nickname SET NOT NULL;",
+        ]
+    );
+
+    // MySQL restates the column, so it needs the audited definition
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+    assert!(ctx.make_not_null("users", "nickname", "''").is_err());
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL).audit(SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![],
+    });
+    ctx.create_table("users", vec![nickname()]).unwrap();
+    ctx.make_not_null("users", "nickname", "''").unwrap();
+
+    let statements: Vec<_> = ctx
+        .statements()
+        .iter()
+        .filter(|sql| !sql.contains("_toasty_schema_log"))
+        .skip(1)
+        .collect();
+    assert_eq!(
+        statements,
+        [
+            "UPDATE users SET nickname = '' WHERE nickname IS NULL;",
+            "ALTER TABLE users MODIFY COLUMN nickname text NOT NULL;",
+        ]
+    );
+}
+
+#[test]
+fn drop_not_null_and_defaults_per_flavor() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL).audit(SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![],
+    });
+    ctx.create_table(
+        "users",
+        vec![ColumnDef {
+            nullable: false,
+            ..nickname()
+        }],
+    )
+    .unwrap();
+    ctx.set_column_default("users", "nickname", Some("'anon'"))
+        .unwrap();
+    ctx.drop_not_null("users", "nickname").unwrap();
+
+    // MODIFY COLUMN restates the default set just before
+    let statements: Vec<_> = ctx
+        .statements()
+        .iter()
+        .filter(|sql| !sql.contains("_toasty_schema_log"))
+        .skip(1)
+        .collect();
+    assert_eq!(
+        statements,
+        [
+            "ALTER TABLE users ALTER COLUMN nickname SET DEFAULT ('anon');",
+            "ALTER TABLE users MODIFY COLUMN nickname text DEFAULT ('anon');",
+        ]
+    );
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    ctx.set_column_default("users", "nickname", None).unwrap();
+    assert!(ctx.statements()[0].starts_with("-- SQLite: Cannot change the default"));
+}
+
+#[test]
+fn modify_column_per_flavor() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.modify_column("users", "score", "text", "integer")
+        .unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["ALTER TABLE users ALTER COLUMN score TYPE integer USING score::integer;"]
+    );
+
+    // MySQL restates the column, so it needs the audited definition
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL);
+    assert!(ctx
+        .modify_column("users", "nickname", "text", "varchar(64)")
+        .is_err());
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::MySQL).audit(SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![],
+    });
+    ctx.create_table(
+        "users",
+        vec![ColumnDef {
+            nullable: false,
+            ..nickname()
+        }],
+    )
+    .unwrap();
+    ctx.modify_column("users", "nickname", "text", "varchar(64)")
+        .unwrap();
+    let statements: Vec<_> = ctx
+        .statements()
+        .iter()
+        .filter(|sql| !sql.contains("_toasty_schema_log"))
+        .skip(1)
+        .collect();
+    assert_eq!(
+        statements,
+        ["ALTER TABLE users MODIFY COLUMN nickname varchar(64) NOT NULL;"]
+    );
+
+    // SQLite rebuilds the table, which needs its definition
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    ctx.modify_column("users", "score", "text", "integer")
+        .unwrap();
+    assert!(ctx.statements()[0].starts_with("-- SQLite: Cannot change users.score"));
+}
+
+#[test]
+fn sqlite_modify_column_rebuilds_the_table() {
+    // `schema` tracks the definition without writing to the schema log
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite).schema(SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![],
+    });
+    ctx.create_table(
+        "users",
+        vec![
+            ColumnDef {
+                name: "id".into(),
+                ty: "integer".into(),
+                primary_key: true,
+                ..Default::default()
+            },
+            ColumnDef {
+                name: "score".into(),
+                ty: "text".into(),
+                default: Some("'0'".into()),
+                ..Default::default()
+            },
+        ],
+    )
+    .unwrap();
+    ctx.create_index(
+        "users",
+        IndexDef {
+            name: "index_users_by_score".into(),
+            columns: vec!["score".into()],
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.modify_column("users", "score", "text", "integer")
+        .unwrap();
+
+    assert_eq!(
+        &ctx.statements()[2..],
+        [
+            "CREATE TABLE _toasty_rebuild_users (\n  id integer NOT NULL,\n  score integer NOT NULL DEFAULT '0',\n  PRIMARY KEY (id)\n);",
+            "INSERT INTO _toasty_rebuild_users (id, score) SELECT id, CAST(score AS integer) FROM users;",
+            "DROP TABLE users;",
+            "ALTER TABLE _toasty_rebuild_users RENAME TO users;",
+            "CREATE INDEX index_users_by_score ON users (score);",
+        ]
+    );
+}
+
+fn events_columns() -> Vec<ColumnDef> {
+    ["id", "created_at"]
+        .into_iter()
+        .map(|name| ColumnDef {
+            name: name.into(),
+            ty: if name == "id" {
+                "bigint"
+            } else {
+                "timestamptz"
+            }
+            .into(),
+            primary_key: true,
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn by_month() -> PartitionBy {
+    PartitionBy {
+        strategy: PartitionStrategy::Range,
+        columns: vec!["created_at".into()],
+    }
+}
+
+#[test]
+fn postgres_partitioned_table_and_partition() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+
+    ctx.create_partitioned_table("events", events_columns(), by_month())
+        .unwrap();
+    ctx.create_partition("events", "events_2025_01", "'2025-01-01'", "'2025-02-01'")
+        .unwrap();
+
+    assert_eq!(
+        ctx.statements(),
+        [
+            "CREATE TABLE events (\n  id bigint NOT NULL,\n  created_at timestamptz NOT NULL,\n  PRIMARY KEY (id, created_at)\n) PARTITION BY RANGE (created_at);",
+            "CREATE TABLE events_2025_01 PARTITION OF events FOR VALUES FROM ('2025-01-01') TO ('2025-02-01');",
+        ]
+    );
+}
+
+#[test]
+fn partitioning_is_skipped_with_a_note_without_postgres() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    ctx.create_partitioned_table("events", events_columns(), by_month())
+        .unwrap();
+    ctx.create_partition("events", "events_2025_01", "'2025-01-01'", "'2025-02-01'")
+        .unwrap();
+
+    let statements = ctx.statements();
+    assert_eq!(statements.len(), 3, "{statements:?}");
+    assert!(statements[0].starts_with("-- SQLite: PARTITION BY is not supported"));
+    assert!(statements[1].ends_with("PRIMARY KEY (id, created_at)\n);"));
+    assert!(statements[2].starts_with("-- SQLite: events is not partitioned"));
+}
+
+fn id_column() -> ColumnDef {
+    ColumnDef {
+        name: "id".into(),
+        ty: "text".into(),
+        primary_key: true,
+        ..Default::default()
+    }
+}
+
+fn email_index() -> IndexDef {
+    IndexDef {
+        name: "index_users_by_email".into(),
+        columns: vec!["email".into()],
+        unique: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn table_statements_follow_its_create_and_repeats_are_dropped() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+
+    ctx.create_index("users", email_index()).unwrap();
+    ctx.add_column("users", nickname()).unwrap();
+    ctx.create_index("users", email_index()).unwrap();
+    ctx.create_table("posts", vec![id_column()]).unwrap();
+    ctx.create_table("users", vec![id_column()]).unwrap();
+    ctx.create_table("users", vec![id_column()]).unwrap();
+
+    assert_eq!(
+        ctx.statements(),
+        [
+            "CREATE TABLE posts (\n  id text NOT NULL,\n  PRIMARY KEY (id)\n);",
+            "CREATE TABLE users (\n  id text NOT NULL,\n  PRIMARY KEY (id)\n);",
+            "CREATE UNIQUE INDEX index_users_by_email ON users (email);",
+            "ALTER TABLE users ADD COLUMN nickname text;",
+        ]
+    );
+}
+
+#[test]
+fn contradictory_table_statements_are_an_error() {
+    // Dropping an index from a table that is only created afterwards
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.drop_index("users", "index_users_by_email").unwrap();
+    let err = ctx.create_table("users", vec![id_column()]).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("changes table users before it is created"),
+        "{err}"
+    );
+
+    // Two different definitions of the same table
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    ctx.create_table("users", vec![id_column()]).unwrap();
+    let err = ctx
+        .create_table("users", vec![id_column(), nickname()])
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("table users is created twice"),
+        "{err}"
+    );
+}
+
+#[test]
+fn dropped_tables_can_be_recreated_and_reindexed() {
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    ctx.create_table("users", vec![id_column()]).unwrap();
+    ctx.create_index("users", email_index()).unwrap();
+    ctx.drop_table("users").unwrap();
+    ctx.create_table("users", vec![id_column()]).unwrap();
+    ctx.create_index("users", email_index()).unwrap();
+
+    assert_eq!(ctx.statements().len(), 5, "{:?}", ctx.statements());
+    assert_eq!(ctx.statements()[2], "DROP TABLE users;");
+    assert_eq!(
+        ctx.statements()[4],
+        "CREATE UNIQUE INDEX index_users_by_email ON users (email);"
+    );
+}
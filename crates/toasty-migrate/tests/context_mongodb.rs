@@ -0,0 +1,64 @@
+#![cfg(feature = "mongodb_introspect")]
+
+use mongodb::bson::{doc, Document};
+use toasty_migrate::{IndexDef, MigrationContext, NoSqlMigrationContext};
+
+fn url() -> String {
+    std::env::var("TOASTY_TEST_MONGODB_URL")
+        .unwrap_or_else(|_| "mongodb://localhost:27017/toasty_test".to_string())
+}
+
+#[tokio::test]
+async fn apply_runs_every_recorded_operation() {
+    let client = mongodb::Client::with_uri_str(url())
+        .await
+        .unwrap_or_else(|e| panic!("MongoDB connection failed: {e}"));
+    let db = client.default_database().unwrap();
+
+    for name in ["context_cache", "context_sessions"] {
+        db.collection::<Document>(name).drop().await.unwrap();
+    }
+
+    let mut ctx = NoSqlMigrationContext::new();
+    ctx.create_table("context_cache", vec![]).unwrap();
+    ctx.create_index(
+        "context_cache",
+        IndexDef {
+            name: "index_context_cache_by_key".into(),
+            columns: vec!["key".into()],
+            unique: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    ctx.execute_sql(r#"{"insert": "context_cache", "documents": [{"key": "a"}, {"key": "b"}]}"#)
+        .unwrap();
+    ctx.truncate_table("context_cache").unwrap();
+    ctx.rename_table("context_cache", "context_sessions")
+        .unwrap();
+    ctx.apply(&db).await.unwrap();
+
+    let names = db.list_collection_names().await.unwrap();
+    assert!(names.contains(&"context_sessions".to_string()), "{names:?}");
+    assert!(!names.contains(&"context_cache".to_string()), "{names:?}");
+
+    let sessions = db.collection::<Document>("context_sessions");
+    assert_eq!(sessions.count_documents(doc! {}).await.unwrap(), 0);
+    let indexes = sessions.list_index_names().await.unwrap();
+    assert!(
+        indexes.contains(&"index_context_cache_by_key".to_string()),
+        "{indexes:?}"
+    );
+
+    let mut ctx = NoSqlMigrationContext::new();
+    ctx.drop_index("context_sessions", "index_context_cache_by_key")
+        .unwrap();
+    ctx.drop_table("context_sessions").unwrap();
+    ctx.apply(&db).await.unwrap();
+
+    let names = db.list_collection_names().await.unwrap();
+    assert!(
+        !names.contains(&"context_sessions".to_string()),
+        "{names:?}"
+    );
+}
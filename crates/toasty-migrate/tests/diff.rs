@@ -0,0 +1,646 @@
+use toasty_migrate::{
+    detect_changes, detect_changes_with, normalize_default, normalize_type,
+    snapshot::{ColumnSnapshot, IndexSnapshot, SchemaSnapshot, TableSnapshot},
+    DiffOptions, MigrationGenerator, SchemaChange, SqlFlavor, SqlMigrationContext, TableOptions,
+};
+
+fn schema(generated: Option<&str>, stored: bool) -> SchemaSnapshot {
+    SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![TableSnapshot {
+            schema: None,
+            name: "users".to_string(),
+            columns: vec![ColumnSnapshot {
+                name: "full_name".to_string(),
+                ty: "text".to_string(),
+                nullable: true,
+                generated: generated.map(str::to_string),
+                stored,
+                default: None,
+                auto: false,
+                collation: None,
+                comment: None,
+            }],
+            indices: vec![],
+            primary_key: vec![],
+            comment: None,
+            foreign_keys: vec![],
+            options: Default::default(),
+            partition_by: None,
+        }],
+    }
+}
+
+#[test]
+fn generated_expression_change_is_detected() {
+    let old = schema(Some("first_name || ' ' || last_name"), true);
+    let new = schema(Some("last_name || ', ' || first_name"), true);
+
+    let diff = detect_changes(&old, &new).unwrap();
+
+    match &diff.changes[..] {
+        [SchemaChange::ModifyColumn { old, new, .. }] => {
+            assert_eq!(
+                old.generated.as_deref(),
+                Some("first_name || ' ' || last_name")
+            );
+            assert_eq!(
+                new.generated.as_deref(),
+                Some("last_name || ', ' || first_name")
+            );
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    // Switching between stored and virtual is a change too
+    let diff = detect_changes(
+        &new,
+        &schema(Some("last_name || ', ' || first_name"), false),
+    )
+    .unwrap();
+    assert_eq!(diff.changes.len(), 1);
+
+    // Unchanged expressions produce no changes
+    assert!(detect_changes(&new, &new).unwrap().changes.is_empty());
+}
+
+fn with_default(default: Option<&str>, auto: bool) -> SchemaSnapshot {
+    let mut schema = schema(None, false);
+    let column = &mut schema.tables[0].columns[0];
+    column.default = default.map(str::to_string);
+    column.auto = auto;
+    schema
+}
+
+#[test]
+fn normalize_default_strips_casts_and_quoting() {
+    assert_eq!(normalize_default("'active'::text"), "'active'");
+    // A double-quoted name is an identifier, not a string
+    assert_eq!(normalize_default("\"Active\""), "\"Active\"");
+    assert_eq!(normalize_default("LOWER(\"Name\")"), "lower(\"Name\")");
+    assert_eq!(normalize_default("('active')"), "'active'");
+    assert_eq!(
+        normalize_default("'a'::character varying(255) || 'B'::text"),
+        "'a' || 'B'"
+    );
+    assert_eq!(
+        normalize_default("nextval('users_id_seq'::regclass)"),
+        "nextval('users_id_seq')"
+    );
+    assert_eq!(normalize_default("CURRENT_TIMESTAMP"), "current_timestamp");
+    assert_eq!(normalize_default("'It''s'::text"), "'It''s'");
+}
+
+#[test]
+fn placeholder_default_of_required_column_is_not_drift() {
+    // Generated migrations create required columns with `DEFAULT ''`
+    let mut live = with_default(Some("''"), false);
+    live.tables[0].columns[0].nullable = false;
+    let mut entities = with_default(None, false);
+    entities.tables[0].columns[0].nullable = false;
+    assert!(detect_changes(&live, &entities).unwrap().changes.is_empty());
+
+    // On a nullable column `''` is a real default
+    let live = with_default(Some("''"), false);
+    let entities = with_default(None, false);
+    assert_eq!(detect_changes(&live, &entities).unwrap().changes.len(), 1);
+}
+
+#[test]
+fn normalize_type_folds_case_and_aliases() {
+    assert_eq!(normalize_type("TEXT", SqlFlavor::Sqlite), "text");
+    assert_eq!(
+        normalize_type("character varying", SqlFlavor::PostgreSQL),
+        "varchar"
+    );
+    assert_eq!(
+        normalize_type("CHARACTER  VARYING (255)", SqlFlavor::PostgreSQL),
+        "varchar(255)"
+    );
+    assert_eq!(normalize_type("int4", SqlFlavor::PostgreSQL), "integer");
+    assert_eq!(
+        normalize_type("timestamp with time zone", SqlFlavor::PostgreSQL),
+        "timestamptz"
+    );
+    assert_eq!(
+        normalize_type("numeric(10, 2)", SqlFlavor::PostgreSQL),
+        "numeric(10,2)"
+    );
+    assert_eq!(normalize_type("int(11)", SqlFlavor::MySQL), "integer");
+    assert_eq!(normalize_type("tinyint(1)", SqlFlavor::MySQL), "boolean");
+
+    // Different types stay different
+    assert_eq!(
+        normalize_type("varchar(255)", SqlFlavor::PostgreSQL),
+        "varchar(255)"
+    );
+    assert_ne!(
+        normalize_type("text", SqlFlavor::PostgreSQL),
+        normalize_type("varchar", SqlFlavor::PostgreSQL)
+    );
+}
+
+fn with_type(ty: &str) -> SchemaSnapshot {
+    let mut schema = schema(None, false);
+    schema.tables[0].columns[0].ty = ty.to_string();
+    schema
+}
+
+#[test]
+fn type_spelling_is_not_a_change() {
+    for (introspected, entity) in [
+        ("TEXT", "text"),
+        ("Text", "text"),
+        ("character varying", "varchar"),
+        ("CHARACTER VARYING(255)", "varchar(255)"),
+        ("INTEGER", "integer"),
+        ("int8", "bigint"),
+    ] {
+        let diff = detect_changes(&with_type(introspected), &with_type(entity)).unwrap();
+        assert!(
+            diff.changes.is_empty(),
+            "{introspected} -> {entity}: {:?}",
+            diff.changes
+        );
+    }
+
+    let diff = detect_changes(&with_type("TEXT"), &with_type("varchar(255)")).unwrap();
+    assert!(
+        matches!(&diff.changes[..], [SchemaChange::ModifyColumn { .. }]),
+        "{:?}",
+        diff.changes
+    );
+}
+
+#[test]
+fn equivalent_defaults_are_not_a_change() {
+    let introspected = with_default(Some("'active'::text"), false);
+    let entity = with_default(Some("'active'"), false);
+
+    assert!(detect_changes(&introspected, &entity)
+        .unwrap()
+        .changes
+        .is_empty());
+
+    // A different value still is
+    let entity = with_default(Some("'inactive'"), false);
+    assert_eq!(
+        detect_changes(&introspected, &entity)
+            .unwrap()
+            .changes
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn sequence_default_ignored_for_auto_columns() {
+    let introspected = with_default(Some("nextval('users_id_seq'::regclass)"), false);
+
+    let entity = with_default(None, true);
+    assert!(detect_changes(&introspected, &entity)
+        .unwrap()
+        .changes
+        .is_empty());
+
+    let entity = with_default(None, false);
+    assert_eq!(
+        detect_changes(&introspected, &entity)
+            .unwrap()
+            .changes
+            .len(),
+        1
+    );
+}
+
+fn with_collation(collation: Option<&str>) -> SchemaSnapshot {
+    let mut schema = schema(None, false);
+    schema.tables[0].columns[0].collation = collation.map(str::to_string);
+    schema
+}
+
+#[test]
+fn collation_change_is_detected() {
+    let old = with_collation(None);
+    let new = with_collation(Some("C"));
+
+    let diff = detect_changes(&old, &new).unwrap();
+    match &diff.changes[..] {
+        [SchemaChange::ModifyColumn { new, .. }] => {
+            assert_eq!(new.collation.as_deref(), Some("C"));
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    // Quoting and case are not a change
+    let introspected = with_collation(Some("\"utf8mb4_unicode_ci\""));
+    let declared = with_collation(Some("UTF8MB4_UNICODE_CI"));
+    assert!(detect_changes(&introspected, &declared)
+        .unwrap()
+        .changes
+        .is_empty());
+}
+
+fn with_index(include: &[&str]) -> SchemaSnapshot {
+    let mut schema = schema(None, false);
+    schema.tables[0].indices.push(IndexSnapshot {
+        name: "index_users_by_full_name".to_string(),
+        columns: vec!["full_name".to_string()],
+        unique: false,
+        primary_key: false,
+        include: include.iter().map(|c| c.to_string()).collect(),
+        predicate: None,
+    });
+    schema
+}
+
+#[test]
+fn include_column_change_rebuilds_index() {
+    let diff = detect_changes(&with_index(&[]), &with_index(&["email"])).unwrap();
+
+    match &diff.changes[..] {
+        [SchemaChange::DropIndex { index: dropped, .. }, SchemaChange::CreateIndex { index, .. }] =>
+        {
+            assert_eq!(dropped.name, "index_users_by_full_name");
+            assert!(dropped.include.is_empty());
+            assert_eq!(index.include, ["email"]);
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    let same = with_index(&["email"]);
+    assert!(detect_changes(&same, &same).unwrap().changes.is_empty());
+}
+
+fn with_unique(name: &str) -> SchemaSnapshot {
+    let mut schema = with_index(&[]);
+    let index = &mut schema.tables[0].indices[0];
+    index.name = name.to_string();
+    index.unique = true;
+    schema
+}
+
+#[test]
+fn inline_unique_matches_unique_index() {
+    let declared = with_unique("index_users_by_full_name");
+
+    // `full_name text UNIQUE`, as SQLite and PostgreSQL report it
+    for inline in ["sqlite_autoindex_users_1", "users_full_name_key"] {
+        let introspected = with_unique(inline);
+        assert!(detect_changes(&introspected, &declared)
+            .unwrap()
+            .changes
+            .is_empty());
+        assert!(detect_changes(&declared, &introspected)
+            .unwrap()
+            .changes
+            .is_empty());
+    }
+
+    // Dropping the uniqueness is still a change
+    let diff = detect_changes(&with_unique("sqlite_autoindex_users_1"), &with_index(&[])).unwrap();
+    assert_eq!(diff.changes.len(), 2, "{:?}", diff.changes);
+}
+
+#[test]
+fn renamed_index_with_same_definition_is_not_a_change() {
+    let mut hand_named = with_index(&[]);
+    hand_named.tables[0].indices[0].name = "idx_users_full_name".to_string();
+
+    assert!(detect_changes(&hand_named, &with_index(&[]))
+        .unwrap()
+        .changes
+        .is_empty());
+    assert!(detect_changes(&with_index(&[]), &hand_named)
+        .unwrap()
+        .changes
+        .is_empty());
+
+    // A different column set under a different name is rebuilt
+    let mut other_columns = hand_named.clone();
+    other_columns.tables[0].indices[0].columns = vec!["email".to_string()];
+    let diff = detect_changes(&other_columns, &with_index(&[])).unwrap();
+    match &diff.changes[..] {
+        [SchemaChange::DropIndex { index: dropped, .. }, SchemaChange::CreateIndex { index, .. }] =>
+        {
+            assert_eq!(dropped.name, "idx_users_full_name");
+            assert_eq!(index.name, "index_users_by_full_name");
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+}
+
+/// `users` in both the default schema and `tenant`, with the `tenant` copy's
+/// column as given
+fn in_two_schemas(tenant_ty: &str) -> SchemaSnapshot {
+    let mut schema = schema(None, false);
+    let mut tenant = schema.tables[0].clone();
+    tenant.schema = Some("tenant".to_string());
+    tenant.columns[0].ty = tenant_ty.to_string();
+    schema.tables.push(tenant);
+    schema
+}
+
+#[test]
+fn same_table_name_in_two_schemas_is_two_tables() {
+    let old = in_two_schemas("text");
+    assert!(old.validate().is_ok());
+    assert!(detect_changes(&old, &old).unwrap().changes.is_empty());
+
+    // Changing the tenant copy leaves the default schema's table alone
+    let diff = detect_changes(&old, &in_two_schemas("varchar(255)")).unwrap();
+    match &diff.changes[..] {
+        [SchemaChange::ModifyColumn { table, new, .. }] => {
+            assert_eq!(table, "tenant.users");
+            assert_eq!(new.ty, "varchar(255)");
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    // Dropping one schema's table keeps the other
+    let diff = detect_changes(&old, &schema(None, false)).unwrap();
+    match &diff.changes[..] {
+        [SchemaChange::DropTable(table)] => assert_eq!(table.qualified_name(), "tenant.users"),
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    // And creating it again qualifies the DDL
+    let diff = detect_changes(&schema(None, false), &old).unwrap();
+    match &diff.changes[..] {
+        [SchemaChange::CreateTable(table)] => {
+            assert_eq!(table.schema.as_deref(), Some("tenant"));
+            assert_eq!(table.qualified_name(), "tenant.users");
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    MigrationGenerator::new("migrations")
+        .apply(&diff, &mut ctx)
+        .unwrap();
+    assert!(ctx.statements()[0].starts_with("CREATE TABLE tenant.users ("));
+}
+
+fn with_options(engine: Option<&str>, charset: Option<&str>) -> SchemaSnapshot {
+    let mut schema = schema(None, false);
+    schema.tables[0].options = TableOptions {
+        engine: engine.map(str::to_string),
+        charset: charset.map(str::to_string),
+    };
+    schema
+}
+
+#[test]
+fn charset_change_converts_the_table() {
+    let old = with_options(Some("InnoDB"), Some("utf8"));
+    let new = with_options(Some("InnoDB"), Some("utf8mb4"));
+
+    let diff = detect_changes(&old, &new).unwrap();
+    match &diff.changes[..] {
+        [SchemaChange::AlterTableOptions { table, new, .. }] => {
+            assert_eq!(table, "users");
+            assert_eq!(new.engine, None);
+            assert_eq!(new.charset.as_deref(), Some("utf8mb4"));
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    let mut db = SqlMigrationContext::new(SqlFlavor::MySQL);
+    MigrationGenerator::new("migrations")
+        .apply(&diff, &mut db)
+        .unwrap();
+    assert_eq!(
+        db.statements(),
+        ["ALTER TABLE users CONVERT TO CHARACTER SET utf8mb4;"]
+    );
+
+    let migration = MigrationGenerator::new("migrations")
+        .generate(&diff, "utf8mb4")
+        .unwrap();
+    assert_eq!(
+        migration.down_statements,
+        ["db.alter_table_options(\"users\", TableOptions { charset: Some(\"utf8\".into()), ..Default::default() })?;"]
+    );
+
+    // Other databases have nothing to convert
+    let mut db = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    MigrationGenerator::new("migrations")
+        .apply(&diff, &mut db)
+        .unwrap();
+    assert!(
+        db.statements()[0].starts_with("-- PostgreSQL:"),
+        "{:?}",
+        db.statements()
+    );
+}
+
+#[test]
+fn unset_or_recased_options_are_not_a_change() {
+    let introspected = with_options(Some("InnoDB"), Some("utf8mb4"));
+
+    for entity in [
+        with_options(None, None),
+        with_options(Some("innodb"), None),
+        with_options(None, Some("UTF8MB4")),
+    ] {
+        let diff = detect_changes(&introspected, &entity).unwrap();
+        assert!(diff.changes.is_empty(), "{:?}", diff.changes);
+    }
+}
+
+/// `users` keyed on `key`, with its primary key index under `index_name`
+fn keyed(key: &[&str], index_name: &str) -> SchemaSnapshot {
+    let mut schema = schema(None, false);
+    let table = &mut schema.tables[0];
+    table.primary_key = key.iter().map(|c| c.to_string()).collect();
+    table.indices.push(IndexSnapshot {
+        name: index_name.to_string(),
+        columns: table.primary_key.clone(),
+        unique: true,
+        primary_key: true,
+        include: vec![],
+        predicate: None,
+    });
+    schema
+}
+
+#[test]
+fn primary_key_index_name_is_not_a_change() {
+    let introspected = keyed(&["full_name"], "users_pkey");
+    let entity = keyed(&["full_name"], "index_users_by_full_name");
+
+    let diff = detect_changes(&introspected, &entity).unwrap();
+    assert!(diff.changes.is_empty(), "{:?}", diff.changes);
+}
+
+#[test]
+fn primary_key_column_change_is_detected() {
+    let old = keyed(&["full_name"], "users_pkey");
+    let mut new = keyed(&["id", "full_name"], "index_users_by_id_and_full_name");
+    let id = ColumnSnapshot {
+        name: "id".to_string(),
+        ..new.tables[0].columns[0].clone()
+    };
+    new.tables[0].columns.push(id);
+
+    let diff = detect_changes(&old, &new).unwrap();
+    let key_changes: Vec<_> = diff
+        .changes
+        .iter()
+        .filter(|change| !matches!(change, SchemaChange::AddColumn { .. }))
+        .collect();
+    match &key_changes[..] {
+        [SchemaChange::ChangePrimaryKey { table, old, new }] => {
+            assert_eq!(table, "users");
+            assert_eq!(old, &["full_name"]);
+            assert_eq!(new, &["id", "full_name"]);
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+    assert!(key_changes[0].is_destructive());
+}
+
+/// `users` with an indexed column under each of `names`
+fn with_columns(names: &[&str]) -> SchemaSnapshot {
+    let mut schema = schema(None, false);
+    let table = &mut schema.tables[0];
+    table.columns = names
+        .iter()
+        .map(|name| ColumnSnapshot {
+            name: name.to_string(),
+            ..table.columns[0].clone()
+        })
+        .collect();
+    table.indices.push(IndexSnapshot {
+        name: "index_users_by_first".to_string(),
+        columns: vec![names[0].to_string()],
+        unique: false,
+        primary_key: false,
+        include: vec![],
+        predicate: None,
+    });
+    schema
+}
+
+fn detect_renames(old: &SchemaSnapshot, new: &SchemaSnapshot) -> Vec<SchemaChange> {
+    detect_changes_with(old, new, &DiffOptions::new().detect_renames(true))
+        .unwrap()
+        .changes
+}
+
+#[test]
+fn single_column_swap_is_a_rename() {
+    let old = with_columns(&["password"]);
+    let new = with_columns(&["password_hash"]);
+
+    // Off by default
+    match &detect_changes(&old, &new).unwrap().changes[..] {
+        [SchemaChange::DropColumn { .. }, SchemaChange::AddColumn { .. }] => {}
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    // The index follows the column, so it is left alone
+    match &detect_renames(&old, &new)[..] {
+        [SchemaChange::RenameColumn { table, from, to }] => {
+            assert_eq!(table, "users");
+            assert_eq!(from, "password");
+            assert_eq!(to, "password_hash");
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    // A type change too is not a rename
+    let mut retyped = new.clone();
+    retyped.tables[0].columns[0].ty = "bytea".to_string();
+    let changes = detect_renames(&old, &retyped);
+    assert!(
+        changes
+            .iter()
+            .any(|c| matches!(c, SchemaChange::DropColumn { .. })),
+        "{:?}",
+        changes
+    );
+}
+
+#[test]
+fn ambiguous_column_swap_is_not_a_rename() {
+    let old = with_columns(&["id", "password", "salt"]);
+    let new = with_columns(&["id", "password_hash", "salt_hash"]);
+
+    let changes = detect_renames(&old, &new);
+    assert!(
+        !changes
+            .iter()
+            .any(|c| matches!(c, SchemaChange::RenameColumn { .. })),
+        "{:?}",
+        changes
+    );
+    assert_eq!(
+        changes
+            .iter()
+            .filter(|c| matches!(c, SchemaChange::DropColumn { .. }))
+            .count(),
+        2
+    );
+}
+
+fn renamed_table(name: &str) -> SchemaSnapshot {
+    let mut schema = with_columns(&["email"]);
+    schema.tables[0].name = name.to_string();
+    schema.tables[0].indices[0].name = format!("index_{}_by_email", name);
+    schema
+}
+
+#[test]
+fn table_with_same_columns_is_a_rename() {
+    let old = renamed_table("users");
+    let new = renamed_table("accounts");
+
+    match &detect_changes(&old, &new).unwrap().changes[..] {
+        [SchemaChange::DropTable(_), SchemaChange::CreateTable(_)] => {}
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    let changes = detect_renames(&old, &new);
+    match &changes[..] {
+        [SchemaChange::RenameTable { from, to }] => {
+            assert_eq!(from, "users");
+            assert_eq!(to, "accounts");
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+    assert!(!changes[0].is_destructive());
+
+    let diff = toasty_migrate::SchemaDiff { changes };
+    let migration = MigrationGenerator::new("migrations")
+        .generate(&diff, "rename users")
+        .unwrap();
+    assert_eq!(
+        migration.up_statements,
+        ["db.rename_table(\"users\", \"accounts\")?;"]
+    );
+    assert_eq!(
+        migration.down_statements,
+        ["db.rename_table(\"accounts\", \"users\")?;"]
+    );
+
+    let mut db = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    MigrationGenerator::new("migrations")
+        .apply(&diff, &mut db)
+        .unwrap();
+    assert_eq!(db.statements(), ["ALTER TABLE users RENAME TO accounts;"]);
+}
+
+#[test]
+fn ambiguous_table_swap_is_not_a_rename() {
+    let mut old = renamed_table("users");
+    old.tables.extend(renamed_table("admins").tables);
+    let new = renamed_table("accounts");
+
+    let changes = detect_renames(&old, &new);
+    assert_eq!(changes.len(), 3, "{:?}", changes);
+    assert!(!changes
+        .iter()
+        .any(|c| matches!(c, SchemaChange::RenameTable { .. })));
+}
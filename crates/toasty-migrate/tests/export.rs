@@ -0,0 +1,121 @@
+use toasty_migrate::{
+    snapshot::{ColumnSnapshot, ForeignKeySnapshot, TableSnapshot},
+    DiagramFormat, SchemaSnapshot,
+};
+
+fn column(name: &str, ty: &str, nullable: bool) -> ColumnSnapshot {
+    ColumnSnapshot {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        nullable,
+        generated: None,
+        stored: false,
+        default: None,
+        auto: false,
+        collation: None,
+        comment: None,
+    }
+}
+
+fn table(name: &str, columns: Vec<ColumnSnapshot>) -> TableSnapshot {
+    TableSnapshot {
+        schema: None,
+        name: name.to_string(),
+        columns,
+        indices: vec![],
+        primary_key: vec!["id".to_string()],
+        comment: None,
+        foreign_keys: vec![],
+        options: Default::default(),
+        partition_by: None,
+    }
+}
+
+fn blog() -> SchemaSnapshot {
+    let mut users = table(
+        "users",
+        vec![column("id", "text", false), column("email", "text", false)],
+    );
+    users.comment = Some("People who can sign in".to_string());
+
+    let mut posts = table(
+        "posts",
+        vec![
+            column("id", "text", false),
+            column("user_id", "text", false),
+            column("editor_id", "text", true),
+            column("published_at", "timestamp with time zone", true),
+        ],
+    );
+    posts.foreign_keys = vec![
+        ForeignKeySnapshot {
+            column: "user_id".to_string(),
+            references_table: "users".to_string(),
+            references_column: "id".to_string(),
+        },
+        ForeignKeySnapshot {
+            column: "editor_id".to_string(),
+            references_table: "users".to_string(),
+            references_column: "id".to_string(),
+        },
+    ];
+
+    SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![users, posts],
+    }
+}
+
+#[test]
+fn dbml_lists_tables_and_relationships() {
+    let dbml = blog().to_diagram(DiagramFormat::Dbml);
+
+    assert_eq!(
+        dbml,
+        "\
+Table users {
+  id text [pk]
+  email text [not null]
+
+  Note: 'People who can sign in'
+}
+
+Table posts {
+  id text [pk]
+  user_id text [not null]
+  editor_id text
+  published_at \"timestamp with time zone\"
+}
+
+Ref: posts.user_id > users.id
+Ref: posts.editor_id > users.id
+"
+    );
+}
+
+#[test]
+fn plantuml_links_entities_by_foreign_key() {
+    let plantuml = blog().to_diagram(DiagramFormat::PlantUml);
+
+    assert!(plantuml.starts_with("@startuml\n"), "{plantuml}");
+    assert!(
+        plantuml.contains(
+            "entity \"users\" as users {\n  * id : text <<PK>>\n  --\n  * email : text\n}"
+        ),
+        "{plantuml}"
+    );
+    assert!(
+        plantuml.contains("  * user_id : text <<FK>>\n"),
+        "{plantuml}"
+    );
+    assert!(
+        plantuml.contains("posts }o--|| users : user_id\n"),
+        "{plantuml}"
+    );
+    assert!(
+        plantuml.contains("posts }o--o| users : editor_id\n"),
+        "{plantuml}"
+    );
+    assert!(plantuml.ends_with("@enduml\n"), "{plantuml}");
+}
@@ -0,0 +1,890 @@
+use toasty_migrate::{
+    migration_slug,
+    snapshot::{ColumnSnapshot, IndexSnapshot, TableSnapshot},
+    ConstraintDef, ConstraintKind, MigrationGenerator, PartitionBy, PartitionStrategy,
+    SchemaChange, SchemaDiff, SchemaSnapshot, SchemaSource, SqlFlavor, SqlMigrationContext,
+};
+
+fn add_fk() -> SchemaDiff {
+    SchemaDiff {
+        changes: vec![SchemaChange::AddConstraint {
+            table: "posts".to_string(),
+            constraint: ConstraintDef {
+                name: "fk_posts_user_id".into(),
+                kind: ConstraintKind::ForeignKey {
+                    columns: vec!["user_id".into()],
+                    references_table: "users".into(),
+                    references_columns: vec!["id".into()],
+                },
+                not_valid: false,
+            },
+        }],
+    }
+}
+
+#[test]
+fn safe_constraints_split_add_and_validate() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path()).safe_constraints(true);
+
+    let migration = generator.generate(&add_fk(), "add fk").unwrap();
+
+    assert_eq!(migration.up_statements.len(), 1);
+    assert!(migration.up_statements[0].starts_with("db.add_constraint(\"posts\", ConstraintDef {"));
+    assert!(migration.up_statements[0].contains("not_valid: true"));
+    assert_eq!(
+        migration.followup_statements,
+        ["db.validate_constraint(\"posts\", \"fk_posts_user_id\")?;"]
+    );
+
+    generator.write_migration_file(&migration).unwrap();
+    let code = std::fs::read_to_string(dir.path().join(&migration.filename)).unwrap();
+    assert!(code.contains("fn followup(&self, db: &mut dyn MigrationContext) -> Result<()> {"));
+}
+
+#[test]
+fn constraints_validate_inline_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path());
+
+    let migration = generator.generate(&add_fk(), "add fk").unwrap();
+
+    assert!(migration.up_statements[0].contains("not_valid: false"));
+    assert!(migration.followup_statements.is_empty());
+
+    generator.write_migration_file(&migration).unwrap();
+    let code = std::fs::read_to_string(dir.path().join(&migration.filename)).unwrap();
+    assert!(!code.contains("fn followup"));
+}
+
+fn add_not_null_column(default: &str) -> SchemaDiff {
+    SchemaDiff {
+        changes: vec![SchemaChange::AddColumn {
+            table: "users".to_string(),
+            column: ColumnSnapshot {
+                name: "role".to_string(),
+                ty: "text".to_string(),
+                nullable: false,
+                generated: None,
+                stored: false,
+                default: Some(default.to_string()),
+                auto: false,
+                collation: None,
+                comment: None,
+            },
+        }],
+    }
+}
+
+#[test]
+fn safe_add_column_splits_not_null_add_with_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path()).safe_add_column(true);
+    let diff = add_not_null_column("'member'");
+
+    let migration = generator.generate(&diff, "add role").unwrap();
+
+    let up = migration.up_statements.join("\n");
+    assert!(up.contains("nullable: true, default: None"), "{up}");
+    assert!(
+        up.contains("db.set_column_default(\"users\", \"role\", Some(\"'member'\"))?;"),
+        "{up}"
+    );
+    assert_eq!(
+        migration.followup_statements,
+        [
+            "db.backfill_column(\"users\", \"role\", \"'member'\", 10000)?;",
+            "db.make_not_null(\"users\", \"role\", \"'member'\")?;",
+        ]
+    );
+
+    let mut db = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    generator.apply(&diff, &mut db).unwrap();
+    assert_eq!(
+        db.statements(),
+        [
+            "ALTER TABLE users ADD COLUMN role text;",
+            "ALTER TABLE users ALTER COLUMN role SET DEFAULT 'member';",
+            "DO $$ BEGIN LOOP UPDATE users SET role = 'member' WHERE ctid IN (SELECT ctid FROM users WHERE role IS NULL LIMIT 10000); EXIT WHEN NOT FOUND; COMMIT; END LOOP; END $$;",
+            "UPDATE users SET role = 'member' WHERE role IS NULL;",
+            "ALTER TABLE users ALTER COLUMN role SET NOT NULL;",
+        ]
+    );
+
+    let migration = generator
+        .batch_size(500)
+        .generate(&diff, "add role")
+        .unwrap();
+    assert_eq!(
+        migration.followup_statements[0],
+        "db.backfill_column(\"users\", \"role\", \"'member'\", 500)?;"
+    );
+}
+
+#[test]
+fn safe_add_column_keeps_fast_default_on_postgres_11() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path())
+        .safe_add_column(true)
+        .postgres_version(160002);
+
+    let migration = generator
+        .generate(&add_not_null_column("'member'"), "add role")
+        .unwrap();
+    assert_eq!(migration.up_statements.len(), 1);
+    assert!(
+        migration.up_statements[0].contains("nullable: false, default: Some(\"'member'\".into())")
+    );
+    assert!(migration.followup_statements.is_empty());
+
+    // A volatile default would still rewrite every row
+    let migration = generator
+        .generate(&add_not_null_column("gen_random_uuid()::text"), "add role")
+        .unwrap();
+    assert_eq!(migration.followup_statements.len(), 2);
+
+    // Servers before 11 can't commit between batches
+    let generator = MigrationGenerator::new(dir.path())
+        .safe_add_column(true)
+        .postgres_version(100023);
+    let migration = generator
+        .generate(&add_not_null_column("'member'"), "add role")
+        .unwrap();
+    assert_eq!(
+        migration.followup_statements,
+        ["db.make_not_null(\"users\", \"role\", \"'member'\")?;"]
+    );
+}
+
+struct FakeSource(SchemaSnapshot);
+
+#[async_trait::async_trait]
+impl SchemaSource for FakeSource {
+    async fn snapshot(&self) -> anyhow::Result<SchemaSnapshot> {
+        Ok(self.0.clone())
+    }
+}
+
+fn schema(tables: Vec<TableSnapshot>) -> SchemaSnapshot {
+    SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables,
+    }
+}
+
+fn users() -> TableSnapshot {
+    TableSnapshot {
+        schema: None,
+        name: "users".to_string(),
+        columns: vec![ColumnSnapshot {
+            name: "id".to_string(),
+            ty: "text".to_string(),
+            nullable: false,
+            generated: None,
+            stored: false,
+            default: None,
+            auto: false,
+            collation: None,
+            comment: None,
+        }],
+        indices: vec![],
+        primary_key: vec!["id".to_string()],
+        comment: None,
+        foreign_keys: vec![],
+        options: Default::default(),
+        partition_by: None,
+    }
+}
+
+#[tokio::test]
+async fn generate_from_fake_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path());
+
+    let current = FakeSource(schema(vec![]));
+    let desired = FakeSource(schema(vec![users()]));
+
+    let migration = generator
+        .generate_from(&current, &desired, "create users")
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(migration.up_statements[0].starts_with("db.create_table(\"users\""));
+    assert_eq!(migration.down_statements, ["db.drop_table(\"users\")?;"]);
+
+    // Nothing to do once both sides match
+    assert!(generator
+        .generate_from(&desired, &desired, "noop")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn description_slug_from_spaces_slashes_and_unicode() {
+    assert_eq!(migration_slug("Add user email").unwrap(), "add_user_email");
+    assert_eq!(
+        migration_slug("users/posts: add FK!").unwrap(),
+        "users_posts_add_fk"
+    );
+    assert_eq!(
+        migration_slug("ajouter café à l'index").unwrap(),
+        "ajouter_caf_l_index"
+    );
+    assert_eq!(
+        migration_slug("  --leading and trailing--  ").unwrap(),
+        "leading_and_trailing"
+    );
+}
+
+#[test]
+fn empty_description_after_sanitizing_is_an_error() {
+    let err = migration_slug("日本語 / ?").unwrap_err();
+    assert!(err.to_string().contains("no letters or digits"));
+}
+
+#[test]
+fn generated_file_name_and_struct_are_sanitized() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path());
+
+    let migration = generator
+        .generate(&add_fk(), "users/posts: add FK")
+        .unwrap();
+    assert!(migration.version.ends_with("_users_posts_add_fk"));
+    assert!(!migration.filename.contains('/'));
+
+    generator.write_migration_file(&migration).unwrap();
+    let code = std::fs::read_to_string(dir.path().join(&migration.filename)).unwrap();
+    assert!(code.contains(&format!("pub struct Migration_{};", migration.version)));
+}
+
+#[test]
+fn sql_down_reverses_with_explicit_sql() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path()).sql_down(SqlFlavor::PostgreSQL);
+
+    let diff = SchemaDiff {
+        changes: vec![
+            SchemaChange::CreateTable(users()),
+            SchemaChange::DropTable(TableSnapshot {
+                name: "legacy_users".to_string(),
+                ..users()
+            }),
+        ],
+    };
+    let migration = generator.generate(&diff, "replace users").unwrap();
+
+    assert_eq!(
+        migration.down_statements,
+        [
+            "db.execute_sql(\"CREATE TABLE legacy_users (\\n  id text NOT NULL,\\n  PRIMARY KEY (id)\\n);\")?;",
+            "db.execute_sql(\"DROP TABLE users;\")?;",
+        ]
+    );
+
+    generator.write_migration_file(&migration).unwrap();
+    let code = std::fs::read_to_string(dir.path().join(&migration.filename)).unwrap();
+    let down = code.split("fn down").nth(1).unwrap();
+    assert!(down.contains("db.execute_sql(\"DROP TABLE users;\")?;"));
+    assert!(!down.contains("db.drop_table("));
+}
+
+#[test]
+fn sql_down_drops_constraints_per_flavor() {
+    let down = |flavor| {
+        MigrationGenerator::new("migrations")
+            .sql_down(flavor)
+            .generate(&add_fk(), "add fk")
+            .map(|migration| migration.down_statements)
+    };
+
+    assert_eq!(
+        down(SqlFlavor::PostgreSQL).unwrap(),
+        ["db.execute_sql(\"ALTER TABLE posts DROP CONSTRAINT fk_posts_user_id;\")?;"]
+    );
+    assert_eq!(
+        down(SqlFlavor::MySQL).unwrap(),
+        ["db.execute_sql(\"ALTER TABLE posts DROP FOREIGN KEY fk_posts_user_id;\")?;"]
+    );
+    // SQLite can't drop a constraint without rebuilding the table
+    assert!(down(SqlFlavor::Sqlite).is_err());
+
+    // Structural down drops it through the context
+    let migration = MigrationGenerator::new("migrations")
+        .generate(&add_fk(), "add fk")
+        .unwrap();
+    assert!(
+        migration.down_statements[0].starts_with("db.drop_constraint(\"posts\", ConstraintDef {")
+    );
+}
+
+#[test]
+fn sql_down_restores_modified_columns_and_dropped_indexes() {
+    let mut old = users();
+    old.columns.push(ColumnSnapshot {
+        name: "score".to_string(),
+        ty: "text".to_string(),
+        nullable: true,
+        ..old.columns[0].clone()
+    });
+    old.indices.push(IndexSnapshot {
+        name: "index_users_by_score".to_string(),
+        columns: vec!["score".to_string()],
+        unique: false,
+        primary_key: false,
+        include: vec![],
+        predicate: None,
+    });
+    let mut new = old.clone();
+    new.columns[1].ty = "integer".to_string();
+    new.indices.clear();
+
+    let diff =
+        toasty_migrate::detect_changes(&schema(vec![old.clone()]), &schema(vec![new])).unwrap();
+    let migration = MigrationGenerator::new("migrations")
+        .sql_down(SqlFlavor::PostgreSQL)
+        .generate(&diff, "score as integer")
+        .unwrap();
+
+    let down = migration.down_statements.join("\n");
+    assert!(
+        down.contains("ALTER TABLE users ALTER COLUMN score TYPE text USING score::text;"),
+        "{down}"
+    );
+    assert!(
+        down.contains("CREATE INDEX index_users_by_score ON users (score);"),
+        "{down}"
+    );
+    assert!(!down.contains("//"), "{down}");
+
+    // A primary key change has no SQL reversal to generate
+    let mut rekeyed = old.clone();
+    rekeyed.primary_key = vec!["score".to_string()];
+    let diff = toasty_migrate::detect_changes(&schema(vec![old]), &schema(vec![rekeyed])).unwrap();
+    let err = MigrationGenerator::new("migrations")
+        .sql_down(SqlFlavor::PostgreSQL)
+        .generate(&diff, "rekey")
+        .unwrap_err();
+    assert!(err.to_string().contains("primary key of users"), "{err}");
+}
+
+#[test]
+fn custom_template_renders_migration() {
+    let migrations = tempfile::tempdir().unwrap();
+    let templates = tempfile::tempdir().unwrap();
+    std::fs::write(
+        templates
+            .path()
+            .join(toasty_migrate::MIGRATION_TEMPLATE_FILE),
+        r#"// Generated migration {{version}} -- do not edit by hand
+use anyhow::Result;
+use toasty_migrate::prelude::*;
+
+pub struct {{struct_name}};
+
+impl Migration for {{struct_name}} {
+    fn version(&self) -> &str { "{{version}}" }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+      {{up}}
+      Ok(())
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {
+      {{down}}
+      Ok(())
+    }
+    {{followup}}
+}
+"#,
+    )
+    .unwrap();
+
+    let generator = MigrationGenerator::new(migrations.path())
+        .safe_constraints(true)
+        .template_dir(templates.path());
+    let migration = generator.generate(&add_fk(), "add fk").unwrap();
+    generator.write_migration_file(&migration).unwrap();
+
+    let code = std::fs::read_to_string(migrations.path().join(&migration.filename)).unwrap();
+    let version = &migration.version;
+    assert!(
+        code.starts_with(&format!("// Generated migration {version} -- do not edit")),
+        "{code}"
+    );
+    assert!(code.contains("use toasty_migrate::prelude::*;"), "{code}");
+    assert!(
+        code.contains(&format!("impl Migration for Migration_{version} {{")),
+        "{code}"
+    );
+    assert!(
+        code.contains(&format!("fn version(&self) -> &str {{ \"{version}\" }}")),
+        "{code}"
+    );
+    assert!(code.contains("      db.add_constraint(\"posts\""), "{code}");
+    assert!(
+        code.contains("      db.drop_constraint(\"posts\""),
+        "{code}"
+    );
+    assert!(
+        code.contains("\n    fn followup(&self, db: &mut dyn MigrationContext)"),
+        "{code}"
+    );
+    assert!(!code.contains("{{"), "{code}");
+}
+
+#[test]
+fn template_without_up_placeholder_is_rejected() {
+    let migrations = tempfile::tempdir().unwrap();
+    let templates = tempfile::tempdir().unwrap();
+    std::fs::write(
+        templates
+            .path()
+            .join(toasty_migrate::MIGRATION_TEMPLATE_FILE),
+        "pub struct {{struct_name}}; // {{down}} {{followup}}\n",
+    )
+    .unwrap();
+
+    let generator = MigrationGenerator::new(migrations.path()).template_dir(templates.path());
+    let migration = generator.generate(&add_fk(), "add fk").unwrap();
+    let err = generator
+        .write_migration_file(&migration)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("missing the {{up}} placeholder"), "{err}");
+    assert!(!migrations.path().join(&migration.filename).exists());
+}
+
+#[test]
+fn nullable_to_not_null_with_default_uses_make_not_null() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path());
+
+    let old = ColumnSnapshot {
+        nullable: true,
+        default: Some("'member'".to_string()),
+        ..users().columns[0].clone()
+    };
+    let new = ColumnSnapshot {
+        nullable: false,
+        ..old.clone()
+    };
+    let diff = SchemaDiff {
+        changes: vec![SchemaChange::ModifyColumn {
+            table: "users".to_string(),
+            old: old.clone(),
+            new,
+        }],
+    };
+
+    let migration = generator.generate(&diff, "require id").unwrap();
+    assert_eq!(
+        migration.up_statements,
+        ["db.make_not_null(\"users\", \"id\", \"'member'\")?;"]
+    );
+
+    // Without a default there is nothing to backfill with
+    let diff = SchemaDiff {
+        changes: vec![SchemaChange::ModifyColumn {
+            table: "users".to_string(),
+            old: ColumnSnapshot {
+                default: None,
+                ..old.clone()
+            },
+            new: ColumnSnapshot {
+                default: None,
+                nullable: false,
+                ..old
+            },
+        }],
+    };
+    let migration = generator.generate(&diff, "require id").unwrap();
+    assert!(migration.up_statements[1].starts_with("// TODO"));
+}
+
+#[test]
+fn type_nullability_and_default_change_together() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path());
+
+    let old = ColumnSnapshot {
+        name: "score".to_string(),
+        ty: "text".to_string(),
+        nullable: false,
+        default: Some("'0'".to_string()),
+        ..users().columns[0].clone()
+    };
+    let new = ColumnSnapshot {
+        ty: "integer".to_string(),
+        nullable: true,
+        default: None,
+        ..old.clone()
+    };
+    let diff = SchemaDiff {
+        changes: vec![SchemaChange::ModifyColumn {
+            table: "users".to_string(),
+            old,
+            new,
+        }],
+    };
+
+    // The old default goes before the conversion, which it might not survive
+    let migration = generator.generate(&diff, "loosen score").unwrap();
+    assert_eq!(
+        migration.up_statements,
+        [
+            "db.set_column_default(\"users\", \"score\", None)?;",
+            "db.drop_not_null(\"users\", \"score\")?;",
+            "db.modify_column(\"users\", \"score\", \"text\", \"integer\")?;",
+        ]
+    );
+    // Going back, NULLs are filled with the restored default
+    assert_eq!(
+        migration.down_statements,
+        [
+            "db.modify_column(\"users\", \"score\", \"integer\", \"text\")?;",
+            "db.set_column_default(\"users\", \"score\", Some(\"'0'\"))?;",
+            "db.make_not_null(\"users\", \"score\", \"'0'\")?;",
+        ]
+    );
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    generator.apply(&diff, &mut ctx).unwrap();
+    assert_eq!(
+        ctx.statements(),
+        [
+            "ALTER TABLE users ALTER COLUMN score DROP DEFAULT;",
+            "ALTER TABLE users ALTER COLUMN score DROP NOT NULL;",
+            "ALTER TABLE users ALTER COLUMN score TYPE integer USING score::integer;",
+        ]
+    );
+}
+
+#[test]
+fn create_table_emits_columns_and_indexes() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path());
+
+    let column = |name: &str, ty: &str, nullable: bool| ColumnSnapshot {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        nullable,
+        ..users().columns[0].clone()
+    };
+    let posts = TableSnapshot {
+        name: "posts".to_string(),
+        columns: vec![
+            // A nullable key column is still emitted as required
+            column("id", "text", true),
+            column("user_id", "text", false),
+            column("title", "text", true),
+        ],
+        indices: vec![IndexSnapshot {
+            name: "index_posts_by_user_id".to_string(),
+            columns: vec!["user_id".to_string()],
+            unique: false,
+            primary_key: false,
+            include: vec![],
+            predicate: None,
+        }],
+        ..users()
+    };
+    let diff = SchemaDiff {
+        changes: vec![
+            SchemaChange::CreateTable(users()),
+            SchemaChange::CreateTable(posts),
+        ],
+    };
+
+    let up = generator
+        .generate(&diff, "initial schema")
+        .unwrap()
+        .up_statements
+        .join("\n");
+    for expected in [
+        "db.create_table(\"users\", vec![",
+        "ColumnDef { name: \"id\".into(), ty: \"text\".into(), nullable: false, default: None, primary_key: true, ..Default::default() }",
+        "db.create_table(\"posts\", vec![",
+        "ColumnDef { name: \"user_id\".into(), ty: \"text\".into(), nullable: false, default: Some(\"''\".into()), ..Default::default() }",
+        "ColumnDef { name: \"title\".into(), ty: \"text\".into(), nullable: true, default: None, ..Default::default() }",
+        "db.create_index(\"posts\", IndexDef { name: \"index_posts_by_user_id\".into(), columns: vec![\"user_id\".into()], unique: false, ..Default::default() })?;",
+    ] {
+        assert!(up.contains(expected), "missing {expected} in:\n{up}");
+    }
+    let key = "name: \"id\".into(), ty: \"text\".into(), nullable: false, default: None, primary_key: true";
+    assert_eq!(up.matches(key).count(), 2, "{up}");
+}
+
+#[test]
+fn create_index_lists_its_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path());
+
+    let diff = SchemaDiff {
+        changes: vec![SchemaChange::CreateIndex {
+            table: "users".to_string(),
+            index: IndexSnapshot {
+                name: "idx_users_email".to_string(),
+                columns: vec!["email".to_string()],
+                unique: true,
+                primary_key: false,
+                include: vec![],
+                predicate: None,
+            },
+        }],
+    };
+
+    let migration = generator.generate(&diff, "add user email").unwrap();
+    assert_eq!(
+        migration.up_statements,
+        ["db.create_index(\"users\", IndexDef { name: \"idx_users_email\".into(), columns: vec![\"email\".into()], unique: true, ..Default::default() })?;"]
+    );
+}
+
+#[test]
+fn partitioned_table_is_created_with_its_partition_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut events = users();
+    events.name = "events".to_string();
+    events.partition_by = Some(PartitionBy {
+        strategy: PartitionStrategy::Range,
+        columns: vec!["id".to_string()],
+    });
+    let diff = SchemaDiff {
+        changes: vec![SchemaChange::CreateTable(events)],
+    };
+
+    let migration = MigrationGenerator::new(dir.path())
+        .generate(&diff, "events")
+        .unwrap();
+    let up = migration.up_statements.join("\n");
+    assert!(
+        up.starts_with("db.create_partitioned_table(\"events\", vec!["),
+        "{up}"
+    );
+    assert!(
+        up.contains("], PartitionBy { strategy: PartitionStrategy::Range, columns: vec![\"id\".into()] })?;"),
+        "{up}"
+    );
+    assert!(
+        up.contains("// db.create_partition(\"events\", \"events_2025_01\""),
+        "{up}"
+    );
+
+    let mut db = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    MigrationGenerator::new(dir.path())
+        .apply(&diff, &mut db)
+        .unwrap();
+    assert!(
+        db.statements()[0].ends_with(") PARTITION BY RANGE (id);"),
+        "{:?}",
+        db.statements()
+    );
+}
+
+#[test]
+fn dropped_column_is_restored_in_down() {
+    let mut table = users();
+    let bio = ColumnSnapshot {
+        name: "bio".to_string(),
+        nullable: true,
+        ..table.columns[0].clone()
+    };
+    table.columns.push(bio);
+    let old = schema(vec![table]);
+    let mut new = old.clone();
+    let bio = new.tables[0].columns.pop().unwrap();
+
+    let diff = toasty_migrate::detect_changes(&old, &new).unwrap();
+    match &diff.changes[..] {
+        [SchemaChange::DropColumn { table, column }] => {
+            assert_eq!(table, "users");
+            assert_eq!(column.name, bio.name);
+            assert_eq!(column.ty, bio.ty);
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    let migration = MigrationGenerator::new("migrations")
+        .generate(&diff, "drop column")
+        .unwrap();
+    assert_eq!(
+        migration.up_statements,
+        [format!("db.drop_column(\"users\", \"{}\")?;", bio.name)]
+    );
+    assert_eq!(
+        migration.down_statements,
+        [format!(
+            "db.add_column(\"users\", ColumnDef {{ name: \"{}\".into(), ty: \"{}\".into(), nullable: {}, default: None, ..Default::default() }})?;",
+            bio.name, bio.ty, bio.nullable
+        )]
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn dropped_column_round_trips_on_sqlite() {
+    use toasty_migrate::SqlIntrospector;
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id integer NOT NULL PRIMARY KEY, bio text NOT NULL DEFAULT 'none');
+         INSERT INTO users (id) VALUES (1);",
+    )
+    .unwrap();
+    let old = SqlIntrospector::introspect_sqlite_connection(&conn).unwrap();
+    let mut new = old.clone();
+    new.tables[0].columns.retain(|column| column.name != "bio");
+    let diff = toasty_migrate::detect_changes(&old, &new).unwrap();
+
+    // The SQLite context leaves dropping the column to a table rebuild, but
+    // SQLite 3.35+ can drop it directly
+    conn.execute_batch("ALTER TABLE users DROP COLUMN bio;")
+        .unwrap();
+
+    let migration = MigrationGenerator::new("migrations")
+        .sql_down(SqlFlavor::Sqlite)
+        .generate(&diff, "drop bio")
+        .unwrap();
+    for statement in &migration.down_statements {
+        let sql = statement
+            .strip_prefix("db.execute_sql(\"")
+            .and_then(|sql| sql.strip_suffix("\")?;"))
+            .unwrap();
+        conn.execute_batch(sql).unwrap();
+    }
+
+    // The column is back as it was; its data isn't
+    let restored = SqlIntrospector::introspect_sqlite_connection(&conn).unwrap();
+    let diff = toasty_migrate::detect_changes(&restored, &old).unwrap();
+    assert!(diff.changes.is_empty(), "{:?}", diff.changes);
+}
+
+#[test]
+fn dropped_table_is_restored_in_down() {
+    let mut table = users();
+    table.columns.push(ColumnSnapshot {
+        name: "status".to_string(),
+        default: Some("'active'".to_string()),
+        ..table.columns[0].clone()
+    });
+    table.indices.push(IndexSnapshot {
+        name: "index_users_by_status".to_string(),
+        columns: vec!["status".to_string()],
+        unique: false,
+        primary_key: false,
+        include: vec![],
+        predicate: None,
+    });
+    let old = schema(vec![table.clone()]);
+    let new = schema(vec![]);
+
+    let diff = toasty_migrate::detect_changes(&old, &new).unwrap();
+    match &diff.changes[..] {
+        [SchemaChange::DropTable(dropped)] => {
+            assert_eq!(dropped.name, "users");
+            assert_eq!(dropped.columns.len(), 2);
+            assert_eq!(dropped.columns[1].default.as_deref(), Some("'active'"));
+            assert_eq!(dropped.indices.len(), 1);
+        }
+        changes => panic!("unexpected changes: {:?}", changes),
+    }
+
+    let migration = MigrationGenerator::new("migrations")
+        .generate(&diff, "drop users")
+        .unwrap();
+    assert_eq!(migration.up_statements, ["db.drop_table(\"users\")?;"]);
+
+    // The recorded defaults come back, not the placeholder a new table gets
+    let down = migration.down_statements.join("\n");
+    assert!(
+        down.starts_with("db.create_table(\"users\", vec!["),
+        "{down}"
+    );
+    assert!(down.contains("name: \"id\".into(), ty: \"text\".into(), nullable: false, default: None, primary_key: true"), "{down}");
+    assert!(down.contains("name: \"status\".into(), ty: \"text\".into(), nullable: false, default: Some(\"'active'\".into())"), "{down}");
+    assert!(
+        down.contains(
+            "db.create_index(\"users\", IndexDef { name: \"index_users_by_status\".into()"
+        ),
+        "{down}"
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn dropped_table_round_trips_on_sqlite() {
+    use toasty_migrate::SqlIntrospector;
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id integer NOT NULL PRIMARY KEY, email text NOT NULL, bio text);
+         CREATE UNIQUE INDEX index_users_by_email ON users (email);
+         CREATE INDEX index_users_by_bio_email ON users (bio, email);",
+    )
+    .unwrap();
+    let old = SqlIntrospector::introspect_sqlite_connection(&conn).unwrap();
+    let diff = toasty_migrate::detect_changes(&old, &schema(vec![])).unwrap();
+
+    let migration = MigrationGenerator::new("migrations")
+        .sql_down(SqlFlavor::Sqlite)
+        .generate(&diff, "drop users")
+        .unwrap();
+    conn.execute_batch("DROP TABLE users;").unwrap();
+    for statement in &migration.down_statements {
+        let sql = statement
+            .strip_prefix("db.execute_sql(\"")
+            .and_then(|sql| sql.strip_suffix("\")?;"))
+            .unwrap()
+            .replace("\\n", "\n");
+        conn.execute_batch(&sql).unwrap();
+    }
+
+    // Every column and index is back
+    let restored = SqlIntrospector::introspect_sqlite_connection(&conn).unwrap();
+    let diff = toasty_migrate::detect_changes(&restored, &old).unwrap();
+    assert!(diff.changes.is_empty(), "{:?}", diff.changes);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn modified_column_type_round_trips_on_sqlite() {
+    use toasty_migrate::SqlIntrospector;
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id integer NOT NULL PRIMARY KEY, score text NOT NULL);
+         CREATE INDEX index_users_by_score ON users (score);
+         INSERT INTO users (id, score) VALUES (1, '10'), (2, '7');",
+    )
+    .unwrap();
+    let old = SqlIntrospector::introspect_sqlite_connection(&conn).unwrap();
+    let mut new = old.clone();
+    new.tables[0].columns[1].ty = "integer".to_string();
+    let diff = toasty_migrate::detect_changes(&old, &new).unwrap();
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite).schema(old.clone());
+    MigrationGenerator::new("migrations")
+        .apply(&diff, &mut ctx)
+        .unwrap();
+    for sql in ctx.statements() {
+        conn.execute_batch(sql).unwrap();
+    }
+
+    // The values were converted, so they now sort as numbers
+    let scores: Vec<i64> = conn
+        .prepare("SELECT score FROM users ORDER BY score")
+        .unwrap()
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(scores, [7, 10]);
+
+    let migrated = SqlIntrospector::introspect_sqlite_connection(&conn).unwrap();
+    let diff = toasty_migrate::detect_changes(&migrated, &new).unwrap();
+    assert!(diff.changes.is_empty(), "{:?}", diff.changes);
+}
@@ -0,0 +1,228 @@
+#![cfg(feature = "sqlite")]
+
+use toasty_migrate::{open_sqlite, sqlite_url_parts, SchemaSnapshot, SqlIntrospector};
+
+#[tokio::test]
+async fn sqlite_generated_columns() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let conn = rusqlite::Connection::open(file.path()).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE people (
+            first_name text NOT NULL,
+            last_name text NOT NULL,
+            full_name text GENERATED ALWAYS AS (first_name || ' ' || last_name) STORED,
+            initials text GENERATED ALWAYS AS (substr(first_name, 1, 1)) VIRTUAL
+        );",
+    )
+    .unwrap();
+
+    let introspector = SqlIntrospector::new(format!("sqlite:{}", file.path().display()));
+    let schema = introspector.introspect_schema().await.unwrap();
+    let columns = &schema.tables[0].columns;
+
+    assert_eq!(columns.len(), 4);
+    assert_eq!(columns[0].generated, None);
+
+    assert_eq!(columns[2].name, "full_name");
+    assert_eq!(
+        columns[2].generated.as_deref(),
+        Some("first_name || ' ' || last_name")
+    );
+    assert!(columns[2].stored);
+
+    assert_eq!(columns[3].name, "initials");
+    assert_eq!(
+        columns[3].generated.as_deref(),
+        Some("substr(first_name, 1, 1)")
+    );
+    assert!(!columns[3].stored);
+}
+
+#[tokio::test]
+async fn sqlite_generated_columns_ignore_look_alikes() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let conn = rusqlite::Connection::open(file.path()).unwrap();
+    conn.execute_batch(
+        r#"CREATE TABLE people (
+            username text DEFAULT 'AS (nope), COLLATE nocase',
+            "name" text COLLATE rtrim,
+            label text generated always as(upper(username) || ')' || "name") virtual,
+            size integer AS (length(label || ')'))
+        );"#,
+    )
+    .unwrap();
+
+    let introspector = SqlIntrospector::new(format!("sqlite:{}", file.path().display()));
+    let schema = introspector.introspect_schema().await.unwrap();
+    let columns = &schema.tables[0].columns;
+
+    assert_eq!(columns[0].name, "username");
+    assert_eq!(columns[0].generated, None);
+    assert_eq!(columns[0].collation, None);
+
+    assert_eq!(columns[1].collation.as_deref(), Some("rtrim"));
+
+    assert_eq!(
+        columns[2].generated.as_deref(),
+        Some(r#"upper(username) || ')' || "name""#)
+    );
+    assert_eq!(
+        columns[3].generated.as_deref(),
+        Some("length(label || ')')")
+    );
+}
+
+#[tokio::test]
+async fn sqlite_types_are_normalized() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let conn = rusqlite::Connection::open(file.path()).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, code CHARACTER VARYING(8));",
+    )
+    .unwrap();
+
+    let introspector = SqlIntrospector::new(format!("sqlite:{}", file.path().display()));
+    let schema = introspector.introspect_schema().await.unwrap();
+    let types: Vec<_> = schema.tables[0]
+        .columns
+        .iter()
+        .map(|c| c.ty.as_str())
+        .collect();
+
+    assert_eq!(types, ["integer", "text", "varchar(8)"]);
+}
+
+#[test]
+fn sqlite_url_pragmas_apply_on_connect() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let url = format!(
+        "sqlite:{}?journal_mode=WAL&synchronous=NORMAL",
+        file.path().display()
+    );
+
+    let conn = open_sqlite(&url).unwrap();
+    let mode: String = conn
+        .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(mode, "wal");
+
+    // synchronous = NORMAL is reported as 1
+    let synchronous: i64 = conn
+        .query_row("PRAGMA synchronous", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(synchronous, 1);
+}
+
+#[test]
+fn sqlite_url_rejects_malformed_pragmas() {
+    assert!(sqlite_url_parts("sqlite:app.db?journal_mode").is_err());
+    assert!(sqlite_url_parts("sqlite:app.db?journal_mode=WAL;DROP").is_err());
+
+    let (path, pragmas) = sqlite_url_parts("sqlite:app.db").unwrap();
+    assert_eq!(path, "app.db");
+    assert!(pragmas.is_empty());
+}
+
+#[tokio::test]
+async fn sqlite_parallel_matches_sequential() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let conn = rusqlite::Connection::open(file.path()).unwrap();
+    for i in 0..8 {
+        conn.execute_batch(&format!(
+            "CREATE TABLE table_{i} (id integer PRIMARY KEY, name text NOT NULL, score integer DEFAULT {i});
+             CREATE UNIQUE INDEX index_table_{i}_by_name ON table_{i} (name);"
+        ))
+        .unwrap();
+    }
+
+    let url = format!("sqlite:{}", file.path().display());
+    let sequential = SqlIntrospector::new(url.clone())
+        .introspect_schema()
+        .await
+        .unwrap();
+    let parallel = SqlIntrospector::new(url)
+        .parallel(4)
+        .introspect_schema()
+        .await
+        .unwrap();
+
+    assert_eq!(parallel.tables.len(), 8);
+    assert_eq!(parallel.tables[0].name, "table_0");
+    assert_eq!(
+        serde_json::to_value(&parallel.tables).unwrap(),
+        serde_json::to_value(&sequential.tables).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn per_table_snapshots_merge_into_full_introspection() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let conn = rusqlite::Connection::open(file.path()).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id integer PRIMARY KEY, email text NOT NULL);
+         CREATE UNIQUE INDEX index_users_by_email ON users (email);
+         CREATE TABLE posts (id integer PRIMARY KEY, user_id integer NOT NULL, title text);",
+    )
+    .unwrap();
+
+    let introspector = SqlIntrospector::new(format!("sqlite:{}", file.path().display()));
+    let full = introspector.introspect_schema().await.unwrap();
+
+    let mut merged = SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: full.timestamp.clone(),
+        tables: vec![],
+    };
+    for name in ["users", "posts"] {
+        merged.merge(SchemaSnapshot {
+            tables: vec![introspector.introspect_table(name).await.unwrap()],
+            ..merged.clone()
+        });
+    }
+
+    assert_eq!(
+        serde_json::to_value(&merged).unwrap(),
+        serde_json::to_value(&full).unwrap()
+    );
+
+    // Re-reading a table replaces its entry
+    conn.execute_batch("ALTER TABLE posts ADD COLUMN body text")
+        .unwrap();
+    merged.merge(SchemaSnapshot {
+        tables: vec![introspector.introspect_table("posts").await.unwrap()],
+        ..merged.clone()
+    });
+    assert_eq!(merged.tables.len(), 2);
+    assert_eq!(merged.tables[0].columns.len(), 4);
+
+    assert!(introspector.introspect_table("missing").await.is_err());
+}
+
+#[tokio::test]
+async fn sqlite_partial_index_predicate_round_trips() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let conn = rusqlite::Connection::open(file.path()).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id text PRIMARY KEY, email text NOT NULL, deleted_at timestamptz);
+         CREATE UNIQUE INDEX index_users_by_email ON users (email) WHERE deleted_at IS NULL;
+         CREATE INDEX index_users_by_deleted_at ON users (deleted_at);",
+    )
+    .unwrap();
+
+    let introspector = SqlIntrospector::new(format!("sqlite:{}", file.path().display()));
+    let schema = introspector.introspect_schema().await.unwrap();
+    let predicate = |name: &str| {
+        let index = schema.tables[0]
+            .indices
+            .iter()
+            .find(|i| i.name == name)
+            .unwrap();
+        index.predicate.clone()
+    };
+
+    assert_eq!(
+        predicate("index_users_by_email").as_deref(),
+        Some("deleted_at IS NULL")
+    );
+    assert_eq!(predicate("index_users_by_deleted_at"), None);
+}
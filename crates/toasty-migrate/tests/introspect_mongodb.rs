@@ -0,0 +1,96 @@
+#![cfg(feature = "mongodb_introspect")]
+
+use mongodb::bson::doc;
+use mongodb::options::IndexOptions;
+use mongodb::IndexModel;
+use toasty_migrate::MongoDbIntrospector;
+
+fn url() -> String {
+    std::env::var("TOASTY_TEST_MONGODB_URL")
+        .unwrap_or_else(|_| "mongodb://localhost:27017/toasty_test".to_string())
+}
+
+#[tokio::test]
+async fn collections_indexes_and_sampled_fields() {
+    let client = mongodb::Client::with_uri_str(url())
+        .await
+        .unwrap_or_else(|e| panic!("MongoDB connection failed: {e}"));
+    let db = client.default_database().unwrap();
+
+    let users = db.collection::<mongodb::bson::Document>("introspect_users");
+    users.drop().await.unwrap();
+    users
+        .insert_many([
+            doc! { "email": "alice@example.com", "age": 30 },
+            doc! { "email": "bob@example.com", "nickname": "bob", "age": "unknown" },
+        ])
+        .await
+        .unwrap();
+    users
+        .create_index(
+            IndexModel::builder()
+                .keys(doc! { "email": 1 })
+                .options(
+                    IndexOptions::builder()
+                        .name("index_users_by_email".to_string())
+                        .unique(true)
+                        .build(),
+                )
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    let schema = MongoDbIntrospector::new(url())
+        .introspect_schema()
+        .await
+        .unwrap();
+    let table = schema
+        .tables
+        .iter()
+        .find(|t| t.name == "introspect_users")
+        .expect("collection should be introspected");
+
+    assert_eq!(table.primary_key, ["_id"]);
+
+    let id_index = table.indices.iter().find(|i| i.name == "_id_").unwrap();
+    assert!(id_index.primary_key && id_index.unique);
+    let email_index = table
+        .indices
+        .iter()
+        .find(|i| i.name == "index_users_by_email")
+        .unwrap();
+    assert_eq!(email_index.columns, ["email"]);
+    assert!(email_index.unique && !email_index.primary_key);
+
+    // Fields of every sampled document, in the order first seen
+    let columns: Vec<_> = table
+        .columns
+        .iter()
+        .map(|c| (c.name.as_str(), c.ty.as_str(), c.nullable))
+        .collect();
+    assert_eq!(
+        columns,
+        [
+            ("_id", "objectId", true),
+            ("email", "string", true),
+            ("age", "mixed", true),
+            ("nickname", "string", true),
+        ]
+    );
+
+    // A sample of one document only sees its fields
+    let schema = MongoDbIntrospector::new(url())
+        .sample_size(1)
+        .introspect_schema()
+        .await
+        .unwrap();
+    let table = schema
+        .tables
+        .iter()
+        .find(|t| t.name == "introspect_users")
+        .unwrap();
+    assert!(!table.columns.iter().any(|c| c.name == "nickname"));
+
+    users.drop().await.unwrap();
+}
@@ -0,0 +1,210 @@
+#![cfg(feature = "postgresql")]
+
+use toasty_migrate::SqlIntrospector;
+
+fn url() -> String {
+    std::env::var("TOASTY_TEST_POSTGRES_URL")
+        .unwrap_or_else(|_| "postgresql://localhost:5432/toasty_test".to_string())
+}
+
+#[tokio::test]
+async fn serial_and_identity_columns_are_auto() {
+    let (client, connection) = tokio_postgres::connect(&url(), tokio_postgres::NoTls)
+        .await
+        .unwrap_or_else(|e| panic!("PostgreSQL connection failed: {e}"));
+    tokio::spawn(connection);
+
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS introspect_serial_counters;
+             CREATE TABLE introspect_serial_counters (
+                 id SERIAL PRIMARY KEY,
+                 ticket integer GENERATED ALWAYS AS IDENTITY,
+                 hits integer NOT NULL DEFAULT 0
+             );",
+        )
+        .await
+        .unwrap();
+
+    let schema = SqlIntrospector::new(url())
+        .introspect_schema()
+        .await
+        .unwrap();
+
+    client
+        .batch_execute("DROP TABLE introspect_serial_counters;")
+        .await
+        .unwrap();
+
+    let table = schema
+        .tables
+        .iter()
+        .find(|table| table.name == "introspect_serial_counters")
+        .unwrap();
+
+    let id = &table.columns[0];
+    assert_eq!(id.name, "id");
+    assert!(id.auto);
+    // The sequence default is implied by `auto`
+    assert_eq!(id.default, None);
+
+    let ticket = &table.columns[1];
+    assert_eq!(ticket.name, "ticket");
+    assert!(ticket.auto);
+
+    let hits = &table.columns[2];
+    assert!(!hits.auto);
+    assert_eq!(hits.default.as_deref(), Some("0"));
+}
+
+#[tokio::test]
+async fn streamed_snapshot_matches_buffered_catalog() {
+    const TABLES: usize = 20;
+    const COLUMNS: usize = 400;
+
+    let (client, connection) = tokio_postgres::connect(&url(), tokio_postgres::NoTls)
+        .await
+        .unwrap_or_else(|e| panic!("PostgreSQL connection failed: {e}"));
+    tokio::spawn(connection);
+
+    let mut setup = String::new();
+    for t in 0..TABLES {
+        let columns: Vec<String> = (0..COLUMNS)
+            .map(|c| match c % 3 {
+                0 => format!("c{c} integer NOT NULL DEFAULT {c}"),
+                1 => format!("c{c} varchar(40)"),
+                _ => format!("c{c} timestamp with time zone"),
+            })
+            .collect();
+        setup.push_str(&format!(
+            "DROP TABLE IF EXISTS introspect_wide_{t};
+             CREATE TABLE introspect_wide_{t} (id bigint PRIMARY KEY, {});",
+            columns.join(", ")
+        ));
+        for c in (0..COLUMNS).step_by(50) {
+            setup.push_str(&format!(
+                "CREATE INDEX introspect_wide_{t}_c{c} ON introspect_wide_{t} (c{c});"
+            ));
+        }
+    }
+    client.batch_execute(&setup).await.unwrap();
+
+    let introspector = SqlIntrospector::new(url());
+    let schema = introspector.introspect_schema().await.unwrap();
+    let parallel = introspector.parallel(4).introspect_schema().await.unwrap();
+
+    // The same catalog, read in one buffered query per table
+    for t in 0..TABLES {
+        let name = format!("introspect_wide_{t}");
+        let table = schema
+            .tables
+            .iter()
+            .find(|table| table.name == name)
+            .unwrap();
+
+        let rows = client
+            .query(
+                "SELECT column_name, data_type, is_nullable
+                 FROM information_schema.columns
+                 WHERE table_name = $1 AND table_schema = 'public'
+                 ORDER BY ordinal_position",
+                &[&name],
+            )
+            .await
+            .unwrap();
+        let buffered: Vec<(String, String, bool)> = rows
+            .iter()
+            .map(|row| {
+                let ty: String = row.get(1);
+                let nullable: String = row.get(2);
+                (
+                    row.get(0),
+                    toasty_migrate::normalize_type(&ty, toasty_migrate::SqlFlavor::PostgreSQL),
+                    nullable == "YES",
+                )
+            })
+            .collect();
+        let streamed: Vec<(String, String, bool)> = table
+            .columns
+            .iter()
+            .map(|column| (column.name.clone(), column.ty.clone(), column.nullable))
+            .collect();
+        assert_eq!(streamed, buffered);
+
+        let rows = client
+            .query(
+                "SELECT indexname FROM pg_indexes
+                 WHERE tablename = $1 AND schemaname = 'public'
+                 ORDER BY indexname",
+                &[&name],
+            )
+            .await
+            .unwrap();
+        let buffered: Vec<String> = rows.iter().map(|row| row.get(0)).collect();
+        let mut streamed: Vec<String> = table.indices.iter().map(|idx| idx.name.clone()).collect();
+        streamed.sort();
+        assert_eq!(streamed, buffered);
+        assert_eq!(table.primary_key, ["id"]);
+    }
+
+    // Parallel workers stream into the same snapshot
+    assert_eq!(
+        serde_json::to_value(&schema.tables).unwrap(),
+        serde_json::to_value(&parallel.tables).unwrap(),
+    );
+
+    let drop: String = (0..TABLES)
+        .map(|t| format!("DROP TABLE introspect_wide_{t};"))
+        .collect();
+    client.batch_execute(&drop).await.unwrap();
+}
+
+#[tokio::test]
+async fn partial_index_predicate_drops_wrapping_parens() {
+    let (client, connection) = tokio_postgres::connect(&url(), tokio_postgres::NoTls)
+        .await
+        .unwrap_or_else(|e| panic!("PostgreSQL connection failed: {e}"));
+    tokio::spawn(connection);
+
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS introspect_soft_users;
+             CREATE TABLE introspect_soft_users (
+                 id text PRIMARY KEY,
+                 email text NOT NULL,
+                 deleted_at timestamptz
+             );
+             CREATE UNIQUE INDEX introspect_soft_users_email
+                 ON introspect_soft_users (email) WHERE deleted_at IS NULL;",
+        )
+        .await
+        .unwrap();
+
+    let schema = SqlIntrospector::new(url())
+        .introspect_schema()
+        .await
+        .unwrap();
+
+    client
+        .batch_execute("DROP TABLE introspect_soft_users;")
+        .await
+        .unwrap();
+
+    let table = schema
+        .tables
+        .iter()
+        .find(|table| table.name == "introspect_soft_users")
+        .unwrap();
+    let email = table
+        .indices
+        .iter()
+        .find(|index| index.name == "introspect_soft_users_email")
+        .unwrap();
+
+    assert_eq!(email.predicate.as_deref(), Some("deleted_at IS NULL"));
+    assert!(table
+        .indices
+        .iter()
+        .filter(|index| index.primary_key)
+        .all(|index| index.predicate.is_none()));
+}
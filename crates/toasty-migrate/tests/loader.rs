@@ -0,0 +1,107 @@
+use toasty_migrate::{
+    MigrationLoader, MigrationRegistry, MigrationScript, SqlFlavor, SqlMigrationContext,
+};
+
+const MIGRATION: &str = r#"use toasty_migrate::prelude::*;
+
+pub struct Migration_20250101_000000_create_users;
+
+impl Migration for Migration_20250101_000000_create_users {
+    fn version(&self) -> &str {
+        "20250101_000000_create_users"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        db.drop_table("users")
+    }
+}
+"#;
+
+#[test]
+fn content_hash_ignores_whitespace_differences() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a");
+    let b = dir.path().join("b");
+    std::fs::create_dir_all(&a).unwrap();
+    std::fs::create_dir_all(&b).unwrap();
+
+    let reformatted = MIGRATION.replace("    ", "\t").replace('\n', "\r\n") + "\n\n";
+    std::fs::write(a.join("20250101_000000_create_users.rs"), MIGRATION).unwrap();
+    std::fs::write(b.join("20250101_000000_create_users.rs"), reformatted).unwrap();
+
+    let hash = |dir| {
+        let files = MigrationLoader::new(dir).discover_migrations().unwrap();
+        files[0].content_hash().unwrap()
+    };
+    assert_eq!(hash(&a), hash(&b));
+    assert_eq!(hash(&a).len(), 16);
+
+    // Anything beyond whitespace changes it
+    std::fs::write(
+        b.join("20250101_000000_create_users.rs"),
+        MIGRATION.replace("\"users\"", "\"accounts\""),
+    )
+    .unwrap();
+    assert_ne!(hash(&a), hash(&b));
+}
+
+#[test]
+fn example_migrations_run_in_version_order() {
+    let dir = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../examples/migration-test/migration"
+    );
+    let registry = MigrationLoader::new(dir).collect_migrations().unwrap();
+    assert_eq!(
+        registry.versions(),
+        [
+            "20251122_074459_baseline",
+            "20251122_074505_add_bio",
+            "20251122_074804_test"
+        ]
+    );
+
+    let mut db = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    for migration in registry.iter() {
+        migration.up(&mut db).unwrap();
+    }
+    let statements = db.statements();
+    assert_eq!(statements.len(), 4);
+    assert!(
+        statements[0].starts_with("CREATE TABLE users ("),
+        "{statements:?}"
+    );
+    assert_eq!(
+        statements[1],
+        "CREATE UNIQUE INDEX index_users_by_email ON users (email);"
+    );
+    assert_eq!(statements[2], "ALTER TABLE users ADD COLUMN bio text;");
+    assert_eq!(statements[3], "ALTER TABLE users ADD COLUMN age text;");
+}
+
+#[test]
+fn registry_orders_by_version_and_rejects_duplicates() {
+    let script = |version: &str| {
+        let source = MIGRATION.replace("20250101_000000_create_users", version);
+        Box::new(MigrationScript::parse(&source).unwrap())
+    };
+
+    let mut registry = MigrationRegistry::new();
+    registry.register(script("20250103_000000_c")).unwrap();
+    registry.register(script("20250101_000000_a")).unwrap();
+    registry.register(script("20250102_000000_b")).unwrap();
+    assert_eq!(
+        registry.versions(),
+        [
+            "20250101_000000_a",
+            "20250102_000000_b",
+            "20250103_000000_c"
+        ]
+    );
+    assert!(registry.get("20250102_000000_b").is_some());
+    assert!(registry.get("20250104_000000_d").is_none());
+
+    let err = registry.register(script("20250102_000000_b")).unwrap_err();
+    assert!(err.to_string().contains("registered twice"), "{err}");
+    assert_eq!(registry.len(), 3);
+}
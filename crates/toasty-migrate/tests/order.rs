@@ -0,0 +1,39 @@
+use toasty_migrate::dependency_order;
+
+fn names(tables: &[&str]) -> Vec<String> {
+    tables.iter().map(|table| table.to_string()).collect()
+}
+
+fn refs(edges: &[(&str, &str)]) -> Vec<(String, String)> {
+    edges
+        .iter()
+        .map(|(table, parent)| (table.to_string(), parent.to_string()))
+        .collect()
+}
+
+#[test]
+fn parents_come_before_children() {
+    let tables = names(&["accounts", "comments", "posts", "users"]);
+    let references = refs(&[
+        ("comments", "posts"),
+        ("comments", "users"),
+        ("posts", "users"),
+        ("users", "accounts"),
+    ]);
+
+    let order = dependency_order(&tables, &references);
+    assert_eq!(order, ["accounts", "users", "posts", "comments"]);
+
+    // Dropping walks the same order backwards
+    let drops: Vec<_> = order.iter().rev().map(String::as_str).collect();
+    assert_eq!(drops, ["comments", "posts", "users", "accounts"]);
+}
+
+#[test]
+fn self_references_and_cycles_still_include_every_table() {
+    let tables = names(&["a", "b", "c", "tree"]);
+    let references = refs(&[("tree", "tree"), ("a", "b"), ("b", "a"), ("c", "missing")]);
+
+    let order = dependency_order(&tables, &references);
+    assert_eq!(order, ["c", "tree", "a", "b"]);
+}
@@ -0,0 +1,723 @@
+use toasty_migrate::{
+    detect_changes,
+    snapshot::{ForeignKeySnapshot, SchemaSnapshot, TableSnapshot},
+    EntityParser, MigrationGenerator, PartitionStrategy, SqlFlavor, SqlMigrationContext,
+};
+
+const TODO_MODEL: &str = r#"
+use toasty::stmt::Id;
+
+#[derive(Debug, toasty::Model)]
+pub struct Todo {
+    #[key]
+    #[auto]
+    pub id: Id<Self>,
+
+    pub owner_id: Id<User>,
+
+    #[belongs_to(key = owner_id, references = id)]
+    pub owner: toasty::BelongsTo<User>,
+
+    pub user_id: Id<User>,
+
+    #[belongs_to]
+    pub user: toasty::BelongsTo<User>,
+
+    #[index]
+    pub list_id: Id<List>,
+
+    #[belongs_to]
+    pub list: toasty::BelongsTo<List>,
+
+    pub title: String,
+}
+"#;
+
+fn parse(parser: impl FnOnce(std::path::PathBuf) -> EntityParser, model: &str) -> SchemaSnapshot {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/todo.rs"), model).unwrap();
+
+    parser(dir.path().to_path_buf()).parse_entities().unwrap()
+}
+
+fn index_columns(table: &TableSnapshot) -> Vec<Vec<String>> {
+    table
+        .indices
+        .iter()
+        .filter(|index| !index.primary_key)
+        .map(|index| index.columns.clone())
+        .collect()
+}
+
+#[test]
+fn belongs_to_key_is_indexed() {
+    let schema = parse(EntityParser::new, TODO_MODEL);
+    let todos = &schema.tables[0];
+
+    let index = todos
+        .indices
+        .iter()
+        .find(|index| index.columns == ["owner_id"])
+        .expect("owner_id should be indexed");
+    assert_eq!(index.name, "index_todos_by_owner_id");
+    assert!(!index.unique);
+
+    // Bare `#[belongs_to]` uses `<field>_id`; existing indexes are not duplicated
+    assert_eq!(
+        index_columns(todos),
+        [vec!["list_id"], vec!["owner_id"], vec!["user_id"]]
+    );
+}
+
+#[test]
+fn auto_fk_index_can_be_disabled() {
+    let schema = parse(
+        |dir| EntityParser::new(dir).auto_fk_index(false),
+        TODO_MODEL,
+    );
+
+    assert_eq!(index_columns(&schema.tables[0]), [vec!["list_id"]]);
+}
+
+#[test]
+fn struct_doc_comment_becomes_table_comment() {
+    let model = r#"
+/// A thing to do.
+///
+/// Owned by a single user.
+#[derive(Debug, toasty::Model)]
+pub struct Todo {
+    #[key]
+    pub id: String,
+}
+"#;
+
+    let schema = parse(EntityParser::new, model);
+
+    assert_eq!(
+        schema.tables[0].comment.as_deref(),
+        Some("A thing to do.\n\nOwned by a single user.")
+    );
+    assert_eq!(parse(EntityParser::new, TODO_MODEL).tables[0].comment, None);
+}
+
+#[test]
+fn generated_attribute() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Person {
+    #[key]
+    pub id: String,
+
+    pub first_name: String,
+
+    pub last_name: String,
+
+    #[generated("first_name || ' ' || last_name", stored)]
+    pub full_name: String,
+
+    #[generated("length(first_name)")]
+    pub first_name_len: i64,
+}
+"#;
+
+    let schema = parse(EntityParser::new, model);
+    let columns = &schema.tables[0].columns;
+
+    assert_eq!(columns[3].name, "full_name");
+    assert_eq!(
+        columns[3].generated.as_deref(),
+        Some("first_name || ' ' || last_name")
+    );
+    assert!(columns[3].stored);
+
+    assert_eq!(columns[4].generated.as_deref(), Some("length(first_name)"));
+    assert!(!columns[4].stored);
+
+    assert_eq!(columns[1].generated, None);
+}
+
+#[test]
+fn collation_attribute() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Tag {
+    #[key]
+    pub id: String,
+
+    #[collation = "C"]
+    pub name: String,
+}
+"#;
+
+    let schema = parse(EntityParser::new, model);
+    let columns = &schema.tables[0].columns;
+
+    assert_eq!(columns[0].collation, None);
+    assert_eq!(columns[1].collation.as_deref(), Some("C"));
+}
+
+#[test]
+fn optional_belongs_to_key_is_nullable() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Todo {
+    #[key]
+    #[auto]
+    pub id: Id<Self>,
+
+    pub assignee_id: Option<Id<User>>,
+
+    #[belongs_to]
+    pub assignee: Option<toasty::BelongsTo<User>>,
+
+    pub reviewer_id: Id<User>,
+
+    #[belongs_to(key = reviewer_id, references = id)]
+    pub reviewer: Option<BelongsTo<User>>,
+
+    pub title: String,
+}
+"#;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/todo.rs"), model).unwrap();
+
+    let (schema, warnings) = EntityParser::new(dir.path())
+        .parse_entities_with_warnings()
+        .unwrap();
+    let table = &schema.tables[0];
+    let column = |name: &str| table.columns.iter().find(|c| c.name == name).unwrap();
+
+    // Only an `Option` key is nullable, whatever the relation says
+    assert!(column("assignee_id").nullable);
+    assert!(!column("reviewer_id").nullable);
+    assert!(!column("title").nullable);
+    let warnings: Vec<_> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        warnings,
+        ["todos.reviewer: optional relation with a non-Option key `reviewer_id`; the column is NOT NULL"]
+    );
+    assert_eq!(
+        index_columns(table),
+        [
+            vec!["assignee_id".to_string()],
+            vec!["reviewer_id".to_string()]
+        ]
+    );
+
+    // Nullable keys get no placeholder default, so unset rows hold NULL
+    // rather than a value that would fail the foreign key
+    let empty = SchemaSnapshot {
+        tables: vec![],
+        ..schema.clone()
+    };
+    let diff = detect_changes(&empty, &schema).unwrap();
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    MigrationGenerator::new("migrations")
+        .apply(&diff, &mut ctx)
+        .unwrap();
+    let create_table = &ctx.statements()[0];
+    assert!(
+        create_table.contains("  assignee_id text,\n"),
+        "{create_table}"
+    );
+}
+
+#[test]
+fn natural_key_becomes_primary_key() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Country {
+    #[key]
+    pub code: String,
+
+    pub name: String,
+}
+"#;
+
+    let schema = parse(EntityParser::new, model);
+    let table = &schema.tables[0];
+    assert_eq!(table.primary_key, ["code"]);
+
+    let empty = SchemaSnapshot {
+        tables: vec![],
+        ..schema.clone()
+    };
+    let diff = detect_changes(&empty, &schema).unwrap();
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    MigrationGenerator::new("migrations")
+        .apply(&diff, &mut ctx)
+        .unwrap();
+
+    // The key column keeps its type and gets no placeholder default
+    assert_eq!(
+        ctx.statements()[0],
+        "CREATE TABLE countries (\n  code text NOT NULL,\n  name text NOT NULL DEFAULT '',\n  PRIMARY KEY (code)\n);"
+    );
+}
+
+#[test]
+fn key_field_gets_only_the_primary_key() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Account {
+    #[key]
+    #[unique]
+    pub handle: String,
+
+    #[unique]
+    pub email: String,
+}
+"#;
+
+    let schema = parse(EntityParser::new, model);
+    let table = &schema.tables[0];
+    assert_eq!(table.primary_key, ["handle"]);
+
+    // No index for the key itself, exactly one for the unique field
+    assert_eq!(table.indices.len(), 1);
+    assert_eq!(table.indices[0].name, "index_accounts_by_email");
+    assert_eq!(table.indices[0].columns, ["email"]);
+    assert!(table.indices[0].unique);
+    assert!(!table.indices[0].primary_key);
+}
+
+#[test]
+fn doc_comments_and_attributes_interleave_with_fields() {
+    let model = r#"
+use toasty::stmt::Id;
+
+/// A thing to do.
+#[derive(
+    Debug,
+    toasty::Model,
+)]
+pub struct Todo {
+    /// The todo's id
+    #[key]
+    /// Assigned by the database
+    #[auto]
+    pub id: Id<Self>,
+
+    #[belongs_to(
+        key = owner_id,
+        references = id,
+    )]
+    /// Who the todo belongs to
+    pub owner: toasty::BelongsTo<User>,
+
+    #[cfg(feature = "owners")]
+    #[index]
+    pub owner_id:
+        Id<User>,
+
+    #[unique]
+    /// Shown in lists
+    #[collation = "C"]
+    pub title: Option<String>,
+}
+
+/// Not a model
+pub struct Draft {
+    pub title: String,
+}
+"#;
+
+    let schema = parse(EntityParser::new, model);
+    assert_eq!(schema.tables.len(), 1);
+
+    let todos = &schema.tables[0];
+    assert_eq!(todos.name, "todos");
+    assert_eq!(todos.comment.as_deref(), Some("A thing to do."));
+    assert_eq!(todos.primary_key, ["id"]);
+
+    let columns: Vec<_> = todos.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(columns, ["id", "owner_id", "title"]);
+    assert!(todos.columns[0].auto);
+    assert!(todos.columns[2].nullable);
+    assert_eq!(todos.columns[2].collation.as_deref(), Some("C"));
+
+    // `owner_id` is indexed once, by its own `#[index]`
+    assert_eq!(index_columns(todos), [vec!["owner_id"], vec!["title"]]);
+    assert!(todos.indices[1].unique);
+}
+
+#[test]
+fn unmapped_types_and_bare_relations_warn() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Todo {
+    #[key]
+    pub id: Id<Self>,
+
+    pub weight: f64,
+
+    pub user: BelongsTo<User>,
+}
+"#;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/todo.rs"), model).unwrap();
+
+    let (schema, warnings) = EntityParser::new(dir.path())
+        .parse_entities_with_warnings()
+        .unwrap();
+
+    // The column is still there, as text
+    assert_eq!(schema.tables[0].columns[1].ty, "text");
+    let warnings: Vec<_> = warnings.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        warnings,
+        [
+            "todos.weight: no SQL type for `f64`; using text",
+            "todos.user: relation type without #[belongs_to], #[has_many] or #[has_one]; skipped",
+        ]
+    );
+}
+
+#[test]
+fn belongs_to_records_foreign_key() {
+    let user = r#"
+#[derive(Debug, toasty::Model)]
+#[table_name = "accounts"]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+}
+"#;
+    let post = r#"
+#[derive(Debug, toasty::Model)]
+pub struct Post {
+    #[key]
+    pub id: Id<Self>,
+
+    pub user_id: Id<User>,
+
+    #[belongs_to(key = user_id, references = id)]
+    pub user: toasty::BelongsTo<User>,
+
+    pub editor_id: Option<Id<User>>,
+
+    #[belongs_to]
+    pub editor: Option<toasty::BelongsTo<User>>,
+}
+"#;
+
+    // The referenced model lives in another file and names its own table
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/user.rs"), user).unwrap();
+    std::fs::write(dir.path().join("src/post.rs"), post).unwrap();
+    let schema = EntityParser::new(dir.path()).parse_entities().unwrap();
+
+    let posts = schema.tables.iter().find(|t| t.name == "posts").unwrap();
+    assert_eq!(
+        posts.foreign_keys,
+        [
+            ForeignKeySnapshot {
+                column: "user_id".into(),
+                references_table: "accounts".into(),
+                references_column: "id".into(),
+            },
+            ForeignKeySnapshot {
+                column: "editor_id".into(),
+                references_table: "accounts".into(),
+                references_column: "id".into(),
+            },
+        ]
+    );
+
+    let accounts = schema.tables.iter().find(|t| t.name == "accounts").unwrap();
+    assert!(accounts.foreign_keys.is_empty());
+}
+
+#[test]
+fn table_name_attribute_overrides_derived_name() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+#[table_name = "user_roles"]
+pub struct UserRole {
+    #[key]
+    pub id: Id<Self>,
+}
+
+#[derive(Debug, toasty::Model)]
+pub struct Dummy {
+    #[key]
+    pub id: Id<Self>,
+
+    #[index]
+    pub name: String,
+}
+"#;
+
+    let schema = parse(EntityParser::new, model);
+
+    assert_eq!(schema.tables[0].name, "user_roles");
+    assert_eq!(schema.tables[1].name, "dummies");
+    assert_eq!(schema.tables[1].indices[0].name, "index_dummies_by_name");
+}
+
+#[test]
+fn derived_table_names_are_pluralized() {
+    let cases = [
+        ("User", "users"),
+        ("UserRole", "user_roles"),
+        ("Category", "categories"),
+        ("Country", "countries"),
+        ("Day", "days"),
+        ("Address", "addresses"),
+        ("Box", "boxes"),
+        ("Buzz", "buzzes"),
+        ("Match", "matches"),
+        ("Wish", "wishes"),
+        ("Todo", "todos"),
+    ];
+
+    let model: String = cases
+        .iter()
+        .map(|(name, _)| {
+            format!("#[derive(toasty::Model)]\npub struct {name} {{\n    #[key]\n    pub id: Id<Self>,\n}}\n")
+        })
+        .collect();
+    let schema = parse(EntityParser::new, &model);
+
+    let names: Vec<_> = schema.tables.iter().map(|t| t.name.as_str()).collect();
+    let expected: Vec<_> = cases.iter().map(|(_, table)| *table).collect();
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn colliding_index_names_name_both_fields() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    #[unique]
+    pub email: String,
+
+    #[index(name = "index_users_by_email")]
+    pub login: String,
+}
+"#;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/user.rs"), model).unwrap();
+
+    let err = EntityParser::new(dir.path())
+        .parse_entities()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.ends_with(
+            "index index_users_by_email on users comes from both #[unique] on `email` and #[index] on `login`"
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn index_attribute_can_name_the_index() {
+    let model = r#"
+#[derive(Debug, toasty::Model)]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    #[unique(name = "users_email_key")]
+    pub email: String,
+}
+"#;
+
+    let schema = parse(EntityParser::new, model);
+    assert_eq!(schema.tables[0].indices[0].name, "users_email_key");
+    assert!(schema.tables[0].indices[0].unique);
+}
+
+#[test]
+fn table_options_are_captured() {
+    let schema = parse(
+        EntityParser::new,
+        r#"
+#[derive(Debug, toasty::Model)]
+#[table_options(engine = "InnoDB", charset = "utf8mb4")]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+}
+"#,
+    );
+
+    let options = &schema.tables[0].options;
+    assert_eq!(options.engine.as_deref(), Some("InnoDB"));
+    assert_eq!(options.charset.as_deref(), Some("utf8mb4"));
+}
+
+#[test]
+fn partition_attribute_is_captured() {
+    let schema = parse(
+        EntityParser::new,
+        r#"
+#[derive(Debug, toasty::Model)]
+#[partition(by = "range", key = "created_at")]
+pub struct Event {
+    #[key]
+    pub id: Id<Self>,
+
+    pub created_at: String,
+}
+"#,
+    );
+
+    let partition_by = schema.tables[0].partition_by.as_ref().unwrap();
+    assert_eq!(partition_by.strategy, PartitionStrategy::Range);
+    assert_eq!(partition_by.columns, ["created_at"]);
+}
+
+#[test]
+fn doc_comment_change_only_updates_the_column_comment() {
+    let model = |doc: &str| {
+        format!(
+            r#"
+#[derive(Debug, toasty::Model)]
+pub struct Country {{
+    #[key]
+    pub code: String,
+
+    /// {doc}
+    pub name: String,
+}}
+"#
+        )
+    };
+
+    let old = parse(EntityParser::new, &model("Common name"));
+    let new = parse(EntityParser::new, &model("Name in English, e.g. Germany"));
+    assert_eq!(
+        old.tables[0].columns[1].comment.as_deref(),
+        Some("Common name")
+    );
+
+    let diff = detect_changes(&old, &new).unwrap();
+    assert_eq!(diff.changes.len(), 1, "{:?}", diff.changes);
+    assert!(!diff.changes[0].is_destructive());
+
+    let migration = MigrationGenerator::new("migrations")
+        .generate_version(&diff, "20250101_000000_document_names")
+        .unwrap();
+    assert_eq!(
+        migration.up_statements,
+        ["db.comment_on_column(\"countries\", \"name\", Some(\"Name in English, e.g. Germany\"))?;"]
+    );
+    assert_eq!(
+        migration.down_statements,
+        ["db.comment_on_column(\"countries\", \"name\", Some(\"Common name\"))?;"]
+    );
+
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    MigrationGenerator::new("migrations")
+        .apply(&diff, &mut ctx)
+        .unwrap();
+    assert_eq!(
+        ctx.statements(),
+        ["COMMENT ON COLUMN countries.name IS 'Name in English, e.g. Germany';"]
+    );
+}
+
+const SOFT_DELETE_MODEL: &str = r#"
+#[derive(Debug, toasty::Model)]
+#[soft_delete]
+pub struct User {
+    #[key]
+    pub id: Id<Self>,
+
+    #[unique]
+    pub email: String,
+
+    #[index]
+    pub name: String,
+}
+"#;
+
+#[test]
+fn soft_delete_adds_deleted_at_and_scopes_unique_indexes() {
+    let schema = parse(EntityParser::new, SOFT_DELETE_MODEL);
+    let users = &schema.tables[0];
+
+    let deleted_at = users
+        .columns
+        .iter()
+        .find(|c| c.name == "deleted_at")
+        .unwrap();
+    assert_eq!(deleted_at.ty, "timestamptz");
+    assert!(deleted_at.nullable);
+
+    let email = users
+        .indices
+        .iter()
+        .find(|i| i.columns == ["email"])
+        .unwrap();
+    assert_eq!(email.predicate.as_deref(), Some("deleted_at IS NULL"));
+
+    // Deleted rows still count for non-unique lookups
+    let name = users
+        .indices
+        .iter()
+        .find(|i| i.columns == ["name"])
+        .unwrap();
+    assert_eq!(name.predicate, None);
+
+    let empty = SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![],
+    };
+    let mut db = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    let dir = tempfile::tempdir().unwrap();
+    MigrationGenerator::new(dir.path())
+        .apply(&detect_changes(&empty, &schema).unwrap(), &mut db)
+        .unwrap();
+
+    assert!(db
+        .statements()
+        .iter()
+        .any(|sql| sql.contains("deleted_at timestamptz")));
+    assert!(
+        db.statements().contains(
+            &"CREATE UNIQUE INDEX index_users_by_email ON users (email) WHERE deleted_at IS NULL;"
+                .to_string()
+        ),
+        "{:?}",
+        db.statements()
+    );
+
+    // Recorded in the snapshot, so diffing it against itself is stable
+    let saved: SchemaSnapshot =
+        serde_json::from_str(&serde_json::to_string(&schema).unwrap()).unwrap();
+    assert!(detect_changes(&saved, &schema).unwrap().changes.is_empty());
+}
+
+#[test]
+fn soft_delete_column_type_follows_the_flavor() {
+    let deleted_at_type = |flavor| {
+        let schema = parse(
+            |dir| EntityParser::new(dir).flavor(flavor),
+            SOFT_DELETE_MODEL,
+        );
+        let users = &schema.tables[0];
+        let deleted_at = users.columns.iter().find(|c| c.name == "deleted_at");
+        deleted_at.unwrap().ty.clone()
+    };
+
+    assert_eq!(deleted_at_type(Some(SqlFlavor::PostgreSQL)), "timestamptz");
+    assert_eq!(deleted_at_type(Some(SqlFlavor::MySQL)), "datetime(6)");
+    assert_eq!(deleted_at_type(Some(SqlFlavor::Sqlite)), "timestamp");
+    assert_eq!(deleted_at_type(None), "timestamptz");
+}
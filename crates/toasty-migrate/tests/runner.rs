@@ -0,0 +1,719 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use toasty_migrate::{
+    Migration, MigrationContext, MigrationRunner, MigrationTracker, SqlFlavor, SqlMigrationContext,
+};
+
+struct TestMigration {
+    version: &'static str,
+    requires: Vec<&'static str>,
+}
+
+impl Migration for TestMigration {
+    fn version(&self) -> &str {
+        self.version
+    }
+
+    fn requires(&self) -> Vec<&str> {
+        self.requires.clone()
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.execute_sql(self.version)
+    }
+
+    fn down(&self, _db: &mut dyn MigrationContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn migration(version: &'static str, requires: &[&'static str]) -> Box<dyn Migration> {
+    Box::new(TestMigration {
+        version,
+        requires: requires.to_vec(),
+    })
+}
+
+async fn run(
+    tracker: MigrationTracker,
+    migrations: Vec<Box<dyn Migration>>,
+) -> Result<Vec<String>> {
+    let mut runner = MigrationRunner::new(tracker);
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    let mut followup = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    runner
+        .run_pending(migrations, &mut ctx, &mut followup)
+        .await?;
+    Ok(ctx.statements().to_vec())
+}
+
+#[tokio::test]
+async fn requires_reorders_apply_sequence() {
+    // `20250101_b` sorts first, but was merged from a branch that depends on
+    // `20250102_a`.
+    let applied = run(
+        MigrationTracker::new(),
+        vec![
+            migration("20250101_b", &["20250102_a"]),
+            migration("20250102_a", &[]),
+            migration("20250103_c", &[]),
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(applied, ["20250102_a", "20250101_b", "20250103_c"]);
+}
+
+#[tokio::test]
+async fn requires_falls_back_to_version_order() {
+    let applied = run(
+        MigrationTracker::new(),
+        vec![
+            migration("20250103_c", &[]),
+            migration("20250101_a", &[]),
+            migration("20250102_b", &["20250101_a"]),
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(applied, ["20250101_a", "20250102_b", "20250103_c"]);
+}
+
+#[tokio::test]
+async fn requires_already_applied_migration() {
+    let mut tracker = MigrationTracker::new();
+    tracker.mark_applied("20250101_a".to_string());
+
+    let applied = run(tracker, vec![migration("20250102_b", &["20250101_a"])])
+        .await
+        .unwrap();
+
+    assert_eq!(applied, ["20250102_b"]);
+}
+
+#[tokio::test]
+async fn requires_missing_migration_is_error() {
+    let err = run(
+        MigrationTracker::new(),
+        vec![migration("20250102_b", &["20250101_a"])],
+    )
+    .await
+    .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .contains("requires missing migration 20250101_a"));
+}
+
+#[tokio::test]
+async fn requires_cycle_is_error() {
+    let err = run(
+        MigrationTracker::new(),
+        vec![
+            migration("20250101_a", &["20250102_b"]),
+            migration("20250102_b", &["20250101_a"]),
+            migration("20250103_c", &[]),
+        ],
+    )
+    .await
+    .unwrap_err();
+
+    let message = err.to_string();
+    assert!(message.contains("cycle"), "{message}");
+    assert!(message.contains("20250101_a, 20250102_b"), "{message}");
+}
+
+#[tokio::test]
+async fn run_report_lists_applied_and_skipped() {
+    let mut tracker = MigrationTracker::new();
+    tracker.mark_applied("20250101_a".to_string());
+
+    let mut runner = MigrationRunner::new(tracker);
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    let report = runner
+        .run_pending(
+            vec![
+                migration("20250101_a", &[]),
+                migration("20250102_b", &[]),
+                migration("20250103_c", &["20250102_b"]),
+            ],
+            &mut ctx,
+            &mut SqlMigrationContext::new(SqlFlavor::Sqlite),
+        )
+        .await
+        .unwrap();
+
+    let applied: Vec<&str> = report.applied.iter().map(|m| m.version.as_str()).collect();
+    assert_eq!(applied, ["20250102_b", "20250103_c"]);
+    assert_eq!(report.skipped, ["20250101_a"]);
+    assert_eq!(report.applied_count(), 2);
+}
+
+/// Adds an index in `up()` and validates it in `followup()`
+struct ValidatesAfterCommit;
+
+impl Migration for ValidatesAfterCommit {
+    fn version(&self) -> &str {
+        "20250101_validate"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.execute_sql("CREATE INDEX idx_users_email ON users (email)")
+    }
+
+    fn followup(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.execute_sql("ANALYZE users")
+    }
+
+    fn down(&self, _db: &mut dyn MigrationContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn buffered_run_keeps_followups_apart() {
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    let mut followup = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    runner
+        .run_pending(
+            vec![Box::new(ValidatesAfterCommit)],
+            &mut ctx,
+            &mut followup,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        ctx.statements(),
+        ["CREATE INDEX idx_users_email ON users (email)"]
+    );
+    assert_eq!(followup.statements(), ["ANALYZE users"]);
+}
+
+#[tokio::test]
+async fn until_applies_migrations_up_to_cutoff() {
+    let mut runner = MigrationRunner::new(MigrationTracker::new()).until("20250102_120000");
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    let report = runner
+        .run_pending(
+            vec![
+                migration("20250101_090000_a", &[]),
+                migration("20250102_120000_b", &[]),
+                migration("20250102_120001_c", &[]),
+                migration("20250103_090000_d", &[]),
+            ],
+            &mut ctx,
+            &mut SqlMigrationContext::new(SqlFlavor::Sqlite),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(ctx.statements(), ["20250101_090000_a", "20250102_120000_b"]);
+    assert_eq!(report.deferred, ["20250102_120001_c", "20250103_090000_d"]);
+    assert!(!runner.tracker().is_applied("20250102_120001_c"));
+    assert!(!runner.tracker().is_applied("20250103_090000_d"));
+}
+
+#[tokio::test]
+async fn hooks_run_around_each_migration() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (before, after) = (events.clone(), events.clone());
+
+    let mut runner = MigrationRunner::new(MigrationTracker::new())
+        .on_before(move |version| before.lock().unwrap().push(format!("before {version}")))
+        .on_after(move |version| after.lock().unwrap().push(format!("after {version}")));
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    let migrations = || vec![migration("20250101_a", &[]), migration("20250102_b", &[])];
+    let mut followup = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    runner
+        .run_pending(migrations(), &mut ctx, &mut followup)
+        .await
+        .unwrap();
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "before 20250101_a",
+            "after 20250101_a",
+            "before 20250102_b",
+            "after 20250102_b",
+        ]
+    );
+
+    // Rolling back runs them too, newest migration first
+    events.lock().unwrap().clear();
+    runner.rollback(2, migrations(), &mut ctx).await.unwrap();
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "before 20250102_b",
+            "after 20250102_b",
+            "before 20250101_a",
+            "after 20250101_a",
+        ]
+    );
+}
+
+/// Creates the table `.1`
+struct CreateTable(&'static str, &'static str);
+
+impl Migration for CreateTable {
+    fn version(&self) -> &str {
+        self.0
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.execute_sql(&format!("CREATE TABLE {} (id INTEGER PRIMARY KEY)", self.1))
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.execute_sql(&format!("DROP TABLE {}", self.1))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn dump_schema_writes_a_snapshot_per_applied_migration() {
+    let project = tempfile::tempdir().unwrap();
+    let db = project.path().join("app.db");
+    rusqlite::Connection::open(&db)
+        .unwrap()
+        .execute_batch("CREATE TABLE users (id text NOT NULL PRIMARY KEY);")
+        .unwrap();
+    let url = format!("sqlite:{}", db.display());
+
+    let mut tracker = MigrationTracker::new();
+    tracker.mark_applied("20250101_a".to_string());
+
+    let history = project.path().join(toasty_migrate::SCHEMA_HISTORY_DIR);
+    let mut runner = MigrationRunner::new(tracker).dump_schema(&url, &history);
+    let mut conn = toasty_migrate::SqliteMigrationConnection::open(&url).unwrap();
+
+    runner
+        .run_pending_live(
+            vec![
+                Box::new(CreateTable("20250101_a", "users")),
+                Box::new(CreateTable("20250102_b", "posts")),
+                Box::new(CreateTable("20250103_c", "tags")),
+            ],
+            &mut conn,
+        )
+        .await
+        .unwrap();
+
+    // Only the migrations applied by this run leave a snapshot
+    let mut files: Vec<String> = std::fs::read_dir(&history)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["20250102_b.json", "20250103_c.json"]);
+
+    // Each snapshot is taken once its migration has committed
+    let tables = |version: &str| -> Vec<String> {
+        let path = history.join(format!("{version}.json"));
+        let snapshot = toasty_migrate::load_snapshot(path).unwrap();
+        snapshot
+            .tables
+            .into_iter()
+            .map(|table| table.name)
+            .collect()
+    };
+    assert!(tables("20250102_b").contains(&"posts".to_string()));
+    assert!(!tables("20250102_b").contains(&"tags".to_string()));
+    assert!(tables("20250103_c").contains(&"tags".to_string()));
+}
+
+#[tokio::test]
+async fn dump_schema_rejects_buffered_runs() {
+    let mut runner =
+        MigrationRunner::new(MigrationTracker::new()).dump_schema("sqlite::memory:", "history");
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    let err = runner
+        .run_pending(
+            vec![migration("20250101_a", &[])],
+            &mut ctx,
+            &mut SqlMigrationContext::new(SqlFlavor::Sqlite),
+        )
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("needs a live run"), "{err}");
+    assert!(ctx.statements().is_empty());
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn rerun_resumes_after_last_recorded_migration() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let url = format!("sqlite:{}", file.path().display());
+
+    // The previous run committed and recorded three of five migrations
+    // before the process died
+    let conn = rusqlite::Connection::open(file.path()).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE _toasty_migrations (
+            version TEXT PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+        INSERT INTO _toasty_migrations (version) VALUES
+            ('20250101_a'), ('20250102_b'), ('20250103_c');",
+    )
+    .unwrap();
+    drop(conn);
+
+    let tracker = MigrationTracker::load_from_database(&url).await.unwrap();
+    let mut runner = MigrationRunner::new(tracker);
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+
+    let report = runner
+        .run_pending(
+            vec![
+                migration("20250101_a", &[]),
+                migration("20250102_b", &[]),
+                migration("20250103_c", &[]),
+                migration("20250104_d", &[]),
+                migration("20250105_e", &[]),
+            ],
+            &mut ctx,
+            &mut SqlMigrationContext::new(SqlFlavor::Sqlite),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(ctx.statements(), ["20250104_d", "20250105_e"]);
+    assert_eq!(report.skipped, ["20250101_a", "20250102_b", "20250103_c"]);
+    assert!(runner.tracker().is_applied("20250105_e"));
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn tracker_persists_applied_versions_across_restarts() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let url = format!("sqlite:{}", file.path().display());
+    let migrations = || -> Vec<Box<dyn Migration>> {
+        vec![
+            Box::new(CreateTable("20250101_a", "users")),
+            Box::new(CreateTable("20250102_b", "posts")),
+        ]
+    };
+
+    let mut db = toasty_migrate::SqliteMigrationConnection::open(&url).unwrap();
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    runner
+        .run_pending_live(migrations(), &mut db)
+        .await
+        .unwrap();
+    drop((runner, db));
+
+    // A new process starts with an empty set and reads it back
+    let tracker = MigrationTracker::load_from_database(&url).await.unwrap();
+    assert_eq!(tracker.applied_migrations(), ["20250101_a", "20250102_b"]);
+
+    let mut db = toasty_migrate::SqliteMigrationConnection::open(&url).unwrap();
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    runner
+        .rollback_live(1, migrations(), &mut db)
+        .await
+        .unwrap();
+
+    let tracker = MigrationTracker::load_from_database(&url).await.unwrap();
+    assert!(tracker.is_applied("20250101_a"));
+    assert!(!tracker.is_applied("20250102_b"));
+
+    // Running again only applies the rolled back migration
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    let report = runner
+        .run_pending_live(migrations(), &mut db)
+        .await
+        .unwrap();
+    assert_eq!(report.skipped, ["20250101_a"]);
+    assert_eq!(report.applied[0].version, "20250102_b");
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn buffered_run_leaves_tracking_to_the_caller() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let url = format!("sqlite:{}", file.path().display());
+
+    let mut db = toasty_migrate::SqliteMigrationConnection::open(&url).unwrap();
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    runner.initialize(&mut db).await.unwrap();
+
+    // Nothing has executed the buffered statements, so nothing is recorded
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    runner
+        .run_pending(
+            vec![migration("20250101_a", &[])],
+            &mut ctx,
+            &mut SqlMigrationContext::new(SqlFlavor::Sqlite),
+        )
+        .await
+        .unwrap();
+    assert!(runner.tracker().is_applied("20250101_a"));
+
+    let tracker = MigrationTracker::load_from_database(&url).await.unwrap();
+    assert!(tracker.applied_migrations().is_empty());
+}
+
+/// Adds `users.email_domain` and fills it in from each row's email
+struct SplitEmailDomain;
+
+#[async_trait::async_trait]
+impl Migration for SplitEmailDomain {
+    fn version(&self) -> &str {
+        "20250106_split_email_domain"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.add_column(
+            "users",
+            toasty_migrate::ColumnDef {
+                name: "email_domain".into(),
+                ty: "text".into(),
+                nullable: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    async fn up_async(&self, db: &mut dyn toasty_migrate::AsyncMigrationContext) -> Result<()> {
+        for row in db.query("SELECT id, email FROM users", &[]).await? {
+            let email = row.get_by_name("email").unwrap().expect_string();
+            let domain = email.rsplit('@').next().unwrap().to_lowercase();
+
+            db.execute(
+                "UPDATE users SET email_domain = ?1 WHERE id = ?2",
+                &[domain.into(), row.get(0).unwrap().clone()],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.drop_column("users", "email_domain")
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn live_run_applies_data_migration() {
+    use toasty_migrate::AsyncMigrationContext;
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL);
+         INSERT INTO users (email) VALUES ('alice@Example.com'), ('bob@toasty.dev');",
+    )
+    .unwrap();
+    let mut db = toasty_migrate::SqliteMigrationConnection::new(conn);
+
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    let report = runner
+        .run_pending_live(vec![Box::new(SplitEmailDomain)], &mut db)
+        .await
+        .unwrap();
+    assert_eq!(report.applied_count(), 1);
+
+    let domains: Vec<_> = db
+        .query("SELECT email_domain FROM users ORDER BY id", &[])
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| row.get(0).unwrap().expect_string().to_string())
+        .collect();
+    assert_eq!(domains, ["example.com", "toasty.dev"]);
+
+    // A buffered run has no connection to read from, so only `up()` runs
+    let mut ctx = SqlMigrationContext::new(SqlFlavor::Sqlite);
+    MigrationRunner::new(MigrationTracker::new())
+        .run_pending(
+            vec![Box::new(SplitEmailDomain)],
+            &mut ctx,
+            &mut SqlMigrationContext::new(SqlFlavor::Sqlite),
+        )
+        .await
+        .unwrap();
+    assert_eq!(ctx.statements().len(), 1);
+}
+
+/// Writes a row, then fails
+struct FailsHalfway;
+
+#[async_trait::async_trait]
+impl Migration for FailsHalfway {
+    fn version(&self) -> &str {
+        "20250107_fails_halfway"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.execute_sql("UPDATE users SET email = 'changed'")
+    }
+
+    async fn up_async(&self, db: &mut dyn toasty_migrate::AsyncMigrationContext) -> Result<()> {
+        db.execute("DELETE FROM users WHERE id = ?1", &[1i64.into()])
+            .await?;
+        Err(anyhow::anyhow!("transform failed"))
+    }
+
+    fn down(&self, _db: &mut dyn MigrationContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn failed_data_migration_rolls_back() {
+    use toasty_migrate::AsyncMigrationContext;
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL);
+         INSERT INTO users (email) VALUES ('alice@example.com'), ('bob@toasty.dev');",
+    )
+    .unwrap();
+    let mut db = toasty_migrate::SqliteMigrationConnection::new(conn);
+
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    let err = runner
+        .run_pending_live(vec![Box::new(FailsHalfway)], &mut db)
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "transform failed");
+    assert!(!runner.tracker().is_applied("20250107_fails_halfway"));
+
+    // The writes from `up()` and `up_async()` were rolled back with it
+    let rows = db
+        .query("SELECT email FROM users ORDER BY id", &[])
+        .await
+        .unwrap();
+    let emails: Vec<_> = rows
+        .iter()
+        .map(|row| row.get(0).unwrap().expect_string())
+        .collect();
+    assert_eq!(emails, ["alice@example.com", "bob@toasty.dev"]);
+
+    // So was its tracking row
+    let applied = MigrationTracker::applied_versions(&mut db).await.unwrap();
+    assert!(applied.is_empty());
+}
+
+/// Counts the transactions committed on a SQLite connection
+#[cfg(feature = "sqlite")]
+struct CountCommits {
+    db: toasty_migrate::SqliteMigrationConnection,
+    commits: usize,
+}
+
+#[cfg(feature = "sqlite")]
+#[async_trait::async_trait]
+impl toasty_migrate::AsyncMigrationContext for CountCommits {
+    fn flavor(&self) -> SqlFlavor {
+        self.db.flavor()
+    }
+
+    async fn execute(&mut self, sql: &str, params: &[toasty_core::stmt::Value]) -> Result<u64> {
+        self.db.execute(sql, params).await
+    }
+
+    async fn query(
+        &mut self,
+        sql: &str,
+        params: &[toasty_core::stmt::Value],
+    ) -> Result<Vec<toasty_migrate::Row>> {
+        self.db.query(sql, params).await
+    }
+
+    async fn execute_batch(&mut self, sql: &str) -> Result<()> {
+        self.db.execute_batch(sql).await
+    }
+
+    async fn commit(&mut self) -> Result<()> {
+        self.commits += 1;
+        self.db.commit().await
+    }
+}
+
+/// Adds `users.status` and backfills it 100 rows at a time
+struct BackfillStatus;
+
+#[async_trait::async_trait]
+impl Migration for BackfillStatus {
+    fn version(&self) -> &str {
+        "20250108_backfill_status"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.add_column(
+            "users",
+            toasty_migrate::ColumnDef {
+                name: "status".into(),
+                ty: "text".into(),
+                nullable: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    async fn followup_async(
+        &self,
+        db: &mut dyn toasty_migrate::AsyncMigrationContext,
+    ) -> Result<()> {
+        let updated = db
+            .backfill("users", "status = 'active'", "status IS NULL", 100)
+            .await?;
+        assert_eq!(updated, 1000);
+        Ok(())
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.drop_column("users", "status")
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn backfill_commits_each_batch() {
+    use toasty_migrate::AsyncMigrationContext;
+
+    let conn = rusqlite::Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL);
+         WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 1000)
+         INSERT INTO users (email) SELECT 'user' || i || '@toasty.dev' FROM n;",
+    )
+    .unwrap();
+    let mut db = CountCommits {
+        db: toasty_migrate::SqliteMigrationConnection::new(conn),
+        commits: 0,
+    };
+
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    runner
+        .run_pending_live(vec![Box::new(BackfillStatus)], &mut db)
+        .await
+        .unwrap();
+
+    // One commit for the migration, one per batch of 100, and one for the
+    // empty batch that ends the loop
+    assert_eq!(db.commits, 1 + 10 + 1);
+
+    let rows = db
+        .query(
+            "SELECT count(*) FROM users WHERE status = ?1",
+            &["active".into()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get(0), Some(&toasty_core::stmt::Value::I64(1000)));
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "postgresql")]
+
+use anyhow::Result;
+use toasty_migrate::{
+    AsyncMigrationContext, ColumnDef, Migration, MigrationContext, MigrationRunner,
+    MigrationTracker, PostgresMigrationConnection,
+};
+
+fn url() -> String {
+    std::env::var("TOASTY_TEST_POSTGRES_URL")
+        .unwrap_or_else(|_| "postgresql://localhost:5432/toasty_test".to_string())
+}
+
+/// Adds `runner_pg_users.role` and backfills it 10 rows at a time
+struct BackfillRole;
+
+impl Migration for BackfillRole {
+    fn version(&self) -> &str {
+        "20250101_000000_backfill_role"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.add_column(
+            "runner_pg_users",
+            ColumnDef {
+                name: "role".into(),
+                ty: "text".into(),
+                nullable: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn followup(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.backfill_column("runner_pg_users", "role", "'member'", 10)
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.drop_column("runner_pg_users", "role")
+    }
+}
+
+#[tokio::test]
+async fn followup_backfill_commits_outside_the_migration_transaction() {
+    let mut db = PostgresMigrationConnection::connect(&url())
+        .await
+        .unwrap_or_else(|e| panic!("PostgreSQL connection failed: {e}"));
+
+    MigrationTracker::new().initialize(&mut db).await.unwrap();
+    db.execute_batch(
+        "DROP TABLE IF EXISTS runner_pg_users;
+         DELETE FROM _toasty_migrations WHERE version = '20250101_000000_backfill_role';
+         CREATE TABLE runner_pg_users (id SERIAL PRIMARY KEY);
+         INSERT INTO runner_pg_users SELECT FROM generate_series(1, 25);",
+    )
+    .await
+    .unwrap();
+
+    // The backfill's `DO` block commits after each batch, which PostgreSQL
+    // rejects inside a transaction
+    let mut runner = MigrationRunner::new(MigrationTracker::new());
+    let result = runner
+        .run_pending_live(vec![Box::new(BackfillRole)], &mut db)
+        .await;
+
+    let rows = db
+        .query(
+            "SELECT count(*)::int8 FROM runner_pg_users WHERE role = 'member'",
+            &[],
+        )
+        .await
+        .unwrap();
+    db.execute_batch(
+        "DROP TABLE runner_pg_users;
+         DELETE FROM _toasty_migrations WHERE version = '20250101_000000_backfill_role';",
+    )
+    .await
+    .unwrap();
+
+    result.unwrap();
+    assert_eq!(rows[0].get(0), Some(&toasty_core::stmt::Value::I64(25)));
+}
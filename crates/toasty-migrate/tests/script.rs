@@ -0,0 +1,204 @@
+use toasty_migrate::{
+    detect_changes,
+    snapshot::{ColumnSnapshot, ForeignKeySnapshot, IndexSnapshot, TableSnapshot},
+    ConstraintDef, ConstraintKind, Migration, MigrationGenerator, MigrationLoader, MigrationScript,
+    PartitionBy, PartitionStrategy, SchemaChange, SchemaSnapshot, SqlFlavor, SqlMigrationContext,
+    TableOptions,
+};
+
+fn column(name: &str, ty: &str) -> ColumnSnapshot {
+    ColumnSnapshot {
+        name: name.to_string(),
+        ty: ty.to_string(),
+        nullable: false,
+        generated: None,
+        stored: false,
+        default: None,
+        auto: false,
+        collation: None,
+        comment: None,
+    }
+}
+
+fn table(name: &str, columns: Vec<ColumnSnapshot>) -> TableSnapshot {
+    TableSnapshot {
+        schema: None,
+        name: name.to_string(),
+        columns,
+        indices: vec![],
+        primary_key: vec!["id".to_string()],
+        comment: None,
+        foreign_keys: vec![],
+        options: Default::default(),
+        partition_by: None,
+    }
+}
+
+fn schema(tables: Vec<TableSnapshot>) -> SchemaSnapshot {
+    SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables,
+    }
+}
+
+#[test]
+fn generated_migration_runs_uncompiled() {
+    let old = schema(vec![table("users", vec![column("id", "text")])]);
+
+    let mut users = table(
+        "users",
+        vec![
+            column("id", "text"),
+            ColumnSnapshot {
+                default: Some("''".to_string()),
+                collation: Some("C".to_string()),
+                ..column("email", "text")
+            },
+        ],
+    );
+    users.indices.push(IndexSnapshot {
+        name: "index_users_by_email".to_string(),
+        columns: vec!["email".to_string()],
+        unique: true,
+        primary_key: false,
+        include: vec!["id".to_string()],
+        predicate: None,
+    });
+
+    let mut orders = table(
+        "orders",
+        vec![
+            column("id", "bigint"),
+            ColumnSnapshot {
+                nullable: true,
+                ..column("user_id", "text")
+            },
+        ],
+    );
+    orders.comment = Some("Orders placed by \"users\"".to_string());
+    orders.options = TableOptions {
+        engine: Some("InnoDB".to_string()),
+        charset: None,
+    };
+    orders.partition_by = Some(PartitionBy {
+        strategy: PartitionStrategy::Range,
+        columns: vec!["id".to_string()],
+    });
+    orders.foreign_keys.push(ForeignKeySnapshot {
+        column: "user_id".to_string(),
+        references_table: "users".to_string(),
+        references_column: "id".to_string(),
+    });
+
+    let mut diff = detect_changes(&old, &schema(vec![users, orders])).unwrap();
+    diff.changes.push(SchemaChange::AddConstraint {
+        table: "orders".to_string(),
+        constraint: ConstraintDef {
+            name: "orders_total_check".to_string(),
+            kind: ConstraintKind::Check {
+                expr: "id > 0".to_string(),
+            },
+            not_valid: false,
+        },
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let generator = MigrationGenerator::new(dir.path())
+        .safe_add_column(true)
+        .safe_constraints(true);
+    let migration = generator.generate(&diff, "orders").unwrap();
+    generator.write_migration_file(&migration).unwrap();
+
+    let script = MigrationScript::load(dir.path().join(&migration.filename)).unwrap();
+    assert_eq!(script.version(), migration.version);
+
+    // Replaying the file renders exactly what the generator would run
+    let mut expected = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    generator.apply(&diff, &mut expected).unwrap();
+
+    let mut replayed = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    script.up(&mut replayed).unwrap();
+    script.followup(&mut replayed).unwrap();
+    assert_eq!(replayed.statements(), expected.statements());
+
+    let mut down = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    script.down(&mut down).unwrap();
+    assert!(down
+        .statements()
+        .iter()
+        .any(|sql| sql.starts_with("DROP TABLE orders")));
+
+    let loaded = MigrationLoader::new(dir.path()).load_migrations().unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].version(), migration.version);
+}
+
+const HAND_WRITTEN: &str = r#"
+use toasty_migrate::prelude::*;
+use anyhow::Result;
+
+pub struct Migration_20250101_000000_seed;
+
+impl Migration for Migration_20250101_000000_seed {
+    fn version(&self) -> &str {
+        "20250101_000000_seed"
+    }
+
+    fn requires(&self) -> Vec<&str> {
+        vec!["20241231_000000_create_users"]
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.execute_sql("INSERT INTO users (id) VALUES ('admin')")?;
+        db.set_column_default("users", "id", None)?;
+        db.truncate_table_with("sessions", TruncateOptions { cascade: true, ..Default::default() })
+    }
+
+    fn down(&self, _db: &mut dyn MigrationContext) -> Result<()> {
+        Ok(())
+    }
+}
+"#;
+
+#[test]
+fn hand_written_calls_and_tail_expressions_are_replayed() {
+    let script = MigrationScript::parse(HAND_WRITTEN).unwrap();
+    assert_eq!(script.requires(), ["20241231_000000_create_users"]);
+
+    let mut db = SqlMigrationContext::new(SqlFlavor::PostgreSQL);
+    script.up(&mut db).unwrap();
+    assert_eq!(
+        db.statements(),
+        [
+            "INSERT INTO users (id) VALUES ('admin')",
+            "ALTER TABLE users ALTER COLUMN id DROP DEFAULT;",
+            "TRUNCATE TABLE sessions CASCADE;",
+        ]
+    );
+}
+
+#[test]
+fn code_that_needs_compiling_is_rejected() {
+    let with_loop = HAND_WRITTEN.replace(
+        "db.set_column_default(\"users\", \"id\", None)?;",
+        "for table in [\"users\"] { db.drop_table(table)?; }",
+    );
+    let err = MigrationScript::parse(&with_loop).err().unwrap();
+    assert!(
+        err.to_string()
+            .contains("`up()` may only call `db` methods"),
+        "{err}"
+    );
+
+    let with_variable = HAND_WRITTEN.replace("None)?;", "default)?;");
+    let err = MigrationScript::parse(&with_variable).err().unwrap();
+    assert!(err.to_string().contains("`db.set_column_default`"), "{err}");
+
+    let with_async = HAND_WRITTEN.replace(
+        "    fn down(",
+        "    async fn up_async(&self, db: &mut dyn AsyncMigrationContext) -> Result<()> {\n        Ok(())\n    }\n\n    fn down(",
+    );
+    let err = MigrationScript::parse(&with_async).err().unwrap();
+    assert!(err.to_string().contains("`up_async()`"), "{err}");
+}
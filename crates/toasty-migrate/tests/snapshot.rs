@@ -0,0 +1,154 @@
+use toasty_migrate::{
+    load_snapshot, load_validated_snapshot, save_snapshot,
+    snapshot::{ColumnSnapshot, ForeignKeySnapshot, IndexSnapshot, SchemaSnapshot, TableSnapshot},
+};
+
+fn column(name: &str) -> ColumnSnapshot {
+    ColumnSnapshot {
+        name: name.to_string(),
+        ty: "text".to_string(),
+        nullable: false,
+        generated: None,
+        stored: false,
+        default: None,
+        auto: false,
+        collation: None,
+        comment: None,
+    }
+}
+
+fn users() -> SchemaSnapshot {
+    SchemaSnapshot {
+        version: "1.0".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        tables: vec![TableSnapshot {
+            schema: None,
+            name: "users".to_string(),
+            columns: vec![column("id"), column("email")],
+            indices: vec![
+                IndexSnapshot {
+                    name: "index_users_by_id".to_string(),
+                    columns: vec!["id".to_string()],
+                    unique: true,
+                    primary_key: true,
+                    include: vec![],
+                    predicate: None,
+                },
+                IndexSnapshot {
+                    name: "index_users_by_email".to_string(),
+                    columns: vec!["email".to_string()],
+                    unique: true,
+                    primary_key: false,
+                    include: vec![],
+                    predicate: None,
+                },
+            ],
+            primary_key: vec!["id".to_string()],
+            comment: Some("Registered users".to_string()),
+            foreign_keys: vec![],
+            options: Default::default(),
+            partition_by: None,
+        }],
+    }
+}
+
+#[test]
+fn valid_snapshot_round_trips() {
+    let snapshot = users();
+    snapshot.validate().unwrap();
+
+    let file = tempfile::NamedTempFile::new().unwrap();
+    save_snapshot(&snapshot, file.path()).unwrap();
+
+    let loaded = load_validated_snapshot(file.path()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&loaded).unwrap(),
+        serde_json::to_value(&snapshot).unwrap()
+    );
+}
+
+#[test]
+fn dangling_index_column_is_rejected() {
+    let mut snapshot = users();
+    snapshot.tables[0].indices[1].columns = vec!["mail".to_string()];
+
+    let err = snapshot.validate().unwrap_err().to_string();
+    assert!(err.contains("index_users_by_email"), "{err}");
+    assert!(err.contains("missing column mail"), "{err}");
+
+    // Plain loading still accepts the file; only the validating loader fails
+    let file = tempfile::NamedTempFile::new().unwrap();
+    save_snapshot(&snapshot, file.path()).unwrap();
+    load_snapshot(file.path()).unwrap();
+    let err = load_validated_snapshot(file.path())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Invalid snapshot"), "{err}");
+}
+
+#[test]
+fn missing_primary_key_column_and_duplicate_tables_are_rejected() {
+    let mut snapshot = users();
+    snapshot.tables[0].primary_key = vec!["user_id".to_string()];
+    let err = snapshot.validate().unwrap_err().to_string();
+    assert!(
+        err.contains("primary key references missing column user_id"),
+        "{err}"
+    );
+
+    let mut snapshot = users();
+    snapshot.tables.push(snapshot.tables[0].clone());
+    let err = snapshot.validate().unwrap_err().to_string();
+    assert!(err.contains("appears more than once"), "{err}");
+}
+
+#[test]
+fn duplicate_index_names_are_rejected() {
+    let mut snapshot = users();
+    let mut index = snapshot.tables[0].indices[1].clone();
+    index.columns = vec!["id".to_string()];
+    snapshot.tables[0].indices.push(index);
+
+    let err = snapshot.validate().unwrap_err().to_string();
+    assert_eq!(
+        err,
+        "Table users: index index_users_by_email is defined twice, on (email) and on (id)"
+    );
+}
+
+#[test]
+fn dangling_foreign_keys_are_rejected() {
+    let with_fk = |key: &str, references_table: &str, references_column: &str| {
+        let mut snapshot = users();
+        let mut posts = snapshot.tables[0].clone();
+        posts.name = "posts".to_string();
+        posts.columns.push(column("user_id"));
+        posts.indices.clear();
+        posts.foreign_keys.push(ForeignKeySnapshot {
+            column: key.to_string(),
+            references_table: references_table.to_string(),
+            references_column: references_column.to_string(),
+        });
+        snapshot.tables.push(posts);
+        snapshot.validate()
+    };
+
+    with_fk("user_id", "users", "id").unwrap();
+
+    let err = with_fk("owner_id", "users", "id").unwrap_err().to_string();
+    assert_eq!(err, "Table posts: foreign key on missing column owner_id");
+
+    let err = with_fk("user_id", "accounts", "id")
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        err,
+        "Table posts: foreign key user_id references missing table accounts"
+    );
+
+    let err = with_fk("user_id", "users", "uuid").unwrap_err().to_string();
+    assert_eq!(
+        err,
+        "Table posts: foreign key user_id references missing column users.uuid"
+    );
+}
@@ -61,12 +61,19 @@ struct Formatter<'a, T> {
 
     /// True when serializing VALUES in an INSERT statement context
     in_insert: bool,
+
+    /// Set when the statement can't be expressed in SQL
+    error: Option<toasty_core::Error>,
 }
 
 pub type ExprContext<'a> = toasty_core::stmt::ExprContext<'a, db::Schema>;
 
 impl<'a> Serializer<'a> {
-    pub fn serialize(&self, stmt: &Statement, params: &mut impl Params) -> String {
+    pub fn serialize(
+        &self,
+        stmt: &Statement,
+        params: &mut impl Params,
+    ) -> toasty_core::Result<String> {
         let mut ret = String::new();
 
         let mut fmt = Formatter {
@@ -76,14 +83,19 @@ impl<'a> Serializer<'a> {
             depth: 0,
             alias: false,
             in_insert: false,
+            error: None,
         };
 
         let cx = ExprContext::new(self.schema);
 
         stmt.to_sql(&cx, &mut fmt);
 
+        if let Some(err) = fmt.error {
+            return Err(err);
+        }
+
         ret.push(';');
-        ret
+        Ok(ret)
     }
 
     fn table(&self, id: impl Into<db::TableId>) -> &'a Table {
@@ -134,9 +134,18 @@ impl ToSql for &stmt::InsertTarget {
 
 impl ToSql for &stmt::Limit {
     fn to_sql<P: Params>(self, cx: &ExprContext<'_>, f: &mut super::Formatter<'_, P>) {
-        assert!(self.offset.is_none(), "TODO; {:#?}", self);
+        let offset = match &self.offset {
+            Some(stmt::Offset::Count(count)) => Some((" OFFSET ", count)),
+            Some(stmt::Offset::After(_)) => {
+                f.error.get_or_insert_with(|| {
+                    anyhow::anyhow!("keyset offsets must be lowered before serializing; {self:?}")
+                });
+                None
+            }
+            None => None,
+        };
 
-        fmt!(cx, f, "LIMIT " self.limit);
+        fmt!(cx, f, "LIMIT " self.limit offset);
     }
 }
 
@@ -1,4 +1,6 @@
 mod builder;
+#[cfg(feature = "migrations")]
+mod migrate;
 pub use builder::Builder;
 use tokio::{
     sync::{mpsc, oneshot},
@@ -7,14 +9,20 @@ use tokio::{
 
 use crate::{engine::Engine, stmt, Cursor, Model, Result, Statement};
 
-use toasty_core::{stmt::ValueStream, Schema};
+use std::sync::Arc;
+use toasty_core::{
+    stmt::{Value, ValueStream},
+    Schema,
+};
 
 #[derive(Debug)]
 pub struct Db {
     pub(crate) engine: Engine,
 
-    /// Handle to send statements to be executed
+    /// Handle to send statements to be executed, each with the engine to run
+    /// it on
     pub(crate) in_tx: mpsc::UnboundedSender<(
+        Engine,
         toasty_core::stmt::Statement,
         oneshot::Sender<Result<ValueStream>>,
     )>,
@@ -28,7 +36,59 @@ impl Db {
         Builder::default()
     }
 
+    /// Spawn the task driving `engine`
+    ///
+    /// The task only holds the engine while running a statement, so dropping
+    /// the `Db` drops the driver right away. A transaction's driver rolls it
+    /// back when dropped, which must happen before the parent's next
+    /// statement.
+    pub(crate) fn new(engine: Engine) -> Db {
+        let (in_tx, mut in_rx) = mpsc::unbounded_channel::<(
+            Engine,
+            toasty_core::stmt::Statement,
+            oneshot::Sender<Result<ValueStream>>,
+        )>();
+
+        let join_handle = tokio::spawn(async move {
+            // Ends once the `Db` is dropped
+            while let Some((engine, stmt, tx)) = in_rx.recv().await {
+                let res = engine.exec(stmt).await;
+                drop(engine);
+
+                match res {
+                    Ok(mut value_stream) => {
+                        let (row_tx, mut row_rx) =
+                            mpsc::unbounded_channel::<crate::Result<Value>>();
+
+                        let _ = tx.send(Ok(ValueStream::from_stream(async_stream::stream! {
+                            while let Some(res) = row_rx.recv().await {
+                                yield res
+                            }
+                        })));
+
+                        while let Some(res) = value_stream.next().await {
+                            let _ = row_tx.send(res);
+                        }
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Err(err));
+                    }
+                }
+            }
+        });
+
+        Db {
+            engine,
+            in_tx,
+            join_handle,
+        }
+    }
+
     /// Execute a query, returning all matching records
+    ///
+    /// Records are streamed through the returned cursor as the driver
+    /// produces them. Use `limit` / `offset` on the query to page through
+    /// large result sets.
     pub async fn all<M: Model>(&self, query: stmt::Select<M>) -> Result<Cursor<M>> {
         let records = self.exec(query.into()).await?;
         Ok(Cursor::new(self.engine.schema.clone(), records))
@@ -63,7 +123,9 @@ impl Db {
         let (tx, rx) = oneshot::channel();
 
         // Send the statement to the execution engine
-        self.in_tx.send((statement.untyped, tx)).unwrap();
+        self.in_tx
+            .send((self.engine.clone(), statement.untyped, tx))
+            .unwrap();
 
         // Return the typed result
         rx.await.unwrap()
@@ -97,13 +159,27 @@ impl Db {
     }
 
     /// Start a transaction with explicit control
+    ///
+    /// The driver opens the transaction right away on a connection reserved
+    /// for it, so statements run through [`Transaction::db`] are undone by
+    /// [`Transaction::rollback`], and other statements on this `Db` don't run
+    /// inside it. Dropping the transaction without committing rolls it back.
+    /// Fails on databases without transactions, such as DynamoDB.
+    ///
+    /// On SQLite and PostgreSQL the `Db` has a single connection, so its
+    /// other statements wait until the transaction ends.
+    ///
+    /// [`Transaction::db`]: crate::Transaction::db
+    /// [`Transaction::rollback`]: crate::Transaction::rollback
     pub async fn begin(&self) -> Result<crate::Transaction> {
-        self.engine
-            .driver
-            .exec(&self.engine.schema.db, toasty_core::driver::operation::Transaction::Start.into())
-            .await?;
+        if !self.engine.capability().transactions {
+            anyhow::bail!("the database driver does not support transactions");
+        }
 
-        Ok(crate::Transaction::new(self))
+        let driver = self.engine.driver.begin().await?;
+        let engine = Engine::new(self.engine.schema.clone(), Arc::from(driver));
+
+        Ok(crate::Transaction::new(Db::new(engine)))
     }
 
     /// Execute operations within a transaction (automatic commit/rollback)
@@ -126,6 +202,14 @@ impl Db {
         }
     }
 
+    /// Drop and recreate all tables for the registered models.
+    ///
+    /// On SQL databases, concurrent calls against the same database (for
+    /// example, examples run from parallel tests) are serialized by the
+    /// driver, so each caller sees a consistent, empty schema when this
+    /// returns. Tests that need full
+    /// isolation from one another should still use separate databases.
+    ///
     /// TODO: remove
     pub async fn reset_db(&self) -> Result<()> {
         self.engine.driver.reset_db(&self.engine.schema.db).await
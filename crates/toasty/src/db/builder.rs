@@ -1,12 +1,12 @@
-use crate::{driver::Driver, engine::Engine, Db, Model, Result};
-
-use toasty_core::{
-    schema::{self, app},
-    stmt::{Value, ValueStream},
+use crate::{
+    driver::{redact_url, Driver},
+    engine::Engine,
+    Db, Model, Result,
 };
-use tokio::sync::oneshot;
 
-use std::sync::Arc;
+use toasty_core::schema::{self, app};
+
+use std::{sync::Arc, time::Duration};
 
 #[derive(Default)]
 pub struct Builder {
@@ -26,6 +26,13 @@ impl Builder {
         self
     }
 
+    /// Register a set of models at once, typically with the `register_all`
+    /// function generated by [`register_models!`](crate::register_models) in
+    /// the crate that defines them.
+    pub fn register_all(&mut self, register: impl FnOnce(&mut Self) -> &mut Self) -> &mut Self {
+        register(self)
+    }
+
     /// Set the table name prefix for all tables
     pub fn table_name_prefix(&mut self, prefix: &str) -> &mut Self {
         self.core.table_name_prefix(prefix);
@@ -37,8 +44,84 @@ impl Builder {
     }
 
     pub async fn connect(&mut self, url: &str) -> Result<Db> {
+        self.connect_with_retries(url, 0, Duration::ZERO).await
+    }
+
+    /// Connect to the database, retrying up to `retries` more times with
+    /// `delay` between attempts before giving up.
+    ///
+    /// Useful when the application may start before the database is ready to
+    /// accept connections. Only I/O errors, such as a refused connection, are
+    /// retried; any other error, like a bad URL or rejected credentials, is
+    /// returned right away.
+    pub async fn connect_with_retries(
+        &mut self,
+        url: &str,
+        retries: usize,
+        delay: Duration,
+    ) -> Result<Db> {
         use crate::driver::Connection;
-        self.build(Connection::connect(url).await?).await
+
+        let mut attempt = 0;
+
+        let connection = loop {
+            match Connection::connect(url).await {
+                Ok(connection) => break connection,
+                Err(err) if attempt < retries && is_connection_error(&err) => {
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                }
+                Err(err) => {
+                    return Err(err.context(format!(
+                        "failed to connect to database at {} after {} attempt(s); \
+                         check that the database server is running and the URL is correct",
+                        redact_url(url),
+                        attempt + 1,
+                    )))
+                }
+            }
+        };
+
+        self.build(connection).await
+    }
+
+    /// Connect to the database, then fail if any migration in
+    /// `migrations_dir` has not been applied to it yet.
+    ///
+    /// Call this at application startup so the app refuses to serve traffic
+    /// against an out-of-date schema. `_toasty_migrations` is read through
+    /// the new connection.
+    #[cfg(feature = "migrations")]
+    pub async fn connect_and_check_migrations(
+        &mut self,
+        url: &str,
+        migrations_dir: impl AsRef<std::path::Path>,
+    ) -> Result<Db> {
+        use toasty_migrate::{MigrationLoader, MigrationTracker};
+
+        let db = self.connect(url).await?;
+
+        let mut context = super::migrate::DbMigrationContext::new(&db)?;
+        let applied = MigrationTracker::applied_versions(&mut context).await?;
+
+        let pending: Vec<_> = MigrationLoader::new(migrations_dir.as_ref())
+            .discover_migrations()?
+            .into_iter()
+            .filter(|m| !applied.iter().any(|(version, _)| *version == m.version))
+            .collect();
+
+        if !pending.is_empty() {
+            let versions: Vec<_> = pending.iter().map(|m| m.version.as_str()).collect();
+            anyhow::bail!(
+                "database at {} is behind: {} pending migration(s): {}; \
+                 run `toasty migrate:up` before starting the application",
+                redact_url(url),
+                pending.len(),
+                versions.join(", "),
+            );
+        }
+
+        Ok(db)
     }
 
     pub async fn build(&mut self, mut driver: impl Driver) -> Result<Db> {
@@ -49,43 +132,37 @@ impl Builder {
         driver.register_schema(&schema.db).await.unwrap();
 
         let engine = Engine::new(Arc::new(schema), Arc::new(driver));
-        let engine2 = engine.clone();
-
-        let (in_tx, mut in_rx) = tokio::sync::mpsc::unbounded_channel::<(
-            toasty_core::stmt::Statement,
-            oneshot::Sender<Result<ValueStream>>,
-        )>();
-
-        let join_handle = tokio::spawn(async move {
-            loop {
-                let (stmt, tx) = in_rx.recv().await.unwrap();
-
-                match engine2.exec(stmt).await {
-                    Ok(mut value_stream) => {
-                        let (row_tx, mut row_rx) =
-                            tokio::sync::mpsc::unbounded_channel::<crate::Result<Value>>();
-
-                        let _ = tx.send(Ok(ValueStream::from_stream(async_stream::stream! {
-                            while let Some(res) = row_rx.recv().await {
-                                yield res
-                            }
-                        })));
-
-                        while let Some(res) = value_stream.next().await {
-                            let _ = row_tx.send(res);
-                        }
-                    }
-                    Err(err) => {
-                        let _ = tx.send(Err(err));
-                    }
-                }
-            }
-        });
-
-        Ok(Db {
-            engine,
-            in_tx,
-            join_handle,
-        })
+        Ok(Db::new(engine))
     }
 }
+
+/// Whether a failed connection attempt could succeed on a retry, which is the
+/// case when it failed on I/O, e.g. because the server isn't up yet
+fn is_connection_error(err: &crate::Error) -> bool {
+    err.chain().any(|cause| cause.is::<std::io::Error>())
+}
+
+/// Generate a `register_all` function that registers every listed model with
+/// a [`Builder`]. Invoke it once in the crate that defines the models, next to
+/// their re-exports, so applications don't list models at each connect call:
+///
+/// ```ignore
+/// // entity/src/lib.rs
+/// toasty::register_models!(User, Post);
+///
+/// // app
+/// let db = toasty::Db::builder()
+///     .register_all(entity::register_all)
+///     .connect(&url)
+///     .await?;
+/// ```
+#[macro_export]
+macro_rules! register_models {
+    ( $( $model:ty ),* $(,)? ) => {
+        /// Register every model in this crate with a `Db` builder
+        pub fn register_all(builder: &mut $crate::db::Builder) -> &mut $crate::db::Builder {
+            $( builder.register::<$model>(); )*
+            builder
+        }
+    };
+}
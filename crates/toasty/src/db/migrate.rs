@@ -0,0 +1,168 @@
+use super::Db;
+use crate::Result;
+
+use std::sync::Arc;
+use toasty_core::{
+    async_trait,
+    driver::{
+        operation::{RawSql, Transaction},
+        Driver, Response, Rows,
+    },
+    stmt::Value,
+};
+use toasty_migrate::{
+    AsyncMigrationContext, MigrationLoader, MigrationRegistry, MigrationRunner, MigrationTracker,
+    Row, SqlFlavor,
+};
+
+impl Db {
+    /// Apply the migration files in `dir` that haven't been applied yet, as
+    /// `toasty migrate:up` would, over this `Db`'s own driver, and return
+    /// their versions in the order they ran.
+    ///
+    /// The files are parsed rather than compiled in, so only the statements
+    /// their `up()` builds run. To also run `up_async()` data changes,
+    /// compile the migrations into the app and use [`migrate_registry`].
+    ///
+    /// [`migrate_registry`]: Db::migrate_registry
+    pub async fn migrate(&self, dir: &str) -> Result<Vec<String>> {
+        let migrations = MigrationLoader::new(dir).collect_migrations()?;
+        self.migrate_registry(migrations).await
+    }
+
+    /// Apply the migrations in `migrations` that haven't been applied yet,
+    /// over this `Db`'s own driver, and return their versions in the order
+    /// they ran.
+    ///
+    /// Migrations run through [`MigrationRunner`] as they do for `toasty
+    /// migrate:up`: each migration's `up()` and `up_async()` commit together
+    /// with its record in `_toasty_migrations`, and its followups run after.
+    ///
+    /// MySQL commits implicitly after each schema change, so a migration
+    /// that fails there may leave the changes it made before failing in
+    /// place. It is still not recorded as applied.
+    pub async fn migrate_registry(&self, migrations: MigrationRegistry) -> Result<Vec<String>> {
+        let mut db = DbMigrationContext::new(self)?;
+
+        let report = MigrationRunner::new(MigrationTracker::new())
+            .quiet()
+            .run_pending_live(migrations.into_vec(), &mut db)
+            .await?;
+
+        Ok(report
+            .applied
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect())
+    }
+}
+
+/// Runs migrations' statements through a `Db`'s driver. A transaction
+/// opened by the runner gets its own connection from [`Driver::begin`].
+pub(crate) struct DbMigrationContext<'a> {
+    db: &'a Db,
+    flavor: SqlFlavor,
+
+    /// The open transaction, if any
+    tx: Option<Box<dyn Driver>>,
+}
+
+impl<'a> DbMigrationContext<'a> {
+    pub(crate) fn new(db: &'a Db) -> Result<Self> {
+        let name = db.engine.capability().name;
+        let Some(flavor) = SqlFlavor::from_url(name) else {
+            anyhow::bail!("migrations need a SQL database; `{name}` is not one");
+        };
+
+        Ok(Self {
+            db,
+            flavor,
+            tx: None,
+        })
+    }
+
+    async fn exec(&self, op: impl Into<toasty_core::driver::Operation>) -> Result<Response> {
+        let driver = match &self.tx {
+            Some(tx) => &**tx,
+            None => &*self.db.engine.driver,
+        };
+        driver.exec(&self.db.engine.schema.db, op.into()).await
+    }
+
+    async fn end(&mut self, op: Transaction) -> Result<()> {
+        let Some(tx) = self.tx.take() else {
+            anyhow::bail!("no open transaction");
+        };
+
+        // Dropped either way, which rolls back a transaction that failed to
+        // commit
+        tx.exec(&self.db.engine.schema.db, op.into()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncMigrationContext for DbMigrationContext<'_> {
+    fn flavor(&self) -> SqlFlavor {
+        self.flavor
+    }
+
+    async fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64> {
+        let response = self
+            .exec(RawSql {
+                sql: sql.to_string(),
+                params: params.to_vec(),
+                ret: None,
+            })
+            .await?;
+
+        match response.rows {
+            Rows::Count(count) => Ok(count),
+            Rows::Values(_) => anyhow::bail!("expected a row count; sql={sql}"),
+        }
+    }
+
+    async fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+        let response = self
+            .exec(RawSql {
+                sql: sql.to_string(),
+                params: params.to_vec(),
+                ret: Some(vec![]),
+            })
+            .await?;
+
+        let Rows::Values(rows) = response.rows else {
+            anyhow::bail!("expected rows; sql={sql}");
+        };
+
+        let columns: Arc<[String]> = response.columns.into();
+        Ok(rows
+            .collect()
+            .await?
+            .into_iter()
+            .map(|row| Row::new(columns.clone(), row.into_record().into_iter().collect()))
+            .collect())
+    }
+
+    async fn execute_batch(&mut self, sql: &str) -> Result<()> {
+        self.exec(RawSql::batch(sql)).await?;
+        Ok(())
+    }
+
+    async fn begin(&mut self) -> Result<()> {
+        if self.tx.is_some() {
+            anyhow::bail!("a transaction is already open");
+        }
+
+        self.tx = Some(self.db.engine.driver.begin().await?);
+        Ok(())
+    }
+
+    async fn commit(&mut self) -> Result<()> {
+        self.end(Transaction::Commit).await
+    }
+
+    async fn rollback(&mut self) -> Result<()> {
+        self.end(Transaction::Rollback).await
+    }
+}
@@ -3,7 +3,7 @@ use crate::Result;
 pub use toasty_core::{
     driver::{
         operation::{self, Operation},
-        Capability, Driver, Response, Rows,
+        redact_url, Capability, Driver, Response, Rows,
     },
     schema::db::Schema,
 };
@@ -44,7 +44,8 @@ impl Connection {
             "postgresql" => Self::connect_postgresql(&url).await,
             "sqlite" => Self::connect_sqlite(&url),
             scheme => Err(anyhow::anyhow!(
-                "unsupported database; schema={scheme}; url={url}"
+                "unsupported database; schema={scheme}; url={}",
+                redact_url(url.as_str())
             )),
         }
     }
@@ -147,6 +148,10 @@ impl Driver for Connection {
         match_db!(self, ref driver => driver.exec(schema, op).await)
     }
 
+    async fn begin(&self) -> Result<Box<dyn Driver>> {
+        match_db!(self, ref driver => driver.begin().await)
+    }
+
     async fn reset_db(&self, schema: &Schema) -> Result<()> {
         #[allow(unused_variables)]
         let schema = schema;
@@ -2,11 +2,13 @@ use crate::{
     engine::exec::{Action, Exec, Output, VarId},
     Result,
 };
+use std::sync::Arc;
 use toasty_core::{
     driver::{
         operation::{self, Transaction},
-        Rows,
+        Driver, Rows,
     },
+    schema::db::Schema,
     stmt::{self, ValueStream},
 };
 
@@ -32,20 +34,38 @@ impl Exec<'_> {
     ) -> Result<()> {
         assert!(action.input.is_empty(), "TODO");
 
-        let res = self
-            .engine
-            .driver
-            .exec(&self.engine.schema.db, Transaction::Start.into())
-            .await?;
-        assert!(matches!(res.rows, Rows::Count(0)));
+        // Nested in the user's transaction, if there is one, as a savepoint
+        let tx = self.engine.driver.begin().await?;
+
+        let res = Self::read_modify_write(&*tx, &self.engine.schema.db, action).await;
+        let end = if res.is_ok() {
+            Transaction::Commit
+        } else {
+            Transaction::Rollback
+        };
 
+        tx.exec(&self.engine.schema.db, end.into()).await?;
+        res?;
+
+        if let Some(output) = &action.output {
+            let rows = Rows::value_stream(ValueStream::default());
+            self.vars.store(output.var, output.num_uses, rows);
+        }
+
+        Ok(())
+    }
+
+    /// Check the read statement's counts match, then run the write
+    async fn read_modify_write(
+        driver: &dyn Driver,
+        schema: &Arc<Schema>,
+        action: &ReadModifyWrite,
+    ) -> Result<()> {
         let ty = Some(vec![stmt::Type::I64, stmt::Type::I64]);
 
-        let res = self
-            .engine
-            .driver
+        let res = driver
             .exec(
-                &self.engine.schema.db,
+                schema,
                 operation::QuerySql {
                     stmt: action.read.clone().into(),
                     ret: ty,
@@ -74,11 +94,9 @@ impl Exec<'_> {
             anyhow::bail!("update condition did not match");
         }
 
-        let res = self
-            .engine
-            .driver
+        let res = driver
             .exec(
-                &self.engine.schema.db,
+                schema,
                 operation::QuerySql {
                     stmt: action.write.clone(),
                     ret: None,
@@ -93,18 +111,6 @@ impl Exec<'_> {
 
         assert_eq!(actual, count as u64);
 
-        let res = self
-            .engine
-            .driver
-            .exec(&self.engine.schema.db, Transaction::Commit.into())
-            .await?;
-        assert!(matches!(res.rows, Rows::Count(0)));
-
-        if let Some(output) = &action.output {
-            let rows = Rows::value_stream(ValueStream::default());
-            self.vars.store(output.var, output.num_uses, rows);
-        }
-
         Ok(())
     }
 }
@@ -13,8 +13,13 @@ impl LowerStatement<'_, '_> {
             return;
         };
 
-        let Some(stmt::Offset::After(offset)) = limit.offset.take() else {
+        // Count-based offsets are passed through to the database as-is
+        if !matches!(limit.offset, Some(stmt::Offset::After(_))) {
             return;
+        }
+
+        let Some(stmt::Offset::After(offset)) = limit.offset.take() else {
+            unreachable!()
         };
 
         let Some(order_by) = &mut stmt.order_by else {
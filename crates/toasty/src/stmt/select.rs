@@ -7,6 +7,9 @@ pub struct Select<M> {
     /// How to filter the data source
     pub(crate) untyped: stmt::Query,
 
+    /// Offset set before a limit, applied once the limit is set
+    offset: Option<stmt::Offset>,
+
     _p: PhantomData<M>,
 }
 
@@ -14,6 +17,7 @@ impl<M: Model> Select<M> {
     pub fn unit() -> Self {
         Self {
             untyped: stmt::Query::unit(),
+            offset: None,
             _p: PhantomData,
         }
     }
@@ -21,6 +25,7 @@ impl<M: Model> Select<M> {
     pub(crate) const fn from_untyped(untyped: stmt::Query) -> Self {
         Self {
             untyped,
+            offset: None,
             _p: PhantomData,
         }
     }
@@ -61,6 +66,34 @@ impl<M: Model> Select<M> {
         self
     }
 
+    /// Limit the number of records returned
+    pub fn limit(&mut self, n: usize) -> &mut Self {
+        let offset = self
+            .untyped
+            .limit
+            .take()
+            .and_then(|limit| limit.offset)
+            .or_else(|| self.offset.take());
+
+        self.untyped.limit = Some(stmt::Limit {
+            limit: stmt::Value::from(n as i64).into(),
+            offset,
+        });
+        self
+    }
+
+    /// Skip the first `n` records. The offset only applies to a query with a
+    /// `limit`; when it is set first, it is held until `limit` is called.
+    pub fn offset(&mut self, n: usize) -> &mut Self {
+        let offset = stmt::Offset::Count(stmt::Value::from(n as i64).into());
+
+        match self.untyped.limit.as_mut() {
+            Some(limit) => limit.offset = Some(offset),
+            None => self.offset = Some(offset),
+        }
+        self
+    }
+
     // TODO: not quite right
     pub fn delete(self) -> Statement<M> {
         Delete::from_untyped(self.untyped.delete()).into()
@@ -86,6 +119,7 @@ impl<M> Clone for Select<M> {
     fn clone(&self) -> Self {
         Self {
             untyped: self.untyped.clone(),
+            offset: self.offset.clone(),
             _p: PhantomData,
         }
     }
@@ -1,18 +1,27 @@
 use crate::{Cursor, Db, Model, Result, Statement};
+use std::marker::PhantomData;
 use toasty_core::{driver::operation::Transaction as TransactionOp, stmt::ValueStream};
 
 /// A database transaction
+///
+/// Dropping it without calling [`commit`](Self::commit) rolls it back.
 #[derive(Debug)]
 pub struct Transaction<'a> {
-    db: &'a Db,
+    /// Runs statements on the transaction's connection
+    db: Db,
+
+    /// The `Db` the transaction was started from
+    _parent: PhantomData<&'a Db>,
+
     committed: bool,
     rolled_back: bool,
 }
 
 impl<'a> Transaction<'a> {
-    pub(crate) fn new(db: &'a Db) -> Self {
+    pub(crate) fn new(db: Db) -> Self {
         Self {
             db,
+            _parent: PhantomData,
             committed: false,
             rolled_back: false,
         }
@@ -62,19 +71,10 @@ impl<'a> Transaction<'a> {
         self.db.all(query).await
     }
 
-    /// Get database reference (for passing to model methods)
+    /// Database handle running statements inside this transaction (for
+    /// passing to model methods). It borrows the transaction, so using it
+    /// after `commit` or `rollback` doesn't compile.
     pub fn db(&self) -> &Db {
-        self.db
-    }
-}
-
-impl<'a> Drop for Transaction<'a> {
-    fn drop(&mut self) {
-        if !self.committed && !self.rolled_back {
-            // Auto-commit on successful drop
-            // Note: We can't do async in Drop, so this is best-effort
-            // The transaction should be explicitly committed or rolled back
-            eprintln!("⚠️  Transaction dropped without explicit commit - auto-committing");
-        }
+        &self.db
     }
 }
@@ -0,0 +1,83 @@
+#![cfg(all(feature = "sqlite", feature = "migrations"))]
+
+use toasty::Db;
+use toasty_migrate::prelude::*;
+use toasty_migrate::MigrationRegistry;
+
+struct CreateUsers;
+
+impl Migration for CreateUsers {
+    fn version(&self) -> &str {
+        "20250101_000000_create_users"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        db.execute_sql("CREATE TABLE users (id TEXT PRIMARY KEY)")
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        db.drop_table("users")
+    }
+}
+
+#[tokio::test]
+async fn test_connect_fails_with_pending_migrations() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    std::fs::write(
+        migrations.join("20250101_000000_create_users.rs"),
+        "// migration placeholder\n",
+    )
+    .unwrap();
+
+    let url = format!("sqlite:{}", tmp.path().join("app.db").display());
+
+    let err = Db::builder()
+        .connect_and_check_migrations(&url, &migrations)
+        .await
+        .unwrap_err();
+
+    let message = format!("{err:#}");
+    assert!(message.contains("1 pending migration(s)"), "{message}");
+    assert!(
+        message.contains("20250101_000000_create_users"),
+        "{message}"
+    );
+}
+
+#[tokio::test]
+async fn test_connect_succeeds_without_pending_migrations() {
+    let tmp = tempfile::tempdir().unwrap();
+    let url = format!("sqlite:{}", tmp.path().join("app.db").display());
+
+    Db::builder()
+        .connect_and_check_migrations(&url, tmp.path().join("migrations"))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_connect_reads_applied_migrations_through_the_driver() {
+    let tmp = tempfile::tempdir().unwrap();
+    let migrations = tmp.path().join("migrations");
+    std::fs::create_dir_all(&migrations).unwrap();
+    std::fs::write(
+        migrations.join("20250101_000000_create_users.rs"),
+        "// migration placeholder\n",
+    )
+    .unwrap();
+
+    let url = format!("sqlite:{}", tmp.path().join("app.db").display());
+
+    let mut registry = MigrationRegistry::new();
+    registry.register(Box::new(CreateUsers)).unwrap();
+    let db = Db::builder().connect(&url).await.unwrap();
+    db.migrate_registry(registry).await.unwrap();
+    drop(db);
+
+    Db::builder()
+        .connect_and_check_migrations(&url, &migrations)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,226 @@
+#![cfg(all(feature = "sqlite", feature = "migrations"))]
+
+use toasty::{stmt::Id, Db};
+use toasty_core::stmt::Value;
+use toasty_migrate::prelude::*;
+use toasty_migrate::MigrationRegistry;
+
+#[derive(Debug, toasty::Model)]
+struct User {
+    #[key]
+    #[auto]
+    id: Id<Self>,
+
+    name: String,
+}
+
+struct CreateUsers;
+
+impl Migration for CreateUsers {
+    fn version(&self) -> &str {
+        "20250101_000000_create_users"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        db.create_table(
+            "users",
+            vec![
+                ColumnDef {
+                    name: "id".into(),
+                    ty: "TEXT".into(),
+                    primary_key: true,
+                    ..Default::default()
+                },
+                ColumnDef {
+                    name: "name".into(),
+                    ty: "TEXT".into(),
+                    ..Default::default()
+                },
+            ],
+        )
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        db.drop_table("users")
+    }
+}
+
+struct IndexUsers;
+
+impl Migration for IndexUsers {
+    fn version(&self) -> &str {
+        "20250102_000000_index_users"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        db.create_index(
+            "users",
+            IndexDef {
+                name: "index_users_by_name".into(),
+                columns: vec!["name".into()],
+                ..Default::default()
+            },
+        )
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        db.drop_index("users", "index_users_by_name")
+    }
+}
+
+/// Renames every user, reading rows by column name and writing them back
+/// through the `Db`'s driver
+struct ShoutNames;
+
+#[async_trait]
+impl Migration for ShoutNames {
+    fn version(&self) -> &str {
+        "20250103_000000_shout_names"
+    }
+
+    fn up(&self, _db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn up_async(&self, db: &mut dyn AsyncMigrationContext) -> anyhow::Result<()> {
+        for row in db.query("SELECT id, name FROM users", &[]).await? {
+            let (Some(id), Some(Value::String(name))) =
+                (row.get_by_name("id"), row.get_by_name("name"))
+            else {
+                anyhow::bail!("unexpected row: {row:?}");
+            };
+
+            db.execute(
+                "UPDATE users SET name = ?1 WHERE id = ?2",
+                &[name.to_uppercase().into(), id.clone()],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    fn down(&self, _db: &mut dyn MigrationContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+fn migrations(all: Vec<Box<dyn Migration>>) -> MigrationRegistry {
+    let mut registry = MigrationRegistry::new();
+    for migration in all {
+        registry.register(migration).unwrap();
+    }
+    registry
+}
+
+#[tokio::test]
+async fn test_migrate_through_db_connection() {
+    // An in-memory database only exists on this connection, so the table
+    // can only be there if the migrations ran through it
+    let db = Db::builder()
+        .register::<User>()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let applied = db
+        .migrate_registry(migrations(vec![
+            Box::new(IndexUsers),
+            Box::new(CreateUsers),
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(
+        applied,
+        [
+            "20250101_000000_create_users",
+            "20250102_000000_index_users"
+        ]
+    );
+
+    let alice = User::create().name("Alice").exec(&db).await.unwrap();
+    let read = User::get_by_id(&db, &alice.id).await.unwrap();
+    assert_eq!(read.name, "Alice");
+
+    // Already applied
+    let applied = db
+        .migrate_registry(migrations(vec![
+            Box::new(CreateUsers),
+            Box::new(IndexUsers),
+        ]))
+        .await
+        .unwrap();
+    assert!(applied.is_empty());
+}
+
+#[tokio::test]
+async fn test_migrate_runs_data_changes_through_db_connection() {
+    let db = Db::builder()
+        .register::<User>()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    db.migrate_registry(migrations(vec![Box::new(CreateUsers)]))
+        .await
+        .unwrap();
+    let alice = User::create().name("Alice").exec(&db).await.unwrap();
+
+    let applied = db
+        .migrate_registry(migrations(vec![
+            Box::new(CreateUsers),
+            Box::new(ShoutNames),
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(applied, ["20250103_000000_shout_names"]);
+
+    let read = User::get_by_id(&db, &alice.id).await.unwrap();
+    assert_eq!(read.name, "ALICE");
+}
+
+#[tokio::test]
+async fn test_migrate_runs_migration_files_from_a_directory() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("20250101_000000_create_users.rs"),
+        r#"use toasty_migrate::prelude::*;
+use anyhow::Result;
+
+pub struct Migration_20250101_000000_create_users;
+
+impl Migration for Migration_20250101_000000_create_users {
+    fn version(&self) -> &str {
+        "20250101_000000_create_users"
+    }
+
+    fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.create_table("users", vec![
+            ColumnDef { name: "id".into(), ty: "TEXT".into(), primary_key: true, ..Default::default() },
+            ColumnDef { name: "name".into(), ty: "TEXT".into(), ..Default::default() },
+        ])
+    }
+
+    fn down(&self, db: &mut dyn MigrationContext) -> Result<()> {
+        db.drop_table("users")
+    }
+}
+"#,
+    )
+    .unwrap();
+    let dir = tmp.path().to_str().unwrap();
+
+    let db = Db::builder()
+        .register::<User>()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let applied = db.migrate(dir).await.unwrap();
+    assert_eq!(applied, ["20250101_000000_create_users"]);
+
+    let alice = User::create().name("Alice").exec(&db).await.unwrap();
+    let read = User::get_by_id(&db, &alice.id).await.unwrap();
+    assert_eq!(read.name, "Alice");
+
+    assert!(db.migrate(dir).await.unwrap().is_empty());
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "sqlite")]
+
+use toasty::{stmt::Id, Db};
+
+// The attributes `toasty migrate:generate` reads are accepted by the derive
+// and leave the model as it is
+#[derive(Debug, toasty::Model)]
+#[soft_delete]
+#[partition(by = "range", key = "id")]
+#[table_options(engine = "InnoDB", charset = "utf8mb4")]
+struct User {
+    #[key]
+    #[auto]
+    id: Id<Self>,
+
+    #[unique]
+    #[collation = "NOCASE"]
+    email: String,
+}
+
+#[tokio::test]
+async fn test_migration_attributes_compile_and_leave_the_model_alone() {
+    let db = Db::builder()
+        .register::<User>()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    db.reset_db().await.unwrap();
+
+    let alice = User::create()
+        .email("alice@example.com")
+        .exec(&db)
+        .await
+        .unwrap();
+    let read = User::get_by_email(&db, "alice@example.com").await.unwrap();
+    assert_eq!(read.id, alice.id);
+    assert_eq!(read.email, "alice@example.com");
+}
@@ -10,9 +10,9 @@ impl Migration for Migration_20251122_074459_baseline {
 
     fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
         db.create_table("users", vec![
-            ColumnDef { name: "id".into(), ty: "text".into(), nullable: false, default: Some("''".into()) },
-            ColumnDef { name: "name".into(), ty: "text".into(), nullable: false, default: Some("''".into()) },
-            ColumnDef { name: "email".into(), ty: "text".into(), nullable: false, default: Some("''".into()) }
+            ColumnDef { name: "id".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() },
+            ColumnDef { name: "name".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() },
+            ColumnDef { name: "email".into(), ty: "text".into(), nullable: false, default: Some("''".into()), ..Default::default() }
         ])?;
         db.create_index("users", IndexDef { name: "index_users_by_email".into(), columns: vec!["email".into()], unique: true })?;
         Ok(())
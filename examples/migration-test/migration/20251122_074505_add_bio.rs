@@ -9,7 +9,7 @@ impl Migration for Migration_20251122_074505_add_bio {
     }
 
     fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
-        db.add_column("users", ColumnDef { name: "bio".into(), ty: "text".into(), nullable: true })?;
+        db.add_column("users", ColumnDef { name: "bio".into(), ty: "text".into(), nullable: true, ..Default::default() })?;
         Ok(())
     }
 
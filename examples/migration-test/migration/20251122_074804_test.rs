@@ -9,7 +9,7 @@ impl Migration for Migration_20251122_074804_test {
     }
 
     fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
-        db.add_column("users", ColumnDef { name: "age".into(), ty: "text".into(), nullable: true })?;
+        db.add_column("users", ColumnDef { name: "age".into(), ty: "text".into(), nullable: true, ..Default::default() })?;
         Ok(())
     }
 
@@ -5,14 +5,17 @@
 // - blog/      Blog-related models (posts, comments, etc.)
 // - rbac/      Role-Based Access Control models
 
-pub mod user;
 pub mod blog;
 pub mod rbac;
+pub mod user;
 
 // Re-export all models for convenience
-pub use user::User;
 pub use blog::Post;
 pub use rbac::{Role, UserRole};
+pub use user::User;
+
+// Every model, for `Db::builder().register_all(entity::register_all)`
+toasty::register_models!(User, Post, Role, UserRole);
 
 // Re-export toasty for use in applications
 pub use toasty;
@@ -119,13 +119,13 @@ async fn test_rollback_on_error(db: &toasty::Db) -> toasty::Result<()> {
 
     match duplicate_result {
         Ok(_) => println!("❌ Should have failed (duplicate name)"),
-        Err(e) => {
-            println!("   Error (expected): {}", e);
-            tx.rollback().await?;
-            println!("✅ Transaction rolled back after error");
-        }
+        Err(e) => println!("   Error (expected): {}", e),
     }
 
+    // End the transaction either way, so `db` isn't left waiting on it
+    tx.rollback().await?;
+    println!("✅ Transaction rolled back");
+
     let david = Account::filter_by_name("David").get(&db).await;
 
     match david {
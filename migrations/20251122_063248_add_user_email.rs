@@ -1,4 +1,4 @@
-use toasty_migrate::{Migration, MigrationContext, ColumnDef, IndexDef};
+use toasty_migrate::prelude::*;
 use anyhow::Result;
 
 pub struct Migration_20251122_063248_add_user_email;
@@ -9,8 +9,8 @@ impl Migration for Migration_20251122_063248_add_user_email {
     }
 
     fn up(&self, db: &mut dyn MigrationContext) -> Result<()> {
-        db.add_column("users", ColumnDef { name: "email".into(), ty: "String".into(), nullable: false })?;
-        db.create_index("users", IndexDef { name: "idx_users_email".into(), columns: vec![/* ... */], unique: true })?;
+        db.add_column("users", ColumnDef { name: "email".into(), ty: "String".into(), nullable: false, ..Default::default() })?;
+        db.create_index("users", IndexDef { name: "idx_users_email".into(), columns: vec!["email".into()], unique: true, ..Default::default() })?;
         Ok(())
     }
 
@@ -71,6 +71,14 @@ impl Driver for LoggingDriver {
         Ok(response)
     }
 
+    async fn begin(&self) -> Result<Box<dyn Driver>> {
+        // Log the transaction's operations alongside everything else
+        Ok(Box::new(LoggingDriver {
+            inner: self.inner.begin().await?,
+            ops_log: self.ops_log.clone(),
+        }))
+    }
+
     async fn reset_db(&self, schema: &Schema) -> Result<()> {
         self.inner.reset_db(schema).await
     }
@@ -88,5 +96,8 @@ async fn duplicate_response_mut(response: &mut Response) -> Result<Response> {
         }
     };
 
-    Ok(Response { rows })
+    Ok(Response {
+        rows,
+        columns: response.columns.clone(),
+    })
 }
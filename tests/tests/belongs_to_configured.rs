@@ -1,4 +1,5 @@
 use tests::{models, tests, DbTest};
+use toasty_core::schema::app::FieldTy;
 
 async fn different_field_name(test: &mut DbTest) {
     #[derive(Debug, toasty::Model)]
@@ -48,4 +49,80 @@ async fn different_field_name(test: &mut DbTest) {
     assert_eq!(user.id, user_reloaded.id)
 }
 
-tests!(different_field_name,);
+async fn inferred_key_matches_explicit(test: &mut DbTest) {
+    #[derive(Debug, toasty::Model)]
+    struct User {
+        #[key]
+        #[auto]
+        id: toasty::stmt::Id<Self>,
+
+        #[has_many]
+        todos: toasty::HasMany<Todo>,
+
+        #[has_many]
+        notes: toasty::HasMany<Note>,
+    }
+
+    #[derive(Debug, toasty::Model)]
+    struct Todo {
+        #[key]
+        #[auto]
+        id: toasty::stmt::Id<Self>,
+
+        #[belongs_to]
+        user: toasty::BelongsTo<User>,
+
+        #[index]
+        user_id: toasty::stmt::Id<User>,
+    }
+
+    #[derive(Debug, toasty::Model)]
+    struct Note {
+        #[key]
+        #[auto]
+        id: toasty::stmt::Id<Self>,
+
+        #[belongs_to(key = user_id, references = id)]
+        user: toasty::BelongsTo<User>,
+
+        #[index]
+        user_id: toasty::stmt::Id<User>,
+    }
+
+    let db = test.setup_db(models!(User, Todo, Note)).await;
+    let schema = &db.schema().app;
+
+    // Resolve a model's `user` foreign key to (source, target) field names
+    let fk = |model_name: &str| -> Vec<(String, String)> {
+        let model = schema
+            .models()
+            .find(|model| model.name.upper_camel_case() == model_name)
+            .unwrap();
+
+        let FieldTy::BelongsTo(rel) = &model.field_by_name("user").unwrap().ty else {
+            panic!("expected `user` to be a belongs_to relation");
+        };
+
+        rel.foreign_key
+            .fields
+            .iter()
+            .map(|fk_field| {
+                (
+                    schema.field(fk_field.source).name.app_name.clone(),
+                    schema.field(fk_field.target).name.app_name.clone(),
+                )
+            })
+            .collect()
+    };
+
+    let expect = vec![("user_id".to_string(), "id".to_string())];
+    assert_eq!(fk("Todo"), expect);
+    assert_eq!(fk("Note"), expect);
+
+    // The inferred relation works end to end
+    let user = User::create().exec(&db).await.unwrap();
+    let todo = user.todos().create().exec(&db).await.unwrap();
+    assert_eq!(todo.user().get(&db).await.unwrap().id, user.id);
+}
+
+tests!(different_field_name, inferred_key_matches_explicit,);
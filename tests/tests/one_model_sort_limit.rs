@@ -84,4 +84,63 @@ async fn paginate(test: &mut DbTest) {
     }
 }
 
-tests!(sort_asc, paginate,);
+async fn limit_offset(test: &mut DbTest) {
+    if !test.capability().sql {
+        return;
+    }
+
+    let db = test.setup_db(models!(Foo)).await;
+
+    for i in 0..100 {
+        Foo::create().order(i).exec(&db).await.unwrap();
+    }
+
+    let mut seen = vec![];
+
+    for page in 0..10 {
+        let mut cursor = Foo::all()
+            .order_by(Foo::FIELDS.order().asc())
+            .limit(10)
+            .offset(page * 10)
+            .all(&db)
+            .await
+            .unwrap();
+
+        let mut count = 0;
+        while let Some(foo) = cursor.next().await {
+            seen.push(foo.unwrap().order);
+            count += 1;
+        }
+
+        assert_eq!(count, 10);
+    }
+
+    assert_eq!(seen, (0..100).collect::<Vec<_>>());
+
+    // An offset set before the limit is applied once the limit is set
+    let foos: Vec<_> = Foo::all()
+        .order_by(Foo::FIELDS.order().asc())
+        .offset(20)
+        .limit(5)
+        .collect(&db)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        foos.iter().map(|foo| foo.order).collect::<Vec<_>>(),
+        (20..25).collect::<Vec<_>>()
+    );
+
+    // Past the end yields nothing
+    let foos: Vec<_> = Foo::all()
+        .order_by(Foo::FIELDS.order().asc())
+        .limit(10)
+        .offset(100)
+        .collect(&db)
+        .await
+        .unwrap();
+
+    assert!(foos.is_empty());
+}
+
+tests!(sort_asc, paginate, limit_offset,);
@@ -0,0 +1,45 @@
+use tests::{tests, DbTest};
+
+async fn register_all_registers_every_model(test: &mut DbTest) {
+    #[derive(Debug, toasty::Model)]
+    struct User {
+        #[key]
+        #[auto]
+        id: toasty::stmt::Id<Self>,
+
+        name: String,
+    }
+
+    #[derive(Debug, toasty::Model)]
+    struct Todo {
+        #[key]
+        #[auto]
+        id: toasty::stmt::Id<Self>,
+
+        title: String,
+    }
+
+    toasty::register_models!(User, Todo);
+
+    let mut builder = toasty::Db::builder();
+    builder.register_all(register_all);
+
+    let db = test.setup_db(builder).await;
+
+    let mut models: Vec<String> = db
+        .schema()
+        .app
+        .models()
+        .map(|model| model.name.upper_camel_case())
+        .collect();
+    models.sort();
+    assert_eq!(models, ["Todo", "User"]);
+
+    // Both models are usable through the same `Db`
+    let user = User::create().name("Alice").exec(&db).await.unwrap();
+    let todo = Todo::create().title("write docs").exec(&db).await.unwrap();
+    assert_eq!(user.name, "Alice");
+    assert_eq!(todo.title, "write docs");
+}
+
+tests!(register_all_registers_every_model,);
@@ -0,0 +1,46 @@
+use tests::prelude::*;
+use toasty::stmt::Id;
+
+async fn concurrent_reset_db(test: &mut DbTest) {
+    // Only SQL drivers serialize concurrent resets
+    if !test.capability().sql {
+        return;
+    }
+
+    #[derive(Debug, toasty::Model)]
+    struct Foo {
+        #[key]
+        #[auto]
+        id: Id<Self>,
+
+        #[index]
+        name: String,
+    }
+
+    let db1 = test.setup_db(models!(Foo)).await;
+
+    // A second, independent connection to the same database
+    let mut builder = models!(Foo);
+    test.configure_builder(&mut builder);
+    let db2 = builder.build(test.connect().await.unwrap()).await.unwrap();
+
+    Foo::create().name("stale").exec(&db1).await.unwrap();
+
+    for _ in 0..5 {
+        let (res1, res2) = tokio::join!(db1.reset_db(), db2.reset_db());
+        res1.unwrap();
+        res2.unwrap();
+
+        // Both connections see the same, empty schema
+        let foos1: Vec<_> = Foo::all().collect(&db1).await.unwrap();
+        let foos2: Vec<_> = Foo::all().collect(&db2).await.unwrap();
+        assert!(foos1.is_empty());
+        assert!(foos2.is_empty());
+    }
+
+    let created = Foo::create().name("fresh").exec(&db1).await.unwrap();
+    let found = Foo::get_by_id(&db2, &created.id).await.unwrap();
+    assert_eq!(found.name, "fresh");
+}
+
+tests!(concurrent_reset_db,);
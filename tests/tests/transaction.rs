@@ -0,0 +1,98 @@
+use tests::{assert_err, models, tests, DbTest};
+use toasty::stmt::Id;
+
+#[derive(Debug, toasty::Model)]
+struct User {
+    #[key]
+    #[auto]
+    id: Id<Self>,
+
+    name: String,
+}
+
+async fn rollback_discards_writes(test: &mut DbTest) {
+    let db = test.setup_db(models!(User)).await;
+
+    if !test.capability().transactions {
+        assert_err!(db.begin().await);
+        return;
+    }
+
+    let tx = db.begin().await.unwrap();
+    let alice = User::create().name("Alice").exec(tx.db()).await.unwrap();
+    let bob = User::create().name("Bob").exec(tx.db()).await.unwrap();
+    tx.rollback().await.unwrap();
+
+    // Had the driver not opened a transaction, the inserts would have been
+    // committed one by one and survive the rollback
+    assert_err!(User::get_by_id(&db, &alice.id).await);
+    assert_err!(User::get_by_id(&db, &bob.id).await);
+}
+
+async fn commit_keeps_writes(test: &mut DbTest) {
+    let db = test.setup_db(models!(User)).await;
+
+    if !test.capability().transactions {
+        return;
+    }
+
+    let tx = db.begin().await.unwrap();
+    let alice = User::create().name("Alice").exec(tx.db()).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let read = User::get_by_id(&db, &alice.id).await.unwrap();
+    assert_eq!(read.name, "Alice");
+}
+
+async fn dropped_transaction_rolls_back(test: &mut DbTest) {
+    let db = test.setup_db(models!(User)).await;
+
+    if !test.capability().transactions {
+        return;
+    }
+
+    let tx = db.begin().await.unwrap();
+    let alice = User::create().name("Alice").exec(tx.db()).await.unwrap();
+    drop(tx);
+
+    assert_err!(User::get_by_id(&db, &alice.id).await);
+
+    // The connection is out of the abandoned transaction, so statements
+    // commit on their own and a new transaction can be opened
+    let bob = User::create().name("Bob").exec(&db).await.unwrap();
+
+    let tx = db.begin().await.unwrap();
+    let carl = User::create().name("Carl").exec(tx.db()).await.unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(User::get_by_id(&db, &bob.id).await.unwrap().name, "Bob");
+    assert_eq!(User::get_by_id(&db, &carl.id).await.unwrap().name, "Carl");
+}
+
+async fn dropped_nested_transaction_rolls_back_before_parent_commits(test: &mut DbTest) {
+    let db = test.setup_db(models!(User)).await;
+
+    if !test.capability().transactions {
+        return;
+    }
+
+    let tx = db.begin().await.unwrap();
+    let alice = User::create().name("Alice").exec(tx.db()).await.unwrap();
+
+    let nested = tx.db().begin().await.unwrap();
+    let bob = User::create().name("Bob").exec(nested.db()).await.unwrap();
+    drop(nested);
+
+    // The savepoint is rolled back before the parent commits, not after
+    tx.commit().await.unwrap();
+
+    assert_eq!(User::get_by_id(&db, &alice.id).await.unwrap().name, "Alice");
+    assert_err!(User::get_by_id(&db, &bob.id).await);
+}
+
+tests!(
+    rollback_discards_writes,
+    commit_keeps_writes,
+    dropped_transaction_rolls_back,
+    dropped_nested_transaction_rolls_back_before_parent_commits
+);
@@ -0,0 +1,23 @@
+use toasty::stmt::Id;
+
+#[derive(toasty::Model)]
+struct User {
+    #[key]
+    #[auto]
+    id: Id<Self>,
+}
+
+#[derive(toasty::Model)]
+struct Profile {
+    #[key]
+    #[auto]
+    id: Id<Self>,
+
+    #[index]
+    owner_id: Id<User>,
+
+    #[belongs_to]
+    user: toasty::BelongsTo<User>,
+}
+
+fn main() {}
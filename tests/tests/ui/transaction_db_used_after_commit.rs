@@ -0,0 +1,23 @@
+use toasty::stmt::Id;
+
+#[derive(toasty::Model)]
+struct User {
+    #[key]
+    #[auto]
+    id: Id<Self>,
+
+    name: String,
+}
+
+async fn create_after_commit(db: &toasty::Db) -> toasty::Result<()> {
+    let tx = db.begin().await?;
+    let tx_db = tx.db();
+
+    tx.commit().await?;
+
+    // The transaction is gone, so its handle must be too
+    User::create().name("Alice").exec(tx_db).await?;
+    Ok(())
+}
+
+fn main() {}